@@ -0,0 +1,40 @@
+//! Re-records the provider fixtures in `fixtures/` against the live APIs.
+//!
+//! Run with `cargo run --example record_fixtures` after an upstream API change,
+//! then review the diff before committing.
+
+use magneto::{
+    transport::RecordingTransport, Knaben, PirateBay, ReqwestTransport, SearchProvider,
+    SearchRequest, Yts,
+};
+
+#[tokio::main]
+async fn main() {
+    let recordings: Vec<(Box<dyn SearchProvider>, &str, Vec<&str>)> = vec![
+        (Box::new(Knaben::new()), "knaben", vec!["ubuntu"]),
+        (
+            Box::new(PirateBay::new()),
+            "piratebay",
+            vec!["ubuntu", "no such torrent"],
+        ),
+        (Box::new(Yts::new()), "yts", vec!["inception"]),
+    ];
+
+    for (provider, name, queries) in recordings {
+        let path = format!("{}/fixtures/{}.json", env!("CARGO_MANIFEST_DIR"), name);
+        let transport = RecordingTransport::new(ReqwestTransport::new(), &path);
+
+        for query in queries {
+            match provider
+                .send_request(&transport, SearchRequest::new(query))
+                .await
+            {
+                Ok(torrents) => {
+                    println!("{}: '{}' returned {} torrents", name, query, torrents.len())
+                }
+                Err(e) => eprintln!("{}: '{}' failed: {}", name, query, e),
+            }
+        }
+        println!("recorded {}", path);
+    }
+}
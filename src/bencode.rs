@@ -0,0 +1,447 @@
+//! # Bencode
+//!
+//! A decoder and encoder for bencode, the serialization format used by
+//! .torrent files, the BitTorrent wire protocol extensions and the DHT.
+//!
+//! Decoding is zero-copy: byte strings and dictionary keys borrow from the
+//! input. Dictionaries are kept sorted by key, so encoding a decoded value
+//! reproduces canonical bencode.
+
+use std::{borrow::Cow, collections::BTreeMap};
+
+use thiserror::Error;
+
+/// The maximum nesting depth of lists and dictionaries accepted by the decoder.
+const MAX_DEPTH: usize = 64;
+
+/// Represents errors that can occur while decoding bencode.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BencodeError {
+    /// The input ended before the value was complete.
+    #[error("unexpected end of input at byte {0}")]
+    UnexpectedEof(usize),
+
+    /// A byte that cannot start or continue a value was found.
+    #[error("unexpected byte {byte:#04x} at byte {position}")]
+    UnexpectedByte {
+        /// The unexpected byte.
+        byte: u8,
+        /// The position of the byte in the input.
+        position: usize,
+    },
+
+    /// An integer or byte string length is malformed or out of range.
+    #[error("invalid number at byte {0}")]
+    InvalidNumber(usize),
+
+    /// Dictionary keys are not byte strings.
+    #[error("dictionary key at byte {0} is not a byte string")]
+    InvalidKey(usize),
+
+    /// Lists or dictionaries are nested deeper than the decoder allows.
+    #[error("nesting deeper than {MAX_DEPTH} levels at byte {0}")]
+    TooDeep(usize),
+
+    /// Data follows the decoded value.
+    #[error("trailing data at byte {0}")]
+    TrailingData(usize),
+}
+
+/// A bencode value, borrowing byte strings from the decoded input where possible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value<'a> {
+    /// An integer, e.g. `i42e`.
+    Integer(i64),
+
+    /// A byte string, e.g. `4:spam`.
+    Bytes(Cow<'a, [u8]>),
+
+    /// A list of values, e.g. `l4:spami42ee`.
+    List(Vec<Value<'a>>),
+
+    /// A dictionary with byte string keys, e.g. `d3:cow3:mooe`.
+    Dict(BTreeMap<Cow<'a, [u8]>, Value<'a>>),
+}
+
+impl<'a> Value<'a> {
+    /// Creates a byte string value from anything convertible to bytes.
+    pub fn bytes(value: impl Into<Vec<u8>>) -> Self {
+        Value::Bytes(Cow::Owned(value.into()))
+    }
+
+    /// Creates an empty dictionary value.
+    pub fn dict() -> Self {
+        Value::Dict(BTreeMap::new())
+    }
+
+    /// Inserts an entry into a dictionary value, returning the dictionary.
+    ///
+    /// Values other than dictionaries are returned unchanged.
+    pub fn with(mut self, key: &str, value: Value<'a>) -> Self {
+        if let Value::Dict(dict) = &mut self {
+            dict.insert(Cow::Owned(key.as_bytes().to_vec()), value);
+        }
+        self
+    }
+
+    /// Returns the integer if the value is an integer.
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes if the value is a byte string.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the string if the value is a UTF-8 byte string.
+    pub fn as_str(&self) -> Option<&str> {
+        self.as_bytes()
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+    }
+
+    /// Returns the values if the value is a list.
+    pub fn as_list(&self) -> Option<&[Value<'a>]> {
+        match self {
+            Value::List(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Returns the entries if the value is a dictionary.
+    pub fn as_dict(&self) -> Option<&BTreeMap<Cow<'a, [u8]>, Value<'a>>> {
+        match self {
+            Value::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    /// Looks up a key if the value is a dictionary.
+    pub fn get(&self, key: &str) -> Option<&Value<'a>> {
+        self.as_dict().and_then(|dict| dict.get(key.as_bytes()))
+    }
+
+    /// Converts the value into one that does not borrow from the decoded input.
+    pub fn into_owned(self) -> Value<'static> {
+        match self {
+            Value::Integer(value) => Value::Integer(value),
+            Value::Bytes(value) => Value::Bytes(Cow::Owned(value.into_owned())),
+            Value::List(values) => Value::List(values.into_iter().map(Value::into_owned).collect()),
+            Value::Dict(dict) => Value::Dict(
+                dict.into_iter()
+                    .map(|(key, value)| (Cow::Owned(key.into_owned()), value.into_owned()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Decodes a single bencode value spanning the whole input.
+///
+/// # Parameters
+/// - `input`: The bencoded bytes.
+///
+/// # Returns
+/// - `Ok(Value)`: The decoded value, borrowing from `input`.
+/// - `Err(BencodeError)`: An error if the input is malformed or has trailing data.
+///
+/// # Example
+/// ```rust
+/// use magneto::bencode::{decode, Value};
+///
+/// let value = decode(b"d3:cow3:moo4:spami42ee").unwrap();
+/// assert_eq!(value.get("cow").and_then(Value::as_str), Some("moo"));
+/// assert_eq!(value.get("spam").and_then(Value::as_integer), Some(42));
+/// ```
+pub fn decode(input: &[u8]) -> Result<Value<'_>, BencodeError> {
+    let (value, consumed) = decode_prefix(input)?;
+    if consumed != input.len() {
+        return Err(BencodeError::TrailingData(consumed));
+    }
+    Ok(value)
+}
+
+/// Decodes a single bencode value from the start of the input.
+///
+/// Unlike `decode`, data following the value is allowed, which is needed for
+/// messages that append raw data after a bencoded header (e.g. BEP 9).
+///
+/// # Returns
+/// - `Ok((Value, usize))`: The decoded value and the number of bytes it spans.
+/// - `Err(BencodeError)`: An error if the value is malformed.
+pub fn decode_prefix(input: &[u8]) -> Result<(Value<'_>, usize), BencodeError> {
+    let mut decoder = Decoder { input, position: 0 };
+    let value = decoder.value(0)?;
+    Ok((value, decoder.position))
+}
+
+/// Returns the raw bencoded bytes of a top-level dictionary entry.
+///
+/// This is used to hash the `info` dictionary of a .torrent file exactly as it
+/// appears in the file, even when it is not canonically encoded.
+///
+/// # Returns
+/// - `Ok(Some(&[u8]))`: The raw bytes of the entry's value.
+/// - `Ok(None)`: If the input is a dictionary without the key.
+/// - `Err(BencodeError)`: An error if the input is malformed or not a dictionary.
+pub fn raw_dict_entry<'a>(input: &'a [u8], key: &[u8]) -> Result<Option<&'a [u8]>, BencodeError> {
+    let mut decoder = Decoder { input, position: 0 };
+    match decoder.peek()? {
+        b'd' => decoder.position += 1,
+        byte => return Err(BencodeError::UnexpectedByte { byte, position: 0 }),
+    }
+
+    while decoder.peek()? != b'e' {
+        let entry_key = decoder.byte_string()?;
+        let start = decoder.position;
+        decoder.value(1)?;
+        if entry_key == key {
+            return Ok(Some(&input[start..decoder.position]));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Encodes a value into bencode.
+///
+/// # Example
+/// ```rust
+/// use magneto::bencode::{encode, Value};
+///
+/// let value = Value::dict()
+///     .with("spam", Value::Integer(42))
+///     .with("cow", Value::bytes("moo"));
+/// assert_eq!(encode(&value), b"d3:cow3:moo4:spami42ee");
+/// ```
+pub fn encode(value: &Value<'_>) -> Vec<u8> {
+    let mut output = Vec::new();
+    encode_into(value, &mut output);
+    output
+}
+
+/// Encodes a value into bencode, appending to the output buffer.
+pub fn encode_into(value: &Value<'_>, output: &mut Vec<u8>) {
+    match value {
+        Value::Integer(value) => {
+            output.push(b'i');
+            output.extend_from_slice(value.to_string().as_bytes());
+            output.push(b'e');
+        }
+        Value::Bytes(bytes) => encode_bytes(bytes, output),
+        Value::List(values) => {
+            output.push(b'l');
+            for value in values {
+                encode_into(value, output);
+            }
+            output.push(b'e');
+        }
+        Value::Dict(dict) => {
+            output.push(b'd');
+            for (key, value) in dict {
+                encode_bytes(key, output);
+                encode_into(value, output);
+            }
+            output.push(b'e');
+        }
+    }
+}
+
+/// Encodes a byte string, e.g. `4:spam`.
+fn encode_bytes(bytes: &[u8], output: &mut Vec<u8>) {
+    output.extend_from_slice(bytes.len().to_string().as_bytes());
+    output.push(b':');
+    output.extend_from_slice(bytes);
+}
+
+/// A recursive descent decoder over a borrowed input.
+struct Decoder<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Decoder<'a> {
+    /// Returns the next byte without consuming it.
+    fn peek(&self) -> Result<u8, BencodeError> {
+        self.input
+            .get(self.position)
+            .copied()
+            .ok_or(BencodeError::UnexpectedEof(self.position))
+    }
+
+    /// Decodes the value at the current position.
+    fn value(&mut self, depth: usize) -> Result<Value<'a>, BencodeError> {
+        if depth > MAX_DEPTH {
+            return Err(BencodeError::TooDeep(self.position));
+        }
+
+        match self.peek()? {
+            b'i' => {
+                self.position += 1;
+                let value = self.integer(b'e')?;
+                Ok(Value::Integer(value))
+            }
+            b'0'..=b'9' => Ok(Value::Bytes(Cow::Borrowed(self.byte_string()?))),
+            b'l' => {
+                self.position += 1;
+                let mut values = Vec::new();
+                while self.peek()? != b'e' {
+                    values.push(self.value(depth + 1)?);
+                }
+                self.position += 1;
+                Ok(Value::List(values))
+            }
+            b'd' => {
+                self.position += 1;
+                let mut dict = BTreeMap::new();
+                while self.peek()? != b'e' {
+                    if !self.peek()?.is_ascii_digit() {
+                        return Err(BencodeError::InvalidKey(self.position));
+                    }
+                    let key = self.byte_string()?;
+                    let value = self.value(depth + 1)?;
+                    dict.insert(Cow::Borrowed(key), value);
+                }
+                self.position += 1;
+                Ok(Value::Dict(dict))
+            }
+            byte => Err(BencodeError::UnexpectedByte {
+                byte,
+                position: self.position,
+            }),
+        }
+    }
+
+    /// Decodes a byte string, e.g. `4:spam`.
+    fn byte_string(&mut self) -> Result<&'a [u8], BencodeError> {
+        let start = self.position;
+        let length = self.integer(b':')?;
+        let length = usize::try_from(length).map_err(|_| BencodeError::InvalidNumber(start))?;
+
+        let end = self
+            .position
+            .checked_add(length)
+            .ok_or(BencodeError::InvalidNumber(start))?;
+        if end > self.input.len() {
+            return Err(BencodeError::UnexpectedEof(self.input.len()));
+        }
+
+        let bytes = &self.input[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    /// Decodes a decimal integer up to the terminator, consuming the terminator.
+    ///
+    /// Leading zeros and negative zero are rejected as required by the specification.
+    fn integer(&mut self, terminator: u8) -> Result<i64, BencodeError> {
+        let start = self.position;
+        let length = self.input[start..]
+            .iter()
+            .position(|byte| *byte == terminator)
+            .ok_or(BencodeError::UnexpectedEof(self.input.len()))?;
+
+        let digits = &self.input[start..start + length];
+        let unsigned = digits.strip_prefix(b"-").unwrap_or(digits);
+        let valid = !unsigned.is_empty()
+            && unsigned.iter().all(u8::is_ascii_digit)
+            && (unsigned == b"0" || unsigned[0] != b'0')
+            && digits != b"-0";
+        if !valid {
+            return Err(BencodeError::InvalidNumber(start));
+        }
+
+        let value = std::str::from_utf8(digits)
+            .ok()
+            .and_then(|digits| digits.parse().ok())
+            .ok_or(BencodeError::InvalidNumber(start))?;
+
+        self.position = start + length + 1;
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests decoding every value type and that decoded byte strings borrow from the input.
+    #[test]
+    fn test_decode_values() {
+        assert_eq!(decode(b"i42e"), Ok(Value::Integer(42)));
+        assert_eq!(decode(b"i-7e"), Ok(Value::Integer(-7)));
+        assert_eq!(decode(b"0:"), Ok(Value::bytes("")));
+        assert_eq!(
+            decode(b"l4:spami0ee"),
+            Ok(Value::List(vec![Value::bytes("spam"), Value::Integer(0)]))
+        );
+
+        let input = b"d3:cow3:moo4:listl1:ae4:spamd1:xi1eee".to_vec();
+        let value = decode(&input).unwrap();
+        assert_eq!(value.get("cow").and_then(Value::as_str), Some("moo"));
+        assert_eq!(
+            value.get("list").and_then(Value::as_list).map(<[_]>::len),
+            Some(1)
+        );
+        assert_eq!(
+            value.get("spam").and_then(|spam| spam.get("x")),
+            Some(&Value::Integer(1))
+        );
+        assert!(matches!(
+            value.get("cow"),
+            Some(Value::Bytes(Cow::Borrowed(_)))
+        ));
+    }
+
+    /// Tests that malformed input is rejected with the error position.
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(decode(b""), Err(BencodeError::UnexpectedEof(0)));
+        assert_eq!(decode(b"i42"), Err(BencodeError::UnexpectedEof(3)));
+        assert_eq!(decode(b"i04e"), Err(BencodeError::InvalidNumber(1)));
+        assert_eq!(decode(b"i-0e"), Err(BencodeError::InvalidNumber(1)));
+        assert_eq!(decode(b"ie"), Err(BencodeError::InvalidNumber(1)));
+        assert_eq!(decode(b"5:abc"), Err(BencodeError::UnexpectedEof(5)));
+        assert_eq!(decode(b"di1e1:ae"), Err(BencodeError::InvalidKey(1)));
+        assert_eq!(decode(b"i1ei2e"), Err(BencodeError::TrailingData(3)));
+        assert_eq!(
+            decode(b"x"),
+            Err(BencodeError::UnexpectedByte {
+                byte: b'x',
+                position: 0
+            })
+        );
+        assert_eq!(decode(&[b'l'; 100]), Err(BencodeError::TooDeep(65)));
+        assert_eq!(
+            decode(b"99999999999999999999:"),
+            Err(BencodeError::InvalidNumber(0))
+        );
+    }
+
+    /// Tests that encoding a decoded value reproduces the canonical input.
+    #[test]
+    fn test_encode_roundtrip() {
+        let input = b"d4:infod6:lengthi1024e4:name8:file.txte4:listli-1e0:ee".to_vec();
+        assert_eq!(encode(&decode(&input).unwrap()), input);
+        assert_eq!(encode(&decode(&input).unwrap().into_owned()), input);
+    }
+
+    /// Tests extracting the raw bytes of a dictionary entry.
+    #[test]
+    fn test_raw_dict_entry() {
+        let input = b"d8:announce3:url4:infod4:name1:xee";
+
+        assert_eq!(
+            raw_dict_entry(input, b"info"),
+            Ok(Some(&b"d4:name1:xe"[..]))
+        );
+        assert_eq!(raw_dict_entry(input, b"missing"), Ok(None));
+        assert!(raw_dict_entry(b"i1e", b"info").is_err());
+    }
+}
@@ -0,0 +1,298 @@
+//! # Command line
+//!
+//! The arguments of the `magneto` binary.
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use magneto::{
+    release::Resolution, search_providers::SearchProvider, size::parse_size, Category, Eztv,
+    Knaben, Leetx, Nyaa, OrderBy, PirateBay, Yts,
+};
+
+/// Search torrents across multiple providers.
+#[derive(Parser, Debug)]
+#[command(name = "magneto", version, about)]
+pub struct Cli {
+    /// The command to run.
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// The commands of the binary.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Search torrents.
+    Search(SearchArgs),
+
+    /// Browse search results interactively.
+    #[cfg(feature = "tui")]
+    Tui(TuiArgs),
+
+    /// Serve searches over HTTP as a Torznab indexer and a JSON API, configured from the config file.
+    #[cfg(feature = "server")]
+    Serve(ServeArgs),
+}
+
+/// The arguments of the `search` command.
+#[derive(Args, Debug)]
+pub struct SearchArgs {
+    /// What to search.
+    #[command(flatten)]
+    pub query: QueryArgs,
+
+    /// How to filter the results.
+    #[command(flatten)]
+    pub filters: FilterArgs,
+
+    /// The output format.
+    #[arg(short, long, value_name = "FORMAT", value_enum, default_value_t = Format::Table)]
+    pub format: Format,
+}
+
+/// The arguments of the `tui` command.
+#[cfg(feature = "tui")]
+#[derive(Args, Debug)]
+pub struct TuiArgs {
+    /// What to search.
+    #[command(flatten)]
+    pub query: QueryArgs,
+
+    /// How to filter the results.
+    #[command(flatten)]
+    pub filters: FilterArgs,
+
+    /// The command sending a magnet link to a torrent client, where "{}" is
+    /// replaced by the link, e.g. "transmission-remote -a {}".
+    #[arg(long, value_name = "COMMAND", env = "MAGNETO_TORRENT_CLIENT")]
+    pub client: Option<String>,
+}
+
+/// The arguments of the `serve` command.
+#[cfg(feature = "server")]
+#[derive(Args, Debug)]
+pub struct ServeArgs {
+    /// The address to listen on.
+    #[arg(
+        short,
+        long,
+        value_name = "ADDRESS",
+        default_value = "127.0.0.1:9117",
+        env = "MAGNETO_LISTEN"
+    )]
+    pub listen: std::net::SocketAddr,
+
+    /// The name reported to clients.
+    #[arg(long, default_value = "magneto")]
+    pub title: String,
+
+    /// A TOML or JSON file of API keys; without it every request is accepted.
+    #[arg(long, value_name = "PATH", env = "MAGNETO_KEYS_FILE")]
+    pub keys: Option<std::path::PathBuf>,
+
+    /// An origin allowed to call the server from browsers, or "*" for any.
+    #[arg(
+        long = "cors-origin",
+        value_name = "ORIGIN",
+        env = "MAGNETO_CORS_ORIGINS",
+        value_delimiter = ','
+    )]
+    pub cors_origins: Vec<String>,
+}
+
+/// The arguments describing what to search.
+#[derive(Args, Debug, Clone)]
+pub struct QueryArgs {
+    /// The search query; several words are joined with spaces.
+    #[arg(required = true)]
+    pub query: Vec<String>,
+
+    /// Only return torrents of these categories. Can be repeated.
+    #[arg(short, long = "category", value_name = "CATEGORY", value_enum)]
+    pub categories: Vec<CategoryArg>,
+
+    /// Providers to query. Can be repeated. Defaults to Knaben, PirateBay and YTS.
+    #[arg(short, long = "provider", value_name = "PROVIDER", value_enum)]
+    pub providers: Vec<ProviderArg>,
+
+    /// The order of the results.
+    #[arg(short, long, value_name = "ORDER", value_enum, default_value_t = OrderArg::Seeders)]
+    pub order_by: OrderArg,
+
+    /// The maximum number of results.
+    #[arg(short = 'n', long, default_value_t = 50)]
+    pub limit: usize,
+}
+
+impl QueryArgs {
+    /// Returns the query words joined with spaces.
+    pub fn query(&self) -> String {
+        self.query.join(" ")
+    }
+
+    /// Returns the selected providers, or the default providers if none was selected.
+    pub fn providers(&self) -> &[ProviderArg] {
+        if self.providers.is_empty() {
+            &ProviderArg::DEFAULT
+        } else {
+            &self.providers
+        }
+    }
+}
+
+/// The arguments filtering the results.
+#[derive(Args, Debug, Clone)]
+pub struct FilterArgs {
+    /// Only return torrents with at least this many seeders.
+    #[arg(long, value_name = "N")]
+    pub min_seeders: Option<u32>,
+
+    /// Only return torrents of at least this size, e.g. "700MB".
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub min_size: Option<u64>,
+
+    /// Only return torrents of at most this size, e.g. "4.5GiB".
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_size: Option<u64>,
+
+    /// Only return releases of these resolutions. Can be repeated.
+    #[arg(short, long = "resolution", value_name = "RESOLUTION", value_enum)]
+    pub resolutions: Vec<ResolutionArg>,
+
+    /// Skip torrents whose name contains this word, case-insensitively. Can be repeated.
+    #[arg(short = 'x', long = "exclude", value_name = "WORD")]
+    pub excludes: Vec<String>,
+}
+
+/// A provider selectable on the command line.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderArg {
+    /// The Knaben meta search.
+    Knaben,
+
+    /// The PirateBay.
+    Piratebay,
+
+    /// YTS.
+    Yts,
+
+    /// Nyaa, for anime.
+    Nyaa,
+
+    /// EZTV, for TV shows; the query must start with an IMDb id, e.g. "tt0944947 S08E06".
+    Eztv,
+
+    /// 1337x, fetching a details page per result.
+    Leetx,
+}
+
+impl ProviderArg {
+    /// The providers searched when none is selected.
+    pub const DEFAULT: [ProviderArg; 3] = [
+        ProviderArg::Knaben,
+        ProviderArg::Piratebay,
+        ProviderArg::Yts,
+    ];
+
+    /// Returns the name of the provider on the command line.
+    pub fn name(&self) -> String {
+        self.to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Creates the provider.
+    pub fn provider(&self) -> Box<dyn SearchProvider> {
+        match self {
+            ProviderArg::Knaben => Box::new(Knaben::new()),
+            ProviderArg::Piratebay => Box::new(PirateBay::new()),
+            ProviderArg::Yts => Box::new(Yts::new()),
+            ProviderArg::Nyaa => Box::new(Nyaa::new()),
+            ProviderArg::Eztv => Box::new(Eztv::new()),
+            ProviderArg::Leetx => Box::new(Leetx::new()),
+        }
+    }
+}
+
+/// A `Category` selectable on the command line.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CategoryArg {
+    Movies,
+    TvShows,
+    Games,
+    Software,
+    Audio,
+    Anime,
+    Xxx,
+}
+
+impl From<CategoryArg> for Category {
+    fn from(category: CategoryArg) -> Self {
+        match category {
+            CategoryArg::Movies => Category::Movies,
+            CategoryArg::TvShows => Category::TvShows,
+            CategoryArg::Games => Category::Games,
+            CategoryArg::Software => Category::Software,
+            CategoryArg::Audio => Category::Audio,
+            CategoryArg::Anime => Category::Anime,
+            CategoryArg::Xxx => Category::Xxx,
+        }
+    }
+}
+
+/// An `OrderBy` selectable on the command line.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderArg {
+    Seeders,
+    Peers,
+}
+
+impl From<OrderArg> for OrderBy {
+    fn from(order: OrderArg) -> Self {
+        match order {
+            OrderArg::Seeders => OrderBy::Seeders,
+            OrderArg::Peers => OrderBy::Peers,
+        }
+    }
+}
+
+/// A `Resolution` selectable on the command line.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolutionArg {
+    #[value(name = "480p")]
+    P480,
+    #[value(name = "576p")]
+    P576,
+    #[value(name = "720p")]
+    P720,
+    #[value(name = "1080p")]
+    P1080,
+    #[value(name = "2160p", alias = "4k")]
+    P2160,
+}
+
+impl From<ResolutionArg> for Resolution {
+    fn from(resolution: ResolutionArg) -> Self {
+        match resolution {
+            ResolutionArg::P480 => Resolution::P480,
+            ResolutionArg::P576 => Resolution::P576,
+            ResolutionArg::P720 => Resolution::P720,
+            ResolutionArg::P1080 => Resolution::P1080,
+            ResolutionArg::P2160 => Resolution::P2160,
+        }
+    }
+}
+
+/// The output format of the results.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// An aligned table for humans.
+    Table,
+
+    /// A JSON array.
+    Json,
+
+    /// One JSON object per line.
+    Ndjson,
+
+    /// One magnet link per line.
+    Magnet,
+}
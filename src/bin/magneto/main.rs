@@ -0,0 +1,66 @@
+//! The `magneto` command line tool, searching torrents from the terminal.
+//!
+//! ```text
+//! magneto search ubuntu 24.04 --category software --format magnet
+//! ```
+//!
+//! Exits with status 1 when every provider failed, and 2 on invalid arguments.
+//! The interactive `tui` command requires the `tui` feature, and the `serve`
+//! command the `server` feature.
+
+mod cli;
+mod output;
+mod search;
+#[cfg(feature = "server")]
+mod serve;
+#[cfg(feature = "tui")]
+mod tui;
+
+use std::{io, process::ExitCode};
+
+use clap::Parser;
+
+use cli::{Cli, Command};
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    match Cli::parse().command {
+        Command::Search(args) => {
+            let outcome = search::run(&args).await;
+            for (provider, error) in &outcome.failures {
+                eprintln!("magneto: {} failed: {}", provider, error);
+            }
+            if outcome.total_failure() {
+                return ExitCode::FAILURE;
+            }
+
+            match output::write_results(&mut io::stdout().lock(), &outcome.torrents, args.format) {
+                Ok(()) => ExitCode::SUCCESS,
+                // Piping into e.g. `head` closes stdout early, which is not an error
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("magneto: cannot write results: {}", e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        #[cfg(feature = "tui")]
+        Command::Tui(args) => match tui::run(args).await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("magneto: {}", e);
+                ExitCode::FAILURE
+            }
+        },
+        #[cfg(feature = "server")]
+        Command::Serve(args) => match serve::run(args).await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("magneto: {:#}", e);
+                ExitCode::FAILURE
+            }
+        },
+    }
+}
@@ -0,0 +1,146 @@
+//! # Output
+//!
+//! Writes search results as a table, JSON, NDJSON or magnet links.
+
+use std::io::{self, Write};
+
+use magneto::Torrent;
+
+use crate::cli::Format;
+
+/// The table columns before the name, which is last and not padded.
+const HEADERS: [&str; 4] = ["SEEDERS", "PEERS", "SIZE", "PROVIDER"];
+
+/// Writes the results in the given format.
+pub fn write_results(out: &mut impl Write, torrents: &[Torrent], format: Format) -> io::Result<()> {
+    match format {
+        Format::Table => write_table(out, torrents),
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *out, torrents)?;
+            writeln!(out)
+        }
+        Format::Ndjson => {
+            for torrent in torrents {
+                serde_json::to_writer(&mut *out, torrent)?;
+                writeln!(out)?;
+            }
+            Ok(())
+        }
+        Format::Magnet => {
+            for torrent in torrents {
+                writeln!(out, "{}", torrent.magnet_link)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Writes the results as an aligned table with a header row.
+fn write_table(out: &mut impl Write, torrents: &[Torrent]) -> io::Result<()> {
+    let rows: Vec<[String; 4]> = torrents
+        .iter()
+        .map(|torrent| {
+            [
+                torrent.seeders.to_string(),
+                torrent.peers.to_string(),
+                torrent.size_human(),
+                torrent.provider.clone(),
+            ]
+        })
+        .collect();
+
+    let mut widths = HEADERS.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let header = HEADERS.map(str::to_string);
+    write_row(out, &header, &widths, "NAME")?;
+    for (row, torrent) in rows.iter().zip(torrents) {
+        write_row(out, row, &widths, &torrent.name)?;
+    }
+    Ok(())
+}
+
+/// Writes one table row; numbers are right-aligned and text left-aligned.
+fn write_row(
+    out: &mut impl Write,
+    cells: &[String; 4],
+    widths: &[usize; 4],
+    name: &str,
+) -> io::Result<()> {
+    write!(
+        out,
+        "{:>w0$}  {:>w1$}  {:>w2$}  {:<w3$}  ",
+        cells[0],
+        cells[1],
+        cells[2],
+        cells[3],
+        w0 = widths[0],
+        w1 = widths[1],
+        w2 = widths[2],
+        w3 = widths[3],
+    )?;
+    writeln!(out, "{}", name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a torrent for tests.
+    fn torrent(name: &str, seeders: u32, provider: &str) -> Torrent {
+        Torrent {
+            name: name.to_string(),
+            magnet_link: format!("magnet:?xt=urn:btih:{}", name.len()),
+            seeders,
+            peers: 3,
+            size_bytes: 1_500_000_000,
+            provider: provider.to_string(),
+            metadata: Default::default(),
+            stats_updated_at: None,
+        }
+    }
+
+    /// Returns the output of `write_results` as a string.
+    fn render(torrents: &[Torrent], format: Format) -> String {
+        let mut out = Vec::new();
+        write_results(&mut out, torrents, format).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    /// Tests every output format.
+    #[test]
+    fn test_write_results() {
+        let torrents = vec![
+            torrent("Ubuntu 24.04", 1200, "piratebay"),
+            torrent("Debian 12", 7, "yts"),
+        ];
+
+        assert_eq!(
+            render(&torrents, Format::Table),
+            "SEEDERS  PEERS     SIZE  PROVIDER   NAME\n\
+             \x20  1200      3  1.50 GB  piratebay  Ubuntu 24.04\n\
+             \x20     7      3  1.50 GB  yts        Debian 12\n"
+        );
+
+        assert_eq!(
+            render(&torrents, Format::Magnet),
+            "magnet:?xt=urn:btih:12\nmagnet:?xt=urn:btih:9\n"
+        );
+
+        let ndjson = render(&torrents, Format::Ndjson);
+        let lines: Vec<serde_json::Value> = ndjson
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["name"], "Debian 12");
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&torrents, Format::Json)).unwrap();
+        assert_eq!(json[0]["seeders"], 1200);
+    }
+}
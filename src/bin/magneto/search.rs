@@ -0,0 +1,105 @@
+//! # Search
+//!
+//! Runs the `search` command: queries every selected provider concurrently,
+//! keeps going when some of them fail and filters the merged results.
+
+use std::cmp::Reverse;
+
+use magneto::{ClientError, Magneto, OrderBy, SearchFilters, SearchRequest, Torrent};
+use tokio::task::JoinSet;
+
+use crate::cli::{FilterArgs, ProviderArg, QueryArgs, SearchArgs};
+
+/// The searches of running providers, each yielding the provider and its results.
+pub type Searches = JoinSet<(ProviderArg, Result<Vec<Torrent>, ClientError>)>;
+
+/// The outcome of a search across several providers.
+#[derive(Debug)]
+pub struct SearchOutcome {
+    /// The filtered and sorted results.
+    pub torrents: Vec<Torrent>,
+
+    /// The names of the providers that failed, with their error message.
+    pub failures: Vec<(String, String)>,
+
+    /// The number of providers queried.
+    pub queried: usize,
+}
+
+impl SearchOutcome {
+    /// Returns whether every provider failed.
+    pub fn total_failure(&self) -> bool {
+        self.queried > 0 && self.failures.len() == self.queried
+    }
+}
+
+/// Creates the filters of the command line arguments.
+pub fn filters(args: &FilterArgs) -> SearchFilters {
+    SearchFilters {
+        min_seeders: args.min_seeders,
+        min_size: args.min_size,
+        max_size: args.max_size,
+        resolutions: args.resolutions.iter().map(|&r| r.into()).collect(),
+        excludes: args.excludes.clone(),
+    }
+}
+
+/// Starts searching a provider in the background.
+///
+/// Each provider is searched with its own `Magneto`, so a failing provider
+/// does not fail the others.
+pub fn spawn(searches: &mut Searches, provider: ProviderArg, args: &QueryArgs) {
+    let magneto = Magneto::with_providers(vec![provider.provider()]);
+    let query = args.query();
+    let order_by: OrderBy = args.order_by.into();
+    let categories = args.categories.iter().map(|&c| c.into()).collect();
+    let limit = args.limit;
+
+    searches.spawn(async move {
+        let request = SearchRequest {
+            query: &query,
+            order_by,
+            categories,
+            number_of_results: limit,
+        };
+        (provider, magneto.search(request).await)
+    });
+}
+
+/// Runs the search of the `search` arguments.
+pub async fn run(args: &SearchArgs) -> SearchOutcome {
+    let providers = args.query.providers();
+    let mut searches = Searches::new();
+    for &provider in providers {
+        spawn(&mut searches, provider, &args.query);
+    }
+
+    let mut torrents = Vec::new();
+    let mut failures = Vec::new();
+    while let Some(joined) = searches.join_next().await {
+        match joined {
+            Ok((_, Ok(results))) => torrents.extend(results),
+            Ok((provider, Err(e))) => failures.push((provider.name(), e.to_string())),
+            Err(e) => failures.push(("unknown provider".to_string(), e.to_string())),
+        }
+    }
+
+    let filters = filters(&args.filters);
+    torrents.retain(|torrent| filters.accepts(torrent));
+    sort(&mut torrents, &args.query.order_by.into());
+    torrents.truncate(args.query.limit);
+
+    SearchOutcome {
+        torrents,
+        failures,
+        queried: providers.len(),
+    }
+}
+
+/// Sorts merged results by the given order.
+fn sort(torrents: &mut [Torrent], order_by: &OrderBy) {
+    match order_by {
+        OrderBy::Peers => torrents.sort_by_key(|torrent| Reverse(torrent.peers)),
+        _ => torrents.sort_by_key(|torrent| Reverse(torrent.seeders)),
+    }
+}
@@ -0,0 +1,39 @@
+//! # Serve
+//!
+//! Runs the HTTP server with the instance described by the config file.
+
+use anyhow::Context;
+use log::{info, warn};
+use magneto::{
+    server::{ApiKeys, Server},
+    Config, Magneto,
+};
+use tokio::net::TcpListener;
+
+use crate::cli::ServeArgs;
+
+/// Serves searches until the process is stopped.
+pub async fn run(args: ServeArgs) -> anyhow::Result<()> {
+    let config = Config::load().context("cannot load config")?;
+    let magneto = Magneto::from_config(&config)?;
+
+    let listener = TcpListener::bind(args.listen)
+        .await
+        .with_context(|| format!("cannot listen on {}", args.listen))?;
+    info!("serving on http://{}, torznab api at /api", args.listen);
+
+    let mut server = Server::new(magneto)
+        .with_title(args.title)
+        .with_cors(args.cors_origins);
+    match args.keys {
+        Some(path) => {
+            let keys = ApiKeys::from_file(&path)
+                .with_context(|| format!("cannot load keys from {}", path.display()))?;
+            info!("requiring one of {} api keys", keys.keys.len());
+            server = server.with_api_keys(keys);
+        }
+        None => warn!("no keys file, accepting every request"),
+    }
+
+    server.serve(listener).await.context("server failed")
+}
@@ -0,0 +1,121 @@
+//! # TUI actions
+//!
+//! Side effects on a magnet link: copying it, opening it and sending it to a
+//! torrent client. Commands are spawned without a shell and not waited for.
+
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+/// The alphabet of standard base64.
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Copies text to the clipboard with the OSC 52 escape sequence.
+///
+/// The terminal sets the clipboard, so this also works over SSH, but only in
+/// terminals supporting OSC 52.
+pub fn copy(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    stdout.flush()
+}
+
+/// Opens a magnet link with the handler registered on the system.
+pub fn open(magnet: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        // Not through cmd, which would run the text after each '&' of the link
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    spawn(command.arg(magnet))
+}
+
+/// Sends a magnet link to a torrent client.
+///
+/// # Parameters
+/// - `client`: The client command; "{}" arguments are replaced by the link,
+///   which is appended if there is none.
+/// - `magnet`: The magnet link.
+pub fn send(client: &str, magnet: &str) -> io::Result<()> {
+    let mut command = client_command(client, magnet)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty client command"))?;
+    spawn(&mut command)
+}
+
+/// Builds the client command for a magnet link.
+fn client_command(client: &str, magnet: &str) -> Option<Command> {
+    let mut words = client.split_whitespace();
+    let mut command = Command::new(words.next()?);
+    let mut substituted = false;
+    for word in words {
+        if word.contains("{}") {
+            substituted = true;
+            command.arg(word.replace("{}", magnet));
+        } else {
+            command.arg(word);
+        }
+    }
+    if !substituted {
+        command.arg(magnet);
+    }
+    Some(command)
+}
+
+/// Spawns a command detached from the terminal.
+fn spawn(command: &mut Command) -> io::Result<()> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(drop)
+}
+
+/// Encodes bytes as padded base64.
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &byte)| n | u32::from(byte) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests base64 encoding and building client commands.
+    #[test]
+    fn test_actions() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+
+        let magnet = "magnet:?xt=urn:btih:abc&dn=a b";
+        let command = client_command("transmission-remote -a {}", magnet).unwrap();
+        assert_eq!(command.get_program(), "transmission-remote");
+        assert_eq!(command.get_args().collect::<Vec<_>>(), ["-a", magnet]);
+
+        let command = client_command("qbittorrent", magnet).unwrap();
+        assert_eq!(command.get_args().collect::<Vec<_>>(), [magnet]);
+
+        assert!(client_command("  ", magnet).is_none());
+    }
+}
@@ -0,0 +1,491 @@
+//! # TUI state
+//!
+//! The state of the terminal UI and its reaction to key presses, independent
+//! of rendering and of the terminal.
+
+use std::cmp::Reverse;
+
+use clap::ValueEnum;
+use magneto::{ClientError, SearchFilters, Torrent};
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+    widgets::TableState,
+};
+
+use crate::cli::ProviderArg;
+
+/// The number of rows moved by page up and page down.
+const PAGE: usize = 10;
+
+/// A side effect requested by a key press, performed by the event loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Starts searching a provider.
+    Search(ProviderArg),
+
+    /// Copies the magnet link to the clipboard.
+    Copy(String),
+
+    /// Opens the magnet link with the system handler.
+    Open(String),
+
+    /// Sends the magnet link to the configured torrent client.
+    Send(String),
+}
+
+/// The search state of a provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProviderStatus {
+    /// The provider has not been searched.
+    Idle,
+
+    /// The search is running.
+    Searching,
+
+    /// The search returned this many results.
+    Done(usize),
+
+    /// The search failed with this message.
+    Failed(String),
+}
+
+/// A provider shown in the header.
+#[derive(Debug, Clone)]
+pub struct ProviderState {
+    /// The provider.
+    pub provider: ProviderArg,
+
+    /// Whether its results are shown.
+    pub enabled: bool,
+
+    /// Its search state.
+    pub status: ProviderStatus,
+}
+
+/// The column results are sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Seeders,
+    Peers,
+    Size,
+    Name,
+}
+
+impl SortKey {
+    /// Returns the next sort key, wrapping around.
+    fn next(self) -> Self {
+        match self {
+            SortKey::Seeders => SortKey::Peers,
+            SortKey::Peers => SortKey::Size,
+            SortKey::Size => SortKey::Name,
+            SortKey::Name => SortKey::Seeders,
+        }
+    }
+
+    /// Returns the name shown in the header.
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Seeders => "seeders",
+            SortKey::Peers => "peers",
+            SortKey::Size => "size",
+            SortKey::Name => "name",
+        }
+    }
+}
+
+/// Whether keys navigate or edit the filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputMode {
+    /// Keys navigate and trigger actions.
+    Normal,
+
+    /// Keys edit the filter text.
+    Filter,
+}
+
+/// The state of the terminal UI.
+#[derive(Debug)]
+pub struct App {
+    /// The search query.
+    pub query: String,
+
+    /// Every provider, searched or not.
+    pub providers: Vec<ProviderState>,
+
+    /// Every result received, with the provider that returned it.
+    results: Vec<(ProviderArg, Torrent)>,
+
+    /// Indices into `results` of the shown rows, in display order.
+    visible: Vec<usize>,
+
+    /// The selection and scroll position of the results table.
+    pub table: TableState,
+
+    /// The sort column.
+    pub sort: SortKey,
+
+    /// Whether the sort is descending.
+    pub descending: bool,
+
+    /// The filter text; every word must appear in shown names.
+    pub filter: String,
+
+    /// Whether keys navigate or edit the filter.
+    pub mode: InputMode,
+
+    /// The filters of the command line, always applied.
+    filters: SearchFilters,
+
+    /// A message shown in the footer.
+    pub status: Option<String>,
+
+    /// Whether the event loop should stop.
+    pub should_quit: bool,
+}
+
+impl App {
+    /// Creates the state of a new session.
+    ///
+    /// # Parameters
+    /// - `query`: The search query.
+    /// - `searched`: The providers searched at start; others are shown disabled.
+    /// - `filters`: The filters of the command line.
+    pub fn new(query: String, searched: &[ProviderArg], filters: SearchFilters) -> Self {
+        let providers = ProviderArg::value_variants()
+            .iter()
+            .map(|&provider| {
+                let enabled = searched.contains(&provider);
+                ProviderState {
+                    provider,
+                    enabled,
+                    status: if enabled {
+                        ProviderStatus::Searching
+                    } else {
+                        ProviderStatus::Idle
+                    },
+                }
+            })
+            .collect();
+
+        Self {
+            query,
+            providers,
+            results: Vec::new(),
+            visible: Vec::new(),
+            table: TableState::default(),
+            sort: SortKey::Seeders,
+            descending: true,
+            filter: String::new(),
+            mode: InputMode::Normal,
+            filters,
+            status: None,
+            should_quit: false,
+        }
+    }
+
+    /// Returns the shown torrents, in display order.
+    pub fn visible(&self) -> impl Iterator<Item = &Torrent> {
+        self.visible.iter().map(|&index| &self.results[index].1)
+    }
+
+    /// Returns the number of shown torrents.
+    pub fn visible_len(&self) -> usize {
+        self.visible.len()
+    }
+
+    /// Returns the selected torrent.
+    pub fn selected(&self) -> Option<&Torrent> {
+        let index = *self.visible.get(self.table.selected()?)?;
+        Some(&self.results[index].1)
+    }
+
+    /// Records the results of a provider search.
+    pub fn add_results(
+        &mut self,
+        provider: ProviderArg,
+        result: Result<Vec<Torrent>, ClientError>,
+    ) {
+        let status = match result {
+            Ok(torrents) => {
+                let count = torrents.len();
+                self.results
+                    .extend(torrents.into_iter().map(|torrent| (provider, torrent)));
+                ProviderStatus::Done(count)
+            }
+            Err(e) => ProviderStatus::Failed(e.to_string()),
+        };
+
+        if let Some(state) = self.provider_mut(provider) {
+            state.status = status;
+        }
+        self.refresh();
+    }
+
+    /// Handles a key press.
+    ///
+    /// # Returns
+    /// - `Option<Action>`: A side effect for the event loop to perform.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.should_quit = true;
+            return None;
+        }
+
+        match self.mode {
+            InputMode::Normal => self.handle_normal_key(key.code),
+            InputMode::Filter => {
+                self.handle_filter_key(key.code);
+                None
+            }
+        }
+    }
+
+    /// Handles a key press while navigating.
+    fn handle_normal_key(&mut self, code: KeyCode) -> Option<Action> {
+        match code {
+            KeyCode::Char('q') => self.should_quit = true,
+            KeyCode::Esc if !self.filter.is_empty() => {
+                self.filter.clear();
+                self.refresh();
+            }
+            KeyCode::Esc => self.should_quit = true,
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::PageDown => self.move_selection(PAGE as isize),
+            KeyCode::PageUp => self.move_selection(-(PAGE as isize)),
+            KeyCode::Home | KeyCode::Char('g') => self.select(0),
+            KeyCode::End | KeyCode::Char('G') => self.select(self.visible.len().saturating_sub(1)),
+            KeyCode::Char('s') => {
+                self.sort = self.sort.next();
+                // Names read best A to Z, counts best highest first
+                self.descending = self.sort != SortKey::Name;
+                self.refresh();
+            }
+            KeyCode::Char('r') => {
+                self.descending = !self.descending;
+                self.refresh();
+            }
+            KeyCode::Char('/') => self.mode = InputMode::Filter,
+            KeyCode::Char(digit @ '1'..='9') => {
+                let index = digit as usize - '1' as usize;
+                return self.toggle_provider(index);
+            }
+            KeyCode::Char('c') => return self.selected_magnet().map(Action::Copy),
+            KeyCode::Char('o') | KeyCode::Enter => return self.selected_magnet().map(Action::Open),
+            KeyCode::Char('t') => return self.selected_magnet().map(Action::Send),
+            _ => {}
+        }
+        None
+    }
+
+    /// Handles a key press while editing the filter.
+    fn handle_filter_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Enter => self.mode = InputMode::Normal,
+            KeyCode::Esc => {
+                self.filter.clear();
+                self.mode = InputMode::Normal;
+            }
+            KeyCode::Backspace => {
+                self.filter.pop();
+            }
+            KeyCode::Char(c) => self.filter.push(c),
+            _ => return,
+        }
+        self.refresh();
+    }
+
+    /// Shows or hides the results of a provider, searching it on first use.
+    fn toggle_provider(&mut self, index: usize) -> Option<Action> {
+        let state = self.providers.get_mut(index)?;
+        state.enabled = !state.enabled;
+
+        let action = if state.enabled && state.status == ProviderStatus::Idle {
+            state.status = ProviderStatus::Searching;
+            Some(Action::Search(state.provider))
+        } else {
+            None
+        };
+        self.refresh();
+        action
+    }
+
+    /// Returns the magnet link of the selected torrent.
+    fn selected_magnet(&self) -> Option<String> {
+        self.selected().map(|torrent| torrent.magnet_link.clone())
+    }
+
+    /// Moves the selection by `delta` rows, staying within the results.
+    fn move_selection(&mut self, delta: isize) {
+        let current = self.table.selected().unwrap_or(0);
+        self.select(current.saturating_add_signed(delta));
+    }
+
+    /// Selects a row, clamped to the results.
+    fn select(&mut self, row: usize) {
+        if self.visible.is_empty() {
+            self.table.select(None);
+        } else {
+            self.table.select(Some(row.min(self.visible.len() - 1)));
+        }
+    }
+
+    /// Returns the state of a provider.
+    fn provider_mut(&mut self, provider: ProviderArg) -> Option<&mut ProviderState> {
+        self.providers
+            .iter_mut()
+            .find(|state| state.provider == provider)
+    }
+
+    /// Recomputes the shown rows, keeping the selected torrent selected.
+    fn refresh(&mut self) {
+        let selected = self.selected_magnet();
+        let words: Vec<String> = self
+            .filter
+            .to_lowercase()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+
+        let mut visible: Vec<usize> = self
+            .results
+            .iter()
+            .enumerate()
+            .filter(|(_, (provider, torrent))| {
+                let name = torrent.name.to_lowercase();
+                self.providers
+                    .iter()
+                    .any(|state| state.provider == *provider && state.enabled)
+                    && self.filters.accepts(torrent)
+                    && words.iter().all(|word| name.contains(word))
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        let results = &self.results;
+        match self.sort {
+            SortKey::Seeders => visible.sort_by_key(|&i| Reverse(results[i].1.seeders)),
+            SortKey::Peers => visible.sort_by_key(|&i| Reverse(results[i].1.peers)),
+            SortKey::Size => visible.sort_by_key(|&i| Reverse(results[i].1.size_bytes)),
+            SortKey::Name => visible.sort_by_key(|&i| Reverse(results[i].1.name.to_lowercase())),
+        }
+        if !self.descending {
+            visible.reverse();
+        }
+        self.visible = visible;
+
+        let row = selected
+            .and_then(|magnet| {
+                self.visible
+                    .iter()
+                    .position(|&i| self.results[i].1.magnet_link == magnet)
+            })
+            .unwrap_or(0);
+        self.select(row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a torrent for tests.
+    fn torrent(name: &str, seeders: u32, size_bytes: u64) -> Torrent {
+        Torrent {
+            name: name.to_string(),
+            magnet_link: format!("magnet:?xt=urn:btih:{}", name),
+            seeders,
+            peers: 100 - seeders,
+            size_bytes,
+            provider: "test".to_string(),
+            metadata: Default::default(),
+            stats_updated_at: None,
+        }
+    }
+
+    /// Presses the keys in order, returning the last action.
+    fn press(app: &mut App, codes: &[KeyCode]) -> Option<Action> {
+        codes
+            .iter()
+            .map(|&code| app.handle_key(KeyEvent::from(code)))
+            .last()
+            .flatten()
+    }
+
+    /// Returns the names of the shown torrents.
+    fn names(app: &App) -> Vec<&str> {
+        app.visible().map(|torrent| torrent.name.as_str()).collect()
+    }
+
+    /// Tests streaming results, sorting, filtering, toggling providers and actions.
+    #[test]
+    fn test_app() {
+        let mut app = App::new(
+            "ubuntu".to_string(),
+            &[ProviderArg::Knaben, ProviderArg::Piratebay],
+            SearchFilters::default(),
+        );
+        assert_eq!(app.providers[2].status, ProviderStatus::Idle);
+
+        app.add_results(
+            ProviderArg::Knaben,
+            Ok(vec![
+                torrent("Ubuntu Server", 10, 3),
+                torrent("Xubuntu", 30, 1),
+            ]),
+        );
+        assert_eq!(names(&app), ["Xubuntu", "Ubuntu Server"]);
+        assert_eq!(app.selected().unwrap().name, "Xubuntu");
+
+        // The selection follows the torrent as results stream in
+        press(&mut app, &[KeyCode::Down]);
+        app.add_results(ProviderArg::Piratebay, Ok(vec![torrent("Kubuntu", 20, 2)]));
+        assert_eq!(names(&app), ["Xubuntu", "Kubuntu", "Ubuntu Server"]);
+        assert_eq!(app.selected().unwrap().name, "Ubuntu Server");
+
+        press(&mut app, &[KeyCode::Char('s')]);
+        assert_eq!(app.sort, SortKey::Peers);
+        assert_eq!(names(&app), ["Ubuntu Server", "Kubuntu", "Xubuntu"]);
+        press(&mut app, &[KeyCode::Char('s'), KeyCode::Char('s')]);
+        assert_eq!(app.sort, SortKey::Name);
+        assert_eq!(names(&app), ["Kubuntu", "Ubuntu Server", "Xubuntu"]);
+        press(&mut app, &[KeyCode::Char('r')]);
+        assert_eq!(names(&app), ["Xubuntu", "Ubuntu Server", "Kubuntu"]);
+
+        press(
+            &mut app,
+            &[KeyCode::Char('/'), KeyCode::Char('K'), KeyCode::Char('u')],
+        );
+        assert_eq!(app.mode, InputMode::Filter);
+        assert_eq!(names(&app), ["Kubuntu"]);
+        press(&mut app, &[KeyCode::Enter, KeyCode::Esc]);
+        assert_eq!(app.mode, InputMode::Normal);
+        assert_eq!(app.visible_len(), 3);
+
+        // Hiding a provider hides its results, enabling an idle one searches it
+        press(&mut app, &[KeyCode::Char('2')]);
+        assert_eq!(names(&app), ["Xubuntu", "Ubuntu Server"]);
+        assert_eq!(
+            press(&mut app, &[KeyCode::Char('3')]),
+            Some(Action::Search(ProviderArg::Yts))
+        );
+        assert_eq!(app.providers[2].status, ProviderStatus::Searching);
+        app.add_results(
+            ProviderArg::Yts,
+            Err(ClientError::ResponseError(anyhow::anyhow!("offline"))),
+        );
+        assert!(matches!(app.providers[2].status, ProviderStatus::Failed(_)));
+
+        assert_eq!(
+            press(&mut app, &[KeyCode::Char('g'), KeyCode::Char('c')]),
+            Some(Action::Copy("magnet:?xt=urn:btih:Xubuntu".to_string()))
+        );
+        assert!(matches!(
+            press(&mut app, &[KeyCode::Char('t')]),
+            Some(Action::Send(_))
+        ));
+
+        press(&mut app, &[KeyCode::Char('q')]);
+        assert!(app.should_quit);
+    }
+}
@@ -0,0 +1,103 @@
+//! # TUI
+//!
+//! The `tui` command: an interactive terminal UI showing results as providers
+//! respond. Key presses are read on a separate thread and merged with search
+//! results in one event loop.
+
+mod actions;
+mod app;
+mod ui;
+
+use std::{io, thread};
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyEventKind},
+    DefaultTerminal,
+};
+use tokio::sync::mpsc;
+
+use crate::{
+    cli::TuiArgs,
+    search::{self, Searches},
+};
+use app::{Action, App};
+
+/// Runs the terminal UI until the user quits.
+pub async fn run(args: TuiArgs) -> io::Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = event_loop(&mut terminal, args).await;
+    ratatui::restore();
+    result
+}
+
+/// Draws the UI and handles key presses and search results.
+async fn event_loop(terminal: &mut DefaultTerminal, args: TuiArgs) -> io::Result<()> {
+    let providers = args.query.providers();
+    let mut app = App::new(
+        args.query.query(),
+        providers,
+        search::filters(&args.filters),
+    );
+
+    let mut searches = Searches::new();
+    for &provider in providers {
+        search::spawn(&mut searches, provider, &args.query);
+    }
+
+    let (sender, mut events) = mpsc::unbounded_channel();
+    thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            if sender.send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    while !app.should_quit {
+        terminal.draw(|frame| ui::draw(frame, &mut app))?;
+
+        tokio::select! {
+            Some(event) = events.recv() => {
+                if let Event::Key(key) = event {
+                    if key.kind == KeyEventKind::Press {
+                        app.status = None;
+                        if let Some(action) = app.handle_key(key) {
+                            perform(&mut app, &mut searches, &args, action);
+                        }
+                    }
+                }
+            }
+            Some(joined) = searches.join_next(), if !searches.is_empty() => match joined {
+                Ok((provider, result)) => app.add_results(provider, result),
+                Err(e) => app.status = Some(format!("search failed: {}", e)),
+            },
+            else => break,
+        }
+    }
+
+    Ok(())
+}
+
+/// Performs an action and reports its outcome in the footer.
+fn perform(app: &mut App, searches: &mut Searches, args: &TuiArgs, action: Action) {
+    let outcome = match action {
+        Action::Search(provider) => {
+            search::spawn(searches, provider, &args.query);
+            return;
+        }
+        Action::Copy(magnet) => actions::copy(&magnet).map(|()| "magnet link copied"),
+        Action::Open(magnet) => actions::open(&magnet).map(|()| "magnet link opened"),
+        Action::Send(magnet) => match &args.client {
+            Some(client) => actions::send(client, &magnet).map(|()| "magnet link sent"),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no torrent client configured, use --client or MAGNETO_TORRENT_CLIENT",
+            )),
+        },
+    };
+
+    app.status = Some(match outcome {
+        Ok(message) => message.to_string(),
+        Err(e) => format!("error: {}", e),
+    });
+}
@@ -0,0 +1,271 @@
+//! # TUI rendering
+//!
+//! Draws the header, the results table, the details pane and the footer.
+
+use magneto::{Torrent, UploaderStatus};
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, Wrap},
+    Frame,
+};
+
+use super::app::{App, InputMode, ProviderStatus};
+
+/// The key hints shown in the footer while navigating.
+const HINTS: &str =
+    "↑↓ move  s sort  r reverse  / filter  1-9 providers  c copy  o open  t send  q quit";
+
+/// Draws the whole UI.
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let [header, body, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(3),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [results, details] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(body);
+
+    draw_header(frame, app, header);
+    draw_results(frame, app, results);
+    draw_details(frame, app.selected(), details);
+    draw_footer(frame, app, footer);
+}
+
+/// Draws the query, the providers with their state and the sort order.
+fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
+    let mut spans = vec![
+        Span::from(format!(" {} ", app.query)).bold().reversed(),
+        Span::from(" "),
+    ];
+
+    for (number, state) in app.providers.iter().enumerate() {
+        let (status, color) = match &state.status {
+            ProviderStatus::Idle => ("-".to_string(), Color::DarkGray),
+            ProviderStatus::Searching => ("…".to_string(), Color::Yellow),
+            ProviderStatus::Done(count) => (count.to_string(), Color::Green),
+            ProviderStatus::Failed(_) => ("failed".to_string(), Color::Red),
+        };
+        let style = if state.enabled {
+            Style::default().fg(color)
+        } else {
+            Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::CROSSED_OUT)
+        };
+        spans.push(Span::styled(
+            format!("{}:{} {}  ", number + 1, state.provider.name(), status),
+            style,
+        ));
+    }
+
+    spans.push(Span::from(format!(
+        "sort: {} {}",
+        app.sort.label(),
+        if app.descending { "↓" } else { "↑" }
+    )));
+    frame.render_widget(Line::from(spans), area);
+}
+
+/// Draws the results table with the selected row highlighted.
+fn draw_results(frame: &mut Frame, app: &mut App, area: Rect) {
+    let rows: Vec<Row> = app
+        .visible()
+        .map(|torrent| {
+            Row::new(vec![
+                Cell::from(Line::from(torrent.seeders.to_string()).right_aligned()),
+                Cell::from(Line::from(torrent.peers.to_string()).right_aligned()),
+                Cell::from(Line::from(torrent.size_human()).right_aligned()),
+                Cell::from(torrent.name.clone()),
+            ])
+        })
+        .collect();
+
+    let title = format!(" {} results ", app.visible_len());
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(7),
+            Constraint::Length(6),
+            Constraint::Length(10),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(Row::new(["SEEDERS", "PEERS", "SIZE", "NAME"]).bold())
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    frame.render_stateful_widget(table, area, &mut app.table);
+}
+
+/// Draws the details of the selected torrent, including its parsed release name.
+fn draw_details(frame: &mut Frame, torrent: Option<&Torrent>, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title(" details ");
+    let Some(torrent) = torrent else {
+        frame.render_widget(Paragraph::new("No result selected").block(block), area);
+        return;
+    };
+
+    let mut lines = vec![
+        Some(Line::from(torrent.name.clone()).bold()),
+        Some(Line::default()),
+        field("Size", Some(torrent.size_human())),
+        field("Seeders", Some(torrent.seeders.to_string())),
+        field("Peers", Some(torrent.peers.to_string())),
+        field("Provider", Some(torrent.provider.clone())),
+    ];
+
+    let release = torrent.release_info();
+    let episodes = match (release.seasons.as_slice(), release.episodes.as_slice()) {
+        ([], _) => None,
+        (seasons, []) => Some(format!("season {}", join(seasons))),
+        (seasons, episodes) => Some(format!(
+            "season {} episode {}",
+            join(seasons),
+            join(episodes)
+        )),
+    };
+    let mut flags = Vec::new();
+    if release.complete {
+        flags.push("complete");
+    }
+    if release.proper {
+        flags.push("proper");
+    }
+    if release.repack {
+        flags.push("repack");
+    }
+    lines.extend([
+        Some(Line::default()),
+        field("Title", Some(release.title.clone())),
+        field("Year", release.year.map(|year| year.to_string())),
+        field("Episodes", episodes),
+        field("Resolution", release.resolution.map(|r| r.to_string())),
+        field("Source", release.source.map(|s| s.to_string())),
+        field("Video", release.video_codec.map(|c| c.to_string())),
+        field("Audio", release.audio_codec.map(|c| c.to_string())),
+        field("HDR", (!release.hdr.is_empty()).then(|| join(&release.hdr))),
+        field(
+            "Languages",
+            (!release.languages.is_empty()).then(|| release.languages.join(", ")),
+        ),
+        field("Group", release.group.clone()),
+        field("Flags", (!flags.is_empty()).then(|| flags.join(", "))),
+    ]);
+
+    let metadata = &torrent.metadata;
+    let uploader = metadata
+        .uploader
+        .as_ref()
+        .map(|uploader| match metadata.uploader_status {
+            Some(UploaderStatus::Trusted) => format!("{} (trusted)", uploader),
+            Some(UploaderStatus::Vip) => format!("{} (vip)", uploader),
+            Some(UploaderStatus::Staff) => format!("{} (staff)", uploader),
+            _ => uploader.clone(),
+        });
+    lines.extend([
+        Some(Line::default()),
+        field("Uploader", uploader),
+        field("Files", metadata.file_count.map(|count| count.to_string())),
+        field("IMDb", metadata.imdb_id.clone()),
+        field("Details", metadata.details_url.clone()),
+        Some(Line::default()),
+        Some(Line::from(torrent.magnet_link.clone()).dark_gray()),
+    ]);
+
+    // Fields the provider and the name do not supply are left out
+    let lines: Vec<Line> = lines.into_iter().flatten().collect();
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+}
+
+/// Draws the filter input, the last status message or the key hints.
+fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
+    let line = match (&app.mode, &app.status) {
+        (InputMode::Filter, _) => {
+            Line::from(vec![Span::from("/").bold(), Span::from(app.filter.clone())])
+        }
+        (InputMode::Normal, Some(status)) => Line::from(status.clone()),
+        (InputMode::Normal, None) if !app.filter.is_empty() => {
+            Line::from(format!("filter: {}  (esc clears)", app.filter)).dark_gray()
+        }
+        (InputMode::Normal, None) => Line::from(HINTS).dark_gray(),
+    };
+    frame.render_widget(line, area);
+
+    if app.mode == InputMode::Filter {
+        let x = area.x + 1 + app.filter.chars().count() as u16;
+        frame.set_cursor_position((x.min(area.right().saturating_sub(1)), area.y));
+    }
+}
+
+/// Returns a "label value" line, or `None` if there is no value.
+fn field(label: &str, value: Option<String>) -> Option<Line<'static>> {
+    let value = value.filter(|value| !value.is_empty())?;
+    Some(Line::from(vec![
+        Span::from(format!("{:<11}", label)).dark_gray(),
+        Span::from(value),
+    ]))
+}
+
+/// Joins displayable values with commas.
+fn join<T: ToString>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::ProviderArg;
+    use magneto::SearchFilters;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    /// Tests drawing results and the details of the selected release.
+    #[test]
+    fn test_draw() {
+        let mut app = App::new(
+            "movie".to_string(),
+            &[ProviderArg::Yts],
+            SearchFilters::default(),
+        );
+        app.add_results(
+            ProviderArg::Yts,
+            Ok(vec![Torrent {
+                name: "Movie.2010.1080p.BluRay.x264-GROUP".to_string(),
+                magnet_link: "magnet:?xt=urn:btih:abc".to_string(),
+                seeders: 42,
+                peers: 7,
+                size_bytes: 2_000_000_000,
+                provider: "yts".to_string(),
+                metadata: Default::default(),
+                stats_updated_at: None,
+            }]),
+        );
+
+        let mut terminal = Terminal::new(TestBackend::new(160, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, &mut app)).unwrap();
+
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("3:yts 1"));
+        assert!(screen.contains("1 results"));
+        assert!(screen.contains("2.00 GB"));
+        assert!(screen.contains("Resolution 1080p"));
+        assert!(screen.contains("Group      GROUP"));
+    }
+}
@@ -0,0 +1,830 @@
+//! # Configuration
+//!
+//! Loads a `Config` from a TOML or JSON file and environment variables, and
+//! builds a fully configured `Magneto` with `Magneto::from_config`.
+//!
+//! `Config::load` reads the file named by `MAGNETO_CONFIG`, or else
+//! `$XDG_CONFIG_HOME/magneto/config.toml` (`~/.config/magneto/config.toml`
+//! when `XDG_CONFIG_HOME` is unset, `config.json` is tried next). A missing
+//! file is not an error. Every key is optional:
+//!
+//! ```toml
+//! [search]
+//! categories = ["movies", "tv_shows"]
+//! order_by = "seeders"
+//! number_of_results = 50
+//!
+//! [http]
+//! timeout_secs = 30
+//! connect_timeout_secs = 10
+//! proxy = "http://proxy.example:8080"
+//! user_agent = "magneto"
+//!
+//! [providers.piratebay]
+//! url = "https://apibay.org/q.php"
+//! mirrors = ["https://apibay.example/q.php"]
+//!
+//! [providers.yts]
+//! enabled = false
+//!
+//! [providers.nyaa]
+//! enabled = true
+//!
+//! [providers.leetx]
+//! enabled = true
+//! url = "https://1337x.st/"
+//! mirrors = ["https://x1337x.ws/"]
+//!
+//! [[providers.torznab]]
+//! name = "prowlarr"
+//! url = "http://localhost:9696/1/api"
+//! api_key = "0123456789abcdef"
+//!
+//! [[providers.rss]]
+//! name = "nyaa"
+//! url = "https://nyaa.si/?page=rss&q={query}&c={category}"
+//! categories = { anime = "1_0", audio = "2_0" }
+//! fields = { info_hash = "nyaa:infoHash", size = "nyaa:size", seeders = "nyaa:seeders", peers = "nyaa:leechers" }
+//!
+//! [providers]
+//! definitions = "/etc/magneto/definitions"
+//!
+//! [trackers]
+//! urls = ["udp://tracker.opentrackr.org:1337/announce"]
+//! timeout_secs = 10
+//! refresh_top = 10
+//!
+//! [cache]
+//! ttl_secs = 300
+//! max_entries = 256
+//! ```
+//!
+//! Environment variables override the file:
+//!
+//! | Variable | Overrides |
+//! |---|---|
+//! | `MAGNETO_PROVIDERS` | The enabled providers, comma-separated |
+//! | `MAGNETO_KNABEN_URL`, `MAGNETO_PIRATEBAY_URL`, `MAGNETO_YTS_URL`, `MAGNETO_NYAA_URL`, `MAGNETO_EZTV_URL`, `MAGNETO_LEETX_URL` | `providers.*.url` |
+//! | `MAGNETO_CATEGORIES` | `search.categories`, comma-separated |
+//! | `MAGNETO_ORDER_BY` | `search.order_by` |
+//! | `MAGNETO_NUMBER_OF_RESULTS` | `search.number_of_results` |
+//! | `MAGNETO_TIMEOUT_SECS` | `http.timeout_secs` |
+//! | `MAGNETO_PROXY` | `http.proxy` |
+//! | `MAGNETO_TRACKERS` | `trackers.urls`, comma-separated |
+//! | `MAGNETO_REFRESH_TOP` | `trackers.refresh_top` |
+//! | `MAGNETO_CACHE_TTL_SECS` | `cache.ttl_secs` |
+
+use std::{
+    collections::HashMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use serde::{de::IntoDeserializer, Deserialize};
+use thiserror::Error;
+
+use crate::{
+    definitions::DefinitionError,
+    scrape::{Scraper, DEFAULT_TRACKERS},
+    search_providers::{Mirrored, SearchProvider},
+    tracker::TrackerClient,
+    transport::{CachingTransport, HttpTransport, ReqwestTransport},
+    Category, Eztv, Knaben, Leetx, Nyaa, PirateBay, RssFields, RssProvider, SearchDefaults,
+    StatsRefresh, Torznab, Yts,
+};
+
+/// The prefix of the environment variables overriding the configuration.
+const ENV_PREFIX: &str = "MAGNETO_";
+
+/// Represents errors that can occur while loading a configuration.
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    /// The configuration file cannot be read.
+    #[error("cannot read config file {path}: {source}")]
+    Io {
+        /// The path of the file.
+        path: PathBuf,
+        /// The underlying error.
+        #[source]
+        source: io::Error,
+    },
+
+    /// The configuration file is not valid TOML or does not match the schema.
+    #[error("invalid toml config: {0}")]
+    Toml(#[from] toml::de::Error),
+
+    /// The configuration file is not valid JSON or does not match the schema.
+    #[error("invalid json config: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// The configuration file has an extension other than `.toml` or `.json`.
+    #[error("unsupported config file format: {0}")]
+    UnsupportedFormat(PathBuf),
+
+    /// An environment variable has an invalid value.
+    #[error("invalid value '{value}' for {name}: {reason}")]
+    InvalidEnv {
+        /// The name of the variable.
+        name: String,
+        /// The value of the variable.
+        value: String,
+        /// Why the value is invalid.
+        reason: String,
+    },
+
+    /// The provider definitions cannot be loaded.
+    #[error("cannot load provider definitions: {0}")]
+    Definitions(#[from] DefinitionError),
+
+    /// The HTTP client cannot be built, e.g. because the proxy URL is invalid.
+    #[error("cannot build http client: {0}")]
+    HttpClient(#[source] reqwest::Error),
+}
+
+/// The configuration of a `Magneto` instance.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The defaults of search requests.
+    pub search: SearchDefaults,
+
+    /// The HTTP client settings.
+    pub http: HttpConfig,
+
+    /// The built-in and Torznab providers.
+    pub providers: ProvidersConfig,
+
+    /// The trackers used to refresh seeder and peer counts.
+    pub trackers: TrackersConfig,
+
+    /// The response cache.
+    pub cache: CacheConfig,
+}
+
+/// The HTTP client settings.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    /// The timeout of a whole request, in seconds.
+    pub timeout_secs: Option<u64>,
+
+    /// The timeout for connecting, in seconds.
+    pub connect_timeout_secs: Option<u64>,
+
+    /// A proxy URL for every request, e.g. "http://proxy.example:8080".
+    pub proxy: Option<String>,
+
+    /// The `User-Agent` header sent to providers.
+    pub user_agent: Option<String>,
+}
+
+/// The settings of the built-in providers, and the Torznab endpoints and RSS feeds to search.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProvidersConfig {
+    /// The `Knaben` provider.
+    pub knaben: ProviderConfig,
+
+    /// The `PirateBay` provider.
+    pub piratebay: ProviderConfig,
+
+    /// The `Yts` provider.
+    pub yts: ProviderConfig,
+
+    /// The `Nyaa` provider, disabled by default.
+    pub nyaa: ProviderConfig,
+
+    /// The `Eztv` provider, disabled by default.
+    pub eztv: ProviderConfig,
+
+    /// The `Leetx` provider for 1337x, disabled by default.
+    pub leetx: ProviderConfig,
+
+    /// Torznab endpoints, e.g. Prowlarr or Jackett indexers.
+    pub torznab: Vec<TorznabConfig>,
+
+    /// Sites exposing search as an RSS or Atom feed.
+    pub rss: Vec<RssConfig>,
+
+    /// A directory of provider definition files, see `Magneto::load_definitions`.
+    pub definitions: Option<PathBuf>,
+}
+
+impl Default for ProvidersConfig {
+    /// Provides the general built-in providers enabled, without Torznab endpoints and RSS feeds.
+    fn default() -> Self {
+        Self {
+            knaben: ProviderConfig::default(),
+            piratebay: ProviderConfig::default(),
+            yts: ProviderConfig::default(),
+            nyaa: ProviderConfig::disabled(),
+            eztv: ProviderConfig::disabled(),
+            leetx: ProviderConfig::disabled(),
+            torznab: Vec::new(),
+            rss: Vec::new(),
+            definitions: None,
+        }
+    }
+}
+
+impl ProvidersConfig {
+    /// The names of the built-in providers.
+    const BUILT_IN: [&'static str; 6] = ["knaben", "piratebay", "yts", "nyaa", "eztv", "leetx"];
+
+    /// Returns the settings of a provider by name.
+    fn get_mut(&mut self, name: &str) -> Option<&mut ProviderConfig> {
+        match name {
+            "knaben" => Some(&mut self.knaben),
+            "piratebay" => Some(&mut self.piratebay),
+            "yts" => Some(&mut self.yts),
+            "nyaa" => Some(&mut self.nyaa),
+            "eztv" => Some(&mut self.eztv),
+            "leetx" => Some(&mut self.leetx),
+            _ => None,
+        }
+    }
+}
+
+/// The settings of a provider.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProviderConfig {
+    /// Whether the provider is searched.
+    pub enabled: bool,
+
+    /// The API URL, replacing the provider's default.
+    pub url: Option<String>,
+
+    /// API URLs of mirrors, tried in order when the provider fails.
+    pub mirrors: Vec<String>,
+}
+
+impl Default for ProviderConfig {
+    /// Provides an enabled provider with its default URL and no mirrors.
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            url: None,
+            mirrors: Vec::new(),
+        }
+    }
+}
+
+/// The settings of a Torznab endpoint.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct TorznabConfig {
+    /// The name reported as the provider of the results.
+    pub name: String,
+
+    /// The API URL of the endpoint.
+    pub url: String,
+
+    /// The API key of the endpoint.
+    pub api_key: String,
+
+    /// Whether the endpoint is searched.
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+}
+
+/// The settings of an RSS or Atom search feed.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct RssConfig {
+    /// The name reported as the provider of the results.
+    pub name: String,
+
+    /// The search URL with `{query}` and `{category}` placeholders.
+    pub url: String,
+
+    /// The code of the site for each supported category.
+    #[serde(default)]
+    pub categories: HashMap<Category, String>,
+
+    /// Where the fields of a torrent are found in an item.
+    #[serde(default)]
+    pub fields: RssFields,
+
+    /// Whether the feed is searched.
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+}
+
+impl RssConfig {
+    /// Builds the provider of the feed.
+    fn build(&self) -> RssProvider {
+        self.categories.iter().fold(
+            RssProvider::new(&self.url)
+                .with_name(&self.name)
+                .with_fields(self.fields.clone()),
+            |provider, (category, code)| provider.with_category(category.clone(), code),
+        )
+    }
+}
+
+/// Returns the default of `TorznabConfig::enabled` and `RssConfig::enabled`.
+fn enabled() -> bool {
+    true
+}
+
+impl ProviderConfig {
+    /// Provides a disabled provider with its default URL and no mirrors.
+    fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Default::default()
+        }
+    }
+
+    /// Builds the provider with the configured URL and mirrors, if enabled.
+    fn build<P: SearchProvider + 'static>(
+        &self,
+        new: fn() -> P,
+        with_url: fn(String) -> P,
+    ) -> Option<Box<dyn SearchProvider>> {
+        if !self.enabled {
+            return None;
+        }
+
+        let primary = self.url.clone().map_or_else(new, with_url);
+        if self.mirrors.is_empty() {
+            return Some(Box::new(primary));
+        }
+
+        let mirrored = self
+            .mirrors
+            .iter()
+            .fold(Mirrored::new(Box::new(primary)), |mirrored, url| {
+                mirrored.with_mirror(Box::new(with_url(url.clone())))
+            });
+        Some(Box::new(mirrored))
+    }
+}
+
+/// The trackers used to refresh seeder and peer counts.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TrackersConfig {
+    /// Trackers scraped in addition to the ones in magnet links.
+    pub urls: Vec<String>,
+
+    /// The timeout of a single tracker request, in seconds.
+    pub timeout_secs: u64,
+
+    /// The number of top results refreshed after each search; 0 disables refreshing.
+    pub refresh_top: usize,
+}
+
+impl Default for TrackersConfig {
+    /// Provides `DEFAULT_TRACKERS` with a 10 second timeout and refreshing disabled.
+    fn default() -> Self {
+        Self {
+            urls: DEFAULT_TRACKERS.iter().map(|url| url.to_string()).collect(),
+            timeout_secs: 10,
+            refresh_top: 0,
+        }
+    }
+}
+
+/// The response cache settings.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    /// How long provider responses are cached, in seconds; 0 disables the cache.
+    pub ttl_secs: u64,
+
+    /// The maximum number of cached responses.
+    pub max_entries: usize,
+}
+
+impl Default for CacheConfig {
+    /// Provides a disabled cache holding up to 256 responses once enabled.
+    fn default() -> Self {
+        Self {
+            ttl_secs: 0,
+            max_entries: 256,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the configuration file and applies the environment overrides.
+    ///
+    /// # Returns
+    /// - `Ok(Config)`: The configuration; the default one if there is no file.
+    /// - `Err(ConfigError)`: An error if the file or an environment variable is invalid.
+    pub fn load() -> Result<Self, ConfigError> {
+        let path = env::var_os("MAGNETO_CONFIG")
+            .map(PathBuf::from)
+            .or_else(Self::default_path);
+
+        let mut config = match path {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        config.apply_env(env::vars())?;
+
+        Ok(config)
+    }
+
+    /// Returns the path of the first existing configuration file in the user's config directory.
+    ///
+    /// # Returns
+    /// - `Some(PathBuf)`: `magneto/config.toml` or `magneto/config.json` in
+    ///   `$XDG_CONFIG_HOME`, `~/.config` or `%APPDATA%`.
+    /// - `None`: If none exists.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+            .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+
+        ["config.toml", "config.json"]
+            .iter()
+            .map(|file| config_dir.join("magneto").join(file))
+            .find(|path| path.is_file())
+    }
+
+    /// Reads a configuration file, choosing the format by extension.
+    ///
+    /// # Parameters
+    /// - `path`: A `.toml` or `.json` file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml(&contents),
+            Some("json") => Self::from_json(&contents),
+            _ => Err(ConfigError::UnsupportedFormat(path.to_path_buf())),
+        }
+    }
+
+    /// Parses a TOML configuration.
+    pub fn from_toml(contents: &str) -> Result<Self, ConfigError> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    /// Parses a JSON configuration.
+    pub fn from_json(contents: &str) -> Result<Self, ConfigError> {
+        Ok(serde_json::from_str(contents)?)
+    }
+
+    /// Applies the `MAGNETO_*` overrides among the given environment variables.
+    ///
+    /// Variables without the prefix are ignored, as are unknown `MAGNETO_*` ones.
+    ///
+    /// # Parameters
+    /// - `vars`: Variable names and values, usually `std::env::vars()`.
+    pub fn apply_env(
+        &mut self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<(), ConfigError> {
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let invalid = |reason: String| ConfigError::InvalidEnv {
+                name: name.clone(),
+                value: value.clone(),
+                reason,
+            };
+
+            match key {
+                "PROVIDERS" => {
+                    let enabled = split_list(&value);
+                    for provider in &enabled {
+                        if self.providers.get_mut(provider).is_none() {
+                            return Err(invalid(format!("unknown provider '{}'", provider)));
+                        }
+                    }
+                    for provider in ProvidersConfig::BUILT_IN {
+                        if let Some(config) = self.providers.get_mut(provider) {
+                            config.enabled = enabled.iter().any(|name| name == provider);
+                        }
+                    }
+                }
+                "CATEGORIES" => {
+                    self.search.categories = split_list(&value)
+                        .into_iter()
+                        .map(|category| parse_enum(&category))
+                        .collect::<Result<_, _>>()
+                        .map_err(invalid)?;
+                }
+                "ORDER_BY" => self.search.order_by = parse_enum(&value).map_err(invalid)?,
+                "NUMBER_OF_RESULTS" => {
+                    self.search.number_of_results =
+                        value.parse().map_err(|e| invalid(format!("{}", e)))?
+                }
+                "TIMEOUT_SECS" => {
+                    self.http.timeout_secs =
+                        Some(value.parse().map_err(|e| invalid(format!("{}", e)))?)
+                }
+                "PROXY" => self.http.proxy = Some(value.clone()),
+                "TRACKERS" => self.trackers.urls = split_list(&value),
+                "REFRESH_TOP" => {
+                    self.trackers.refresh_top =
+                        value.parse().map_err(|e| invalid(format!("{}", e)))?
+                }
+                "CACHE_TTL_SECS" => {
+                    self.cache.ttl_secs = value.parse().map_err(|e| invalid(format!("{}", e)))?
+                }
+                _ => {
+                    let provider = key
+                        .strip_suffix("_URL")
+                        .map(str::to_lowercase)
+                        .and_then(|provider| self.providers.get_mut(&provider));
+                    if let Some(provider) = provider {
+                        provider.url = Some(value.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds the enabled providers.
+    pub fn providers(&self) -> Vec<Box<dyn SearchProvider>> {
+        [
+            self.providers.knaben.build(Knaben::new, Knaben::with_url),
+            self.providers
+                .piratebay
+                .build(PirateBay::new, PirateBay::with_url),
+            self.providers.yts.build(Yts::new, Yts::with_url),
+            self.providers.nyaa.build(Nyaa::new, Nyaa::with_url),
+            self.providers.eztv.build(Eztv::new, Eztv::with_url),
+            self.providers.leetx.build(Leetx::new, Leetx::with_url),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(
+            self.providers
+                .torznab
+                .iter()
+                .filter(|torznab| torznab.enabled)
+                .map(|torznab| -> Box<dyn SearchProvider> {
+                    Box::new(Torznab::new(&torznab.url, &torznab.api_key).with_name(&torznab.name))
+                }),
+        )
+        .chain(
+            self.providers
+                .rss
+                .iter()
+                .filter(|rss| rss.enabled)
+                .map(|rss| -> Box<dyn SearchProvider> { Box::new(rss.build()) }),
+        )
+        .collect()
+    }
+
+    /// Builds the HTTP transport of providers, caching responses if enabled.
+    pub fn transport(&self) -> Result<Arc<dyn HttpTransport>, ConfigError> {
+        let transport = self.reqwest_transport()?;
+        if self.cache.ttl_secs == 0 {
+            return Ok(Arc::new(transport));
+        }
+
+        Ok(Arc::new(
+            CachingTransport::new(transport, Duration::from_secs(self.cache.ttl_secs))
+                .with_max_entries(self.cache.max_entries),
+        ))
+    }
+
+    /// Builds the tracker refresh, if `trackers.refresh_top` is not 0.
+    ///
+    /// Trackers are queried without the response cache, so counts stay live.
+    pub fn stats_refresh(&self) -> Result<Option<StatsRefresh>, ConfigError> {
+        if self.trackers.refresh_top == 0 {
+            return Ok(None);
+        }
+
+        let tracker_client = TrackerClient::new()
+            .with_timeout(Duration::from_secs(self.trackers.timeout_secs))
+            .with_transport(self.reqwest_transport()?);
+        Ok(Some(StatsRefresh {
+            scraper: Scraper::new()
+                .with_trackers(self.trackers.urls.clone())
+                .with_tracker_client(tracker_client),
+            top: self.trackers.refresh_top,
+        }))
+    }
+
+    /// Builds a `ReqwestTransport` with the HTTP settings.
+    fn reqwest_transport(&self) -> Result<ReqwestTransport, ConfigError> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(timeout) = self.http.timeout_secs {
+            builder = builder.timeout(Duration::from_secs(timeout));
+        }
+        if let Some(timeout) = self.http.connect_timeout_secs {
+            builder = builder.connect_timeout(Duration::from_secs(timeout));
+        }
+        if let Some(proxy) = &self.http.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy).map_err(ConfigError::HttpClient)?);
+        }
+        if let Some(user_agent) = &self.http.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+
+        let client = builder.build().map_err(ConfigError::HttpClient)?;
+        Ok(ReqwestTransport::with_client(client))
+    }
+}
+
+/// Splits a comma-separated list, trimming and dropping empty items.
+pub(crate) fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parses a unit enum variant from its configuration name, e.g. "tv_shows".
+pub(crate) fn parse_enum<T: for<'de> Deserialize<'de>>(value: &str) -> Result<T, String> {
+    T::deserialize(value.into_deserializer()).map_err(|e: serde::de::value::Error| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Category, Magneto, OrderBy};
+
+    /// Tests parsing TOML and JSON configurations and building a `Magneto` from them.
+    #[test]
+    fn test_from_config() {
+        let config = Config::from_toml(
+            r#"
+            [search]
+            categories = ["movies", "tv_shows"]
+            order_by = "peers"
+            number_of_results = 20
+
+            [http]
+            timeout_secs = 30
+            proxy = "http://127.0.0.1:8080"
+
+            [providers.piratebay]
+            url = "https://apibay.example/q.php"
+            mirrors = ["https://mirror-1.example/q.php", "https://mirror-2.example/q.php"]
+
+            [providers.yts]
+            enabled = false
+
+            [[providers.torznab]]
+            name = "prowlarr"
+            url = "http://localhost:9696/1/api"
+            api_key = "key"
+
+            [[providers.torznab]]
+            name = "jackett"
+            url = "http://localhost:9117/api"
+            api_key = "key"
+            enabled = false
+
+            [[providers.rss]]
+            name = "nyaa"
+            url = "https://nyaa.si/?page=rss&q={query}&c={category}"
+            categories = { anime = "1_0" }
+            fields = { seeders = "nyaa:seeders", peers = "nyaa:leechers" }
+
+            [trackers]
+            refresh_top = 5
+
+            [cache]
+            ttl_secs = 60
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.search.categories,
+            vec![Category::Movies, Category::TvShows]
+        );
+        assert_eq!(config.http.timeout_secs, Some(30));
+        assert_eq!(config.providers.piratebay.mirrors.len(), 2);
+        assert!(config.providers.knaben.enabled);
+        assert!(!config.providers.nyaa.enabled);
+        assert_eq!(config.trackers.urls.len(), DEFAULT_TRACKERS.len());
+
+        let json = Config::from_json(
+            r#"{"search": {"categories": ["movies", "tv_shows"], "order_by": "peers",
+                "number_of_results": 20}, "providers": {"yts": {"enabled": false}}}"#,
+        )
+        .unwrap();
+        assert_eq!(json.search, config.search);
+        assert_eq!(json.providers.yts, config.providers.yts);
+
+        let magneto = Magneto::from_config(&config).unwrap();
+        let ids: Vec<String> = magneto.active_providers.iter().map(|p| p.id()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "https://api.knaben.org/v1",
+                "https://apibay.example/q.php",
+                "http://localhost:9696/1/api",
+                "https://nyaa.si/?page=rss&q={query}&c={category}"
+            ]
+        );
+        assert_eq!(magneto.stats_refresh.as_ref().unwrap().top, 5);
+
+        let request = magneto.request("ubuntu");
+        assert_eq!(request.order_by, OrderBy::Peers);
+        assert_eq!(request.number_of_results, 20);
+        assert_eq!(request.categories.len(), 2);
+
+        assert!(matches!(
+            Config::from_toml("[search]\nresults = 5"),
+            Err(ConfigError::Toml(_))
+        ));
+        assert!(matches!(
+            Config::from_toml("[search]\norder_by = \"size\""),
+            Err(ConfigError::Toml(_))
+        ));
+    }
+
+    /// Tests the environment overrides.
+    #[test]
+    fn test_apply_env() {
+        let vars = |vars: &[(&str, &str)]| -> Vec<(String, String)> {
+            vars.iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect()
+        };
+
+        let mut config = Config::default();
+        config
+            .apply_env(vars(&[
+                ("PATH", "/usr/bin"),
+                ("MAGNETO_PROVIDERS", "yts, knaben, nyaa"),
+                ("MAGNETO_YTS_URL", "https://yts.example/api"),
+                ("MAGNETO_CATEGORIES", "anime,xxx"),
+                ("MAGNETO_ORDER_BY", "profile_score"),
+                ("MAGNETO_NUMBER_OF_RESULTS", "10"),
+                ("MAGNETO_TRACKERS", "udp://a.example:80,udp://b.example:80"),
+                ("MAGNETO_CACHE_TTL_SECS", "120"),
+            ]))
+            .unwrap();
+
+        assert!(config.providers.knaben.enabled);
+        assert!(!config.providers.piratebay.enabled);
+        assert!(config.providers.nyaa.enabled);
+        assert_eq!(
+            config.providers.yts.url.as_deref(),
+            Some("https://yts.example/api")
+        );
+        assert_eq!(
+            config.search.categories,
+            vec![Category::Anime, Category::Xxx]
+        );
+        assert_eq!(config.search.order_by, OrderBy::ProfileScore);
+        assert_eq!(config.search.number_of_results, 10);
+        assert_eq!(config.trackers.urls.len(), 2);
+        assert_eq!(config.cache.ttl_secs, 120);
+
+        for invalid in [
+            ("MAGNETO_PROVIDERS", "knaben,example"),
+            ("MAGNETO_CATEGORIES", "books"),
+            ("MAGNETO_NUMBER_OF_RESULTS", "many"),
+        ] {
+            assert!(matches!(
+                config.apply_env(vars(&[invalid])),
+                Err(ConfigError::InvalidEnv { .. })
+            ));
+        }
+    }
+
+    /// Tests reading a file by extension and rejecting an invalid proxy.
+    #[test]
+    fn test_from_file() {
+        let dir = env::temp_dir().join(format!("magneto-config-{}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        let json = dir.join("config.json");
+        fs::write(&json, r#"{"http": {"proxy": "not a url"}}"#).unwrap();
+        let yaml = dir.join("config.yaml");
+        fs::write(&yaml, "search: {}").unwrap();
+
+        let config = Config::from_file(&json).unwrap();
+        assert!(matches!(
+            Magneto::from_config(&config),
+            Err(ConfigError::HttpClient(_))
+        ));
+        assert!(matches!(
+            Config::from_file(&yaml),
+            Err(ConfigError::UnsupportedFormat(_))
+        ));
+        assert!(matches!(
+            Config::from_file(dir.join("missing.toml")),
+            Err(ConfigError::Io { .. })
+        ));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,284 @@
+//! # Dates
+//!
+//! Parsing of the dates found on torrent sites, such as "2024-04-25T15:02:11+00:00",
+//! "Thu, 25 Apr 2024 15:02:11 +0000", Unix timestamps or "3 hours ago".
+//! Dates without a time zone are read as UTC.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use thiserror::Error;
+
+/// Month abbreviations, in order.
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// Represents errors that can occur while parsing a date.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseDateError {
+    /// The input is empty or only contains whitespace.
+    #[error("cannot parse date from empty string")]
+    Empty,
+
+    /// The input is not in a supported format, or not a valid date.
+    #[error("invalid date '{0}'")]
+    Invalid(String),
+}
+
+/// Parses a date, reading relative dates such as "3 hours ago" from the current time.
+///
+/// See `parse_date_relative_to` for the supported formats.
+///
+/// # Example
+/// ```rust
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use magneto::date::parse_date;
+///
+/// let date = UNIX_EPOCH + Duration::from_secs(1714057331);
+/// assert_eq!(parse_date("2024-04-25T15:02:11Z"), Ok(date));
+/// assert_eq!(parse_date("Thu, 25 Apr 2024 17:02:11 +0200"), Ok(date));
+/// assert_eq!(parse_date("1714057331"), Ok(date));
+/// ```
+pub fn parse_date(input: &str) -> Result<SystemTime, ParseDateError> {
+    parse_date_relative_to(input, SystemTime::now())
+}
+
+/// Parses a date, reading relative dates from the given time.
+///
+/// Accepts Unix timestamps in seconds or milliseconds, ISO 8601 dates with an
+/// optional time and offset ("2024-04-25", "2024-04-25 15:02",
+/// "2024-04-25T15:02:11.5+02:00"), RFC 2822 dates ("Thu, 25 Apr 2024 15:02:11 GMT"),
+/// and relative dates ("now", "today", "yesterday", "5 mins ago", "2 weeks ago").
+/// Months count as 30 days and years as 365 days.
+///
+/// # Parameters
+/// - `input`: The date to parse.
+/// - `now`: The time relative dates are counted from.
+///
+/// # Returns
+/// - `Ok(SystemTime)`: The parsed date.
+/// - `Err(ParseDateError)`: An error if the format is unsupported or the date invalid.
+pub fn parse_date_relative_to(input: &str, now: SystemTime) -> Result<SystemTime, ParseDateError> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(ParseDateError::Empty);
+    }
+
+    let invalid = || ParseDateError::Invalid(input.to_string());
+    let seconds = if trimmed.bytes().all(|b| b.is_ascii_digit()) {
+        let timestamp: u64 = trimmed.parse().map_err(|_| invalid())?;
+        // Timestamps past the year 5000 in seconds are read as milliseconds
+        Some(if timestamp > 100_000_000_000 {
+            timestamp / 1000
+        } else {
+            timestamp
+        } as i64)
+    } else {
+        let lowercase = trimmed.to_lowercase();
+        if let Some(date) = parse_relative(&lowercase, now) {
+            return Ok(date);
+        }
+        parse_iso(&lowercase).or_else(|| parse_rfc2822(&lowercase))
+    };
+
+    let seconds = u64::try_from(seconds.ok_or_else(invalid)?).map_err(|_| invalid())?;
+    Ok(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+/// Parses "now", "today", "yesterday" and "<n> <unit>(s) ago".
+fn parse_relative(input: &str, now: SystemTime) -> Option<SystemTime> {
+    let ago = match input {
+        "now" | "just now" | "today" => 0,
+        "yesterday" => 24 * 60 * 60,
+        _ => {
+            let mut words = input.strip_suffix(" ago")?.split_whitespace();
+            let count: u64 = match words.next()? {
+                "a" | "an" => 1,
+                count => count.parse().ok()?,
+            };
+            let unit = words.next()?;
+            if words.next().is_some() {
+                return None;
+            }
+
+            let unit_seconds = match unit.trim_end_matches('s') {
+                "sec" | "second" => 1,
+                "min" | "minute" => 60,
+                "hour" | "hr" => 60 * 60,
+                "day" => 24 * 60 * 60,
+                "week" => 7 * 24 * 60 * 60,
+                "month" => 30 * 24 * 60 * 60,
+                "year" | "yr" => 365 * 24 * 60 * 60,
+                _ => return None,
+            };
+            count.checked_mul(unit_seconds)?
+        }
+    };
+
+    now.checked_sub(Duration::from_secs(ago))
+}
+
+/// Parses "YYYY-MM-DD" followed by an optional "[T ]HH:MM[:SS[.fff]]" and offset.
+fn parse_iso(input: &str) -> Option<i64> {
+    let (date, rest) = input.split_at_checked(10)?;
+    let mut parts = date.split('-');
+    let (year, month, day) = (
+        parts.next()?.parse().ok()?,
+        parts.next()?.parse().ok()?,
+        parts.next()?.parse().ok()?,
+    );
+    let days = days_from_civil(year, month, day)?;
+
+    let rest = rest.trim_start_matches(['t', ' ']);
+    if rest.is_empty() {
+        return Some(days * 86400);
+    }
+    let zone_start = rest.find(['z', '+', '-', ' ']).unwrap_or(rest.len());
+    let (time, zone) = rest.split_at(zone_start);
+
+    Some(days * 86400 + parse_time(time)? - parse_offset(zone.trim())?)
+}
+
+/// Parses "[Day, ]DD Mon YYYY HH:MM[:SS] [zone]".
+fn parse_rfc2822(input: &str) -> Option<i64> {
+    let input = input.split_once(", ").map_or(input, |(_, rest)| rest);
+    let mut words = input.split_whitespace();
+
+    let day = words.next()?.parse().ok()?;
+    let month = words.next()?;
+    let month = MONTHS.iter().position(|name| month.starts_with(name))? as u32 + 1;
+    let year = words.next()?.parse().ok()?;
+    let time = parse_time(words.next()?)?;
+    let offset = parse_offset(words.next().unwrap_or_default())?;
+    if words.next().is_some() {
+        return None;
+    }
+
+    Some(days_from_civil(year, month, day)? * 86400 + time - offset)
+}
+
+/// Parses "HH:MM[:SS[.fff]]" into seconds since midnight, ignoring fractions.
+fn parse_time(input: &str) -> Option<i64> {
+    let mut parts = input.split(':');
+    let hours: i64 = parts.next()?.parse().ok()?;
+    let minutes: i64 = parts.next()?.parse().ok()?;
+    let seconds: i64 = match parts.next() {
+        Some(seconds) => seconds.split('.').next()?.parse().ok()?,
+        None => 0,
+    };
+    if parts.next().is_some() || hours > 23 || minutes > 59 || seconds > 60 {
+        return None;
+    }
+
+    Some(hours * 3600 + minutes * 60 + seconds)
+}
+
+/// Parses a time zone, "z", "gmt", "utc", "+HH:MM" or "+HHMM", into seconds east of UTC.
+fn parse_offset(input: &str) -> Option<i64> {
+    let (sign, offset) = match input {
+        "" | "z" | "gmt" | "utc" | "ut" => return Some(0),
+        _ => match input.split_at_checked(1)? {
+            ("+", offset) => (1, offset),
+            ("-", offset) => (-1, offset),
+            _ => return None,
+        },
+    };
+
+    let digits = offset.replace(':', "");
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i64 = digits[..2].parse().ok()?;
+    let minutes: i64 = digits[2..].parse().ok()?;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Returns the number of days from 1970-01-01 to a date of the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u32, day: u32) -> Option<i64> {
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let month_days = [
+        31,
+        if leap { 29 } else { 28 },
+        31,
+        30,
+        31,
+        30,
+        31,
+        31,
+        30,
+        31,
+        30,
+        31,
+    ];
+    if !(1..=12).contains(&month) || day == 0 || day > month_days[month as usize - 1] {
+        return None;
+    }
+
+    // Counts from March so the leap day ends the year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146097 + day_of_era - 719468)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests the absolute formats.
+    #[test]
+    fn test_parse_date_formats() {
+        let date = |seconds| Ok(UNIX_EPOCH + Duration::from_secs(seconds));
+
+        assert_eq!(parse_date("1714057331"), date(1714057331));
+        assert_eq!(parse_date("1714057331000"), date(1714057331));
+        assert_eq!(parse_date("2024-04-25"), date(1714003200));
+        assert_eq!(parse_date("2024-04-25 15:02"), date(1714057320));
+        assert_eq!(parse_date("2024-04-25T15:02:11+00:00"), date(1714057331));
+        assert_eq!(parse_date("2024-04-25T17:02:11.250+0200"), date(1714057331));
+        assert_eq!(parse_date("2024-04-25T10:02:11-05:00"), date(1714057331));
+        assert_eq!(
+            parse_date("Thu, 25 Apr 2024 15:02:11 GMT"),
+            date(1714057331)
+        );
+        assert_eq!(parse_date("25 April 2024 15:02:11"), date(1714057331));
+        assert_eq!(parse_date("2000-02-29"), date(951782400));
+        assert_eq!(parse_date("1970-01-01T00:00:00Z"), date(0));
+
+        assert_eq!(parse_date(" "), Err(ParseDateError::Empty));
+        for invalid in [
+            "2023-02-29",
+            "2024-13-01",
+            "2024-04-25T25:00",
+            "25 Foo 2024 15:02",
+            "last tuesday",
+            "1969-12-31",
+        ] {
+            assert_eq!(
+                parse_date(invalid),
+                Err(ParseDateError::Invalid(invalid.to_string())),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    /// Tests relative dates.
+    #[test]
+    fn test_parse_date_relative() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let ago = |seconds| Ok(now - Duration::from_secs(seconds));
+
+        assert_eq!(parse_date_relative_to("just now", now), ago(0));
+        assert_eq!(parse_date_relative_to("Yesterday", now), ago(86400));
+        assert_eq!(parse_date_relative_to("5 mins ago", now), ago(300));
+        assert_eq!(parse_date_relative_to("an hour ago", now), ago(3600));
+        assert_eq!(parse_date_relative_to("2 weeks ago", now), ago(1_209_600));
+        assert!(parse_date_relative_to("5 fortnights ago", now).is_err());
+        assert!(parse_date_relative_to("ago", now).is_err());
+    }
+}
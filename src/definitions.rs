@@ -0,0 +1,1033 @@
+//! # Definitions
+//!
+//! Providers described in YAML or JSON files instead of Rust, loaded with
+//! `Magneto::load_definitions` or the `providers.definitions` directory of the
+//! configuration. A definition gives the search request, a table mapping
+//! categories to the codes of the site, and how to extract torrents from a JSON
+//! response with JSON pointers, or from an HTML page with CSS selectors:
+//!
+//! ```yaml
+//! id: example
+//! name: Example
+//! search:
+//!   url: https://example.org/search
+//!   params:
+//!     q: "{query}"
+//!     cat: "{categories}"
+//! categories:
+//!   movies: "1"
+//!   tv_shows: "2"
+//! response:
+//!   type: html
+//!   rows: table.results tbody tr
+//!   fields:
+//!     title: td.name a
+//!     magnet: { selector: "a[href^='magnet:']", attribute: href }
+//!     size: td.size
+//!     seeders: td.seeders
+//!     peers: td.leechers
+//!     date: { selector: td.date, filters: [date] }
+//! ```
+//!
+//! Templates can use the placeholders `{query}`, `{category}` (the code of the
+//! first requested category with one), `{categories}` (the codes of every
+//! requested category, comma-separated) and `{limit}`. In a JSON body, the
+//! string "{categories}" is replaced by an array of codes and "{limit}" by a number.
+//!
+//! Extracted values pass through the `filters` of their field. Sizes may be
+//! numbers of bytes or human-readable, dates anything `date::parse_date`
+//! accepts, and relative links are resolved against the search URL. Results
+//! without a magnet link or info hash are skipped.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::{header::CONTENT_TYPE, Request};
+use log::debug;
+use percent_encoding::utf8_percent_encode;
+use scraper::Selector;
+use serde::Deserialize;
+use serde_json::Value;
+use thiserror::Error;
+use url::Url;
+
+use crate::{
+    date::parse_date,
+    search_providers::{
+        html::{resolve_url, HtmlElement, HtmlPage},
+        ENCODED,
+    },
+    size::parse_size,
+    Category, ClientError, Magnet, SearchProvider, SearchRequest, Torrent, TorrentMetadata,
+};
+
+/// Represents errors that can occur while loading definitions.
+#[derive(Error, Debug)]
+pub enum DefinitionError {
+    /// A definition file or directory cannot be read.
+    #[error("cannot read definitions {path}: {source}")]
+    Io {
+        /// The path of the file or directory.
+        path: PathBuf,
+        /// The underlying error.
+        #[source]
+        source: io::Error,
+    },
+
+    /// A definition is not valid YAML or does not match the schema.
+    #[error("invalid yaml definition: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
+    /// A definition is not valid JSON or does not match the schema.
+    #[error("invalid json definition: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// A definition file has an extension other than `.yaml`, `.yml` or `.json`.
+    #[error("unsupported definition file format: {0}")]
+    UnsupportedFormat(PathBuf),
+
+    /// A definition has an invalid URL, JSON pointer or CSS selector.
+    #[error("invalid definition '{id}': {reason}")]
+    Invalid {
+        /// The id of the definition.
+        id: String,
+        /// What is invalid.
+        reason: String,
+    },
+}
+
+/// A provider described as data.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Definition {
+    /// The unique identifier of the provider.
+    pub id: String,
+
+    /// The name reported as the provider of the results, the id if unset.
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// How to send a search.
+    pub search: SearchDefinition,
+
+    /// The code of the site for each supported category.
+    #[serde(default)]
+    pub categories: HashMap<Category, String>,
+
+    /// How to read the response.
+    pub response: ResponseDefinition,
+}
+
+/// How to send a search.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SearchDefinition {
+    /// The search URL template.
+    pub url: String,
+
+    /// The HTTP method.
+    #[serde(default)]
+    pub method: Method,
+
+    /// Query parameter templates by name.
+    #[serde(default)]
+    pub params: BTreeMap<String, String>,
+
+    /// Header templates by name.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+
+    /// The template of a JSON body.
+    #[serde(default)]
+    pub body: Option<Value>,
+}
+
+/// The HTTP method of a search.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Method {
+    /// A GET request.
+    #[default]
+    Get,
+
+    /// A POST request, with the JSON body if any.
+    Post,
+}
+
+/// How to read the response of a search.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum ResponseDefinition {
+    /// A JSON response, with fields as JSON pointers relative to each result.
+    Json {
+        /// The JSON pointer of the array of results, "" for the root.
+        #[serde(default)]
+        results: String,
+
+        /// How to extract the fields of a result.
+        fields: Fields,
+    },
+
+    /// An HTML page, with fields as CSS selectors relative to each row.
+    Html {
+        /// The CSS selector of the result rows.
+        rows: String,
+
+        /// How to extract the fields of a row.
+        fields: Fields,
+    },
+}
+
+impl ResponseDefinition {
+    /// Returns how to extract the fields of a result.
+    fn fields(&self) -> &Fields {
+        match self {
+            ResponseDefinition::Json { fields, .. } | ResponseDefinition::Html { fields, .. } => {
+                fields
+            }
+        }
+    }
+}
+
+/// How to extract the fields of a torrent from a result.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Fields {
+    /// The name of the torrent.
+    pub title: Extractor,
+
+    /// The magnet link.
+    #[serde(default)]
+    pub magnet: Option<Extractor>,
+
+    /// The hex info hash, used to build a magnet link when there is none.
+    #[serde(default)]
+    pub info_hash: Option<Extractor>,
+
+    /// The size, in bytes or human-readable.
+    #[serde(default)]
+    pub size: Option<Extractor>,
+
+    /// The number of seeders.
+    #[serde(default)]
+    pub seeders: Option<Extractor>,
+
+    /// The number of peers that are not seeders.
+    #[serde(default)]
+    pub peers: Option<Extractor>,
+
+    /// The details page of the torrent.
+    #[serde(default)]
+    pub details_url: Option<Extractor>,
+
+    /// The site's own identifier for the torrent.
+    #[serde(default)]
+    pub provider_id: Option<Extractor>,
+
+    /// The name of the uploader.
+    #[serde(default)]
+    pub uploader: Option<Extractor>,
+
+    /// The upload date, as a Unix timestamp or a date.
+    #[serde(default)]
+    pub date: Option<Extractor>,
+}
+
+impl Fields {
+    /// Returns every extractor.
+    fn extractors(&self) -> impl Iterator<Item = &Extractor> {
+        [
+            Some(&self.title),
+            self.magnet.as_ref(),
+            self.info_hash.as_ref(),
+            self.size.as_ref(),
+            self.seeders.as_ref(),
+            self.peers.as_ref(),
+            self.details_url.as_ref(),
+            self.provider_id.as_ref(),
+            self.uploader.as_ref(),
+            self.date.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+/// How to extract a value, given as a path alone or with options.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "ExtractorRepr")]
+pub struct Extractor {
+    /// The JSON pointer or CSS selector of the value, "" for the result itself.
+    pub path: String,
+
+    /// The attribute holding the value in HTML, the text if unset.
+    pub attribute: Option<String>,
+
+    /// The filters applied to the value, in order.
+    pub filters: Vec<Filter>,
+
+    /// The value used when none is found.
+    pub default: Option<String>,
+}
+
+/// The representations of an `Extractor` in definition files.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExtractorRepr {
+    /// A path alone.
+    Path(String),
+
+    /// A path with options.
+    Full {
+        /// The JSON pointer or CSS selector.
+        #[serde(default, alias = "selector")]
+        path: String,
+
+        /// The attribute holding the value in HTML.
+        #[serde(default)]
+        attribute: Option<String>,
+
+        /// The filters applied to the value.
+        #[serde(default)]
+        filters: Vec<Filter>,
+
+        /// The value used when none is found.
+        #[serde(default)]
+        default: Option<String>,
+    },
+}
+
+impl From<ExtractorRepr> for Extractor {
+    fn from(repr: ExtractorRepr) -> Self {
+        match repr {
+            ExtractorRepr::Path(path) => Self {
+                path,
+                attribute: None,
+                filters: Vec::new(),
+                default: None,
+            },
+            ExtractorRepr::Full {
+                path,
+                attribute,
+                filters,
+                default,
+            } => Self {
+                path,
+                attribute,
+                filters,
+                default,
+            },
+        }
+    }
+}
+
+/// A transformation of an extracted value.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Filter {
+    /// Removes surrounding whitespace.
+    Trim,
+
+    /// Converts to lowercase.
+    Lowercase,
+
+    /// Keeps only the digits, e.g. "1,204 seeders" becomes "1204".
+    Digits,
+
+    /// Parses a human-readable size into bytes.
+    Size,
+
+    /// Parses a date into a Unix timestamp.
+    Date,
+
+    /// Adds text before the value.
+    Prepend(String),
+
+    /// Adds text after the value.
+    Append(String),
+
+    /// Replaces every occurrence of a text.
+    Replace {
+        /// The replaced text.
+        from: String,
+        /// The replacement.
+        to: String,
+    },
+
+    /// Splits the value and keeps one part.
+    Split {
+        /// The separator.
+        separator: String,
+        /// The index of the kept part.
+        index: usize,
+    },
+}
+
+impl Filter {
+    /// Applies the filter, or returns `None` if the value cannot be parsed.
+    fn apply(&self, value: String) -> Option<String> {
+        Some(match self {
+            Filter::Trim => value.trim().to_string(),
+            Filter::Lowercase => value.to_lowercase(),
+            Filter::Digits => value.chars().filter(char::is_ascii_digit).collect(),
+            Filter::Size => parse_size(&value).ok()?.to_string(),
+            Filter::Date => parse_date(&value)
+                .ok()?
+                .duration_since(UNIX_EPOCH)
+                .ok()?
+                .as_secs()
+                .to_string(),
+            Filter::Prepend(prefix) => format!("{}{}", prefix, value),
+            Filter::Append(suffix) => format!("{}{}", value, suffix),
+            Filter::Replace { from, to } => value.replace(from, to),
+            Filter::Split { separator, index } => value.split(separator).nth(*index)?.to_string(),
+        })
+    }
+}
+
+impl Definition {
+    /// Loads a definition from a `.yaml`, `.yml` or `.json` file.
+    ///
+    /// # Parameters
+    /// - `path`: The path of the definition file.
+    ///
+    /// # Returns
+    /// - `Ok(Definition)`: The loaded definition.
+    /// - `Err(DefinitionError)`: An error if the file cannot be read or parsed.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, DefinitionError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|source| DefinitionError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("yaml" | "yml") => Self::from_yaml(&content),
+            Some("json") => Self::from_json(&content),
+            _ => Err(DefinitionError::UnsupportedFormat(path.to_path_buf())),
+        }
+    }
+
+    /// Parses a definition from YAML.
+    pub fn from_yaml(content: &str) -> Result<Self, DefinitionError> {
+        Ok(serde_yaml::from_str(content)?)
+    }
+
+    /// Parses a definition from JSON.
+    pub fn from_json(content: &str) -> Result<Self, DefinitionError> {
+        Ok(serde_json::from_str(content)?)
+    }
+}
+
+/// Loads the providers of every `.yaml`, `.yml` and `.json` file in a directory, by file name.
+///
+/// # Parameters
+/// - `dir`: The directory of the definition files.
+///
+/// # Returns
+/// - `Ok(Vec<DefinitionProvider>)`: The providers of the definitions.
+/// - `Err(DefinitionError)`: An error if the directory or a definition cannot be read or is invalid.
+pub fn load_dir(dir: impl AsRef<Path>) -> Result<Vec<DefinitionProvider>, DefinitionError> {
+    let dir = dir.as_ref();
+    let io_error = |source| DefinitionError::Io {
+        path: dir.to_path_buf(),
+        source,
+    };
+
+    let mut paths = fs::read_dir(dir)
+        .map_err(io_error)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(io_error)?;
+    paths.retain(|path| {
+        matches!(
+            path.extension().and_then(|extension| extension.to_str()),
+            Some("yaml" | "yml" | "json")
+        )
+    });
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| {
+            debug!("loading provider definition {}", path.display());
+            DefinitionProvider::new(Definition::from_file(path)?)
+        })
+        .collect()
+}
+
+/// The `DefinitionProvider` searches a site described by a `Definition`.
+pub struct DefinitionProvider {
+    /// The definition of the site.
+    definition: Definition,
+
+    /// The search URL with empty placeholders, relative links are resolved against it.
+    base_url: Url,
+
+    /// The compiled CSS selectors of an HTML definition, by selector.
+    selectors: HashMap<String, Selector>,
+}
+
+impl DefinitionProvider {
+    /// Creates a new `DefinitionProvider`, checking its URL, JSON pointers and CSS selectors.
+    ///
+    /// # Parameters
+    /// - `definition`: The definition of the site.
+    ///
+    /// # Returns
+    /// - `Ok(DefinitionProvider)`: The provider of the definition.
+    /// - `Err(DefinitionError::Invalid)`: An error if the definition is invalid.
+    pub fn new(definition: Definition) -> Result<Self, DefinitionError> {
+        let invalid = |reason: String| DefinitionError::Invalid {
+            id: definition.id.clone(),
+            reason,
+        };
+
+        let base_url = Url::parse(&fill(&definition.search.url, &Placeholders::default()))
+            .map_err(|e| invalid(format!("invalid url: {}", e)))?;
+
+        let mut selectors = HashMap::new();
+        match &definition.response {
+            ResponseDefinition::Json { results, fields } => {
+                let pointers = fields.extractors().map(|extractor| &extractor.path);
+                if let Some(pointer) = [results]
+                    .into_iter()
+                    .chain(pointers)
+                    .find(|pointer| !pointer.is_empty() && !pointer.starts_with('/'))
+                {
+                    return Err(invalid(format!("invalid json pointer '{}'", pointer)));
+                }
+            }
+            ResponseDefinition::Html { rows, fields } => {
+                if rows.is_empty() {
+                    return Err(invalid("empty rows selector".to_string()));
+                }
+                let paths = fields.extractors().map(|extractor| &extractor.path);
+                for selector in [rows].into_iter().chain(paths) {
+                    if selector.is_empty() || selectors.contains_key(selector) {
+                        continue;
+                    }
+                    let compiled = Selector::parse(selector)
+                        .map_err(|e| invalid(format!("invalid selector '{}': {}", selector, e)))?;
+                    selectors.insert(selector.clone(), compiled);
+                }
+            }
+        }
+
+        Ok(Self {
+            definition,
+            base_url,
+            selectors,
+        })
+    }
+
+    /// Returns the values of the placeholders for a request.
+    fn placeholders(&self, request: &SearchRequest<'_>) -> Placeholders {
+        let codes: Vec<String> = request
+            .categories
+            .iter()
+            .filter_map(|category| self.definition.categories.get(category).cloned())
+            .collect();
+
+        Placeholders {
+            query: request.query.to_string(),
+            codes,
+            limit: request.number_of_results,
+        }
+    }
+
+    /// Converts the extracted values of a result into a `Torrent`, or `None` without magnet link.
+    fn torrent(&self, value: impl Fn(&Extractor) -> Option<String>) -> Option<Torrent> {
+        let fields = self.definition.response.fields();
+        let field = |extractor: &Option<Extractor>| extractor.as_ref().and_then(&value);
+        let count = |extractor: &Option<Extractor>| {
+            field(extractor)
+                .and_then(|value| value.replace(',', "").trim().parse::<u64>().ok())
+                .map(|value| u32::try_from(value).unwrap_or(u32::MAX))
+                .unwrap_or(0)
+        };
+        let link = |extractor: &Option<Extractor>| {
+            field(extractor).and_then(|link| resolve_url(&self.base_url, &link))
+        };
+
+        let name = value(&fields.title).filter(|name| !name.is_empty())?;
+        let magnet_link = field(&fields.magnet)
+            .filter(|link| link.starts_with("magnet:"))
+            .or_else(|| {
+                let info_hash: [u8; 20] = hex::decode(field(&fields.info_hash)?.trim())
+                    .ok()?
+                    .try_into()
+                    .ok()?;
+                let mut magnet = Magnet::from_info_hash(info_hash);
+                magnet.display_name = Some(name.clone());
+                Some(magnet.to_string())
+            })?;
+        let size_bytes = field(&fields.size)
+            .and_then(|size| size.trim().parse().ok().or_else(|| parse_size(&size).ok()))
+            .unwrap_or(0);
+        let uploaded_at = field(&fields.date).and_then(|date| match date.trim().parse::<u64>() {
+            Ok(seconds) => UNIX_EPOCH.checked_add(Duration::from_secs(seconds)),
+            Err(_) => parse_date(&date).ok(),
+        });
+
+        Some(Torrent {
+            name,
+            magnet_link,
+            seeders: count(&fields.seeders),
+            peers: count(&fields.peers),
+            size_bytes,
+            provider: self
+                .definition
+                .name
+                .clone()
+                .unwrap_or_else(|| self.definition.id.clone()),
+            metadata: TorrentMetadata {
+                uploader: field(&fields.uploader),
+                provider_id: field(&fields.provider_id),
+                details_url: link(&fields.details_url),
+                uploaded_at,
+                ..Default::default()
+            },
+            stats_updated_at: None,
+        })
+    }
+}
+
+#[async_trait]
+impl SearchProvider for DefinitionProvider {
+    /// Builds the search request from the templates of the definition.
+    ///
+    /// # Parameters
+    /// - `request`: The `SearchRequest` containing query parameters.
+    ///
+    /// # Returns
+    /// - `Ok(Request<Bytes>)`: The constructed HTTP request.
+    /// - `Err(ClientError)`: An error if request building fails.
+    fn build_request(&self, request: SearchRequest<'_>) -> Result<Request<Bytes>, ClientError> {
+        let search = &self.definition.search;
+        let placeholders = self.placeholders(&request);
+        let url = fill(&search.url, &placeholders);
+        let build_error = |source: anyhow::Error| ClientError::RequestBuildError {
+            source,
+            url: url.clone(),
+        };
+
+        let mut parsed_url = Url::parse(&url).map_err(|e| build_error(e.into()))?;
+        if !search.params.is_empty() {
+            parsed_url.query_pairs_mut().extend_pairs(
+                search
+                    .params
+                    .iter()
+                    .map(|(name, value)| (name, substitute(value, &placeholders))),
+            );
+        }
+
+        let mut builder = match search.method {
+            Method::Get => Request::get(parsed_url.as_str()),
+            Method::Post => Request::post(parsed_url.as_str()),
+        };
+        for (name, value) in &search.headers {
+            builder = builder.header(name, substitute(value, &placeholders));
+        }
+        let body = match &search.body {
+            Some(body) => {
+                builder = builder.header(CONTENT_TYPE, "application/json");
+                Bytes::from(fill_json(body, &placeholders).to_string())
+            }
+            None => Bytes::new(),
+        };
+
+        builder.body(body).map_err(|e| build_error(e.into()))
+    }
+
+    /// Extracts torrents from the response as the definition describes.
+    ///
+    /// # Parameters
+    /// - `response`: The raw response body as a string.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Torrent>)`: A list of parsed torrent metadata.
+    /// - `Err(ClientError)`: An error if a JSON response is invalid or has no results array.
+    fn parse_response(&self, response: &str) -> Result<Vec<Torrent>, ClientError> {
+        let torrents: Vec<Torrent> = match &self.definition.response {
+            ResponseDefinition::Json { results, .. } => {
+                let json: Value = serde_json::from_str(response)
+                    .map_err(|e| ClientError::DataParseError(e.into()))?;
+                let results = json
+                    .pointer(results)
+                    .and_then(Value::as_array)
+                    .ok_or_else(|| {
+                        ClientError::DataParseError(anyhow!(
+                            "response has no results array at '{}'",
+                            results
+                        ))
+                    })?;
+
+                results
+                    .iter()
+                    .filter_map(|result| self.torrent(|extractor| json_value(result, extractor)))
+                    .collect()
+            }
+            ResponseDefinition::Html { rows, .. } => {
+                let rows = self.selectors.get(rows).ok_or_else(|| {
+                    ClientError::DataParseError(anyhow!("no rows selector '{}'", rows))
+                })?;
+                let page = HtmlPage::parse(response, self.base_url.as_str())?;
+                page.rows(rows)
+                    .filter_map(|row| {
+                        self.torrent(|extractor| html_value(&self.selectors, row, extractor))
+                    })
+                    .collect()
+            }
+        };
+
+        debug!(
+            "definition '{}' extracted {} torrents",
+            self.definition.id,
+            torrents.len()
+        );
+        Ok(torrents)
+    }
+
+    /// Returns the unique identifier for this provider.
+    ///
+    /// # Returns
+    /// - `String`: The id of the definition.
+    fn id(&self) -> String {
+        self.definition.id.clone()
+    }
+}
+
+/// The values substituted into templates.
+#[derive(Debug, Default)]
+struct Placeholders {
+    /// The query string.
+    query: String,
+
+    /// The codes of the requested categories.
+    codes: Vec<String>,
+
+    /// The number of results.
+    limit: usize,
+}
+
+/// Substitutes the raw values of placeholders into a template.
+fn substitute(template: &str, placeholders: &Placeholders) -> String {
+    template
+        .replace("{query}", &placeholders.query)
+        .replace(
+            "{category}",
+            placeholders.codes.first().map_or("", String::as_str),
+        )
+        .replace("{categories}", &placeholders.codes.join(","))
+        .replace("{limit}", &placeholders.limit.to_string())
+}
+
+/// Substitutes percent-encoded placeholders into a URL template.
+fn fill(template: &str, placeholders: &Placeholders) -> String {
+    let encode = |value: &str| utf8_percent_encode(value, ENCODED).to_string();
+    substitute(
+        template,
+        &Placeholders {
+            query: encode(&placeholders.query),
+            codes: placeholders.codes.iter().map(|code| encode(code)).collect(),
+            limit: placeholders.limit,
+        },
+    )
+}
+
+/// Substitutes placeholders into a JSON body template.
+fn fill_json(template: &Value, placeholders: &Placeholders) -> Value {
+    match template {
+        Value::String(text) if text == "{categories}" => Value::Array(
+            placeholders
+                .codes
+                .iter()
+                .map(|code| match code.parse::<u64>() {
+                    Ok(number) => Value::from(number),
+                    Err(_) => Value::from(code.as_str()),
+                })
+                .collect(),
+        ),
+        Value::String(text) if text == "{limit}" => Value::from(placeholders.limit),
+        Value::String(text) => Value::String(substitute(text, placeholders)),
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| fill_json(value, placeholders))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), fill_json(value, placeholders)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Extracts and filters a value from a JSON result.
+fn json_value(result: &Value, extractor: &Extractor) -> Option<String> {
+    let value = match result.pointer(&extractor.path) {
+        Some(Value::String(text)) => Some(text.clone()),
+        Some(Value::Null) | None => None,
+        Some(value) => Some(value.to_string()),
+    };
+    filtered(value, extractor)
+}
+
+/// Extracts and filters a value from an HTML row.
+fn html_value(
+    selectors: &HashMap<String, Selector>,
+    row: HtmlElement<'_>,
+    extractor: &Extractor,
+) -> Option<String> {
+    let element = match selectors.get(&extractor.path) {
+        Some(selector) => row.find(selector),
+        None => Some(row),
+    };
+    let value = element.and_then(|element| match &extractor.attribute {
+        Some(attribute) => element.attr(attribute).map(str::to_string),
+        None => Some(element.text()),
+    });
+    filtered(value, extractor)
+}
+
+/// Applies the filters of the extractor, falling back to its default.
+fn filtered(value: Option<String>, extractor: &Extractor) -> Option<String> {
+    value
+        .and_then(|value| {
+            extractor
+                .filters
+                .iter()
+                .try_fold(value, |value, filter| filter.apply(value))
+        })
+        .filter(|value| !value.is_empty())
+        .or_else(|| extractor.default.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testkit::ConformanceSuite, Magneto};
+    use assert_matches::assert_matches;
+
+    const JSON_DEFINITION: &str = r#"
+        id: knaben-like
+        name: Knaben
+        search:
+          url: https://api.example.org/v1
+          method: post
+          body:
+            query: "{query}"
+            categories: "{categories}"
+            size: "{limit}"
+        categories:
+          movies: "3000000"
+          tv_shows: "2000000"
+        response:
+          type: json
+          results: /hits
+          fields:
+            title: /title
+            info_hash: /hash
+            size: /bytes
+            seeders: /seeders
+            peers: /peers
+            details_url: /details
+            date: { path: /date, filters: [date] }
+    "#;
+
+    const HTML_DEFINITION: &str = r#"{
+        "id": "html-site",
+        "search": {
+            "url": "https://site.example/search/{query}/",
+            "params": {"cat": "{category}"}
+        },
+        "categories": {"anime": "1_2"},
+        "response": {
+            "type": "html",
+            "rows": "table.results tbody tr",
+            "fields": {
+                "title": "td.name a",
+                "magnet": {"selector": "a.magnet", "attribute": "href"},
+                "size": "td.size",
+                "seeders": {"selector": "td.seeds", "filters": ["digits"]},
+                "peers": {"selector": "td.leeches", "default": "0"},
+                "details_url": {"selector": "td.name a", "attribute": "href"},
+                "uploader": {"selector": "td.user", "filters": ["lowercase", {"split": {"separator": " ", "index": 0}}]},
+                "date": {"selector": "td.date", "filters": [{"replace": {"from": "/", "to": "-"}}]}
+            }
+        }
+    }"#;
+
+    const HTML_PAGE: &str = r#"<html><body><table class="results"><tbody>
+        <tr>
+          <td class="name"><a href="/view/1">Frieren   - 01 <b>[1080p]</b></a></td>
+          <td><a class="magnet" href="magnet:?xt=urn:btih:abc&amp;dn=frieren">magnet</a></td>
+          <td class="size">1.4 GiB</td>
+          <td class="seeds">1,204 seeds</td>
+          <td class="user">SubsPlease (trusted)</td>
+          <td class="date">2024/04/25</td>
+        </tr>
+        <tr><td class="name"><a href="/view/2">No magnet</a></td></tr>
+    </tbody></table></body></html>"#;
+
+    /// Tests a JSON definition with a POST body and category table.
+    #[test]
+    fn test_json_definition() {
+        let provider =
+            DefinitionProvider::new(Definition::from_yaml(JSON_DEFINITION).unwrap()).unwrap();
+        assert_eq!(provider.id(), "knaben-like");
+
+        let request = SearchRequest::new("ubuntu").add_categories(vec![
+            Category::Movies,
+            Category::Software,
+            Category::TvShows,
+        ]);
+        let request = provider.build_request(request).unwrap();
+        assert_eq!(request.method(), http::Method::POST);
+        let body: Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({"query": "ubuntu", "categories": [3000000, 2000000], "size": 50})
+        );
+
+        let torrents = provider
+            .parse_response(
+                r#"{"hits": [
+                    {"title": "Ubuntu 24.04", "hash": "0123456789abcdef0123456789abcdef01234567",
+                     "bytes": 5000, "seeders": 10, "peers": 2, "details": "/torrent/1",
+                     "date": "2024-04-25T15:02:11+00:00"},
+                    {"title": "Without hash", "hash": null}
+                ]}"#,
+            )
+            .unwrap();
+        assert_eq!(torrents.len(), 1);
+        let torrent = &torrents[0];
+        assert_eq!(torrent.provider, "Knaben");
+        assert_eq!(
+            (torrent.seeders, torrent.peers, torrent.size_bytes),
+            (10, 2, 5000)
+        );
+        assert!(torrent.magnet_link.contains("0123456789abcdef"));
+        assert_eq!(
+            torrent.metadata.details_url.as_deref(),
+            Some("https://api.example.org/torrent/1")
+        );
+        assert_eq!(
+            torrent.metadata.uploaded_at,
+            Some(UNIX_EPOCH + Duration::from_secs(1714057331))
+        );
+
+        assert_matches!(
+            provider.parse_response(r#"{"data": []}"#),
+            Err(ClientError::DataParseError(_))
+        );
+
+        // Timestamps out of range are dropped
+        let definition = JSON_DEFINITION.replace("{ path: /date, filters: [date] }", "/date");
+        let provider =
+            DefinitionProvider::new(Definition::from_yaml(&definition).unwrap()).unwrap();
+        let torrents = provider
+            .parse_response(
+                r#"{"hits": [{"title": "Far future", "hash": "0123456789abcdef0123456789abcdef01234567",
+                     "date": "18446744073709551615"}]}"#,
+            )
+            .unwrap();
+        assert_eq!(torrents[0].metadata.uploaded_at, None);
+    }
+
+    /// Tests an HTML definition with selectors, attributes, filters and defaults.
+    #[test]
+    fn test_html_definition() {
+        let provider =
+            DefinitionProvider::new(Definition::from_json(HTML_DEFINITION).unwrap()).unwrap();
+
+        let request = provider
+            .build_request(SearchRequest::new("sousou no").add_category(Category::Anime))
+            .unwrap();
+        assert_eq!(
+            request.uri().to_string(),
+            "https://site.example/search/sousou%20no/?cat=1_2"
+        );
+
+        let torrents = provider.parse_response(HTML_PAGE).unwrap();
+        assert_eq!(torrents.len(), 1);
+        let torrent = &torrents[0];
+        assert_eq!(torrent.name, "Frieren - 01 [1080p]");
+        assert_eq!(torrent.magnet_link, "magnet:?xt=urn:btih:abc&dn=frieren");
+        assert_eq!((torrent.seeders, torrent.peers), (1204, 0));
+        assert_eq!(torrent.size_bytes, 1503238554);
+        assert_eq!(torrent.provider, "html-site");
+        assert_eq!(torrent.metadata.uploader.as_deref(), Some("subsplease"));
+        assert_eq!(
+            torrent.metadata.details_url.as_deref(),
+            Some("https://site.example/view/1")
+        );
+        assert_eq!(
+            torrent.metadata.uploaded_at,
+            Some(UNIX_EPOCH + Duration::from_secs(1714003200))
+        );
+    }
+
+    /// Tests that invalid definitions are rejected and directories are loaded.
+    #[test]
+    fn test_load_definitions() {
+        let invalid = HTML_DEFINITION.replace("td.size", "td..size");
+        assert_matches!(
+            DefinitionProvider::new(Definition::from_json(&invalid).unwrap()).err(),
+            Some(DefinitionError::Invalid { id, .. }) if id == "html-site"
+        );
+        let invalid = HTML_DEFINITION.replace("table.results tbody tr", "");
+        assert_matches!(
+            DefinitionProvider::new(Definition::from_json(&invalid).unwrap()).err(),
+            Some(DefinitionError::Invalid { reason, .. }) if reason.contains("rows")
+        );
+        let invalid = JSON_DEFINITION.replace("/title", "title");
+        assert_matches!(
+            DefinitionProvider::new(Definition::from_yaml(&invalid).unwrap()).err(),
+            Some(DefinitionError::Invalid { .. })
+        );
+        assert_matches!(
+            Definition::from_yaml("id: incomplete"),
+            Err(DefinitionError::Yaml(_))
+        );
+
+        let dir = std::env::temp_dir().join(format!("magneto-definitions-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.yaml"), JSON_DEFINITION).unwrap();
+        fs::write(dir.join("b.json"), HTML_DEFINITION).unwrap();
+        fs::write(dir.join("README.md"), "ignored").unwrap();
+
+        let magneto = Magneto::default().load_definitions(&dir).unwrap();
+        let ids: Vec<String> = magneto.active_providers.iter().map(|p| p.id()).collect();
+        assert_eq!(ids, vec!["knaben-like", "html-site"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert_matches!(
+            Magneto::default().load_definitions(&dir).err(),
+            Some(DefinitionError::Io { .. })
+        );
+    }
+
+    /// Tests that definitions pass the conformance suite.
+    #[tokio::test]
+    async fn test_conformance() {
+        ConformanceSuite::new(|url| {
+            let definition = HTML_DEFINITION.replace("https://site.example", &url);
+            DefinitionProvider::new(Definition::from_json(&definition).unwrap()).unwrap()
+        })
+        .with_sample_response(HTML_PAGE)
+        .run()
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,193 @@
+//! # DHT index
+//!
+//! An in-memory index of torrents discovered on the DHT, searchable by name.
+//! The index holds at most `DEFAULT_MAX_ENTRIES` torrents unless configured
+//! otherwise, dropping the torrents with the fewest peers first.
+
+use std::collections::HashMap;
+
+use crate::{metainfo::Metainfo, Torrent};
+
+/// The maximum number of torrents in an index by default.
+pub const DEFAULT_MAX_ENTRIES: usize = 100_000;
+
+/// A torrent discovered on the DHT.
+#[derive(Debug, Clone)]
+pub struct IndexedTorrent {
+    /// The metadata downloaded from peers.
+    pub metainfo: Metainfo,
+
+    /// The number of peers found for the torrent when it was indexed.
+    pub peers: u32,
+}
+
+/// An in-memory index of torrents keyed by v1 info hash.
+#[derive(Debug, Clone)]
+pub struct DhtIndex {
+    /// The indexed torrents.
+    entries: HashMap<[u8; 20], IndexedTorrent>,
+
+    /// The maximum number of indexed torrents.
+    max_entries: usize,
+}
+
+impl DhtIndex {
+    /// Creates an empty index holding at most `DEFAULT_MAX_ENTRIES` torrents.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of indexed torrents.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    /// Adds a torrent to the index, replacing an existing entry for the same info hash.
+    ///
+    /// When the index is full, the torrent with the fewest peers is dropped,
+    /// unless the new torrent has fewer peers still.
+    pub fn insert(&mut self, info_hash: [u8; 20], torrent: IndexedTorrent) {
+        if !self.entries.contains_key(&info_hash) && self.entries.len() >= self.max_entries {
+            let fewest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.peers)
+                .map(|(info_hash, entry)| (*info_hash, entry.peers));
+            match fewest {
+                Some((evicted, peers)) if peers <= torrent.peers => {
+                    self.entries.remove(&evicted);
+                }
+                _ => return,
+            }
+        }
+        self.entries.insert(info_hash, torrent);
+    }
+
+    /// Returns whether the info hash is indexed.
+    pub fn contains(&self, info_hash: &[u8; 20]) -> bool {
+        self.entries.contains_key(info_hash)
+    }
+
+    /// Returns the number of indexed torrents.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Searches the index for torrents whose name contains every word of the query.
+    ///
+    /// Matching is case-insensitive and ignores separators such as dots and
+    /// underscores. Results are ordered by the number of peers, most first.
+    ///
+    /// # Parameters
+    /// - `query`: The search query.
+    /// - `limit`: The maximum number of results.
+    ///
+    /// # Returns
+    /// - `Vec<Torrent>`: The matching torrents, with `provider` set to "dht" and
+    ///   `seeders` set to the number of peers found when indexing.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<Torrent> {
+        let words = normalize(query);
+        let words: Vec<&str> = words.split_whitespace().collect();
+
+        let mut results: Vec<&IndexedTorrent> = self
+            .entries
+            .values()
+            .filter(|entry| {
+                let name = normalize(&entry.metainfo.name);
+                words.iter().all(|word| name.contains(word))
+            })
+            .collect();
+        results.sort_by(|a, b| {
+            b.peers
+                .cmp(&a.peers)
+                .then_with(|| a.metainfo.name.cmp(&b.metainfo.name))
+        });
+
+        results
+            .into_iter()
+            .take(limit)
+            .map(|entry| Torrent {
+                seeders: entry.peers,
+                provider: "dht".to_string(),
+                ..entry.metainfo.to_torrent()
+            })
+            .collect()
+    }
+}
+
+impl Default for DhtIndex {
+    /// Provides a default implementation for `DhtIndex`, an empty index
+    /// holding at most `DEFAULT_MAX_ENTRIES` torrents.
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            max_entries: DEFAULT_MAX_ENTRIES,
+        }
+    }
+}
+
+/// Lowercases text and replaces separators with spaces.
+fn normalize(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                ' '
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metainfo::MetainfoFile;
+
+    /// Builds a single-file torrent with the given name and number of peers.
+    fn torrent(name: &str, peers: u32) -> IndexedTorrent {
+        IndexedTorrent {
+            metainfo: Metainfo {
+                name: name.to_string(),
+                piece_length: 16384,
+                files: vec![MetainfoFile {
+                    path: vec![name.to_string()],
+                    length: 1,
+                }],
+                announce_list: Vec::new(),
+                info_hash: Some([peers as u8; 20]),
+                info_hash_v2: None,
+                private: false,
+                web_seeds: Vec::new(),
+            },
+            peers,
+        }
+    }
+
+    /// Tests that a full index drops the torrents with the fewest peers.
+    #[test]
+    fn test_max_entries() {
+        let mut index = DhtIndex::new().with_max_entries(2);
+        index.insert([1; 20], torrent("debian one", 1));
+        index.insert([5; 20], torrent("debian five", 5));
+        index.insert([3; 20], torrent("debian three", 3));
+        assert_eq!(index.len(), 2);
+        assert!(!index.contains(&[1; 20]));
+
+        index.insert([2; 20], torrent("debian two", 2));
+        assert!(!index.contains(&[2; 20]));
+
+        let names: Vec<String> = index
+            .search("debian", 10)
+            .into_iter()
+            .map(|torrent| torrent.name)
+            .collect();
+        assert_eq!(names, vec!["debian five", "debian three"]);
+    }
+}
@@ -0,0 +1,133 @@
+//! # Mainline DHT
+//!
+//! A minimal node of the BitTorrent mainline DHT (BEP 5) supporting infohash
+//! sampling (BEP 51). The node answers queries from other nodes, looks up
+//! peers for info hashes, and samples the info hashes stored by other nodes,
+//! which the `Dht` search provider uses to crawl and index torrents.
+//!
+//! Only IPv4 is supported.
+
+use core::fmt;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+
+use thiserror::Error;
+
+use crate::bencode::BencodeError;
+
+pub mod index;
+pub mod node;
+pub mod routing;
+
+pub use index::DhtIndex;
+pub use node::DhtNode;
+pub use routing::RoutingTable;
+
+/// Represents errors that can occur while talking to DHT nodes.
+#[derive(Error, Debug)]
+pub enum DhtError {
+    /// A network error occurred.
+    #[error("dht i/o error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The node did not respond in time.
+    #[error("dht query timed out")]
+    Timeout,
+
+    /// A message is not valid bencode.
+    #[error("invalid dht message: {0}")]
+    Bencode(#[from] BencodeError),
+
+    /// A message is missing fields or has invalid values.
+    #[error("malformed dht message: {0}")]
+    InvalidMessage(String),
+
+    /// The node answered with a KRPC error.
+    #[error("dht node returned error {code}: {message}")]
+    Remote {
+        /// The KRPC error code, e.g. 203 for a protocol error.
+        code: i64,
+        /// The error message.
+        message: String,
+    },
+
+    /// The address is not an IPv4 address.
+    #[error("unsupported address {0}, only ipv4 is supported")]
+    UnsupportedAddress(SocketAddr),
+}
+
+/// A 160 bit DHT node id, in the same space as info hashes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(pub [u8; 20]);
+
+impl NodeId {
+    /// Generates a random node id.
+    pub fn random() -> Self {
+        Self(rand::random())
+    }
+
+    /// Returns the XOR distance to another id.
+    pub fn distance(&self, other: &NodeId) -> [u8; 20] {
+        let mut distance = [0u8; 20];
+        for (i, byte) in distance.iter_mut().enumerate() {
+            *byte = self.0[i] ^ other.0[i];
+        }
+        distance
+    }
+}
+
+impl fmt::Display for NodeId {
+    /// Formats the id as lowercase hex.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+/// The contact information of a DHT node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeInfo {
+    /// The node's id.
+    pub id: NodeId,
+
+    /// The node's UDP address.
+    pub address: SocketAddrV4,
+}
+
+impl NodeInfo {
+    /// The length of a node in compact encoding: a 20 byte id, 4 byte IP and 2 byte port.
+    pub const COMPACT_SIZE: usize = 26;
+
+    /// Encodes the node in compact node info format.
+    pub fn to_compact(&self) -> [u8; Self::COMPACT_SIZE] {
+        let mut compact = [0u8; Self::COMPACT_SIZE];
+        compact[..20].copy_from_slice(&self.id.0);
+        compact[20..].copy_from_slice(&encode_compact_address(&self.address));
+        compact
+    }
+
+    /// Parses a list of nodes in compact node info format, ignoring trailing bytes.
+    pub fn parse_compact_list(compact: &[u8]) -> Vec<NodeInfo> {
+        compact
+            .chunks_exact(Self::COMPACT_SIZE)
+            .map(|entry| NodeInfo {
+                id: NodeId(entry[..20].try_into().unwrap_or_default()),
+                address: parse_compact_address(&entry[20..]),
+            })
+            .collect()
+    }
+}
+
+/// Encodes an address in 6 byte compact peer format.
+pub(crate) fn encode_compact_address(address: &SocketAddrV4) -> [u8; 6] {
+    let mut compact = [0u8; 6];
+    compact[..4].copy_from_slice(&address.ip().octets());
+    compact[4..].copy_from_slice(&address.port().to_be_bytes());
+    compact
+}
+
+/// Parses a 6 byte compact peer address.
+pub(crate) fn parse_compact_address(compact: &[u8]) -> SocketAddrV4 {
+    SocketAddrV4::new(
+        Ipv4Addr::new(compact[0], compact[1], compact[2], compact[3]),
+        u16::from_be_bytes([compact[4], compact[5]]),
+    )
+}
@@ -0,0 +1,993 @@
+//! # DHT node
+//!
+//! A DHT node speaking KRPC over UDP. The node answers `ping`, `find_node`,
+//! `get_peers`, `announce_peer` and `sample_infohashes` queries from other
+//! nodes and sends the same queries itself.
+//!
+//! Peers announced by other nodes expire after `PEER_TTL`, and at most
+//! `MAX_INFO_HASHES` info hashes with `MAX_PEERS_PER_HASH` peers each are stored.
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::{SocketAddr, SocketAddrV4},
+    sync::{
+        atomic::{AtomicU16, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::{Duration, Instant},
+};
+
+use log::debug;
+use sha1::{Digest, Sha1};
+use tokio::{
+    net::UdpSocket,
+    sync::oneshot,
+    task::{JoinHandle, JoinSet},
+    time::timeout,
+};
+
+use super::{
+    encode_compact_address, parse_compact_address, routing::K, DhtError, NodeId, NodeInfo,
+    RoutingTable,
+};
+use crate::bencode::{self, Value};
+
+/// The number of queries sent in parallel during iterative lookups.
+const ALPHA: usize = 3;
+
+/// The maximum number of info hashes returned in a `sample_infohashes` response.
+const MAX_SAMPLES: usize = 20;
+
+/// The interval in seconds other nodes are asked to wait between samples of this node.
+const SAMPLE_INTERVAL: i64 = 6 * 60 * 60;
+
+/// The maximum number of info hashes with announced peers stored.
+pub const MAX_INFO_HASHES: usize = 10_000;
+
+/// The maximum number of announced peers stored per info hash.
+pub const MAX_PEERS_PER_HASH: usize = 100;
+
+/// How long an announced peer is stored without announcing again, as suggested by BEP 5.
+pub const PEER_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// The largest UDP datagram read from other nodes.
+const MAX_DATAGRAM_SIZE: usize = 65_536;
+
+/// KRPC error code for malformed queries.
+const ERROR_PROTOCOL: i64 = 203;
+
+/// KRPC error code for unknown methods.
+const ERROR_METHOD_UNKNOWN: i64 = 204;
+
+/// A pending query waiting for its response, with the address it was sent to.
+type PendingQuery = (
+    SocketAddr,
+    oneshot::Sender<Result<Value<'static>, DhtError>>,
+);
+
+/// Peers announced for an info hash, with the time of their last announce.
+type AnnouncedPeers = Vec<(SocketAddrV4, Instant)>;
+
+/// The response to a `get_peers` query.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GetPeers {
+    /// Peers for the info hash known to the node.
+    pub peers: Vec<SocketAddrV4>,
+
+    /// Nodes closer to the info hash, returned when the node knows no peers.
+    pub nodes: Vec<NodeInfo>,
+
+    /// The token required to announce to the node.
+    pub token: Option<Vec<u8>>,
+}
+
+/// The response to a `sample_infohashes` query (BEP 51).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Samples {
+    /// A sample of the info hashes stored by the node.
+    pub samples: Vec<[u8; 20]>,
+
+    /// Nodes closer to the requested target.
+    pub nodes: Vec<NodeInfo>,
+
+    /// The total number of info hashes stored by the node.
+    pub num: u32,
+
+    /// The number of seconds to wait before sampling the node again.
+    pub interval: u32,
+}
+
+/// A DHT node.
+///
+/// Cloning a `DhtNode` returns a handle to the same node. The node stops
+/// answering queries when the last handle is dropped.
+#[derive(Debug, Clone)]
+pub struct DhtNode {
+    /// The shared state of the node.
+    inner: Arc<Inner>,
+}
+
+/// The shared state of a `DhtNode`.
+#[derive(Debug)]
+struct Inner {
+    /// The socket used for all KRPC messages.
+    socket: Arc<UdpSocket>,
+
+    /// The node's id.
+    id: NodeId,
+
+    /// The timeout for a single query.
+    timeout: Duration,
+
+    /// Known nodes.
+    routing: Mutex<RoutingTable>,
+
+    /// Peers announced to this node, by info hash, oldest announce first.
+    peers: Mutex<HashMap<[u8; 20], AnnouncedPeers>>,
+
+    /// The nodes of the last bootstrap, used to join again when the routing table empties.
+    bootstrap: Mutex<Vec<SocketAddr>>,
+
+    /// Queries waiting for a response, by transaction id.
+    pending: Mutex<HashMap<u16, PendingQuery>>,
+
+    /// The next transaction id.
+    next_transaction: AtomicU16,
+
+    /// The secret used to derive announce tokens.
+    secret: [u8; 16],
+
+    /// The task receiving messages.
+    receiver: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if let Some(receiver) = self.receiver.get_mut().ok().and_then(Option::take) {
+            receiver.abort();
+        }
+    }
+}
+
+impl DhtNode {
+    /// Binds a new node with a random id to a local IPv4 address.
+    ///
+    /// # Parameters
+    /// - `address`: The local address, e.g. "0.0.0.0:6881".
+    /// - `query_timeout`: The timeout for a single query to another node.
+    ///
+    /// # Returns
+    /// - `Ok(DhtNode)`: The running node.
+    /// - `Err(DhtError)`: An error if the address is not IPv4 or cannot be bound.
+    pub async fn bind(address: SocketAddr, query_timeout: Duration) -> Result<Self, DhtError> {
+        if !address.is_ipv4() {
+            return Err(DhtError::UnsupportedAddress(address));
+        }
+
+        let socket = Arc::new(UdpSocket::bind(address).await?);
+        let id = NodeId::random();
+        let inner = Arc::new(Inner {
+            socket: socket.clone(),
+            id,
+            timeout: query_timeout,
+            routing: Mutex::new(RoutingTable::new(id)),
+            peers: Mutex::new(HashMap::new()),
+            bootstrap: Mutex::new(Vec::new()),
+            pending: Mutex::new(HashMap::new()),
+            next_transaction: AtomicU16::new(rand::random()),
+            secret: rand::random(),
+            receiver: Mutex::new(None),
+        });
+
+        let receiver = tokio::spawn(receive_loop(socket, Arc::downgrade(&inner)));
+        *inner.receiver.lock().unwrap_or_else(|e| e.into_inner()) = Some(receiver);
+
+        Ok(Self { inner })
+    }
+
+    /// Returns the node's id.
+    pub fn id(&self) -> NodeId {
+        self.inner.id
+    }
+
+    /// Returns the local address of the node.
+    pub fn local_addr(&self) -> Result<SocketAddr, DhtError> {
+        Ok(self.inner.socket.local_addr()?)
+    }
+
+    /// Returns all nodes in the routing table.
+    pub fn nodes(&self) -> Vec<NodeInfo> {
+        self.inner.routing().nodes()
+    }
+
+    /// Adds a node to the routing table, e.g. one returned by another node.
+    ///
+    /// # Returns
+    /// - `bool`: Whether the node is in the routing table after the call.
+    pub fn add_node(&self, node: NodeInfo) -> bool {
+        self.inner.routing().insert(node)
+    }
+
+    /// Stores a peer for an info hash, as if it had been announced to this node.
+    pub fn add_peer(&self, info_hash: [u8; 20], peer: SocketAddrV4) {
+        self.inner.add_peer(info_hash, peer, Instant::now());
+    }
+
+    /// Joins the DHT by looking up our own id through the given nodes.
+    ///
+    /// # Parameters
+    /// - `bootstrap`: Addresses of known nodes, e.g. "router.bittorrent.com:6881" resolved.
+    ///
+    /// # Returns
+    /// - `usize`: The number of nodes in the routing table afterwards.
+    pub async fn bootstrap(&self, bootstrap: &[SocketAddr]) -> usize {
+        *self
+            .inner
+            .bootstrap
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = bootstrap.to_vec();
+        for address in bootstrap {
+            if let Err(e) = self.ping(*address).await {
+                debug!("bootstrap node {} failed: {}", address, e);
+            }
+        }
+        self.lookup(self.id(), false).await;
+        self.inner.routing().len()
+    }
+
+    /// Joins the DHT again through the nodes of the last bootstrap when the
+    /// routing table is empty, e.g. after every known node stopped responding.
+    ///
+    /// # Returns
+    /// - `usize`: The number of nodes in the routing table afterwards.
+    pub async fn rejoin_if_empty(&self) -> usize {
+        if !self.inner.routing().is_empty() {
+            return self.inner.routing().len();
+        }
+        let bootstrap = self
+            .inner
+            .bootstrap
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        debug!("routing table empty, bootstrapping again");
+        self.bootstrap(&bootstrap).await
+    }
+
+    /// Sends a `ping` query.
+    ///
+    /// # Returns
+    /// - `Ok(NodeId)`: The id of the responding node.
+    pub async fn ping(&self, address: SocketAddr) -> Result<NodeId, DhtError> {
+        let response = self.query(address, "ping", Value::dict()).await?;
+        response_id(&response)
+    }
+
+    /// Sends a `find_node` query.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<NodeInfo>)`: The nodes closest to the target known to the queried node.
+    pub async fn find_node(
+        &self,
+        address: SocketAddr,
+        target: NodeId,
+    ) -> Result<Vec<NodeInfo>, DhtError> {
+        let arguments = Value::dict().with("target", Value::bytes(target.0));
+        let response = self.query(address, "find_node", arguments).await?;
+        Ok(response_nodes(&response))
+    }
+
+    /// Sends a `get_peers` query.
+    ///
+    /// # Returns
+    /// - `Ok(GetPeers)`: The peers or closer nodes, and the announce token.
+    pub async fn get_peers(
+        &self,
+        address: SocketAddr,
+        info_hash: [u8; 20],
+    ) -> Result<GetPeers, DhtError> {
+        let arguments = Value::dict().with("info_hash", Value::bytes(info_hash));
+        let response = self.query(address, "get_peers", arguments).await?;
+
+        let peers = response
+            .get("values")
+            .and_then(Value::as_list)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(Value::as_bytes)
+            .filter(|peer| peer.len() == 6)
+            .map(parse_compact_address)
+            .collect();
+
+        Ok(GetPeers {
+            peers,
+            nodes: response_nodes(&response),
+            token: response
+                .get("token")
+                .and_then(Value::as_bytes)
+                .map(<[u8]>::to_vec),
+        })
+    }
+
+    /// Sends an `announce_peer` query, announcing that we are a peer on `port`.
+    ///
+    /// # Parameters
+    /// - `token`: The token from a previous `get_peers` response of the same node.
+    pub async fn announce_peer(
+        &self,
+        address: SocketAddr,
+        info_hash: [u8; 20],
+        port: u16,
+        token: &[u8],
+    ) -> Result<(), DhtError> {
+        let arguments = Value::dict()
+            .with("info_hash", Value::bytes(info_hash))
+            .with("port", Value::Integer(port.into()))
+            .with("token", Value::bytes(token));
+        self.query(address, "announce_peer", arguments).await?;
+        Ok(())
+    }
+
+    /// Sends a `sample_infohashes` query (BEP 51).
+    ///
+    /// # Returns
+    /// - `Ok(Samples)`: A sample of the node's info hashes and nodes close to the target.
+    pub async fn sample_infohashes(
+        &self,
+        address: SocketAddr,
+        target: NodeId,
+    ) -> Result<Samples, DhtError> {
+        let arguments = Value::dict().with("target", Value::bytes(target.0));
+        let response = self.query(address, "sample_infohashes", arguments).await?;
+
+        let count = |key| {
+            response
+                .get(key)
+                .and_then(Value::as_integer)
+                .and_then(|count| u32::try_from(count).ok())
+                .unwrap_or_default()
+        };
+
+        Ok(Samples {
+            samples: response
+                .get("samples")
+                .and_then(Value::as_bytes)
+                .unwrap_or_default()
+                .chunks_exact(20)
+                .map(|hash| hash.try_into().unwrap_or_default())
+                .collect(),
+            nodes: response_nodes(&response),
+            num: count("num"),
+            interval: count("interval"),
+        })
+    }
+
+    /// Finds peers for an info hash with an iterative `get_peers` lookup.
+    ///
+    /// # Returns
+    /// - `Vec<SocketAddr>`: The peers found, without duplicates.
+    pub async fn lookup_peers(&self, info_hash: [u8; 20]) -> Vec<SocketAddr> {
+        self.lookup(NodeId(info_hash), true).await
+    }
+
+    /// Performs an iterative lookup of the nodes closest to the target,
+    /// using `get_peers` queries when `get_peers` is set and `find_node` otherwise.
+    ///
+    /// Nodes found along the way are added to the routing table.
+    ///
+    /// # Returns
+    /// - `Vec<SocketAddr>`: The peers found, for `get_peers` lookups.
+    async fn lookup(&self, target: NodeId, get_peers: bool) -> Vec<SocketAddr> {
+        let mut candidates = self.inner.routing().closest(&target, K);
+        let mut queried = HashSet::new();
+        let mut peers = Vec::new();
+
+        loop {
+            let next: Vec<NodeInfo> = candidates
+                .iter()
+                .filter(|node| !queried.contains(&node.id))
+                .take(ALPHA)
+                .copied()
+                .collect();
+            if next.is_empty() {
+                break;
+            }
+
+            let mut queries = JoinSet::new();
+            for node in next {
+                queried.insert(node.id);
+                let this = self.clone();
+                queries.spawn(async move {
+                    let address = SocketAddr::V4(node.address);
+                    if get_peers {
+                        this.get_peers(address, target.0).await
+                    } else {
+                        this.find_node(address, target).await.map(|nodes| GetPeers {
+                            nodes,
+                            ..Default::default()
+                        })
+                    }
+                });
+            }
+
+            while let Some(joined) = queries.join_next().await {
+                let Ok(Ok(response)) = joined else { continue };
+                for peer in response.peers {
+                    let peer = SocketAddr::V4(peer);
+                    if !peers.contains(&peer) {
+                        peers.push(peer);
+                    }
+                }
+                for node in response.nodes {
+                    if node.id != self.id() && !candidates.iter().any(|c| c.id == node.id) {
+                        self.inner.routing().insert(node);
+                        candidates.push(node);
+                    }
+                }
+            }
+
+            candidates.sort_by_key(|node| node.id.distance(&target));
+            candidates.truncate(K);
+        }
+
+        peers
+    }
+
+    /// Sends a query and waits for its response.
+    ///
+    /// The responding node is added to the routing table. Unresponsive nodes are removed.
+    async fn query(
+        &self,
+        address: SocketAddr,
+        method: &str,
+        arguments: Value<'_>,
+    ) -> Result<Value<'static>, DhtError> {
+        if !address.is_ipv4() {
+            return Err(DhtError::UnsupportedAddress(address));
+        }
+
+        let transaction = self.inner.next_transaction.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+        self.inner.pending().insert(transaction, (address, sender));
+
+        let message = Value::dict()
+            .with("t", Value::bytes(transaction.to_be_bytes()))
+            .with("y", Value::bytes("q"))
+            .with("q", Value::bytes(method))
+            .with("a", arguments.with("id", Value::bytes(self.id().0)));
+        self.inner
+            .socket
+            .send_to(&bencode::encode(&message), address)
+            .await?;
+
+        let result = match timeout(self.inner.timeout, receiver).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(DhtError::Timeout),
+            Err(_) => {
+                self.inner.pending().remove(&transaction);
+                Err(DhtError::Timeout)
+            }
+        };
+
+        match &result {
+            Ok(response) => {
+                if let (Ok(id), SocketAddr::V4(address)) = (response_id(response), address) {
+                    self.inner.routing().insert(NodeInfo { id, address });
+                }
+            }
+            Err(DhtError::Timeout) => {
+                let mut routing = self.inner.routing();
+                let node = routing
+                    .nodes()
+                    .into_iter()
+                    .find(|node| SocketAddr::V4(node.address) == address);
+                if let Some(node) = node {
+                    routing.remove(&node.id);
+                }
+            }
+            Err(_) => {}
+        }
+
+        result
+    }
+}
+
+impl Inner {
+    /// Locks the routing table.
+    fn routing(&self) -> std::sync::MutexGuard<'_, RoutingTable> {
+        self.routing.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Locks the pending queries.
+    fn pending(&self) -> std::sync::MutexGuard<'_, HashMap<u16, PendingQuery>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Locks the announced peers.
+    fn peers(&self) -> std::sync::MutexGuard<'_, HashMap<[u8; 20], AnnouncedPeers>> {
+        self.peers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Stores a peer for an info hash, announced at `now`.
+    ///
+    /// A full list of peers drops its oldest announce. When `MAX_INFO_HASHES`
+    /// info hashes are stored, expired ones are removed first and the announce
+    /// is ignored if none expired.
+    fn add_peer(&self, info_hash: [u8; 20], peer: SocketAddrV4, now: Instant) {
+        let mut peers = self.peers();
+        if !peers.contains_key(&info_hash) && peers.len() >= MAX_INFO_HASHES {
+            peers.retain(|_, announced| {
+                announced.retain(|(_, at)| !is_expired(*at, now));
+                !announced.is_empty()
+            });
+            if peers.len() >= MAX_INFO_HASHES {
+                debug!("ignoring announce of {}, too many info hashes", peer);
+                return;
+            }
+        }
+
+        let announced = peers.entry(info_hash).or_default();
+        announced.retain(|(address, at)| *address != peer && !is_expired(*at, now));
+        if announced.len() >= MAX_PEERS_PER_HASH {
+            announced.remove(0);
+        }
+        announced.push((peer, now));
+    }
+
+    /// Returns the unexpired peers announced for an info hash, removing expired ones.
+    fn live_peers(&self, info_hash: &[u8; 20], now: Instant) -> Vec<SocketAddrV4> {
+        let mut peers = self.peers();
+        let Some(announced) = peers.get_mut(info_hash) else {
+            return Vec::new();
+        };
+        announced.retain(|(_, at)| !is_expired(*at, now));
+        let live = announced.iter().map(|(address, _)| *address).collect();
+        if announced.is_empty() {
+            peers.remove(info_hash);
+        }
+        live
+    }
+
+    /// Derives the announce token for an address.
+    fn token(&self, address: &SocketAddrV4) -> Vec<u8> {
+        let mut hasher = Sha1::new();
+        hasher.update(self.secret);
+        hasher.update(address.ip().octets());
+        hasher.finalize()[..8].to_vec()
+    }
+
+    /// Handles a received message.
+    async fn handle_message(&self, message: &[u8], from: SocketAddrV4) {
+        let Ok(message) = bencode::decode(message) else {
+            return;
+        };
+        let Some(transaction) = message.get("t").and_then(Value::as_bytes) else {
+            return;
+        };
+
+        match message.get("y").and_then(Value::as_bytes) {
+            Some(b"q") => {
+                let response = match self.handle_query(&message, from) {
+                    Ok(values) => Value::dict()
+                        .with("y", Value::bytes("r"))
+                        .with("r", values.with("id", Value::bytes(self.id.0))),
+                    Err((code, text)) => Value::dict().with("y", Value::bytes("e")).with(
+                        "e",
+                        Value::List(vec![Value::Integer(code), Value::bytes(text)]),
+                    ),
+                };
+                let response = response.with("t", Value::bytes(transaction));
+                if let Err(e) = self
+                    .socket
+                    .send_to(&bencode::encode(&response), SocketAddr::V4(from))
+                    .await
+                {
+                    debug!("failed to respond to {}: {}", from, e);
+                }
+            }
+            Some(kind @ (b"r" | b"e")) => {
+                let Ok(transaction) = <[u8; 2]>::try_from(transaction) else {
+                    return;
+                };
+                let transaction = u16::from_be_bytes(transaction);
+                let mut pending = self.pending();
+                // Responses from other addresses than the queried one are ignored
+                if pending.get(&transaction).map(|(address, _)| *address)
+                    != Some(SocketAddr::V4(from))
+                {
+                    return;
+                }
+                let Some((_, sender)) = pending.remove(&transaction) else {
+                    return;
+                };
+                drop(pending);
+
+                let result = if kind == b"r" {
+                    message
+                        .get("r")
+                        .filter(|values| values.as_dict().is_some())
+                        .map(|values| values.clone().into_owned())
+                        .ok_or_else(|| DhtError::InvalidMessage("missing 'r'".to_string()))
+                } else {
+                    let error = message
+                        .get("e")
+                        .and_then(Value::as_list)
+                        .unwrap_or_default();
+                    Err(DhtError::Remote {
+                        code: error
+                            .first()
+                            .and_then(Value::as_integer)
+                            .unwrap_or_default(),
+                        message: error
+                            .get(1)
+                            .and_then(Value::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                    })
+                };
+                let _ = sender.send(result);
+            }
+            _ => {}
+        }
+    }
+
+    /// Answers a query.
+    ///
+    /// # Returns
+    /// - `Ok(Value)`: The response values, without our id.
+    /// - `Err((i64, &str))`: A KRPC error code and message.
+    fn handle_query(
+        &self,
+        message: &Value<'_>,
+        from: SocketAddrV4,
+    ) -> Result<Value<'static>, (i64, &'static str)> {
+        let arguments = message
+            .get("a")
+            .ok_or((ERROR_PROTOCOL, "missing arguments"))?;
+        let querier = arguments
+            .get("id")
+            .and_then(Value::as_bytes)
+            .and_then(|id| <[u8; 20]>::try_from(id).ok())
+            .ok_or((ERROR_PROTOCOL, "invalid id"))?;
+        self.routing().insert(NodeInfo {
+            id: NodeId(querier),
+            address: from,
+        });
+
+        let hash_argument = |key| {
+            arguments
+                .get(key)
+                .and_then(Value::as_bytes)
+                .and_then(|hash| <[u8; 20]>::try_from(hash).ok())
+                .ok_or((ERROR_PROTOCOL, "invalid info hash or target"))
+        };
+        let compact_nodes = |target: [u8; 20]| {
+            let nodes: Vec<u8> = self
+                .routing()
+                .closest(&NodeId(target), K)
+                .iter()
+                .flat_map(NodeInfo::to_compact)
+                .collect();
+            Value::bytes(nodes)
+        };
+
+        match message.get("q").and_then(Value::as_bytes) {
+            Some(b"ping") => Ok(Value::dict()),
+            Some(b"find_node") => {
+                let target = hash_argument("target")?;
+                Ok(Value::dict().with("nodes", compact_nodes(target)))
+            }
+            Some(b"get_peers") => {
+                let info_hash = hash_argument("info_hash")?;
+                let response = Value::dict().with("token", Value::bytes(self.token(&from)));
+                let peers = self.live_peers(&info_hash, Instant::now());
+                if peers.is_empty() {
+                    Ok(response.with("nodes", compact_nodes(info_hash)))
+                } else {
+                    let values = peers
+                        .iter()
+                        .map(|peer| Value::bytes(encode_compact_address(peer)))
+                        .collect();
+                    Ok(response.with("values", Value::List(values)))
+                }
+            }
+            Some(b"announce_peer") => {
+                let info_hash = hash_argument("info_hash")?;
+                let token = arguments.get("token").and_then(Value::as_bytes);
+                if token != Some(self.token(&from).as_slice()) {
+                    return Err((ERROR_PROTOCOL, "invalid token"));
+                }
+
+                let implied_port = arguments.get("implied_port").and_then(Value::as_integer);
+                let port = match implied_port {
+                    Some(1) => from.port(),
+                    _ => arguments
+                        .get("port")
+                        .and_then(Value::as_integer)
+                        .and_then(|port| u16::try_from(port).ok())
+                        .ok_or((ERROR_PROTOCOL, "invalid port"))?,
+                };
+                self.add_peer(
+                    info_hash,
+                    SocketAddrV4::new(*from.ip(), port),
+                    Instant::now(),
+                );
+                Ok(Value::dict())
+            }
+            Some(b"sample_infohashes") => {
+                let target = hash_argument("target")?;
+                let now = Instant::now();
+                let peers = self.peers();
+                // The last announce of an info hash is the most recent one
+                let mut live = peers.iter().filter(|(_, announced)| {
+                    announced
+                        .last()
+                        .is_some_and(|(_, at)| !is_expired(*at, now))
+                });
+                let samples: Vec<u8> = live
+                    .by_ref()
+                    .take(MAX_SAMPLES)
+                    .flat_map(|(info_hash, _)| *info_hash)
+                    .collect();
+                let num = (samples.len() / 20 + live.count()) as i64;
+                drop(peers);
+
+                Ok(Value::dict()
+                    .with("interval", Value::Integer(SAMPLE_INTERVAL))
+                    .with("nodes", compact_nodes(target))
+                    .with("num", Value::Integer(num))
+                    .with("samples", Value::bytes(samples)))
+            }
+            _ => Err((ERROR_METHOD_UNKNOWN, "method unknown")),
+        }
+    }
+}
+
+/// Returns whether a peer announced at `at` has expired at `now`.
+fn is_expired(at: Instant, now: Instant) -> bool {
+    now.saturating_duration_since(at) >= PEER_TTL
+}
+
+/// Receives messages until the node is dropped.
+async fn receive_loop(socket: Arc<UdpSocket>, node: Weak<Inner>) {
+    let mut buffer = vec![0u8; MAX_DATAGRAM_SIZE];
+    loop {
+        let (length, from) = match socket.recv_from(&mut buffer).await {
+            Ok(received) => received,
+            Err(e) => {
+                // ICMP errors from unreachable nodes surface here on some platforms
+                debug!("dht receive failed: {}", e);
+                continue;
+            }
+        };
+        let SocketAddr::V4(from) = from else {
+            continue;
+        };
+        let Some(node) = node.upgrade() else {
+            return;
+        };
+        node.handle_message(&buffer[..length], from).await;
+    }
+}
+
+/// Reads the responder's id from a response.
+fn response_id(response: &Value<'_>) -> Result<NodeId, DhtError> {
+    response
+        .get("id")
+        .and_then(Value::as_bytes)
+        .and_then(|id| <[u8; 20]>::try_from(id).ok())
+        .map(NodeId)
+        .ok_or_else(|| DhtError::InvalidMessage("missing node id".to_string()))
+}
+
+/// Reads the compact `nodes` of a response.
+fn response_nodes(response: &Value<'_>) -> Vec<NodeInfo> {
+    response
+        .get("nodes")
+        .and_then(Value::as_bytes)
+        .map(NodeInfo::parse_compact_list)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    /// Binds a node on loopback.
+    async fn local_node() -> DhtNode {
+        DhtNode::bind((Ipv4Addr::LOCALHOST, 0).into(), Duration::from_secs(2))
+            .await
+            .unwrap()
+    }
+
+    /// Tests the basic queries between two nodes.
+    #[tokio::test]
+    async fn test_queries() {
+        let a = local_node().await;
+        let b = local_node().await;
+        let b_address = b.local_addr().unwrap();
+
+        assert_eq!(a.ping(b_address).await.unwrap(), b.id());
+        assert_eq!(a.nodes().len(), 1);
+        assert_eq!(b.nodes()[0].id, a.id());
+
+        let info_hash = [0x42; 20];
+        let response = a.get_peers(b_address, info_hash).await.unwrap();
+        assert!(response.peers.is_empty());
+        assert_eq!(response.nodes[0].id, a.id());
+
+        let result = a.announce_peer(b_address, info_hash, 1234, b"bad").await;
+        assert!(matches!(result, Err(DhtError::Remote { code: 203, .. })));
+
+        let token = response.token.unwrap();
+        a.announce_peer(b_address, info_hash, 1234, &token)
+            .await
+            .unwrap();
+        let response = a.get_peers(b_address, info_hash).await.unwrap();
+        assert_eq!(
+            response.peers,
+            vec![SocketAddrV4::new(Ipv4Addr::LOCALHOST, 1234)]
+        );
+
+        let samples = a.sample_infohashes(b_address, a.id()).await.unwrap();
+        assert_eq!(samples.samples, vec![info_hash]);
+        assert_eq!(samples.num, 1);
+    }
+
+    /// Tests that a node joins through a bootstrap node and finds peers with a lookup.
+    #[tokio::test]
+    async fn test_bootstrap_and_lookup() {
+        let bootstrap = local_node().await;
+        let nodes = [local_node().await, local_node().await, local_node().await];
+        for node in &nodes {
+            node.bootstrap(&[bootstrap.local_addr().unwrap()]).await;
+        }
+
+        let peer = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 5555);
+        nodes[2].add_peer([0x17; 20], peer);
+
+        let client = local_node().await;
+        assert!(client.bootstrap(&[bootstrap.local_addr().unwrap()]).await >= 4);
+        assert_eq!(
+            client.lookup_peers([0x17; 20]).await,
+            vec![SocketAddr::V4(peer)]
+        );
+
+        let result = client
+            .ping((Ipv4Addr::LOCALHOST, 9).into())
+            .await
+            .map(|_| ());
+        assert!(result.is_err());
+    }
+
+    /// Tests `find_node`, malformed queries and unknown methods.
+    #[tokio::test]
+    async fn test_query_handlers() {
+        let a = local_node().await;
+        let b = local_node().await;
+        let b_address = b.local_addr().unwrap();
+
+        let nodes = a.find_node(b_address, NodeId([0x42; 20])).await.unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].id, a.id());
+
+        let arguments = Value::dict().with("info_hash", Value::bytes("short"));
+        let result = a.query(b_address, "get_peers", arguments).await;
+        assert!(matches!(result, Err(DhtError::Remote { code: 203, .. })));
+
+        let result = a.query(b_address, "vote", Value::dict()).await;
+        assert!(matches!(result, Err(DhtError::Remote { code: 204, .. })));
+
+        let arguments = Value::dict()
+            .with("info_hash", Value::bytes([0x42; 20]))
+            .with("implied_port", Value::Integer(1))
+            .with(
+                "token",
+                Value::bytes(b.inner.token(&SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0))),
+            );
+        a.query(b_address, "announce_peer", arguments)
+            .await
+            .unwrap();
+        let SocketAddr::V4(a_address) = a.local_addr().unwrap() else {
+            unreachable!()
+        };
+        assert_eq!(
+            a.get_peers(b_address, [0x42; 20]).await.unwrap().peers,
+            vec![SocketAddrV4::new(Ipv4Addr::LOCALHOST, a_address.port())]
+        );
+    }
+
+    /// Tests that announced peers expire and are bounded per info hash and in total.
+    #[tokio::test]
+    async fn test_peer_limits() {
+        let node = local_node().await;
+        let inner = &node.inner;
+        let now = Instant::now();
+        let peer = |port| SocketAddrV4::new(Ipv4Addr::LOCALHOST, port);
+
+        for port in 0..MAX_PEERS_PER_HASH as u16 + 5 {
+            inner.add_peer([1; 20], peer(port), now);
+        }
+        let peers = inner.live_peers(&[1; 20], now);
+        assert_eq!(peers.len(), MAX_PEERS_PER_HASH);
+        assert_eq!(peers[0], peer(5));
+
+        inner.add_peer([1; 20], peer(5), now + Duration::from_secs(60));
+        let later = now + PEER_TTL;
+        assert_eq!(inner.live_peers(&[1; 20], later), vec![peer(5)]);
+        assert!(inner
+            .live_peers(&[1; 20], later + Duration::from_secs(60))
+            .is_empty());
+        assert!(inner.peers().is_empty());
+
+        for i in 0..MAX_INFO_HASHES as u32 {
+            let mut info_hash = [0; 20];
+            info_hash[..4].copy_from_slice(&i.to_be_bytes());
+            inner.add_peer(info_hash, peer(1), now);
+        }
+        inner.add_peer([0xff; 20], peer(1), now);
+        assert!(inner.live_peers(&[0xff; 20], now).is_empty());
+        assert_eq!(inner.peers().len(), MAX_INFO_HASHES);
+
+        // Once the others expired, new info hashes are stored again
+        inner.add_peer([0xff; 20], peer(1), later);
+        assert_eq!(inner.live_peers(&[0xff; 20], later), vec![peer(1)]);
+        assert_eq!(inner.peers().len(), 1);
+    }
+
+    /// Tests that responses are only accepted from the queried address.
+    #[tokio::test]
+    async fn test_response_source() {
+        let node = local_node().await;
+        let remote = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let spoofer = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let remote_address = remote.local_addr().unwrap();
+        let node_address = node.local_addr().unwrap();
+
+        let ping = tokio::spawn({
+            let node = node.clone();
+            async move { node.ping(remote_address).await }
+        });
+
+        let mut buffer = [0u8; 1024];
+        let (length, _) = remote.recv_from(&mut buffer).await.unwrap();
+        let query = bencode::decode(&buffer[..length]).unwrap();
+        let transaction = query.get("t").and_then(Value::as_bytes).unwrap().to_vec();
+        let response = |id: [u8; 20]| {
+            bencode::encode(
+                &Value::dict()
+                    .with("t", Value::bytes(transaction.clone()))
+                    .with("y", Value::bytes("r"))
+                    .with("r", Value::dict().with("id", Value::bytes(id))),
+            )
+        };
+
+        spoofer
+            .send_to(&response([0x66; 20]), node_address)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        remote
+            .send_to(&response([0x11; 20]), node_address)
+            .await
+            .unwrap();
+
+        assert_eq!(ping.await.unwrap().unwrap(), NodeId([0x11; 20]));
+    }
+
+    /// Tests that a node with an empty routing table joins again through its bootstrap nodes.
+    #[tokio::test]
+    async fn test_rejoin_if_empty() {
+        let bootstrap = local_node().await;
+        let node = local_node().await;
+        assert_eq!(node.rejoin_if_empty().await, 0);
+
+        node.bootstrap(&[bootstrap.local_addr().unwrap()]).await;
+        node.inner.routing().remove(&bootstrap.id());
+        assert!(node.nodes().is_empty());
+
+        assert_eq!(node.rejoin_if_empty().await, 1);
+        assert_eq!(node.nodes()[0].id, bootstrap.id());
+    }
+}
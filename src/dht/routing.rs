@@ -0,0 +1,130 @@
+//! # Routing table
+//!
+//! A Kademlia routing table of up to `K` nodes per bucket, where bucket `i`
+//! holds nodes whose distance to our id has its highest set bit at position `i`.
+
+use super::{NodeId, NodeInfo};
+
+/// The maximum number of nodes per bucket, and the number of nodes returned by lookups.
+pub const K: usize = 8;
+
+/// The routing table of a DHT node.
+#[derive(Debug, Clone)]
+pub struct RoutingTable {
+    /// The id of the node owning the table.
+    own_id: NodeId,
+
+    /// The buckets, indexed by the bit length of the distance to `own_id` minus one.
+    buckets: Vec<Vec<NodeInfo>>,
+}
+
+impl RoutingTable {
+    /// Creates an empty routing table for the given node id.
+    pub fn new(own_id: NodeId) -> Self {
+        Self {
+            own_id,
+            buckets: vec![Vec::new(); 160],
+        }
+    }
+
+    /// Inserts or refreshes a node.
+    ///
+    /// Known nodes are moved to the end of their bucket as most recently seen.
+    /// New nodes are dropped when their bucket is full.
+    ///
+    /// # Returns
+    /// - `bool`: Whether the node is in the table after the call.
+    pub fn insert(&mut self, node: NodeInfo) -> bool {
+        let Some(index) = self.bucket_index(&node.id) else {
+            return false;
+        };
+        let bucket = &mut self.buckets[index];
+
+        if let Some(position) = bucket.iter().position(|known| known.id == node.id) {
+            bucket.remove(position);
+        } else if bucket.len() >= K {
+            return false;
+        }
+        bucket.push(node);
+        true
+    }
+
+    /// Removes a node, e.g. after it stopped responding.
+    pub fn remove(&mut self, id: &NodeId) {
+        if let Some(index) = self.bucket_index(id) {
+            self.buckets[index].retain(|node| node.id != *id);
+        }
+    }
+
+    /// Returns up to `count` nodes closest to the target.
+    pub fn closest(&self, target: &NodeId, count: usize) -> Vec<NodeInfo> {
+        let mut nodes = self.nodes();
+        nodes.sort_by_key(|node| node.id.distance(target));
+        nodes.truncate(count);
+        nodes
+    }
+
+    /// Returns all nodes in the table.
+    pub fn nodes(&self) -> Vec<NodeInfo> {
+        self.buckets.iter().flatten().copied().collect()
+    }
+
+    /// Returns the number of nodes in the table.
+    pub fn len(&self) -> usize {
+        self.buckets.iter().map(Vec::len).sum()
+    }
+
+    /// Returns whether the table is empty.
+    pub fn is_empty(&self) -> bool {
+        self.buckets.iter().all(Vec::is_empty)
+    }
+
+    /// Returns the bucket of an id, or `None` for our own id.
+    fn bucket_index(&self, id: &NodeId) -> Option<usize> {
+        let distance = self.own_id.distance(id);
+        let leading_zeros = distance
+            .iter()
+            .position(|byte| *byte != 0)
+            .map(|i| i as u32 * 8 + distance[i].leading_zeros())?;
+        Some(159 - leading_zeros as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    /// Builds a node whose id has the given first byte and zeros elsewhere.
+    fn node(first_byte: u8, last_byte: u8) -> NodeInfo {
+        let mut id = [0u8; 20];
+        id[0] = first_byte;
+        id[19] = last_byte;
+        NodeInfo {
+            id: NodeId(id),
+            address: SocketAddrV4::new(Ipv4Addr::LOCALHOST, 6881),
+        }
+    }
+
+    /// Tests that full buckets drop new nodes and lookups return the closest nodes.
+    #[test]
+    fn test_insert_and_closest() {
+        let mut table = RoutingTable::new(NodeId([0; 20]));
+        assert!(!table.insert(node(0, 0)));
+
+        // All of these share the highest bucket
+        for i in 0..K as u8 {
+            assert!(table.insert(node(0x80, i)));
+        }
+        assert!(!table.insert(node(0x80, 100)));
+        assert!(table.insert(node(0x80, 0)));
+        assert!(table.insert(node(0x01, 0)));
+        assert_eq!(table.len(), K + 1);
+
+        let closest = table.closest(&NodeId([0; 20]), 2);
+        assert_eq!(closest, vec![node(0x01, 0), node(0x80, 0)]);
+
+        table.remove(&node(0x01, 0).id);
+        assert_eq!(table.len(), K);
+    }
+}
@@ -21,7 +21,7 @@ pub enum ClientError {
     #[error("server returned error with status {code}: {content}")]
     ServerResponseError {
         /// The HTTP status code from the server's response.
-        code: reqwest::StatusCode,
+        code: http::StatusCode,
         /// Content of the server's error response.
         content: String,
     },
@@ -42,4 +42,9 @@ pub enum ClientError {
         /// The URL being used when the error occurred.
         url: String,
     },
+
+    /// Represents an error from a provider that does not query an HTTP API,
+    /// or an HTTP operation such a provider does not support.
+    #[error("provider error: {0}")]
+    ProviderError(#[source] anyhow::Error),
 }
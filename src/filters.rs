@@ -0,0 +1,138 @@
+//! # Filters
+//!
+//! Filters applied to search results after they are merged, by seeders, size,
+//! resolution and words rejected in names. They are shared by the `magneto`
+//! binary and the server, where resolutions are written as displayed, e.g. "1080p".
+
+use serde::{de, Deserialize, Deserializer};
+
+use crate::{release::Resolution, Torrent};
+
+/// The resolutions accepted by filters, by their displayed names.
+pub(crate) const RESOLUTIONS: [Resolution; 5] = [
+    Resolution::P480,
+    Resolution::P576,
+    Resolution::P720,
+    Resolution::P1080,
+    Resolution::P2160,
+];
+
+/// Filters applied to search results before they are ranked.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchFilters {
+    /// The minimum number of seeders.
+    pub min_seeders: Option<u32>,
+
+    /// The minimum size in bytes.
+    pub min_size: Option<u64>,
+
+    /// The maximum size in bytes.
+    pub max_size: Option<u64>,
+
+    /// The accepted resolutions, e.g. "1080p"; empty accepts any.
+    #[serde(deserialize_with = "deserialize_resolutions")]
+    pub resolutions: Vec<Resolution>,
+
+    /// Words rejected in names, case-insensitively.
+    pub excludes: Vec<String>,
+}
+
+impl SearchFilters {
+    /// Returns whether the torrent passes every filter.
+    pub fn accepts(&self, torrent: &Torrent) -> bool {
+        if self.min_seeders.is_some_and(|min| torrent.seeders < min)
+            || self.min_size.is_some_and(|min| torrent.size_bytes < min)
+            || self.max_size.is_some_and(|max| torrent.size_bytes > max)
+        {
+            return false;
+        }
+
+        if !self.resolutions.is_empty() {
+            let resolution = torrent.release_info().resolution;
+            if !resolution.is_some_and(|r| self.resolutions.contains(&r)) {
+                return false;
+            }
+        }
+
+        let name = torrent.name.to_lowercase();
+        !self
+            .excludes
+            .iter()
+            .any(|word| name.contains(&word.to_lowercase()))
+    }
+}
+
+/// Parses a resolution from its displayed name, e.g. "1080p".
+pub fn parse_resolution(name: &str) -> Result<Resolution, String> {
+    RESOLUTIONS
+        .into_iter()
+        .find(|resolution| resolution.to_string().eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("invalid resolution '{}'", name))
+}
+
+/// Deserializes resolutions from their displayed names.
+fn deserialize_resolutions<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Resolution>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|name| parse_resolution(name).map_err(de::Error::custom))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that each filter rejects the torrents it should.
+    #[test]
+    fn test_filters() {
+        let torrent = Torrent {
+            name: "Movie.2010.1080p.BluRay.x264-GROUP".to_string(),
+            magnet_link: "magnet:?xt=urn:btih:abc".to_string(),
+            seeders: 10,
+            peers: 2,
+            size_bytes: 2_000_000_000,
+            provider: "yts".to_string(),
+            metadata: Default::default(),
+            stats_updated_at: None,
+        };
+
+        assert!(SearchFilters::default().accepts(&torrent));
+        assert!(SearchFilters {
+            min_seeders: Some(10),
+            min_size: Some(1_000_000_000),
+            max_size: Some(2_000_000_000),
+            resolutions: vec![Resolution::P720, Resolution::P1080],
+            excludes: vec!["cam".to_string()],
+        }
+        .accepts(&torrent));
+
+        assert!(!SearchFilters {
+            min_seeders: Some(11),
+            ..Default::default()
+        }
+        .accepts(&torrent));
+        assert!(!SearchFilters {
+            max_size: Some(1_000_000_000),
+            ..Default::default()
+        }
+        .accepts(&torrent));
+        assert!(!SearchFilters {
+            resolutions: vec![Resolution::P2160],
+            ..Default::default()
+        }
+        .accepts(&torrent));
+        assert!(!SearchFilters {
+            excludes: vec!["BluRay".to_string()],
+            ..Default::default()
+        }
+        .accepts(&torrent));
+
+        let filters: SearchFilters =
+            serde_json::from_str(r#"{"resolutions": ["1080P"], "min_seeders": 5}"#).unwrap();
+        assert_eq!(filters.resolutions, vec![Resolution::P1080]);
+        assert!(serde_json::from_str::<SearchFilters>(r#"{"resolutions": ["8k"]}"#).is_err());
+    }
+}
@@ -3,15 +3,29 @@
 //! custom providers.
 //!
 //! ## Features
-//! - Fully async-powered using `reqwest` and `tokio`.
+//! - Fully async-powered using `tokio`, with a pluggable HTTP transport (`reqwest` by default).
 //! - Query multiple torrent search providers simultaneously.
 //! - Retrieve torrent results in a unified format.
-//! - Add custom providers with minimal effort.
+//! - Configure providers, mirrors, defaults and caching from a TOML or JSON file.
+//! - Serve searches to Sonarr, Radarr and other Torznab clients, or over a JSON API
+//!   with an OpenAPI description and streamed results (`server` feature), protected
+//!   by API keys with per-key limits, provider allowlists and blocked categories.
+//! - Search from the terminal or scripts with the `magneto` command-line tool, or browse
+//!   results interactively with its `tui` command (`tui` feature).
+//! - Define providers for JSON APIs and HTML sites in YAML or JSON files, without Rust.
+//! - Add custom providers with minimal effort, scrape sites without an API with
+//!   the HTML helpers of `search_providers::html`, and check them with the conformance
+//!   suite of the `testkit` feature.
 //!
 //! ## Supported providers
 //! - Knaben: A multi search archiver, acting as a cached proxy towards multiple different trackers.
 //! - PirateBay: The galaxy’s most resilient Public BitTorrent site.
 //! - YTS: A public torrent site specialising in HD movies of small size.
+//! - Nyaa: The largest tracker for anime, with trusted and remake flags on results.
+//! - EZTV: A public tracker for TV shows, searched by IMDb id with season and episode numbers.
+//! - 1337x: A popular public torrent site without an API, scraped along with its mirrors.
+//! - Torznab: Any Torznab endpoint, such as Jackett or Prowlarr indexers.
+//! - RSS: Any site exposing search as an RSS or Atom feed, with configurable fields.
 //!
 //! ## Usage
 //!
@@ -53,7 +67,7 @@
 //! ```no_run
 //! use magneto::{Magneto, Knaben, PirateBay, Yts};
 //!
-//! // By default, all built-in providers are used (Knaben, PirateBay, Yts)
+//! // By default, the general built-in providers are used (Knaben, PirateBay, Yts)
 //! let magneto = Magneto::new();
 //!
 //! // You can specify which providers to use like this
@@ -89,19 +103,15 @@
 //!
 //! ```no_run
 //! use magneto::{
-//!     async_trait, Client, ClientError, Magneto, Request, SearchProvider, SearchRequest, Torrent,
+//!     async_trait, Bytes, ClientError, Magneto, Request, SearchProvider, SearchRequest, Torrent,
 //! };
 //!
 //! struct CustomProvider;
 //!
 //! #[async_trait]
 //! impl SearchProvider for CustomProvider {
-//!     fn build_request(
-//!         &self,
-//!         client: &Client,
-//!         request: SearchRequest<'_>,
-//!     ) -> Result<Request, ClientError> {
-//!         // Convert SearchRequest parameters to a reqwest::Request
+//!     fn build_request(&self, request: SearchRequest<'_>) -> Result<Request<Bytes>, ClientError> {
+//!         // Convert SearchRequest parameters to an http::Request
 //!         unimplemented!();
 //!     }
 //!
@@ -123,14 +133,49 @@
 //!     let magneto = Magneto::new().add_provider(Box::new(custom_provider));
 //! }
 //! ```
+//!
+//! ### Use a custom HTTP client
+//!
+//! ```no_run
+//! use magneto::{Magneto, ReqwestTransport};
+//! use std::time::Duration;
+//!
+//! // Any type implementing `HttpTransport` can be used, this configures the default one
+//! let client = reqwest::Client::builder()
+//!     .timeout(Duration::from_secs(5))
+//!     .build()
+//!     .unwrap();
+//! let magneto = Magneto::new().with_transport(ReqwestTransport::with_client(client));
+//! ```
 
+pub mod bencode;
+pub mod config;
+pub mod date;
+pub mod definitions;
+pub mod dht;
 pub mod errors;
+pub mod filters;
+pub mod magnet;
+pub mod metainfo;
+pub mod quality;
+pub mod release;
+pub mod scrape;
 pub mod search_providers;
-
-use core::fmt;
-
-// Re-exports from reqwest
-pub use reqwest::{Client, Request};
+#[cfg(feature = "server")]
+pub mod server;
+pub mod size;
+#[cfg(any(test, feature = "testkit"))]
+pub mod testkit;
+pub mod tracker;
+pub mod transport;
+pub mod wire;
+
+use core::{cmp::Reverse, fmt};
+use std::{path::Path, sync::Arc, time::SystemTime};
+
+// Re-exports of the HTTP types used by providers and transports
+pub use bytes::Bytes;
+pub use http::{Request, Response};
 
 // Re-export async_trait;
 pub use async_trait::async_trait;
@@ -138,8 +183,20 @@ pub use async_trait::async_trait;
 use log::debug;
 use serde::{Deserialize, Serialize};
 
+pub use config::{Config, ConfigError};
+pub use definitions::{Definition, DefinitionError, DefinitionProvider};
 pub use errors::ClientError;
-pub use search_providers::{Knaben, PirateBay, SearchProvider, Yts};
+pub use filters::SearchFilters;
+pub use magnet::Magnet;
+pub use metainfo::Metainfo;
+pub use quality::QualityProfile;
+pub use release::ReleaseInfo;
+pub use scrape::{Scraper, StatsRefresh};
+pub use search_providers::{
+    Dht, Eztv, Knaben, Leetx, Nyaa, NyaaFilter, PirateBay, RssFields, RssProvider, SearchProvider,
+    Torznab, Yts,
+};
+pub use transport::{HttpTransport, ReqwestTransport};
 
 /// Represents metadata for a torrent returned by a search provider.
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -161,10 +218,148 @@ pub struct Torrent {
 
     /// The identifier of the provider that returned this torrent.
     pub provider: String,
+
+    /// Additional provider-agnostic metadata, populated when the provider supplies it.
+    #[serde(default)]
+    pub metadata: TorrentMetadata,
+
+    /// When `seeders` and `peers` were last refreshed from trackers.
+    ///
+    /// `None` means the counts are the values cached by the provider.
+    #[serde(default)]
+    pub stats_updated_at: Option<SystemTime>,
+}
+
+impl Torrent {
+    /// Parses the torrent name into structured release attributes.
+    ///
+    /// # Returns
+    /// - `ReleaseInfo`: The attributes parsed from `name`, such as resolution,
+    ///   source, codecs, season/episode and release group.
+    ///
+    /// # Example
+    /// ```rust
+    /// use magneto::{release::Resolution, Torrent};
+    ///
+    /// let torrent = Torrent {
+    ///     name: "Show.S01E02.720p.HDTV.x264-GROUP".to_string(),
+    ///     magnet_link: "magnet:?xt=urn:btih:abc123".to_string(),
+    ///     seeders: 20,
+    ///     peers: 10,
+    ///     size_bytes: 2048,
+    ///     provider: "example".to_string(),
+    ///     metadata: Default::default(),
+    ///     stats_updated_at: None,
+    /// };
+    ///
+    /// let info = torrent.release_info();
+    /// assert_eq!(info.title, "Show");
+    /// assert_eq!(info.resolution, Some(Resolution::P720));
+    /// ```
+    pub fn release_info(&self) -> ReleaseInfo {
+        ReleaseInfo::parse(&self.name)
+    }
+
+    /// Parses the torrent's magnet link.
+    ///
+    /// # Returns
+    /// - `Ok(Magnet)`: The parsed magnet link, with info hash and trackers.
+    /// - `Err(MagnetError)`: An error if the magnet link is invalid.
+    pub fn magnet(&self) -> Result<Magnet, magnet::MagnetError> {
+        Magnet::parse(&self.magnet_link)
+    }
+
+    /// Formats the torrent size for display using SI units, e.g. "1.50 GB".
+    ///
+    /// See `size::format_size_iec` for IEC units.
+    pub fn size_human(&self) -> String {
+        size::format_size(self.size_bytes)
+    }
+}
+
+/// Optional metadata describing a torrent's origin on the provider side.
+///
+/// Every field is optional since providers expose different subsets of this information.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TorrentMetadata {
+    /// The name of the user who uploaded the torrent.
+    pub uploader: Option<String>,
+
+    /// The reputation of the uploader on the provider's site.
+    pub uploader_status: Option<UploaderStatus>,
+
+    /// The number of files included in the torrent.
+    pub file_count: Option<u32>,
+
+    /// The IMDb id associated with the torrent, e.g. "tt1375666".
+    pub imdb_id: Option<String>,
+
+    /// The provider's own identifier for the torrent.
+    pub provider_id: Option<String>,
+
+    /// A human-readable details page for the torrent on the provider's site.
+    pub details_url: Option<String>,
+
+    /// The release year of the content, e.g. the year a movie was released.
+    pub year: Option<u32>,
+
+    /// Video and audio details reported by the provider.
+    pub video: Option<VideoDetails>,
+
+    /// When the torrent was uploaded to the provider's site.
+    pub uploaded_at: Option<SystemTime>,
+
+    /// Whether the site flags the torrent as a remake of an existing release,
+    /// e.g. a re-encode or a batch of already released episodes.
+    pub remake: Option<bool>,
+
+    /// The season of a TV episode, as reported by the provider.
+    pub season: Option<u32>,
+
+    /// The episode number within `season`, as reported by the provider.
+    pub episode: Option<u32>,
+}
+
+/// Video and audio details of a release, as reported by the provider.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct VideoDetails {
+    /// The video quality, e.g. "1080p" or "2160p".
+    pub quality: Option<String>,
+
+    /// The release type or source, e.g. "bluray" or "web".
+    pub release_type: Option<String>,
+
+    /// The video codec, e.g. "x264" or "x265".
+    pub video_codec: Option<String>,
+
+    /// The video bit depth, e.g. 8 or 10.
+    pub bit_depth: Option<u8>,
+
+    /// The audio channel layout, e.g. "2.0" or "5.1".
+    pub audio_channels: Option<String>,
+}
+
+/// Enum specifying the reputation of an uploader on a provider's site.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum UploaderStatus {
+    /// A regular, unverified member.
+    Member,
+
+    /// A member marked as trusted by the site.
+    Trusted,
+
+    /// A VIP member, usually a well-known release group or uploader.
+    Vip,
+
+    /// A site staff member (helper, moderator or administrator).
+    Staff,
 }
 
 /// Enum specifying the different categories available for torrents.
-#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+///
+/// Deserializes from snake case names, e.g. "tv_shows".
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[serde(rename_all(deserialize = "snake_case"))]
 pub enum Category {
     /// Represents the category for movies.
     Movies,
@@ -188,16 +383,86 @@ pub enum Category {
     Xxx,
 }
 
+impl Category {
+    /// Every category, in declaration order.
+    pub const ALL: [Category; 7] = [
+        Category::Movies,
+        Category::TvShows,
+        Category::Games,
+        Category::Software,
+        Category::Audio,
+        Category::Anime,
+        Category::Xxx,
+    ];
+
+    /// Returns the Newznab category id of the category, as used by Torznab indexers.
+    ///
+    /// # Returns
+    /// - `u32`: The id, e.g. 2000 for movies or 5070 for anime.
+    pub fn newznab_id(&self) -> u32 {
+        match self {
+            Category::Movies => 2000,
+            Category::TvShows => 5000,
+            Category::Games => 1000,
+            Category::Software => 4000,
+            Category::Audio => 3000,
+            Category::Anime => 5070,
+            Category::Xxx => 6000,
+        }
+    }
+
+    /// Maps a Newznab category id to a category.
+    ///
+    /// Subcategories map to their parent, e.g. 2040 (Movies/HD) to `Category::Movies`,
+    /// except for 4050 (PC/Games) and 5070 (TV/Anime).
+    ///
+    /// # Parameters
+    /// - `id`: The Newznab category id.
+    ///
+    /// # Returns
+    /// - `Some(Category)`: The matching category.
+    /// - `None`: If no category matches, e.g. for books (7000).
+    ///
+    /// # Example
+    /// ```rust
+    /// use magneto::Category;
+    ///
+    /// assert_eq!(Category::from_newznab_id(5040), Some(Category::TvShows));
+    /// assert_eq!(Category::from_newznab_id(5070), Some(Category::Anime));
+    /// assert_eq!(Category::from_newznab_id(7000), None);
+    /// ```
+    pub fn from_newznab_id(id: u32) -> Option<Category> {
+        match (id, id / 1000) {
+            (4050, _) => Some(Category::Games),
+            (5070, _) => Some(Category::Anime),
+            (_, 1) => Some(Category::Games),
+            (_, 2) => Some(Category::Movies),
+            (_, 3) => Some(Category::Audio),
+            (_, 4) => Some(Category::Software),
+            (_, 5) => Some(Category::TvShows),
+            (_, 6) => Some(Category::Xxx),
+            _ => None,
+        }
+    }
+}
+
 /// Enum specifying the order by which search results are sorted.
 ///
-/// Implements fmt::Display
-#[derive(Serialize, Debug, Clone)]
+/// Implements fmt::Display, and deserializes from the displayed names.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all(deserialize = "snake_case"))]
 pub enum OrderBy {
     /// Sort results by the number of seeders.
     Seeders,
 
     /// Sort results by the number of peers.
     Peers,
+
+    /// Sort results by their score in the `Magneto` quality profile, best match first.
+    ///
+    /// Torrents rejected by the profile are removed from the results.
+    /// Providers are queried as if sorting by seeders.
+    ProfileScore,
 }
 
 impl fmt::Display for OrderBy {
@@ -205,6 +470,7 @@ impl fmt::Display for OrderBy {
         match self {
             OrderBy::Seeders => write!(f, "seeders"),
             OrderBy::Peers => write!(f, "peers"),
+            OrderBy::ProfileScore => write!(f, "profile_score"),
         }
     }
 }
@@ -312,14 +578,63 @@ impl<'a> SearchRequest<'a> {
     }
 }
 
+/// Default parameters of search requests built with `Magneto::request`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchDefaults {
+    /// The categories to filter by; empty searches every category.
+    pub categories: Vec<Category>,
+
+    /// The order by which results are sorted.
+    pub order_by: OrderBy,
+
+    /// The maximum number of results.
+    pub number_of_results: usize,
+}
+
+impl Default for SearchDefaults {
+    /// Provides the parameters of `SearchRequest::new`.
+    fn default() -> Self {
+        Self {
+            categories: Vec::new(),
+            order_by: OrderBy::Seeders,
+            number_of_results: 50,
+        }
+    }
+}
+
 /// The main interface for managing and querying torrent providers.
 ///
 /// `Magneto` manages a collection of torrent search providers and allows
 /// querying them simultaneously. It supports adding custom providers, querying
 /// specific providers, and retrieving results in a unified format.
-#[derive(Default)]
 pub struct Magneto {
     pub active_providers: Vec<Box<dyn SearchProvider>>,
+
+    /// The profile used to score results when searching with `OrderBy::ProfileScore`.
+    pub quality_profile: QualityProfile,
+
+    /// Refreshes the seeders and peers of the top results from trackers, if set.
+    pub stats_refresh: Option<StatsRefresh>,
+
+    /// The transport used to send HTTP requests to providers.
+    pub transport: Arc<dyn HttpTransport>,
+
+    /// The parameters of requests built with `request`.
+    pub defaults: SearchDefaults,
+}
+
+impl Default for Magneto {
+    /// Provides a `Magneto` instance without providers, using `ReqwestTransport`.
+    fn default() -> Self {
+        Self {
+            active_providers: Vec::new(),
+            quality_profile: QualityProfile::default(),
+            stats_refresh: None,
+            transport: Arc::new(ReqwestTransport::new()),
+            defaults: SearchDefaults::default(),
+        }
+    }
 }
 
 impl Magneto {
@@ -341,6 +656,7 @@ impl Magneto {
 
         Self {
             active_providers: providers,
+            ..Default::default()
         }
     }
 
@@ -415,6 +731,173 @@ impl Magneto {
         self
     }
 
+    /// Sets the quality profile used to score results for `OrderBy::ProfileScore`.
+    ///
+    /// This method consumes the current `Magneto` instance and returns a new instance
+    /// with the given profile.
+    ///
+    /// # Parameters
+    /// - `profile`: The `QualityProfile` to score results with.
+    ///
+    /// # Returns
+    /// - A new `Magneto` instance with the updated quality profile.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use magneto::{release::Resolution, Magneto, OrderBy, QualityProfile, SearchRequest};
+    ///
+    /// # async fn example() {
+    /// let profile = QualityProfile::new().prefer_resolutions(vec![Resolution::P1080]);
+    /// let magneto = Magneto::new().with_quality_profile(profile);
+    ///
+    /// let mut request = SearchRequest::new("Inception");
+    /// request.order_by = OrderBy::ProfileScore;
+    ///
+    /// // The best match according to the profile is returned first
+    /// let torrents = magneto.search(request).await;
+    /// # }
+    /// ```
+    pub fn with_quality_profile(mut self, profile: QualityProfile) -> Self {
+        self.quality_profile = profile;
+        self
+    }
+
+    /// Refreshes the seeders and peers of the top results from trackers before they are returned.
+    ///
+    /// This method consumes the current `Magneto` instance and returns a new instance
+    /// with the given setting. Refreshed results are sorted again by the request's order.
+    ///
+    /// # Parameters
+    /// - `scraper`: The `Scraper` used to query trackers.
+    /// - `top`: The number of top results to refresh.
+    ///
+    /// # Returns
+    /// - A new `Magneto` instance that refreshes search results.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use magneto::{Magneto, Scraper, SearchRequest};
+    ///
+    /// # async fn example() {
+    /// let magneto = Magneto::new().with_stats_refresh(Scraper::new(), 10);
+    ///
+    /// // The ten best results carry live counts and a `stats_updated_at` timestamp
+    /// let torrents = magneto.search(SearchRequest::new("Ubuntu")).await;
+    /// # }
+    /// ```
+    pub fn with_stats_refresh(mut self, scraper: Scraper, top: usize) -> Self {
+        self.stats_refresh = Some(StatsRefresh { scraper, top });
+        self
+    }
+
+    /// Sets the transport used to send HTTP requests to providers.
+    ///
+    /// This method consumes the current `Magneto` instance and returns a new instance
+    /// with the given transport.
+    ///
+    /// # Parameters
+    /// - `transport`: Any type implementing `HttpTransport`, e.g. an instrumented client.
+    ///
+    /// # Returns
+    /// - A new `Magneto` instance with the updated transport.
+    pub fn with_transport(mut self, transport: impl HttpTransport + 'static) -> Self {
+        self.transport = Arc::new(transport);
+        self
+    }
+
+    /// Sets the parameters of requests built with `request`.
+    ///
+    /// # Parameters
+    /// - `defaults`: The default categories, order and number of results.
+    ///
+    /// # Returns
+    /// - A new `Magneto` instance with the updated defaults.
+    pub fn with_defaults(mut self, defaults: SearchDefaults) -> Self {
+        self.defaults = defaults;
+        self
+    }
+
+    /// Creates a new `Magneto` instance from a configuration.
+    ///
+    /// Providers, transport, tracker refresh and request defaults are all taken
+    /// from the configuration; see the `config` module for the file format.
+    ///
+    /// # Parameters
+    /// - `config`: The configuration, usually from `Config::load`.
+    ///
+    /// # Returns
+    /// - `Ok(Magneto)`: The configured instance.
+    /// - `Err(ConfigError)`: An error if the HTTP client cannot be built, e.g. with an invalid proxy.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use magneto::{Config, Magneto};
+    ///
+    /// # async fn example() {
+    /// let magneto = Magneto::from_config(&Config::load().unwrap()).unwrap();
+    /// let torrents = magneto.search(magneto.request("Ubuntu")).await;
+    /// # }
+    /// ```
+    pub fn from_config(config: &Config) -> Result<Self, ConfigError> {
+        let mut magneto = Self {
+            active_providers: Vec::new(),
+            quality_profile: QualityProfile::default(),
+            stats_refresh: config.stats_refresh()?,
+            transport: config.transport()?,
+            defaults: config.search.clone(),
+        };
+        for provider in config.providers() {
+            magneto = magneto.add_provider(provider);
+        }
+        if let Some(dir) = &config.providers.definitions {
+            magneto = magneto.load_definitions(dir)?;
+        }
+
+        Ok(magneto)
+    }
+
+    /// Adds a provider for every definition file in a directory.
+    ///
+    /// See the `definitions` module for the format of the `.yaml`, `.yml` and
+    /// `.json` files; other files are ignored.
+    ///
+    /// # Parameters
+    /// - `dir`: The directory of the definition files.
+    ///
+    /// # Returns
+    /// - `Ok(Magneto)`: The instance with the defined providers added.
+    /// - `Err(DefinitionError)`: An error if the directory or a definition cannot be read or is invalid.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use magneto::Magneto;
+    ///
+    /// let magneto = Magneto::new().load_definitions("definitions").unwrap();
+    /// ```
+    pub fn load_definitions(self, dir: impl AsRef<Path>) -> Result<Self, DefinitionError> {
+        Ok(definitions::load_dir(dir)?
+            .into_iter()
+            .fold(self, |magneto, provider| {
+                magneto.add_provider(Box::new(provider))
+            }))
+    }
+
+    /// Creates a search request for the query with the instance's defaults.
+    ///
+    /// # Parameters
+    /// - `query`: The query string to search for.
+    ///
+    /// # Returns
+    /// - `SearchRequest`: A request with the default categories, order and number of results.
+    pub fn request<'a>(&self, query: &'a str) -> SearchRequest<'a> {
+        SearchRequest {
+            query,
+            order_by: self.defaults.order_by.clone(),
+            categories: self.defaults.categories.clone(),
+            number_of_results: self.defaults.number_of_results,
+        }
+    }
+
     /// Executes a search query across all active providers in sequence and aggregates the results.
     ///
     /// # Parameters
@@ -435,23 +918,47 @@ impl Magneto {
     /// let torrents = magneto.search(request);
     /// ```
     pub async fn search(&self, request: SearchRequest<'_>) -> Result<Vec<Torrent>, ClientError> {
-        let client = Client::new();
         let mut results = Vec::new();
 
         for provider in &self.active_providers {
-            match provider.send_request(&client, request.clone()).await {
+            match provider
+                .send_request(self.transport.as_ref(), request.clone())
+                .await
+            {
                 Ok(mut torrents) => results.append(&mut torrents),
                 Err(e) => return Err(e),
             }
         }
 
-        results.sort_by(|a, b| match request.order_by {
-            OrderBy::Seeders => b.seeders.cmp(&a.seeders),
-            OrderBy::Peers => b.peers.cmp(&a.peers),
-        });
+        Ok(self.rank(results, &request).await)
+    }
 
+    /// Sorts and truncates the results of a request, refreshing the top results if enabled.
+    pub(crate) async fn rank(
+        &self,
+        results: Vec<Torrent>,
+        request: &SearchRequest<'_>,
+    ) -> Vec<Torrent> {
+        let mut results = self.sort(results, &request.order_by);
         results.truncate(request.number_of_results);
 
-        Ok(results)
+        if let Some(refresh) = &self.stats_refresh {
+            let top = refresh.top.min(results.len());
+            if refresh.scraper.scrape(&mut results[..top]).await > 0 {
+                results = self.sort(results, &request.order_by);
+            }
+        }
+
+        results
+    }
+
+    /// Sorts results by the given order.
+    fn sort(&self, mut results: Vec<Torrent>, order_by: &OrderBy) -> Vec<Torrent> {
+        match order_by {
+            OrderBy::Seeders => results.sort_by_key(|torrent| Reverse(torrent.seeders)),
+            OrderBy::Peers => results.sort_by_key(|torrent| Reverse(torrent.peers)),
+            OrderBy::ProfileScore => results = self.quality_profile.sort(results),
+        }
+        results
     }
 }
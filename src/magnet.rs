@@ -0,0 +1,280 @@
+//! # Magnet links
+//!
+//! Parsing and building of magnet links (BEP 9 and BEP 52). A `Magnet` holds
+//! the v1 (SHA-1) and/or v2 (SHA-256) info hash of a torrent together with its
+//! display name, trackers and web seeds.
+
+use core::fmt;
+
+use thiserror::Error;
+use url::{form_urlencoded, Url};
+
+/// The multihash prefix of a SHA-256 digest in a `urn:btmh` topic.
+const SHA256_MULTIHASH_PREFIX: &str = "1220";
+
+/// Represents errors that can occur while parsing a magnet link.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MagnetError {
+    /// The link is not a valid `magnet:` URI.
+    #[error("invalid magnet link: {0}")]
+    InvalidUri(String),
+
+    /// An exact topic (`xt`) contains an invalid info hash.
+    #[error("invalid info hash in exact topic '{0}'")]
+    InvalidInfoHash(String),
+
+    /// The link contains no BitTorrent info hash.
+    #[error("magnet link has no bittorrent info hash")]
+    MissingInfoHash,
+}
+
+/// A parsed magnet link.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Magnet {
+    /// The v1 info hash (SHA-1 of the info dictionary), from `xt=urn:btih:`.
+    pub info_hash: Option<[u8; 20]>,
+
+    /// The v2 info hash (SHA-256 of the info dictionary), from `xt=urn:btmh:`.
+    pub info_hash_v2: Option<[u8; 32]>,
+
+    /// The display name of the torrent, from `dn`.
+    pub display_name: Option<String>,
+
+    /// The tracker URLs, from `tr`.
+    pub trackers: Vec<String>,
+
+    /// The web seed URLs, from `ws`.
+    pub web_seeds: Vec<String>,
+
+    /// The total size of the torrent in bytes, from `xl`.
+    pub exact_length: Option<u64>,
+}
+
+impl Magnet {
+    /// Creates a magnet link for a v1 info hash.
+    pub fn from_info_hash(info_hash: [u8; 20]) -> Self {
+        Self {
+            info_hash: Some(info_hash),
+            ..Default::default()
+        }
+    }
+
+    /// Parses a magnet link.
+    ///
+    /// Both hex and base32 encoded v1 info hashes are accepted.
+    ///
+    /// # Parameters
+    /// - `link`: The magnet link, e.g. "magnet:?xt=urn:btih:...".
+    ///
+    /// # Returns
+    /// - `Ok(Magnet)`: The parsed magnet link.
+    /// - `Err(MagnetError)`: An error if the link is invalid or has no info hash.
+    ///
+    /// # Example
+    /// ```rust
+    /// use magneto::magnet::Magnet;
+    ///
+    /// let magnet = Magnet::parse(
+    ///     "magnet:?xt=urn:btih:c12fe1c06bba254a9dc9f519b335aa7c1367a88a&dn=Example&tr=udp%3A%2F%2Ftracker.example%3A1337",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(magnet.info_hash_hex().as_deref(), Some("c12fe1c06bba254a9dc9f519b335aa7c1367a88a"));
+    /// assert_eq!(magnet.display_name.as_deref(), Some("Example"));
+    /// assert_eq!(magnet.trackers, vec!["udp://tracker.example:1337"]);
+    /// ```
+    pub fn parse(link: &str) -> Result<Self, MagnetError> {
+        let url = Url::parse(link.trim()).map_err(|e| MagnetError::InvalidUri(e.to_string()))?;
+        if url.scheme() != "magnet" {
+            return Err(MagnetError::InvalidUri(format!(
+                "unexpected scheme '{}'",
+                url.scheme()
+            )));
+        }
+
+        let mut magnet = Magnet::default();
+        for (key, value) in url.query_pairs() {
+            // Multiple topics may be numbered, e.g. "xt.1" and "xt.2"
+            let key = key.split('.').next().unwrap_or_default();
+            match key {
+                "xt" => magnet.parse_topic(&value)?,
+                "dn" => magnet.display_name = Some(value.into_owned()),
+                "tr" => magnet.trackers.push(value.into_owned()),
+                "ws" => magnet.web_seeds.push(value.into_owned()),
+                "xl" => magnet.exact_length = value.parse().ok(),
+                _ => {}
+            }
+        }
+
+        if magnet.info_hash.is_none() && magnet.info_hash_v2.is_none() {
+            return Err(MagnetError::MissingInfoHash);
+        }
+
+        Ok(magnet)
+    }
+
+    /// Returns the v1 info hash as lowercase hex.
+    pub fn info_hash_hex(&self) -> Option<String> {
+        self.info_hash.map(hex::encode)
+    }
+
+    /// Parses an exact topic, ignoring topics of other networks.
+    fn parse_topic(&mut self, topic: &str) -> Result<(), MagnetError> {
+        let invalid = || MagnetError::InvalidInfoHash(topic.to_string());
+
+        if let Some(hash) = topic.strip_prefix("urn:btih:") {
+            let hash = match hash.len() {
+                40 => hex::decode(hash).map_err(|_| invalid())?,
+                32 => decode_base32(hash).ok_or_else(invalid)?,
+                _ => return Err(invalid()),
+            };
+            self.info_hash = Some(hash.try_into().map_err(|_| invalid())?);
+        } else if let Some(hash) = topic.strip_prefix("urn:btmh:") {
+            let hash = hash
+                .strip_prefix(SHA256_MULTIHASH_PREFIX)
+                .ok_or_else(invalid)?;
+            let hash = hex::decode(hash).map_err(|_| invalid())?;
+            self.info_hash_v2 = Some(hash.try_into().map_err(|_| invalid())?);
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Magnet {
+    /// Formats the magnet link as a `magnet:` URI.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "magnet:?")?;
+
+        let mut separator = "";
+        if let Some(hash) = self.info_hash {
+            write!(f, "xt=urn:btih:{}", hex::encode(hash))?;
+            separator = "&";
+        }
+        if let Some(hash) = self.info_hash_v2 {
+            write!(
+                f,
+                "{}xt=urn:btmh:{}{}",
+                separator,
+                SHA256_MULTIHASH_PREFIX,
+                hex::encode(hash)
+            )?;
+            separator = "&";
+        }
+
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        if let Some(name) = &self.display_name {
+            query.append_pair("dn", name);
+        }
+        if let Some(length) = self.exact_length {
+            query.append_pair("xl", &length.to_string());
+        }
+        for tracker in &self.trackers {
+            query.append_pair("tr", tracker);
+        }
+        for web_seed in &self.web_seeds {
+            query.append_pair("ws", web_seed);
+        }
+
+        let query = query.finish();
+        if !query.is_empty() {
+            write!(f, "{}{}", separator, query)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Decodes an RFC 4648 base32 string without padding, case-insensitively.
+fn decode_base32(input: &str) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+
+    for c in input.bytes() {
+        let value = match c.to_ascii_uppercase() {
+            c @ b'A'..=b'Z' => c - b'A',
+            c @ b'2'..=b'7' => c - b'2' + 26,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | u64::from(value);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests parsing a magnet link with hex and base32 encoded info hashes.
+    #[test]
+    fn test_parse_info_hash_encodings() {
+        let hex =
+            Magnet::parse("magnet:?xt=urn:btih:C12FE1C06BBA254A9DC9F519B335AA7C1367A88A").unwrap();
+        let base32 = Magnet::parse("magnet:?xt=urn:btih:YEX6DQDLXISUVHOJ6UM3GNNKPQJWPKEK").unwrap();
+
+        assert_eq!(
+            hex.info_hash_hex().as_deref(),
+            Some("c12fe1c06bba254a9dc9f519b335aa7c1367a88a")
+        );
+        assert_eq!(hex.info_hash, base32.info_hash);
+    }
+
+    /// Tests parsing a hybrid magnet link with all supported parameters.
+    #[test]
+    fn test_parse_full_link() {
+        let v2 = "1220".to_string() + &"ab".repeat(32);
+        let link = format!(
+            "magnet:?xt=urn:btih:{}&xt=urn:btmh:{}&dn=My+File&xl=1024&tr=http%3A%2F%2Fa%2Fannounce&tr=udp%3A%2F%2Fb%3A80&ws=http%3A%2F%2Fseed",
+            "11".repeat(20),
+            v2
+        );
+
+        let magnet = Magnet::parse(&link).unwrap();
+        assert_eq!(magnet.info_hash, Some([0x11; 20]));
+        assert_eq!(magnet.info_hash_v2, Some([0xab; 32]));
+        assert_eq!(magnet.display_name.as_deref(), Some("My File"));
+        assert_eq!(magnet.exact_length, Some(1024));
+        assert_eq!(magnet.trackers, vec!["http://a/announce", "udp://b:80"]);
+        assert_eq!(magnet.web_seeds, vec!["http://seed"]);
+
+        assert_eq!(Magnet::parse(&magnet.to_string()), Ok(magnet));
+    }
+
+    /// Tests that invalid magnet links are rejected.
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            Magnet::parse("not a link"),
+            Err(MagnetError::InvalidUri(_))
+        ));
+        assert!(matches!(
+            Magnet::parse("http://example.org/?xt=urn:btih:abc"),
+            Err(MagnetError::InvalidUri(_))
+        ));
+        assert_eq!(
+            Magnet::parse("magnet:?xt=urn:btih:abc123"),
+            Err(MagnetError::InvalidInfoHash("urn:btih:abc123".to_string()))
+        );
+        assert_eq!(
+            Magnet::parse("magnet:?dn=name"),
+            Err(MagnetError::MissingInfoHash)
+        );
+    }
+
+    /// Tests formatting a magnet link with only an info hash.
+    #[test]
+    fn test_display() {
+        let magnet = Magnet::from_info_hash([0xff; 20]);
+        assert_eq!(
+            magnet.to_string(),
+            format!("magnet:?xt=urn:btih:{}", "ff".repeat(20))
+        );
+    }
+}
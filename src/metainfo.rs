@@ -0,0 +1,531 @@
+//! # Metainfo
+//!
+//! Parsing of .torrent files (BEP 3), including v2 (BEP 52) and hybrid
+//! torrents, multiple trackers (BEP 12), private torrents (BEP 27) and web
+//! seeds (BEP 19). A parsed `Metainfo` can be converted into a `Torrent` or a
+//! `Magnet`.
+
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use thiserror::Error;
+
+use crate::{
+    bencode::{self, BencodeError, Value},
+    magnet::Magnet,
+    Torrent, TorrentMetadata,
+};
+
+/// Represents errors that can occur while parsing a .torrent file.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MetainfoError {
+    /// The file is not valid bencode.
+    #[error("invalid bencode: {0}")]
+    Bencode(#[from] BencodeError),
+
+    /// A required field is missing.
+    #[error("missing field '{0}'")]
+    MissingField(&'static str),
+
+    /// A field has an unexpected type or value.
+    #[error("invalid field '{0}'")]
+    InvalidField(&'static str),
+}
+
+/// Enum specifying the BitTorrent protocol version of a torrent.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MetainfoVersion {
+    /// A v1 torrent, identified by a SHA-1 info hash.
+    V1,
+
+    /// A v2 torrent, identified by a SHA-256 info hash.
+    V2,
+
+    /// A torrent usable with both v1 and v2 clients.
+    Hybrid,
+}
+
+/// A file contained in a torrent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetainfoFile {
+    /// The path components of the file. For multi-file torrents the path is
+    /// relative to the torrent's root directory, which is named after the torrent.
+    pub path: Vec<String>,
+
+    /// The size of the file in bytes.
+    pub length: u64,
+}
+
+/// The parsed contents of a .torrent file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metainfo {
+    /// The suggested name of the torrent's file or root directory.
+    pub name: String,
+
+    /// The number of bytes in each piece.
+    pub piece_length: u64,
+
+    /// The files of the torrent, excluding padding files.
+    pub files: Vec<MetainfoFile>,
+
+    /// Tiers of tracker URLs, from `announce-list` or `announce`.
+    pub announce_list: Vec<Vec<String>>,
+
+    /// The v1 info hash (SHA-1 of the info dictionary).
+    pub info_hash: Option<[u8; 20]>,
+
+    /// The v2 info hash (SHA-256 of the info dictionary).
+    pub info_hash_v2: Option<[u8; 32]>,
+
+    /// Whether the torrent is private, restricting peer discovery to its trackers.
+    pub private: bool,
+
+    /// Web seed URLs, from `url-list`.
+    pub web_seeds: Vec<String>,
+}
+
+impl Metainfo {
+    /// Parses the contents of a .torrent file.
+    ///
+    /// # Parameters
+    /// - `bytes`: The bencoded contents of the file.
+    ///
+    /// # Returns
+    /// - `Ok(Metainfo)`: The parsed metainfo.
+    /// - `Err(MetainfoError)`: An error if the file is malformed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MetainfoError> {
+        let root = bencode::decode(bytes)?;
+        let info_bytes =
+            bencode::raw_dict_entry(bytes, b"info")?.ok_or(MetainfoError::MissingField("info"))?;
+
+        let mut metainfo = Self::from_info_bytes(info_bytes)?;
+
+        if let Some(tiers) = root.get("announce-list").and_then(Value::as_list) {
+            metainfo.announce_list = tiers
+                .iter()
+                .filter_map(Value::as_list)
+                .map(|tier| {
+                    tier.iter()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect::<Vec<_>>()
+                })
+                .filter(|tier| !tier.is_empty())
+                .collect();
+        }
+        if metainfo.announce_list.is_empty() {
+            if let Some(announce) = root.get("announce").and_then(Value::as_str) {
+                metainfo.announce_list = vec![vec![announce.to_string()]];
+            }
+        }
+
+        metainfo.web_seeds = match root.get("url-list") {
+            Some(Value::List(urls)) => urls
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect(),
+            Some(url) => url.as_str().map(str::to_string).into_iter().collect(),
+            None => vec![],
+        };
+
+        Ok(metainfo)
+    }
+
+    /// Parses a bencoded info dictionary on its own, e.g. one received from peers.
+    ///
+    /// Trackers and web seeds are stored outside of the info dictionary and are left empty.
+    ///
+    /// # Parameters
+    /// - `info_bytes`: The bencoded info dictionary, hashed as given.
+    ///
+    /// # Returns
+    /// - `Ok(Metainfo)`: The parsed metainfo.
+    /// - `Err(MetainfoError)`: An error if the dictionary is malformed.
+    pub fn from_info_bytes(info_bytes: &[u8]) -> Result<Self, MetainfoError> {
+        let info = bencode::decode(info_bytes)?;
+        if info.as_dict().is_none() {
+            return Err(MetainfoError::InvalidField("info"));
+        }
+
+        let name = info
+            .get("name.utf-8")
+            .or_else(|| info.get("name"))
+            .ok_or(MetainfoError::MissingField("name"))?
+            .as_str()
+            .ok_or(MetainfoError::InvalidField("name"))?
+            .to_string();
+
+        let piece_length = info
+            .get("piece length")
+            .ok_or(MetainfoError::MissingField("piece length"))?
+            .as_integer()
+            .and_then(|length| u64::try_from(length).ok())
+            .ok_or(MetainfoError::InvalidField("piece length"))?;
+
+        let is_v1 = info.get("pieces").is_some();
+        let is_v2 = info.get("meta version").and_then(Value::as_integer) == Some(2);
+        if !is_v1 && !is_v2 {
+            return Err(MetainfoError::MissingField("pieces"));
+        }
+
+        let files = if let Some(length) = info.get("length") {
+            vec![MetainfoFile {
+                path: vec![name.clone()],
+                length: parse_length(length)?,
+            }]
+        } else if let Some(files) = info.get("files") {
+            parse_v1_files(files)?
+        } else if let Some(tree) = info.get("file tree") {
+            let mut files = Vec::new();
+            parse_file_tree(tree, &mut vec![], &mut files)?;
+            files
+        } else {
+            return Err(MetainfoError::MissingField("files"));
+        };
+
+        Ok(Self {
+            name,
+            piece_length,
+            files,
+            announce_list: vec![],
+            info_hash: is_v1.then(|| Sha1::digest(info_bytes).into()),
+            info_hash_v2: is_v2.then(|| Sha256::digest(info_bytes).into()),
+            private: info.get("private").and_then(Value::as_integer) == Some(1),
+            web_seeds: vec![],
+        })
+    }
+
+    /// Returns the BitTorrent protocol version of the torrent.
+    pub fn version(&self) -> MetainfoVersion {
+        match (self.info_hash, self.info_hash_v2) {
+            (Some(_), Some(_)) => MetainfoVersion::Hybrid,
+            (None, Some(_)) => MetainfoVersion::V2,
+            _ => MetainfoVersion::V1,
+        }
+    }
+
+    /// Returns the total size of all files in bytes, saturating at `u64::MAX`.
+    pub fn total_size(&self) -> u64 {
+        self.files
+            .iter()
+            .map(|file| file.length)
+            .fold(0u64, u64::saturating_add)
+    }
+
+    /// Returns all tracker URLs, in tier order without duplicates.
+    pub fn trackers(&self) -> Vec<String> {
+        let mut trackers: Vec<String> = Vec::new();
+        for tracker in self.announce_list.iter().flatten() {
+            if !trackers.contains(tracker) {
+                trackers.push(tracker.clone());
+            }
+        }
+        trackers
+    }
+
+    /// Builds a magnet link for the torrent, including its trackers and web seeds.
+    pub fn to_magnet(&self) -> Magnet {
+        Magnet {
+            info_hash: self.info_hash,
+            info_hash_v2: self.info_hash_v2,
+            display_name: Some(self.name.clone()),
+            trackers: self.trackers(),
+            web_seeds: self.web_seeds.clone(),
+            exact_length: Some(self.total_size()),
+        }
+    }
+
+    /// Converts the metainfo into a `Torrent`.
+    ///
+    /// Seeders and peers are unknown and set to zero. The provider is set to "metainfo".
+    pub fn to_torrent(&self) -> Torrent {
+        Torrent {
+            name: self.name.clone(),
+            magnet_link: self.to_magnet().to_string(),
+            seeders: 0,
+            peers: 0,
+            size_bytes: self.total_size(),
+            provider: "metainfo".to_string(),
+            metadata: TorrentMetadata {
+                file_count: u32::try_from(self.files.len()).ok(),
+                ..Default::default()
+            },
+            stats_updated_at: None,
+        }
+    }
+}
+
+/// Builds the contents of a .torrent file around a bencoded info dictionary.
+///
+/// The info dictionary is embedded unchanged so the info hash is preserved.
+///
+/// # Parameters
+/// - `info_bytes`: The bencoded info dictionary.
+/// - `announce_list`: Tiers of tracker URLs. The first tracker is also written as `announce`.
+/// - `web_seeds`: Web seed URLs, written as `url-list`.
+pub fn build_torrent_file(
+    info_bytes: &[u8],
+    announce_list: &[Vec<String>],
+    web_seeds: &[String],
+) -> Vec<u8> {
+    let mut output = Vec::with_capacity(info_bytes.len() + 256);
+    // Keys must be written in sorted order: announce, announce-list, info, url-list
+    output.push(b'd');
+    if let Some(announce) = announce_list.iter().flatten().next() {
+        bencode::encode_into(&Value::bytes("announce"), &mut output);
+        bencode::encode_into(&Value::bytes(announce.as_str()), &mut output);
+
+        let tiers = announce_list
+            .iter()
+            .map(|tier| Value::List(tier.iter().map(|url| Value::bytes(url.as_str())).collect()))
+            .collect();
+        bencode::encode_into(&Value::bytes("announce-list"), &mut output);
+        bencode::encode_into(&Value::List(tiers), &mut output);
+    }
+    bencode::encode_into(&Value::bytes("info"), &mut output);
+    output.extend_from_slice(info_bytes);
+    if !web_seeds.is_empty() {
+        let urls = web_seeds
+            .iter()
+            .map(|url| Value::bytes(url.as_str()))
+            .collect();
+        bencode::encode_into(&Value::bytes("url-list"), &mut output);
+        bencode::encode_into(&Value::List(urls), &mut output);
+    }
+    output.push(b'e');
+    output
+}
+
+/// Parses a non-negative file length.
+fn parse_length(length: &Value<'_>) -> Result<u64, MetainfoError> {
+    length
+        .as_integer()
+        .and_then(|length| u64::try_from(length).ok())
+        .ok_or(MetainfoError::InvalidField("length"))
+}
+
+/// Parses the `files` list of a v1 multi-file torrent, skipping padding files (BEP 47).
+fn parse_v1_files(files: &Value<'_>) -> Result<Vec<MetainfoFile>, MetainfoError> {
+    let files = files
+        .as_list()
+        .ok_or(MetainfoError::InvalidField("files"))?;
+
+    let mut parsed = Vec::with_capacity(files.len());
+    for file in files {
+        let is_padding = file
+            .get("attr")
+            .and_then(Value::as_bytes)
+            .is_some_and(|attr| attr.contains(&b'p'));
+        if is_padding {
+            continue;
+        }
+
+        let path = file
+            .get("path.utf-8")
+            .or_else(|| file.get("path"))
+            .and_then(Value::as_list)
+            .ok_or(MetainfoError::MissingField("path"))?
+            .iter()
+            .map(|component| component.as_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(MetainfoError::InvalidField("path"))?;
+
+        let length = file
+            .get("length")
+            .ok_or(MetainfoError::MissingField("length"))?;
+
+        parsed.push(MetainfoFile {
+            path,
+            length: parse_length(length)?,
+        });
+    }
+
+    Ok(parsed)
+}
+
+/// Recursively parses the `file tree` of a v2 torrent.
+///
+/// Files are dictionaries with an empty key holding the file's properties.
+fn parse_file_tree(
+    tree: &Value<'_>,
+    path: &mut Vec<String>,
+    files: &mut Vec<MetainfoFile>,
+) -> Result<(), MetainfoError> {
+    let tree = tree
+        .as_dict()
+        .ok_or(MetainfoError::InvalidField("file tree"))?;
+
+    for (key, value) in tree {
+        if key.is_empty() {
+            let length = value
+                .get("length")
+                .ok_or(MetainfoError::MissingField("length"))?;
+            files.push(MetainfoFile {
+                path: path.clone(),
+                length: parse_length(length)?,
+            });
+            continue;
+        }
+
+        let component =
+            std::str::from_utf8(key).map_err(|_| MetainfoError::InvalidField("file tree"))?;
+        path.push(component.to_string());
+        parse_file_tree(value, path, files)?;
+        path.pop();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bencode::encode;
+
+    /// Builds the info dictionary of a v1 multi-file torrent with a padding file.
+    fn v1_info() -> Value<'static> {
+        let file = |path: &[&str], length: i64| {
+            Value::dict().with("length", Value::Integer(length)).with(
+                "path",
+                Value::List(path.iter().map(|p| Value::bytes(*p)).collect()),
+            )
+        };
+
+        Value::dict()
+            .with("name", Value::bytes("album"))
+            .with("piece length", Value::Integer(16384))
+            .with("pieces", Value::bytes(vec![0u8; 20]))
+            .with("private", Value::Integer(1))
+            .with(
+                "files",
+                Value::List(vec![
+                    file(&["cd1", "01.flac"], 1000),
+                    file(&[".pad", "15384"], 15384).with("attr", Value::bytes("p")),
+                    file(&["cover.jpg"], 24),
+                ]),
+            )
+    }
+
+    /// Tests parsing a v1 multi-file torrent with trackers and web seeds.
+    ///
+    /// The expected info hash was computed independently with Python's hashlib.
+    #[test]
+    fn test_parse_v1_torrent() {
+        let torrent = Value::dict()
+            .with("announce", Value::bytes("http://ignored/announce"))
+            .with(
+                "announce-list",
+                Value::List(vec![
+                    Value::List(vec![Value::bytes("http://a/announce")]),
+                    Value::List(vec![
+                        Value::bytes("udp://b:80"),
+                        Value::bytes("http://a/announce"),
+                    ]),
+                ]),
+            )
+            .with("url-list", Value::bytes("http://seed/"))
+            .with("info", v1_info());
+
+        let metainfo = Metainfo::from_bytes(&encode(&torrent)).unwrap();
+
+        assert_eq!(metainfo.name, "album");
+        assert_eq!(metainfo.piece_length, 16384);
+        assert_eq!(metainfo.version(), MetainfoVersion::V1);
+        assert_eq!(
+            hex::encode(metainfo.info_hash.unwrap()),
+            "659311fe6aea1c70ef6290912022313dee842e68"
+        );
+        assert!(metainfo.private);
+        assert_eq!(
+            metainfo.files,
+            vec![
+                MetainfoFile {
+                    path: vec!["cd1".to_string(), "01.flac".to_string()],
+                    length: 1000
+                },
+                MetainfoFile {
+                    path: vec!["cover.jpg".to_string()],
+                    length: 24
+                },
+            ]
+        );
+        assert_eq!(metainfo.trackers(), vec!["http://a/announce", "udp://b:80"]);
+        assert_eq!(metainfo.web_seeds, vec!["http://seed/"]);
+
+        let magnet = metainfo.to_magnet();
+        assert_eq!(magnet.info_hash, metainfo.info_hash);
+        assert_eq!(magnet.exact_length, Some(1024));
+
+        let torrent = metainfo.to_torrent();
+        assert_eq!(torrent.name, "album");
+        assert_eq!(torrent.size_bytes, 1024);
+        assert_eq!(torrent.metadata.file_count, Some(2));
+        assert_eq!(Magnet::parse(&torrent.magnet_link), Ok(magnet));
+
+        let huge_file = |name: &'static str| {
+            Value::dict()
+                .with("length", Value::Integer(i64::MAX))
+                .with("path", Value::List(vec![Value::bytes(name)]))
+        };
+        let huge = v1_info().with(
+            "files",
+            Value::List(vec![huge_file("a"), huge_file("b"), huge_file("c")]),
+        );
+        let metainfo = Metainfo::from_info_bytes(&encode(&huge)).unwrap();
+        assert_eq!(metainfo.total_size(), u64::MAX);
+    }
+
+    /// Tests parsing single-file v2 and hybrid torrents.
+    #[test]
+    fn test_parse_v2_and_hybrid_torrents() {
+        let file_tree = Value::dict().with(
+            "file.iso",
+            Value::dict().with("", Value::dict().with("length", Value::Integer(4096))),
+        );
+        let v2_info = Value::dict()
+            .with("name", Value::bytes("file.iso"))
+            .with("piece length", Value::Integer(16384))
+            .with("meta version", Value::Integer(2))
+            .with("file tree", file_tree);
+
+        let torrent = Value::dict().with("info", v2_info.clone());
+        let metainfo = Metainfo::from_bytes(&encode(&torrent)).unwrap();
+        assert_eq!(metainfo.version(), MetainfoVersion::V2);
+        assert_eq!(metainfo.info_hash, None);
+        assert_eq!(
+            metainfo.info_hash_v2,
+            Some(Sha256::digest(encode(&v2_info)).into())
+        );
+        assert_eq!(metainfo.files[0].path, vec!["file.iso"]);
+        assert_eq!(metainfo.total_size(), 4096);
+
+        let hybrid_info = v2_info
+            .with("length", Value::Integer(4096))
+            .with("pieces", Value::bytes(vec![0u8; 20]));
+        let torrent = Value::dict().with("info", hybrid_info);
+        let metainfo = Metainfo::from_bytes(&encode(&torrent)).unwrap();
+        assert_eq!(metainfo.version(), MetainfoVersion::Hybrid);
+        assert!(metainfo.info_hash.is_some() && metainfo.info_hash_v2.is_some());
+    }
+
+    /// Tests that malformed torrents are rejected.
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            Metainfo::from_bytes(b"not bencode"),
+            Err(MetainfoError::Bencode(_))
+        ));
+        assert_eq!(
+            Metainfo::from_bytes(b"d8:announce3:urle"),
+            Err(MetainfoError::MissingField("info"))
+        );
+
+        let info = Value::dict()
+            .with("name", Value::bytes("x"))
+            .with("piece length", Value::Integer(-1));
+        assert_eq!(
+            Metainfo::from_bytes(&encode(&Value::dict().with("info", info))),
+            Err(MetainfoError::InvalidField("piece length"))
+        );
+    }
+}
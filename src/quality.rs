@@ -0,0 +1,348 @@
+//! # Quality profiles
+//!
+//! A `QualityProfile` scores torrents by their parsed release attributes
+//! (see `ReleaseInfo`), similar to the quality profiles of Sonarr and Radarr.
+//! Torrents breaking a rule of the profile are rejected, the remaining ones are
+//! scored by how well they match the profile's preferences.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    release::{Resolution, VideoCodec},
+    ReleaseInfo, Torrent,
+};
+
+/// Estimated runtime of a single episode, used when the profile has no runtime.
+const EPISODE_MINUTES: u64 = 45;
+
+/// Estimated runtime of a movie, used when the profile has no runtime.
+const MOVIE_MINUTES: u64 = 120;
+
+/// Describes which releases are acceptable and which are preferred.
+///
+/// Empty lists and `None` values disable the corresponding rule, so the default
+/// profile accepts every torrent and scores them equally.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct QualityProfile {
+    /// Resolutions that are accepted. Empty means all resolutions are accepted.
+    pub allowed_resolutions: Vec<Resolution>,
+
+    /// Resolutions in order of preference, the first being the most preferred.
+    pub preferred_resolutions: Vec<Resolution>,
+
+    /// Video codecs that are accepted. Empty means all codecs are accepted.
+    pub allowed_video_codecs: Vec<VideoCodec>,
+
+    /// Video codecs in order of preference, the first being the most preferred.
+    pub preferred_video_codecs: Vec<VideoCodec>,
+
+    /// The minimum size in bytes per minute of runtime.
+    pub min_bytes_per_minute: Option<u64>,
+
+    /// The maximum size in bytes per minute of runtime.
+    pub max_bytes_per_minute: Option<u64>,
+
+    /// The runtime in minutes used for the size rules. When unset, the runtime
+    /// is estimated from the number of episodes, season packs are not size checked.
+    pub runtime_minutes: Option<u32>,
+
+    /// The minimum number of seeders.
+    pub min_seeders: u32,
+
+    /// Release groups that are rejected, compared case-insensitively.
+    pub banned_groups: Vec<String>,
+
+    /// Keywords that reject a torrent when found in its name, compared case-insensitively.
+    pub banned_keywords: Vec<String>,
+}
+
+impl QualityProfile {
+    /// Creates a new `QualityProfile` accepting every torrent.
+    ///
+    /// # Example
+    /// ```rust
+    /// use magneto::{release::{Resolution, VideoCodec}, QualityProfile};
+    ///
+    /// let profile = QualityProfile::new()
+    ///     .prefer_resolutions(vec![Resolution::P1080, Resolution::P720])
+    ///     .prefer_video_codecs(vec![VideoCodec::H265])
+    ///     .min_seeders(5)
+    ///     .ban_groups(vec!["YIFY".to_string()]);
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restricts the accepted resolutions.
+    pub fn allow_resolutions(mut self, resolutions: Vec<Resolution>) -> Self {
+        self.allowed_resolutions = resolutions;
+        self
+    }
+
+    /// Sets the preferred resolutions, the first being the most preferred.
+    pub fn prefer_resolutions(mut self, resolutions: Vec<Resolution>) -> Self {
+        self.preferred_resolutions = resolutions;
+        self
+    }
+
+    /// Restricts the accepted video codecs.
+    pub fn allow_video_codecs(mut self, codecs: Vec<VideoCodec>) -> Self {
+        self.allowed_video_codecs = codecs;
+        self
+    }
+
+    /// Sets the preferred video codecs, the first being the most preferred.
+    pub fn prefer_video_codecs(mut self, codecs: Vec<VideoCodec>) -> Self {
+        self.preferred_video_codecs = codecs;
+        self
+    }
+
+    /// Sets the accepted range of bytes per minute of runtime.
+    pub fn bytes_per_minute(mut self, min: Option<u64>, max: Option<u64>) -> Self {
+        self.min_bytes_per_minute = min;
+        self.max_bytes_per_minute = max;
+        self
+    }
+
+    /// Sets the minimum number of seeders.
+    pub fn min_seeders(mut self, seeders: u32) -> Self {
+        self.min_seeders = seeders;
+        self
+    }
+
+    /// Adds release groups to reject.
+    pub fn ban_groups(mut self, groups: Vec<String>) -> Self {
+        self.banned_groups.extend(groups);
+        self
+    }
+
+    /// Adds keywords to reject.
+    pub fn ban_keywords(mut self, keywords: Vec<String>) -> Self {
+        self.banned_keywords.extend(keywords);
+        self
+    }
+
+    /// Scores a torrent against the profile.
+    ///
+    /// # Parameters
+    /// - `torrent`: The torrent to score.
+    ///
+    /// # Returns
+    /// - `Some(i64)`: The score of an accepted torrent, higher is better.
+    /// - `None`: If the torrent breaks a rule of the profile.
+    pub fn score(&self, torrent: &Torrent) -> Option<i64> {
+        let info = torrent.release_info();
+
+        if torrent.seeders < self.min_seeders || self.is_banned(torrent, &info) {
+            return None;
+        }
+        if !is_allowed(&self.allowed_resolutions, info.resolution)
+            || !is_allowed(&self.allowed_video_codecs, info.video_codec)
+            || !self.is_size_allowed(torrent, &info)
+        {
+            return None;
+        }
+
+        let mut score = 0;
+        score += preference(&self.preferred_resolutions, info.resolution) * 1000;
+        score += preference(&self.preferred_video_codecs, info.video_codec) * 100;
+        if info.proper || info.repack {
+            score += 10;
+        }
+
+        Some(score)
+    }
+
+    /// Sorts torrents by their score, dropping the rejected ones.
+    ///
+    /// Torrents with equal scores are sorted by their number of seeders.
+    ///
+    /// # Parameters
+    /// - `torrents`: The torrents to sort.
+    ///
+    /// # Returns
+    /// - `Vec<Torrent>`: The accepted torrents, best match first.
+    pub fn sort(&self, torrents: Vec<Torrent>) -> Vec<Torrent> {
+        let mut scored: Vec<(i64, Torrent)> = torrents
+            .into_iter()
+            .filter_map(|torrent| self.score(&torrent).map(|score| (score, torrent)))
+            .collect();
+
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            b_score.cmp(a_score).then(b.seeders.cmp(&a.seeders))
+        });
+
+        scored.into_iter().map(|(_, torrent)| torrent).collect()
+    }
+
+    /// Returns whether the torrent is from a banned group or contains a banned keyword.
+    fn is_banned(&self, torrent: &Torrent, info: &ReleaseInfo) -> bool {
+        let name = torrent.name.to_lowercase();
+        let banned_group = info.group.as_ref().is_some_and(|group| {
+            self.banned_groups
+                .iter()
+                .any(|banned| banned.eq_ignore_ascii_case(group))
+        });
+
+        banned_group
+            || self
+                .banned_keywords
+                .iter()
+                .any(|keyword| name.contains(&keyword.to_lowercase()))
+    }
+
+    /// Returns whether the torrent size per minute of runtime is within the profile's range.
+    fn is_size_allowed(&self, torrent: &Torrent, info: &ReleaseInfo) -> bool {
+        if self.min_bytes_per_minute.is_none() && self.max_bytes_per_minute.is_none() {
+            return true;
+        }
+
+        let runtime = match self.runtime_minutes {
+            Some(minutes) => u64::from(minutes),
+            None if info.is_season_pack() => return true,
+            None if !info.episodes.is_empty() => EPISODE_MINUTES * info.episodes.len() as u64,
+            None => MOVIE_MINUTES,
+        };
+        if runtime == 0 {
+            return true;
+        }
+
+        let bytes_per_minute = torrent.size_bytes / runtime;
+        self.min_bytes_per_minute
+            .is_none_or(|min| bytes_per_minute >= min)
+            && self
+                .max_bytes_per_minute
+                .is_none_or(|max| bytes_per_minute <= max)
+    }
+}
+
+/// Returns whether the value is in the allowed list, an empty list allows everything.
+///
+/// Unknown values are only allowed when the list is empty.
+fn is_allowed<T: PartialEq>(allowed: &[T], value: Option<T>) -> bool {
+    allowed.is_empty() || value.is_some_and(|value| allowed.contains(&value))
+}
+
+/// Returns the preference of a value, the first entry of the list scoring highest.
+fn preference<T: PartialEq>(preferred: &[T], value: Option<T>) -> i64 {
+    value
+        .and_then(|value| preferred.iter().position(|entry| *entry == value))
+        .map_or(0, |position| (preferred.len() - position) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TorrentMetadata;
+
+    /// Creates a torrent with the given name, seeders and size.
+    fn torrent(name: &str, seeders: u32, size_bytes: u64) -> Torrent {
+        Torrent {
+            name: name.to_string(),
+            magnet_link: "magnet:?xt=urn:btih:abc123".to_string(),
+            seeders,
+            peers: 0,
+            size_bytes,
+            provider: "test".to_string(),
+            metadata: TorrentMetadata::default(),
+            stats_updated_at: None,
+        }
+    }
+
+    /// Tests that the default profile accepts every torrent with an equal score.
+    #[test]
+    fn test_default_profile_accepts_all() {
+        let profile = QualityProfile::new();
+
+        assert_eq!(profile.score(&torrent("Anything", 0, 0)), Some(0));
+        assert_eq!(
+            profile.score(&torrent("Movie.2010.1080p.BluRay.x264-GRP", 1, 1)),
+            Some(0)
+        );
+    }
+
+    /// Tests that preferred resolutions and codecs are scored in order.
+    #[test]
+    fn test_score_preferences() {
+        let profile = QualityProfile::new()
+            .prefer_resolutions(vec![Resolution::P1080, Resolution::P720])
+            .prefer_video_codecs(vec![VideoCodec::H265, VideoCodec::H264]);
+
+        let sorted = profile.sort(vec![
+            torrent("Movie.2010.720p.BluRay.x265-GRP", 100, 0),
+            torrent("Movie.2010.1080p.BluRay.x264-GRP", 10, 0),
+            torrent("Movie.2010.1080p.BluRay.x265-GRP", 1, 0),
+            torrent("Movie.2010.480p.DVDRip.XviD-GRP", 1000, 0),
+        ]);
+
+        let names: Vec<&str> = sorted.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Movie.2010.1080p.BluRay.x265-GRP",
+                "Movie.2010.1080p.BluRay.x264-GRP",
+                "Movie.2010.720p.BluRay.x265-GRP",
+                "Movie.2010.480p.DVDRip.XviD-GRP",
+            ]
+        );
+    }
+
+    /// Tests that torrents breaking the profile's rules are rejected.
+    #[test]
+    fn test_rejections() {
+        let profile = QualityProfile::new()
+            .allow_resolutions(vec![Resolution::P1080])
+            .min_seeders(5)
+            .ban_groups(vec!["badgroup".to_string()])
+            .ban_keywords(vec!["CAM".to_string()]);
+
+        assert!(profile
+            .score(&torrent("Movie.2010.1080p.WEB-DL.x264-GRP", 5, 0))
+            .is_some());
+        assert!(profile
+            .score(&torrent("Movie.2010.720p.WEB-DL.x264-GRP", 5, 0))
+            .is_none());
+        assert!(profile
+            .score(&torrent("Movie.2010.1080p.WEB-DL.x264-GRP", 4, 0))
+            .is_none());
+        assert!(profile
+            .score(&torrent("Movie.2010.1080p.WEB-DL.x264-BadGroup", 5, 0))
+            .is_none());
+        assert!(profile
+            .score(&torrent("Movie.2010.1080p.HDCAM.x264-GRP", 5, 0))
+            .is_none());
+        assert!(profile.score(&torrent("Movie 2010", 5, 0)).is_none());
+    }
+
+    /// Tests the size per minute rules for movies, episodes and season packs.
+    #[test]
+    fn test_size_per_minute() {
+        let profile = QualityProfile::new().bytes_per_minute(Some(10_000_000), Some(50_000_000));
+
+        // 120 minutes estimated for a movie
+        assert!(profile
+            .score(&torrent("Movie.2010.1080p", 0, 2_400_000_000))
+            .is_some());
+        assert!(profile
+            .score(&torrent("Movie.2010.1080p", 0, 700_000_000))
+            .is_none());
+
+        // 45 minutes estimated per episode
+        assert!(profile
+            .score(&torrent("Show.S01E01E02.720p", 0, 2_000_000_000))
+            .is_some());
+        assert!(profile
+            .score(&torrent("Show.S01E01.720p", 0, 4_000_000_000))
+            .is_none());
+
+        // Season packs are not size checked without a runtime
+        assert!(profile.score(&torrent("Show.S01.720p", 0, 1)).is_some());
+
+        let profile = QualityProfile {
+            runtime_minutes: Some(10),
+            ..profile
+        };
+        assert!(profile.score(&torrent("Show.S01.720p", 0, 1)).is_none());
+    }
+}
@@ -0,0 +1,758 @@
+//! # Release names
+//!
+//! Parses scene and P2P style release names, such as
+//! `Show.Name.S01E02.1080p.WEB-DL.DDP5.1.H.264-GROUP`, into a structured
+//! `ReleaseInfo`. Parsing is best effort: unknown tokens are ignored and
+//! attributes that cannot be found are left empty.
+
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// File extensions stripped from the end of a release name before parsing.
+const EXTENSIONS: [&str; 7] = [".mkv", ".mp4", ".avi", ".m4v", ".ts", ".iso", ".torrent"];
+
+/// The widest season range expanded, wider ones are not parsed as ranges.
+const MAX_SEASON_RANGE: u32 = 100;
+
+/// The widest episode range expanded, wider ones are not parsed as ranges.
+const MAX_EPISODE_RANGE: u32 = 1000;
+
+/// Structured attributes parsed from a release name.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReleaseInfo {
+    /// The title of the release, with separators replaced by spaces.
+    pub title: String,
+
+    /// The release year, if present.
+    pub year: Option<u32>,
+
+    /// The seasons included in the release. Season packs spanning several
+    /// seasons (e.g. `S01-S03`) list every season.
+    pub seasons: Vec<u32>,
+
+    /// The episodes included in the release. Multi-episode releases
+    /// (e.g. `S01E01-E03`) list every episode.
+    pub episodes: Vec<u32>,
+
+    /// Whether the release is marked as a complete series or season.
+    pub complete: bool,
+
+    /// The video resolution.
+    pub resolution: Option<Resolution>,
+
+    /// The source the release was made from.
+    pub source: Option<Source>,
+
+    /// The video codec.
+    pub video_codec: Option<VideoCodec>,
+
+    /// The audio codec.
+    pub audio_codec: Option<AudioCodec>,
+
+    /// The HDR formats present in the release.
+    pub hdr: Vec<HdrFormat>,
+
+    /// The languages tagged in the release, e.g. "French" or "Multi".
+    pub languages: Vec<String>,
+
+    /// The release group.
+    pub group: Option<String>,
+
+    /// Whether the release is a proper, fixing an earlier release by another group.
+    pub proper: bool,
+
+    /// Whether the release is a repack, fixing an earlier release by the same group.
+    pub repack: bool,
+}
+
+/// Enum specifying the video resolution of a release.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Resolution {
+    /// Standard definition, 480 lines.
+    P480,
+
+    /// Standard definition, 576 lines.
+    P576,
+
+    /// High definition, 720 lines.
+    P720,
+
+    /// Full high definition, 1080 lines.
+    P1080,
+
+    /// Ultra high definition, 2160 lines (4K).
+    P2160,
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Resolution::P480 => write!(f, "480p"),
+            Resolution::P576 => write!(f, "576p"),
+            Resolution::P720 => write!(f, "720p"),
+            Resolution::P1080 => write!(f, "1080p"),
+            Resolution::P2160 => write!(f, "2160p"),
+        }
+    }
+}
+
+/// Enum specifying the source a release was made from, ordered from lowest to highest quality.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Source {
+    /// Recorded in a cinema with a camera.
+    Cam,
+
+    /// Recorded in a cinema with a direct audio source.
+    Telesync,
+
+    /// Ripped from a DVD.
+    Dvd,
+
+    /// Captured from a TV broadcast.
+    Hdtv,
+
+    /// Captured from a streaming service.
+    WebRip,
+
+    /// Downloaded untouched from a streaming service.
+    WebDl,
+
+    /// Ripped from a Blu-ray disc.
+    BluRay,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Cam => write!(f, "CAM"),
+            Source::Telesync => write!(f, "TS"),
+            Source::Dvd => write!(f, "DVD"),
+            Source::Hdtv => write!(f, "HDTV"),
+            Source::WebRip => write!(f, "WEBRip"),
+            Source::WebDl => write!(f, "WEB-DL"),
+            Source::BluRay => write!(f, "BluRay"),
+        }
+    }
+}
+
+/// Enum specifying the video codec of a release.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum VideoCodec {
+    /// MPEG-4 Part 2 (XviD/DivX).
+    Xvid,
+
+    /// H.264 / AVC (x264).
+    H264,
+
+    /// H.265 / HEVC (x265).
+    H265,
+
+    /// VP9.
+    Vp9,
+
+    /// AV1.
+    Av1,
+}
+
+impl fmt::Display for VideoCodec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VideoCodec::Xvid => write!(f, "XviD"),
+            VideoCodec::H264 => write!(f, "H.264"),
+            VideoCodec::H265 => write!(f, "H.265"),
+            VideoCodec::Vp9 => write!(f, "VP9"),
+            VideoCodec::Av1 => write!(f, "AV1"),
+        }
+    }
+}
+
+/// Enum specifying the audio codec of a release.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum AudioCodec {
+    /// MPEG-1 Audio Layer III.
+    Mp3,
+
+    /// Advanced Audio Coding.
+    Aac,
+
+    /// Opus.
+    Opus,
+
+    /// Dolby Digital (AC-3).
+    Ac3,
+
+    /// Dolby Digital Plus (E-AC-3).
+    Eac3,
+
+    /// DTS.
+    Dts,
+
+    /// DTS-HD (Master Audio or High Resolution).
+    DtsHd,
+
+    /// Dolby TrueHD.
+    TrueHd,
+
+    /// Dolby Atmos, when no other codec is tagged.
+    Atmos,
+
+    /// Free Lossless Audio Codec.
+    Flac,
+}
+
+impl fmt::Display for AudioCodec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AudioCodec::Mp3 => write!(f, "MP3"),
+            AudioCodec::Aac => write!(f, "AAC"),
+            AudioCodec::Opus => write!(f, "Opus"),
+            AudioCodec::Ac3 => write!(f, "DD"),
+            AudioCodec::Eac3 => write!(f, "DDP"),
+            AudioCodec::Dts => write!(f, "DTS"),
+            AudioCodec::DtsHd => write!(f, "DTS-HD"),
+            AudioCodec::TrueHd => write!(f, "TrueHD"),
+            AudioCodec::Atmos => write!(f, "Atmos"),
+            AudioCodec::Flac => write!(f, "FLAC"),
+        }
+    }
+}
+
+/// Enum specifying the HDR formats of a release.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum HdrFormat {
+    /// HDR10, also used for releases only tagged "HDR".
+    Hdr10,
+
+    /// HDR10+.
+    Hdr10Plus,
+
+    /// Dolby Vision.
+    DolbyVision,
+
+    /// Hybrid Log-Gamma.
+    Hlg,
+}
+
+impl fmt::Display for HdrFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HdrFormat::Hdr10 => write!(f, "HDR10"),
+            HdrFormat::Hdr10Plus => write!(f, "HDR10+"),
+            HdrFormat::DolbyVision => write!(f, "DV"),
+            HdrFormat::Hlg => write!(f, "HLG"),
+        }
+    }
+}
+
+impl ReleaseInfo {
+    /// Parses a release name into a `ReleaseInfo`.
+    ///
+    /// # Parameters
+    /// - `name`: The release name, e.g. a `Torrent::name`.
+    ///
+    /// # Returns
+    /// - `ReleaseInfo`: The parsed attributes, attributes not found are left empty.
+    ///
+    /// # Example
+    /// ```rust
+    /// use magneto::release::{ReleaseInfo, Resolution, Source, VideoCodec};
+    ///
+    /// let info = ReleaseInfo::parse("Show.Name.S01E02.1080p.WEB-DL.DDP5.1.H.264-GROUP");
+    /// assert_eq!(info.title, "Show Name");
+    /// assert_eq!(info.seasons, vec![1]);
+    /// assert_eq!(info.episodes, vec![2]);
+    /// assert_eq!(info.resolution, Some(Resolution::P1080));
+    /// assert_eq!(info.source, Some(Source::WebDl));
+    /// assert_eq!(info.video_codec, Some(VideoCodec::H264));
+    /// assert_eq!(info.group.as_deref(), Some("GROUP"));
+    /// ```
+    pub fn parse(name: &str) -> Self {
+        let mut info = ReleaseInfo::default();
+
+        let mut name = name.trim();
+        for extension in EXTENSIONS {
+            if name.len() > extension.len() && name.to_lowercase().ends_with(extension) {
+                name = &name[..name.len() - extension.len()];
+                break;
+            }
+        }
+
+        // Anime style releases put the group first: "[Group] Title - 01 [1080p]"
+        if let Some(rest) = name.strip_prefix('[') {
+            if let Some((group, rest)) = rest.split_once(']') {
+                info.group = Some(group.trim().to_string()).filter(|group| !group.is_empty());
+                name = rest;
+            }
+        }
+
+        let mut tokens: Vec<&str> = name
+            .split(|c: char| c == '.' || c == '_' || c == ' ' || "()[]{}".contains(c))
+            .filter(|token| !token.is_empty())
+            .collect();
+
+        // Scene style releases put the group last: "Title.1080p.x264-GROUP"
+        let mut suffixed_token = None;
+        if info.group.is_none() {
+            if let Some(last) = tokens.last_mut() {
+                if let Some((head, group)) = last.rsplit_once('-') {
+                    let known = !matches!(parse_token(group, None), Token::Unknown)
+                        || matches!(group.to_lowercase().as_str(), "dl" | "rip" | "hd" | "ray");
+                    if !head.is_empty() && !group.is_empty() && !known {
+                        suffixed_token = Some(*last);
+                        info.group = Some(group.to_string());
+                        *last = head;
+                    }
+                }
+            }
+        }
+
+        let mut title_end = None;
+        let mut index = 0;
+        while index < tokens.len() {
+            let next = tokens.get(index + 1).copied();
+            let token = parse_token(tokens[index], next);
+
+            // A year is only a marker when it is not the start of the title, and
+            // when the title itself does not end in a year (e.g. "Blade Runner 2049 2017").
+            // A standalone dash only ends the title when an episode number follows it.
+            let is_title = match token {
+                Token::Year(_) => index == 0 || next.is_some_and(|next| parse_year(next).is_some()),
+                Token::Dash => parse_absolute_episodes(next).is_none(),
+                Token::Language(_) | Token::Hdr(_) | Token::Audio(_) | Token::Unknown => true,
+                _ => false,
+            };
+
+            if title_end.is_none() {
+                if is_title {
+                    index += 1;
+                    continue;
+                }
+                title_end = Some(index);
+            }
+
+            index += info.apply(token, next);
+        }
+
+        // Without any release tags the name is not a release name, so a
+        // trailing dash is part of the title rather than a group.
+        if let (None, Some(token)) = (title_end, suffixed_token) {
+            if let Some(last) = tokens.last_mut() {
+                *last = token;
+            }
+            info.group = None;
+        }
+
+        let title_tokens = &tokens[..title_end.unwrap_or(tokens.len())];
+        info.title = title_tokens
+            .iter()
+            .copied()
+            .filter(|token| *token != "-")
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        info
+    }
+
+    /// Returns whether the release contains whole seasons rather than single episodes.
+    pub fn is_season_pack(&self) -> bool {
+        !self.seasons.is_empty() && self.episodes.is_empty()
+    }
+
+    /// Applies a parsed token to the release info.
+    ///
+    /// # Returns
+    /// - `usize`: The number of tokens consumed.
+    fn apply(&mut self, token: Token, next: Option<&str>) -> usize {
+        match token {
+            Token::Year(year) => self.year = self.year.or(Some(year)),
+            Token::SeasonEpisode(seasons, episodes) => {
+                self.seasons.extend(seasons);
+                self.episodes.extend(episodes);
+            }
+            Token::SeasonWord => {
+                if let Some(seasons) = next.and_then(parse_seasons) {
+                    self.seasons.extend(seasons);
+                    return 2;
+                }
+            }
+            Token::Dash => {
+                if let Some(episodes) = parse_absolute_episodes(next) {
+                    self.episodes.extend(episodes);
+                    return 2;
+                }
+            }
+            Token::Complete => self.complete = true,
+            Token::Resolution(resolution) => self.resolution = self.resolution.or(Some(resolution)),
+            Token::Source(source) => self.source = self.source.or(Some(source)),
+            Token::Video(codec, consumed) => {
+                self.video_codec = self.video_codec.or(Some(codec));
+                return consumed;
+            }
+            Token::Audio(codec) => self.audio_codec = self.audio_codec.or(Some(codec)),
+            Token::Hdr(formats) => {
+                for format in formats {
+                    if !self.hdr.contains(&format) {
+                        self.hdr.push(format);
+                    }
+                }
+            }
+            Token::Language(language) => {
+                if !self.languages.iter().any(|existing| existing == language) {
+                    self.languages.push(language.to_string());
+                }
+            }
+            Token::Proper => self.proper = true,
+            Token::Repack => self.repack = true,
+            Token::Unknown => {}
+        }
+        1
+    }
+}
+
+/// A classified token of a release name.
+enum Token {
+    Year(u32),
+    SeasonEpisode(Vec<u32>, Vec<u32>),
+    SeasonWord,
+    Dash,
+    Complete,
+    Resolution(Resolution),
+    Source(Source),
+    /// A video codec and the number of tokens it spans (e.g. "H" "264").
+    Video(VideoCodec, usize),
+    Audio(AudioCodec),
+    Hdr(Vec<HdrFormat>),
+    Language(&'static str),
+    Proper,
+    Repack,
+    Unknown,
+}
+
+/// Classifies a single token, looking at the following token for split tags such as "H.264".
+fn parse_token(token: &str, next: Option<&str>) -> Token {
+    let lower = token.to_lowercase();
+
+    if let Some(year) = parse_year(&lower) {
+        return Token::Year(year);
+    }
+    if let Some((seasons, episodes)) = parse_season_episode(&lower) {
+        return Token::SeasonEpisode(seasons, episodes);
+    }
+
+    match lower.as_str() {
+        "-" => return Token::Dash,
+        "season" | "saison" | "series" if next.and_then(parse_seasons).is_some() => {
+            return Token::SeasonWord
+        }
+        "complete" => return Token::Complete,
+        "480p" | "480i" => return Token::Resolution(Resolution::P480),
+        "576p" | "576i" => return Token::Resolution(Resolution::P576),
+        "720p" => return Token::Resolution(Resolution::P720),
+        "1080p" | "1080i" => return Token::Resolution(Resolution::P1080),
+        "2160p" | "4k" | "uhd" => return Token::Resolution(Resolution::P2160),
+        "cam" | "camrip" | "hdcam" => return Token::Source(Source::Cam),
+        "ts" | "hdts" | "telesync" | "tc" | "telecine" => return Token::Source(Source::Telesync),
+        "dvd" | "dvdrip" | "dvd5" | "dvd9" | "dvdr" => return Token::Source(Source::Dvd),
+        "hdtv" | "pdtv" | "sdtv" | "hdtvrip" => return Token::Source(Source::Hdtv),
+        "webrip" | "web-rip" => return Token::Source(Source::WebRip),
+        "web" | "web-dl" | "webdl" => return Token::Source(Source::WebDl),
+        "bluray" | "blu-ray" | "bdrip" | "brrip" | "bdremux" | "bd" | "remux" => {
+            return Token::Source(Source::BluRay)
+        }
+        "x264" | "h264" | "avc" => return Token::Video(VideoCodec::H264, 1),
+        "x265" | "h265" | "hevc" => return Token::Video(VideoCodec::H265, 1),
+        "xvid" | "divx" => return Token::Video(VideoCodec::Xvid, 1),
+        "vp9" => return Token::Video(VideoCodec::Vp9, 1),
+        "av1" => return Token::Video(VideoCodec::Av1, 1),
+        "h" | "x" => match next {
+            Some("264") => return Token::Video(VideoCodec::H264, 2),
+            Some("265") => return Token::Video(VideoCodec::H265, 2),
+            _ => {}
+        },
+        "hdr" | "hdr10" => return Token::Hdr(vec![HdrFormat::Hdr10]),
+        "hdr10+" | "hdr10plus" => return Token::Hdr(vec![HdrFormat::Hdr10Plus]),
+        "dv" | "dovi" | "dolbyvision" => return Token::Hdr(vec![HdrFormat::DolbyVision]),
+        "dv-hdr" | "dv-hdr10" | "hdr-dv" => {
+            return Token::Hdr(vec![HdrFormat::DolbyVision, HdrFormat::Hdr10])
+        }
+        "hlg" => return Token::Hdr(vec![HdrFormat::Hlg]),
+        "proper" => return Token::Proper,
+        "repack" | "rerip" => return Token::Repack,
+        _ => {}
+    }
+
+    if let Some(language) = parse_language(&lower) {
+        return Token::Language(language);
+    }
+
+    // Audio tags are commonly followed by the channel layout, e.g. "DDP5.1" or "AAC2.0"
+    let audio = lower.trim_end_matches(|c: char| c.is_ascii_digit());
+    let codec = match audio {
+        "mp3" => Some(AudioCodec::Mp3),
+        "aac" | "he-aac" => Some(AudioCodec::Aac),
+        "opus" => Some(AudioCodec::Opus),
+        "ac3" | "dd" => Some(AudioCodec::Ac3),
+        "eac3" | "ddp" | "dd+" => Some(AudioCodec::Eac3),
+        "dts" => Some(AudioCodec::Dts),
+        "dts-hd" | "dtshd" | "dts-x" | "dtsx" | "dts-ma" => Some(AudioCodec::DtsHd),
+        "truehd" => Some(AudioCodec::TrueHd),
+        "atmos" => Some(AudioCodec::Atmos),
+        "flac" => Some(AudioCodec::Flac),
+        _ => None,
+    };
+
+    match codec {
+        Some(codec) => Token::Audio(codec),
+        None => Token::Unknown,
+    }
+}
+
+/// Parses a plausible release year (1900-2099).
+fn parse_year(token: &str) -> Option<u32> {
+    if token.len() != 4 || !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    token
+        .parse()
+        .ok()
+        .filter(|year| (1900..2100).contains(year))
+}
+
+/// Parses season and episode tags such as `S01`, `S01-S03`, `S01E02`,
+/// `S01E02E03`, `S01E02-E04` and `1x02`.
+fn parse_season_episode(token: &str) -> Option<(Vec<u32>, Vec<u32>)> {
+    if let Some((season, episode)) = token.split_once('x') {
+        if is_number(season) && is_number(episode) && season.len() <= 2 && episode.len() <= 3 {
+            return Some((vec![season.parse().ok()?], vec![episode.parse().ok()?]));
+        }
+    }
+
+    let rest = token.strip_prefix('s')?;
+    let season_len = rest.bytes().take_while(u8::is_ascii_digit).count();
+    if season_len == 0 || season_len > 3 {
+        return None;
+    }
+    let season: u32 = rest[..season_len].parse().ok()?;
+    let rest = &rest[season_len..];
+
+    if rest.is_empty() {
+        return Some((vec![season], vec![]));
+    }
+
+    // Season ranges, e.g. "s01-s03" or "s01-03"
+    if let Some(end) = rest.strip_prefix('-') {
+        let end = end.strip_prefix('s').unwrap_or(end);
+        let end: u32 = is_number(end).then(|| end.parse().ok())??;
+        return is_range(season, end, MAX_SEASON_RANGE).then(|| ((season..=end).collect(), vec![]));
+    }
+
+    let mut episodes = Vec::new();
+    let mut rest = rest;
+    while !rest.is_empty() {
+        let (is_range, tail) = if let Some(tail) = rest.strip_prefix("-e") {
+            (true, tail)
+        } else if let Some(tail) = rest.strip_prefix('-') {
+            (true, tail)
+        } else if let Some(tail) = rest.strip_prefix('e') {
+            (false, tail)
+        } else {
+            return None;
+        };
+
+        let episode_len = tail.bytes().take_while(u8::is_ascii_digit).count();
+        if episode_len == 0 {
+            return None;
+        }
+        let episode: u32 = tail[..episode_len].parse().ok()?;
+        match episodes.last().copied() {
+            Some(start) if is_range && start < episode => {
+                if episode - start >= MAX_EPISODE_RANGE {
+                    return None;
+                }
+                episodes.extend(start + 1..=episode);
+            }
+            None if is_range => return None,
+            _ => episodes.push(episode),
+        }
+        rest = &tail[episode_len..];
+    }
+
+    Some((vec![season], episodes))
+}
+
+/// Parses an absolute episode number or range following a standalone dash,
+/// as used by anime releases, e.g. "01", "01v2" or "01-12".
+fn parse_absolute_episodes(token: Option<&str>) -> Option<Vec<u32>> {
+    let token = token?.to_lowercase();
+    let token = match token.split_once('v') {
+        Some((number, version)) if is_number(version) => number.to_string(),
+        _ => token,
+    };
+    if token.len() > 4 && !token.contains(['-', '~']) {
+        return None;
+    }
+    parse_number_range(&token.replace('~', "-"), MAX_EPISODE_RANGE)
+}
+
+/// Parses a season number or range following a season word, e.g. "2" or "1-3".
+fn parse_seasons(token: &str) -> Option<Vec<u32>> {
+    parse_number_range(token, MAX_SEASON_RANGE)
+}
+
+/// Parses a number or an inclusive range of at most `max_width` numbers, e.g. "2" or "1-3".
+fn parse_number_range(token: &str, max_width: u32) -> Option<Vec<u32>> {
+    match token.split_once('-') {
+        Some((start, end)) if is_number(start) && is_number(end) => {
+            let (start, end): (u32, u32) = (start.parse().ok()?, end.parse().ok()?);
+            is_range(start, end, max_width).then(|| (start..=end).collect())
+        }
+        None if is_number(token) && token.len() <= 4 => Some(vec![token.parse().ok()?]),
+        _ => None,
+    }
+}
+
+/// Returns whether `start..=end` is a range of at most `max_width` numbers.
+fn is_range(start: u32, end: u32, max_width: u32) -> bool {
+    start <= end && end - start < max_width
+}
+
+/// Maps a language tag to its normalized language name.
+fn parse_language(token: &str) -> Option<&'static str> {
+    let language = match token {
+        "multi" | "multi-subs" | "multisubs" => "Multi",
+        "dual" | "dual-audio" => "Dual",
+        "english" | "eng" => "English",
+        "french" | "truefrench" | "vff" | "vfq" | "vostfr" => "French",
+        "german" | "ger" => "German",
+        "italian" | "ita" => "Italian",
+        "spanish" | "spa" | "esp" | "latino" => "Spanish",
+        "portuguese" | "por" => "Portuguese",
+        "russian" | "rus" => "Russian",
+        "japanese" | "jpn" => "Japanese",
+        "korean" | "kor" => "Korean",
+        "chinese" | "chi" => "Chinese",
+        "hindi" | "hin" => "Hindi",
+        "dutch" => "Dutch",
+        "polish" | "pol" => "Polish",
+        "swedish" | "swe" => "Swedish",
+        "nordic" => "Nordic",
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// Returns whether the string is a non-empty sequence of ASCII digits.
+fn is_number(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests parsing a scene style TV episode release.
+    ///
+    /// Ensures that the split "H.264" codec and "DDP5.1" audio tags are
+    /// recognized and the trailing group is extracted.
+    #[test]
+    fn test_parse_tv_episode() {
+        let info = ReleaseInfo::parse("The.Show.S02E05.1080p.WEB-DL.DDP5.1.H.264-NTb");
+
+        assert_eq!(info.title, "The Show");
+        assert_eq!(info.seasons, vec![2]);
+        assert_eq!(info.episodes, vec![5]);
+        assert_eq!(info.resolution, Some(Resolution::P1080));
+        assert_eq!(info.source, Some(Source::WebDl));
+        assert_eq!(info.audio_codec, Some(AudioCodec::Eac3));
+        assert_eq!(info.video_codec, Some(VideoCodec::H264));
+        assert_eq!(info.group.as_deref(), Some("NTb"));
+        assert!(!info.is_season_pack());
+    }
+
+    /// Tests parsing a movie release with HDR, audio and language tags.
+    #[test]
+    fn test_parse_movie() {
+        let info = ReleaseInfo::parse(
+            "Blade.Runner.2049.2017.2160p.UHD.BluRay.REMUX.HDR.DV.TrueHD.Atmos.7.1.FRENCH.x265.PROPER-GRP.mkv",
+        );
+
+        assert_eq!(info.title, "Blade Runner 2049");
+        assert_eq!(info.year, Some(2017));
+        assert_eq!(info.resolution, Some(Resolution::P2160));
+        assert_eq!(info.source, Some(Source::BluRay));
+        assert_eq!(info.video_codec, Some(VideoCodec::H265));
+        assert_eq!(info.audio_codec, Some(AudioCodec::TrueHd));
+        assert_eq!(info.hdr, vec![HdrFormat::Hdr10, HdrFormat::DolbyVision]);
+        assert_eq!(info.languages, vec!["French".to_string()]);
+        assert_eq!(info.group.as_deref(), Some("GRP"));
+        assert!(info.proper);
+        assert!(!info.repack);
+    }
+
+    /// Tests parsing multi-episode releases and season packs.
+    #[test]
+    fn test_parse_ranges_and_packs() {
+        let info = ReleaseInfo::parse("Show.S01E01-E03.720p.HDTV.x264-GRP");
+        assert_eq!(info.episodes, vec![1, 2, 3]);
+
+        let info = ReleaseInfo::parse("Show.S01E01E02.REPACK.720p.HDTV.x264-GRP");
+        assert_eq!(info.episodes, vec![1, 2]);
+        assert!(info.repack);
+
+        let info = ReleaseInfo::parse("Show S01-S03 COMPLETE 1080p BluRay x265");
+        assert_eq!(info.title, "Show");
+        assert_eq!(info.seasons, vec![1, 2, 3]);
+        assert!(info.complete);
+        assert!(info.is_season_pack());
+        assert_eq!(info.group, None);
+
+        let info = ReleaseInfo::parse("Show Season 2 Complete 720p WEBRip");
+        assert_eq!(info.seasons, vec![2]);
+        assert_eq!(info.source, Some(Source::WebRip));
+
+        let info = ReleaseInfo::parse("Show.1x05.HDTV.XviD");
+        assert_eq!((info.seasons, info.episodes), (vec![1], vec![5]));
+
+        // Huge ranges from hostile names are not expanded
+        let info = ReleaseInfo::parse("Show S01E01-E50000000 720p");
+        assert!(info.episodes.is_empty());
+        let info = ReleaseInfo::parse("Show S01-S4000000000 720p");
+        assert!(info.seasons.is_empty());
+        let info = ReleaseInfo::parse("Show Season 1-99999999 720p");
+        assert!(info.seasons.is_empty());
+        let info = ReleaseInfo::parse("[Group] Show - 1~99999999 (1080p)");
+        assert!(info.episodes.is_empty());
+    }
+
+    /// Tests parsing an anime style release with a leading group and absolute episode.
+    #[test]
+    fn test_parse_anime() {
+        let info = ReleaseInfo::parse("[SubsPlease] Jujutsu Kaisen - 24 (1080p) [ABCD1234].mkv");
+
+        assert_eq!(info.group.as_deref(), Some("SubsPlease"));
+        assert_eq!(info.title, "Jujutsu Kaisen");
+        assert_eq!(info.episodes, vec![24]);
+        assert_eq!(info.resolution, Some(Resolution::P1080));
+
+        let info = ReleaseInfo::parse("[Group] Spider-Man - 01-12 [720p]");
+        assert_eq!(info.title, "Spider-Man");
+        assert_eq!(info.episodes, (1..=12).collect::<Vec<_>>());
+    }
+
+    /// Tests parsing names produced by the YTS provider and names without tags.
+    #[test]
+    fn test_parse_plain_names() {
+        let info = ReleaseInfo::parse("Inception (2010) [1080p BluRay x265]");
+        assert_eq!(info.title, "Inception");
+        assert_eq!(info.year, Some(2010));
+        assert_eq!(info.source, Some(Source::BluRay));
+
+        let info = ReleaseInfo::parse("ubuntu-24.04-desktop-amd64.iso");
+        assert_eq!(info.title, "ubuntu-24 04-desktop-amd64");
+        assert_eq!(info.group, None);
+
+        let info = ReleaseInfo::parse("");
+        assert_eq!(info, ReleaseInfo::default());
+    }
+}
@@ -0,0 +1,325 @@
+//! # Scraping
+//!
+//! Refreshes the seeder and peer counts of search results by scraping
+//! trackers. Counts returned by providers are cached on their side and can be
+//! days old, while trackers report the live state of the swarm.
+
+use std::{collections::HashMap, time::SystemTime};
+
+use log::debug;
+use tokio::task::JoinSet;
+
+use crate::{
+    tracker::{ScrapeStats, TrackerClient, MAX_UDP_SCRAPE_HASHES},
+    Torrent,
+};
+
+/// Public trackers scraped for torrents whose magnet links carry no trackers.
+pub const DEFAULT_TRACKERS: &[&str] = &[
+    "udp://tracker.opentrackr.org:1337/announce",
+    "udp://open.stealth.si:80/announce",
+    "udp://tracker.torrent.eu.org:451/announce",
+    "udp://exodus.desync.com:6969/announce",
+];
+
+/// Scrapes trackers for live seeder and peer counts of a batch of torrents.
+///
+/// Each torrent is scraped from the trackers in its magnet link and from the
+/// scraper's own trackers. When several trackers know a torrent, the counts of
+/// the tracker reporting the most seeders are used.
+///
+/// # Example
+/// ```no_run
+/// use magneto::{scrape::Scraper, Magneto, SearchRequest};
+///
+/// # async fn example() {
+/// let magneto = Magneto::new();
+/// let mut torrents = magneto.search(SearchRequest::new("Ubuntu")).await.unwrap();
+///
+/// let updated = Scraper::new().scrape(&mut torrents).await;
+/// println!("refreshed {} of {} torrents", updated, torrents.len());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Scraper {
+    /// The client used to scrape trackers.
+    tracker_client: TrackerClient,
+
+    /// Trackers scraped in addition to the ones in each magnet link.
+    trackers: Vec<String>,
+
+    /// The maximum number of info hashes sent in a single scrape request.
+    batch_size: usize,
+
+    /// The maximum number of scrape requests in flight at the same time.
+    max_concurrency: usize,
+}
+
+impl Scraper {
+    /// Creates a new `Scraper` using `DEFAULT_TRACKERS`.
+    pub fn new() -> Self {
+        Self {
+            tracker_client: TrackerClient::new(),
+            trackers: DEFAULT_TRACKERS.iter().map(|t| t.to_string()).collect(),
+            batch_size: MAX_UDP_SCRAPE_HASHES,
+            max_concurrency: 16,
+        }
+    }
+
+    /// Replaces the trackers scraped in addition to the ones in each magnet link.
+    pub fn with_trackers(mut self, trackers: Vec<String>) -> Self {
+        self.trackers = trackers;
+        self
+    }
+
+    /// Sets the client used to scrape trackers, e.g. to change its timeout.
+    pub fn with_tracker_client(mut self, tracker_client: TrackerClient) -> Self {
+        self.tracker_client = tracker_client;
+        self
+    }
+
+    /// Sets the maximum number of info hashes sent in a single scrape request.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Sets the maximum number of scrape requests in flight at the same time.
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Scrapes trackers and updates the torrents' `seeders`, `peers` and `stats_updated_at`.
+    ///
+    /// Torrents without a v1 info hash, or unknown to every tracker, are left unchanged.
+    /// Trackers answer unknown info hashes with zero counts, so entries without any
+    /// seeders or leechers are ignored rather than replacing the provider's counts.
+    /// Tracker errors are logged and otherwise ignored.
+    ///
+    /// # Parameters
+    /// - `torrents`: The torrents to refresh.
+    ///
+    /// # Returns
+    /// - `usize`: The number of torrents that were updated.
+    pub async fn scrape(&self, torrents: &mut [Torrent]) -> usize {
+        let mut hashes_by_tracker: HashMap<String, Vec<[u8; 20]>> = HashMap::new();
+        let mut info_hashes = Vec::with_capacity(torrents.len());
+
+        for torrent in torrents.iter() {
+            let magnet = torrent.magnet().ok();
+            let info_hash = magnet.as_ref().and_then(|magnet| magnet.info_hash);
+            info_hashes.push(info_hash);
+
+            let (Some(magnet), Some(info_hash)) = (magnet, info_hash) else {
+                debug!("skipping scrape of '{}' without v1 info hash", torrent.name);
+                continue;
+            };
+            for tracker in magnet.trackers.iter().chain(&self.trackers) {
+                let hashes = hashes_by_tracker.entry(tracker.clone()).or_default();
+                if !hashes.contains(&info_hash) {
+                    hashes.push(info_hash);
+                }
+            }
+        }
+
+        let mut requests = hashes_by_tracker.into_iter().flat_map(|(tracker, hashes)| {
+            hashes
+                .chunks(self.batch_size)
+                .map(|batch| (tracker.clone(), batch.to_vec()))
+                .collect::<Vec<_>>()
+        });
+
+        let mut best: HashMap<[u8; 20], ScrapeStats> = HashMap::new();
+        let mut tasks = JoinSet::new();
+        loop {
+            while tasks.len() < self.max_concurrency {
+                let Some((tracker, batch)) = requests.next() else {
+                    break;
+                };
+                let client = self.tracker_client.clone();
+                tasks.spawn(async move {
+                    let result = client.scrape(&tracker, &batch).await;
+                    (tracker, result)
+                });
+            }
+
+            let Some(joined) = tasks.join_next().await else {
+                break;
+            };
+            match joined {
+                Ok((_, Ok(stats))) => {
+                    for (info_hash, stats) in stats {
+                        if stats.seeders == 0 && stats.leechers == 0 {
+                            continue;
+                        }
+                        let entry = best.entry(info_hash).or_insert(stats);
+                        if stats.seeders > entry.seeders {
+                            *entry = stats;
+                        }
+                    }
+                }
+                Ok((tracker, Err(e))) => debug!("scraping {} failed: {}", tracker, e),
+                Err(e) => debug!("scrape task failed: {}", e),
+            }
+        }
+
+        let now = SystemTime::now();
+        let mut updated = 0;
+        for (torrent, info_hash) in torrents.iter_mut().zip(info_hashes) {
+            if let Some(stats) = info_hash.and_then(|info_hash| best.get(&info_hash)) {
+                torrent.seeders = stats.seeders;
+                torrent.peers = stats.leechers;
+                torrent.stats_updated_at = Some(now);
+                updated += 1;
+            }
+        }
+
+        updated
+    }
+}
+
+impl Default for Scraper {
+    /// Provides a default implementation for `Scraper`, equal to `Scraper::new()`.
+    fn default() -> Self {
+        Scraper::new()
+    }
+}
+
+/// Settings for refreshing the top search results from trackers in `Magneto::search`.
+#[derive(Debug, Clone)]
+pub struct StatsRefresh {
+    /// The scraper used to refresh the results.
+    pub scraper: Scraper,
+
+    /// The number of top results to refresh.
+    pub top: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Magneto, PirateBay, SearchRequest};
+    use mockito::{Matcher, Server};
+    use tokio::net::UdpSocket;
+
+    /// Builds a torrent with the given info hash byte and cached counts.
+    fn torrent(hash: u8, trackers: &[&str], seeders: u32) -> Torrent {
+        let mut magnet = crate::Magnet::from_info_hash([hash; 20]);
+        magnet.trackers = trackers.iter().map(|t| t.to_string()).collect();
+        Torrent {
+            name: format!("torrent {}", hash),
+            magnet_link: magnet.to_string(),
+            seeders,
+            peers: 0,
+            size_bytes: 0,
+            provider: "test".to_string(),
+            metadata: Default::default(),
+            stats_updated_at: None,
+        }
+    }
+
+    /// Starts a local UDP tracker stand-in answering scrapes with `seeders = 10 * hash byte`
+    /// for known hashes, and returns its URL.
+    async fn spawn_udp_tracker(known: Vec<u8>) -> String {
+        let tracker = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("udp://{}", tracker.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 2048];
+            loop {
+                let Ok((length, from)) = tracker.recv_from(&mut buffer).await else {
+                    return;
+                };
+                let action = u32::from_be_bytes(buffer[8..12].try_into().unwrap());
+                let mut response = Vec::new();
+                if length == 16 && buffer[8..12] == [0; 4] {
+                    response.extend_from_slice(&[0, 0, 0, 0]);
+                    response.extend_from_slice(&buffer[12..16]);
+                    response.extend_from_slice(&7u64.to_be_bytes());
+                } else {
+                    assert_eq!(action, 2);
+                    response.extend_from_slice(&[0, 0, 0, 2]);
+                    response.extend_from_slice(&buffer[12..16]);
+                    for hash in buffer[16..length].chunks_exact(20) {
+                        let (seeders, leechers) = if known.contains(&hash[0]) {
+                            (u32::from(hash[0]) * 10, 3u32)
+                        } else {
+                            (0, 0)
+                        };
+                        response.extend_from_slice(&seeders.to_be_bytes());
+                        response.extend_from_slice(&0u32.to_be_bytes());
+                        response.extend_from_slice(&leechers.to_be_bytes());
+                    }
+                }
+                tracker.send_to(&response, from).await.unwrap();
+            }
+        });
+
+        url
+    }
+
+    /// Tests refreshing torrents from magnet and scraper trackers, keeping the highest counts.
+    #[tokio::test]
+    async fn test_scrape_torrents() {
+        let magnet_tracker = spawn_udp_tracker(vec![1]).await;
+        let extra_tracker = spawn_udp_tracker(vec![1, 2]).await;
+
+        let mut torrents = vec![
+            torrent(1, &[&magnet_tracker], 5),
+            torrent(2, &[], 5),
+            Torrent {
+                magnet_link: "not a magnet".to_string(),
+                ..torrent(3, &[], 5)
+            },
+        ];
+
+        let updated = Scraper::new()
+            .with_trackers(vec![extra_tracker, "wss://unsupported".to_string()])
+            .with_batch_size(1)
+            .scrape(&mut torrents)
+            .await;
+
+        assert_eq!(updated, 2);
+        assert_eq!((torrents[0].seeders, torrents[0].peers), (10, 3));
+        assert_eq!((torrents[1].seeders, torrents[1].peers), (20, 3));
+        assert!(torrents[0].stats_updated_at.is_some());
+        assert_eq!(torrents[2].seeders, 5);
+        assert_eq!(torrents[2].stats_updated_at, None);
+    }
+
+    /// Tests that `Magneto` refreshes only the top results and re-sorts them.
+    #[tokio::test]
+    async fn test_magneto_stats_refresh() {
+        let tracker = spawn_udp_tracker(vec![0x22]).await;
+
+        let mut server = Server::new_async().await;
+        let entry = |id: &str, hash: &str, seeders: &str| {
+            format!(
+                r#"{{"id":"{}","name":"Release {}","info_hash":"{}","leechers":"1","seeders":"{}","num_files":"1","size":"1","username":"u","added":"0","status":"member","category":"0","imdb":""}}"#,
+                id, id, hash, seeders
+            )
+        };
+        let body = format!(
+            "[{},{},{}]",
+            entry("1", &"11".repeat(20), "50"),
+            entry("2", &"22".repeat(20), "40"),
+            entry("3", &"33".repeat(20), "30")
+        );
+        let _mock = server
+            .mock("GET", Matcher::Any)
+            .with_body(body)
+            .create_async()
+            .await;
+
+        let magneto = Magneto::with_providers(vec![Box::new(PirateBay::with_url(server.url()))])
+            .with_stats_refresh(Scraper::new().with_trackers(vec![tracker]), 2);
+        let torrents = magneto.search(SearchRequest::new("release")).await.unwrap();
+
+        let seeders: Vec<u32> = torrents.iter().map(|t| t.seeders).collect();
+        assert_eq!(seeders, vec![340, 50, 30]);
+        assert!(torrents[0].stats_updated_at.is_some());
+        assert!(torrents[1].stats_updated_at.is_none());
+        assert!(torrents[2].stats_updated_at.is_none());
+    }
+}
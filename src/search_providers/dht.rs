@@ -0,0 +1,461 @@
+//! # DHT Search Provider
+//!
+//! The `Dht` implementation of the `SearchProvider` trait searches torrents
+//! discovered on the BitTorrent mainline DHT instead of querying a website.
+//! A background crawler samples info hashes from other nodes (BEP 51), finds
+//! their peers, downloads their metadata (BEP 9) and adds them to a local
+//! index, which answers search requests.
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::Request;
+use log::{debug, info};
+use tokio::{
+    task::{JoinHandle, JoinSet},
+    time::sleep,
+};
+
+use crate::{
+    dht::{
+        index::{DhtIndex, IndexedTorrent},
+        DhtError, DhtNode, NodeId, NodeInfo,
+    },
+    transport::HttpTransport,
+    wire::MetadataFetcher,
+    ClientError, SearchProvider, SearchRequest, Torrent,
+};
+
+/// Well-known nodes used to join the DHT.
+pub const BOOTSTRAP_NODES: &[&str] = &[
+    "router.bittorrent.com:6881",
+    "dht.transmissionbt.com:6881",
+    "router.utorrent.com:6881",
+];
+
+/// The maximum number of nodes sampled in one crawl round.
+const NODES_PER_ROUND: usize = 32;
+
+/// The maximum number of new info hashes resolved in one crawl round.
+const HASHES_PER_ROUND: usize = 16;
+
+/// The maximum number of attempted info hashes remembered, forgotten all at once when reached.
+const MAX_ATTEMPTED: usize = 100_000;
+
+/// The `Dht` provider searches a local index of torrents crawled from the DHT.
+///
+/// # Example
+/// ```no_run
+/// use magneto::{Dht, Magneto};
+///
+/// # async fn example() {
+/// let dht = Dht::start("0.0.0.0:6881".parse().unwrap()).await.unwrap();
+/// let magneto = Magneto::new().add_provider(Box::new(dht));
+///
+/// // Results from the DHT accumulate as the crawler runs
+/// # }
+/// ```
+pub struct Dht {
+    /// The crawling state shared with the background crawler.
+    crawler: Arc<Crawler>,
+
+    /// The background crawler task, if started.
+    task: Option<JoinHandle<()>>,
+}
+
+/// The state used to crawl the DHT.
+struct Crawler {
+    /// The local DHT node.
+    node: DhtNode,
+
+    /// The index of resolved torrents.
+    index: RwLock<DhtIndex>,
+
+    /// Info hashes that have been tried already, resolved or not.
+    attempted: Mutex<HashSet<[u8; 20]>>,
+
+    /// When each sampled node may be sampled again, from the interval it returned.
+    next_sample: Mutex<HashMap<NodeId, Instant>>,
+
+    /// The fetcher used to download metadata from peers.
+    fetcher: MetadataFetcher,
+}
+
+impl Dht {
+    /// Creates a new `Dht` provider on an existing node without starting the crawler.
+    ///
+    /// Call `crawl` to index torrents, or use `start` to crawl in the background.
+    ///
+    /// # Parameters
+    /// - `node`: A DHT node, usually already bootstrapped.
+    pub fn new(node: DhtNode) -> Self {
+        Self {
+            crawler: Arc::new(Crawler {
+                node,
+                index: RwLock::new(DhtIndex::new()),
+                attempted: Mutex::new(HashSet::new()),
+                next_sample: Mutex::new(HashMap::new()),
+                fetcher: MetadataFetcher::new().with_peer_timeout(Duration::from_secs(5)),
+            }),
+            task: None,
+        }
+    }
+
+    /// Binds a DHT node, joins the DHT through `BOOTSTRAP_NODES` and crawls it in the background.
+    ///
+    /// # Parameters
+    /// - `address`: The local IPv4 address of the node, e.g. "0.0.0.0:6881".
+    ///
+    /// # Returns
+    /// - `Ok(Dht)`: The provider, whose index fills up while crawling.
+    /// - `Err(DhtError)`: An error if the node cannot be bound.
+    pub async fn start(address: SocketAddr) -> Result<Self, DhtError> {
+        let mut bootstrap = Vec::new();
+        for host in BOOTSTRAP_NODES {
+            match tokio::net::lookup_host(host).await {
+                Ok(addresses) => bootstrap.extend(addresses.filter(SocketAddr::is_ipv4)),
+                Err(e) => debug!("failed to resolve bootstrap node {}: {}", host, e),
+            }
+        }
+
+        Self::start_with_bootstrap(address, &bootstrap, Duration::from_secs(60)).await
+    }
+
+    /// Binds a DHT node, joins the DHT through the given nodes and crawls it in the background.
+    ///
+    /// # Parameters
+    /// - `address`: The local IPv4 address of the node.
+    /// - `bootstrap`: Addresses of known DHT nodes.
+    /// - `interval`: The pause between crawl rounds.
+    pub async fn start_with_bootstrap(
+        address: SocketAddr,
+        bootstrap: &[SocketAddr],
+        interval: Duration,
+    ) -> Result<Self, DhtError> {
+        let node = DhtNode::bind(address, Duration::from_secs(5)).await?;
+        let nodes = node.bootstrap(bootstrap).await;
+        info!("dht node {} joined with {} nodes", node.id(), nodes);
+
+        let mut dht = Self::new(node);
+        let crawler = dht.crawler.clone();
+        dht.task = Some(tokio::spawn(async move {
+            loop {
+                let indexed = crawler.crawl().await;
+                debug!("dht crawl indexed {} torrents", indexed);
+                sleep(interval).await;
+            }
+        }));
+
+        Ok(dht)
+    }
+
+    /// Returns the local DHT node.
+    pub fn node(&self) -> &DhtNode {
+        &self.crawler.node
+    }
+
+    /// Returns the number of indexed torrents.
+    pub fn indexed(&self) -> usize {
+        self.crawler.index().len()
+    }
+
+    /// Runs one crawl round.
+    ///
+    /// Samples info hashes from known nodes, looks up peers for new ones and
+    /// indexes those whose metadata can be downloaded. The node joins the DHT
+    /// again first if its routing table is empty. Nodes are sampled least
+    /// recently sampled first and not before the interval they asked for has
+    /// passed, and the nodes they return are added to the routing table.
+    ///
+    /// # Returns
+    /// - `usize`: The number of newly indexed torrents.
+    pub async fn crawl(&self) -> usize {
+        self.crawler.crawl().await
+    }
+}
+
+impl Drop for Dht {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+impl Crawler {
+    /// Reads the index.
+    fn index(&self) -> std::sync::RwLockReadGuard<'_, DhtIndex> {
+        self.index.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Runs one crawl round, see `Dht::crawl`.
+    async fn crawl(&self) -> usize {
+        self.node.rejoin_if_empty().await;
+
+        let mut samples = JoinSet::new();
+        for node in self.nodes_to_sample() {
+            let dht_node = self.node.clone();
+            samples.spawn(async move {
+                let response = dht_node
+                    .sample_infohashes(SocketAddr::V4(node.address), NodeId::random())
+                    .await;
+                (node.id, response)
+            });
+        }
+
+        let mut new_hashes = Vec::new();
+        while let Some(joined) = samples.join_next().await {
+            let Ok((id, response)) = joined else { continue };
+            let interval = response
+                .as_ref()
+                .map(|response| Duration::from_secs(response.interval.into()))
+                .unwrap_or_default();
+            self.next_sample
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(id, Instant::now() + interval);
+
+            let Ok(response) = response else { continue };
+            for node in response.nodes {
+                self.node.add_node(node);
+            }
+
+            let mut attempted = self.attempted.lock().unwrap_or_else(|e| e.into_inner());
+            if attempted.len() >= MAX_ATTEMPTED {
+                attempted.clear();
+            }
+            for info_hash in response.samples {
+                if new_hashes.len() < HASHES_PER_ROUND && attempted.insert(info_hash) {
+                    new_hashes.push(info_hash);
+                }
+            }
+        }
+
+        let mut indexed = 0;
+        for info_hash in new_hashes {
+            let peers = self.node.lookup_peers(info_hash).await;
+            if peers.is_empty() {
+                continue;
+            }
+
+            match self.fetcher.fetch_from_peers(&info_hash, &peers).await {
+                Ok(metadata) => {
+                    debug!("indexed '{}' from the dht", metadata.metainfo.name);
+                    let torrent = IndexedTorrent {
+                        metainfo: metadata.metainfo,
+                        peers: u32::try_from(peers.len()).unwrap_or(u32::MAX),
+                    };
+                    self.index
+                        .write()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .insert(info_hash, torrent);
+                    indexed += 1;
+                }
+                Err(e) => debug!("resolving {} failed: {}", hex::encode(info_hash), e),
+            }
+        }
+
+        indexed
+    }
+
+    /// Returns up to `NODES_PER_ROUND` nodes whose sample interval has passed,
+    /// never sampled nodes first, then the least recently sampled ones.
+    fn nodes_to_sample(&self) -> Vec<NodeInfo> {
+        let nodes = self.node.nodes();
+        let now = Instant::now();
+        let mut next_sample = self.next_sample.lock().unwrap_or_else(|e| e.into_inner());
+        next_sample.retain(|id, _| nodes.iter().any(|node| node.id == *id));
+
+        let mut eligible: Vec<(Option<Instant>, NodeInfo)> = nodes
+            .into_iter()
+            .map(|node| (next_sample.get(&node.id).copied(), node))
+            .filter(|(next, _)| next.is_none_or(|next| next <= now))
+            .collect();
+        eligible.sort_by_key(|(next, _)| *next);
+        eligible
+            .into_iter()
+            .take(NODES_PER_ROUND)
+            .map(|(_, node)| node)
+            .collect()
+    }
+}
+
+#[async_trait]
+impl SearchProvider for Dht {
+    /// Searches the local index instead of sending an HTTP request.
+    ///
+    /// # Parameters
+    /// - `transport`: Unused, the DHT is not queried over HTTP.
+    /// - `request`: The `SearchRequest` containing the query and number of results.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Torrent>)`: The indexed torrents matching the query.
+    async fn send_request(
+        &self,
+        _transport: &dyn HttpTransport,
+        request: SearchRequest<'_>,
+    ) -> Result<Vec<Torrent>, ClientError> {
+        Ok(self
+            .crawler
+            .index()
+            .search(request.query, request.number_of_results))
+    }
+
+    /// Always fails, the index is searched without parsing HTTP responses.
+    ///
+    /// # Returns
+    /// - `Err(ClientError::ProviderError)`: The DHT has no HTTP responses.
+    fn parse_response(&self, _response: &str) -> Result<Vec<Torrent>, ClientError> {
+        Err(ClientError::ProviderError(anyhow!(
+            "provider 'dht' does not parse http responses"
+        )))
+    }
+
+    /// Always fails, the index is searched without sending HTTP requests.
+    ///
+    /// # Returns
+    /// - `Err(ClientError::ProviderError)`: The DHT is not queried over HTTP.
+    fn build_request(&self, _request: SearchRequest<'_>) -> Result<Request<Bytes>, ClientError> {
+        Err(ClientError::ProviderError(anyhow!(
+            "provider 'dht' does not build http requests"
+        )))
+    }
+
+    /// Returns the unique identifier for this provider.
+    ///
+    /// # Returns
+    /// - `String`: "dht".
+    fn id(&self) -> String {
+        "dht".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bencode::{self, Value},
+        transport::ReqwestTransport,
+        wire::tests::{spawn_peer, PeerBehavior},
+    };
+    use sha1::{Digest, Sha1};
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    /// Tests crawling a network of local DHT nodes and searching the resulting index.
+    #[tokio::test]
+    async fn test_crawl_and_search() {
+        let info = bencode::encode(
+            &Value::dict()
+                .with("name", Value::bytes("Debian.12.Netinst.iso"))
+                .with("length", Value::Integer(650_000_000))
+                .with("piece length", Value::Integer(1 << 18))
+                .with("pieces", Value::bytes(vec![1u8; 20])),
+        );
+        let info_hash: [u8; 20] = Sha1::digest(&info).into();
+        let peer = spawn_peer(info, PeerBehavior::Serve).await;
+
+        let timeout = Duration::from_secs(2);
+        let bootstrap = DhtNode::bind((Ipv4Addr::LOCALHOST, 0).into(), timeout)
+            .await
+            .unwrap();
+        let bootstrap_address = bootstrap.local_addr().unwrap();
+
+        let mut nodes = Vec::new();
+        for _ in 0..3 {
+            let node = DhtNode::bind((Ipv4Addr::LOCALHOST, 0).into(), timeout)
+                .await
+                .unwrap();
+            node.bootstrap(&[bootstrap_address]).await;
+            nodes.push(node);
+        }
+
+        // A seeder announces itself to one of the nodes
+        let seeder = DhtNode::bind((Ipv4Addr::LOCALHOST, 0).into(), timeout)
+            .await
+            .unwrap();
+        let storing_node = nodes[1].local_addr().unwrap();
+        let token = seeder
+            .get_peers(storing_node, info_hash)
+            .await
+            .unwrap()
+            .token
+            .unwrap();
+        seeder
+            .announce_peer(storing_node, info_hash, peer.port(), &token)
+            .await
+            .unwrap();
+
+        let crawler = DhtNode::bind((Ipv4Addr::LOCALHOST, 0).into(), timeout)
+            .await
+            .unwrap();
+        crawler.bootstrap(&[bootstrap_address]).await;
+        let dht = Dht::new(crawler);
+
+        assert_eq!(dht.crawl().await, 1);
+        assert_eq!(dht.indexed(), 1);
+        assert_eq!(dht.crawl().await, 0);
+
+        let results = dht
+            .send_request(
+                &ReqwestTransport::new(),
+                SearchRequest::new("debian netinst"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "Debian.12.Netinst.iso");
+        assert_eq!(results[0].size_bytes, 650_000_000);
+        assert_eq!(results[0].seeders, 1);
+        assert_eq!(results[0].provider, "dht");
+        assert_eq!(results[0].magnet().unwrap().info_hash, Some(info_hash));
+        assert_eq!(
+            dht.node().lookup_peers(info_hash).await,
+            vec![SocketAddr::V4(SocketAddrV4::new(
+                Ipv4Addr::LOCALHOST,
+                peer.port()
+            ))]
+        );
+
+        let results = dht
+            .send_request(&ReqwestTransport::new(), SearchRequest::new("ubuntu"))
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+    }
+
+    /// Tests that sampled nodes widen the routing table and are not re-sampled early.
+    #[tokio::test]
+    async fn test_crawl_discovers_nodes() {
+        let timeout = Duration::from_secs(2);
+        let bind = || DhtNode::bind((Ipv4Addr::LOCALHOST, 0).into(), timeout);
+        let info = |node: &DhtNode| {
+            let SocketAddr::V4(address) = node.local_addr().unwrap() else {
+                unreachable!()
+            };
+            NodeInfo {
+                id: node.id(),
+                address,
+            }
+        };
+
+        let known = bind().await.unwrap();
+        let discovered = bind().await.unwrap();
+        known.add_node(info(&discovered));
+
+        let crawler = bind().await.unwrap();
+        crawler.add_node(info(&known));
+        let dht = Dht::new(crawler);
+
+        assert_eq!(dht.crawler.nodes_to_sample(), vec![info(&known)]);
+        assert_eq!(dht.crawl().await, 0);
+        assert!(dht.node().nodes().contains(&info(&discovered)));
+        assert_eq!(dht.crawler.nodes_to_sample(), vec![info(&discovered)]);
+    }
+}
@@ -0,0 +1,535 @@
+//! # EZTV Search Provider
+//!
+//! The `Eztv` implementation of the `SearchProvider` trait queries the EZTV
+//! API for the episodes of a TV show, and parses the resulting JSON response
+//! into a unified `Torrent` structure with the season and episode numbers.
+//!
+//! The API only looks shows up by IMDb id, so queries must start with one,
+//! optionally followed by a season or episode: "tt0944947", "tt0944947 S08"
+//! or "tt0944947 S08E06". Other queries, and searches restricted to other
+//! categories than `Category::TvShows`, return no results without a request.
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::Request;
+use log::debug;
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    errors::ClientError,
+    search_providers::{build_get_request, html},
+    transport::HttpTransport,
+    Category, SearchProvider, SearchRequest, Torrent, TorrentMetadata,
+};
+
+/// The maximum number of torrents of a page of the API.
+const MAX_PAGE_SIZE: usize = 100;
+
+/// The maximum number of pages fetched for a search, bounding searches for a single episode.
+const MAX_PAGES: usize = 10;
+
+/// The `Eztv` provider handles querying and parsing data from the EZTV API.
+pub struct Eztv {
+    /// The URL of the `get-torrents` endpoint of the API.
+    api_url: String,
+}
+
+impl Eztv {
+    /// Creates a new instance of the `Eztv` provider.
+    ///
+    /// # Returns
+    /// - `Eztv`: A new provider instance with the default API URL.
+    pub fn new() -> Self {
+        Self::with_url("https://eztvx.to/api/get-torrents")
+    }
+
+    /// Creates a new instance of the `Eztv` provider with a custom API URL.
+    ///
+    /// # Parameters
+    /// - `url`: The URL of the `get-torrents` endpoint, e.g. of a mirror.
+    ///
+    /// # Returns
+    /// - `Eztv`: A new provider instance with the specified API URL.
+    pub fn with_url(url: impl Into<String>) -> Self {
+        Self {
+            api_url: url.into(),
+        }
+    }
+
+    /// Builds the request for a page of the torrents of a show.
+    fn page_request(
+        &self,
+        query: &EpisodeQuery,
+        limit: usize,
+        page: usize,
+    ) -> Result<Request<Bytes>, ClientError> {
+        build_get_request(
+            &self.api_url,
+            &[
+                ("imdb_id", query.imdb_id.as_str()),
+                ("limit", &limit.to_string()),
+                ("page", &page.to_string()),
+            ],
+        )
+    }
+}
+
+impl Default for Eztv {
+    /// Provides a default implementation for `Eztv`, returning an instance with the default API URL.
+    fn default() -> Self {
+        Eztv::new()
+    }
+}
+
+#[async_trait]
+impl SearchProvider for Eztv {
+    /// Fetches the torrents of the show, page by page, until enough episodes match the query.
+    ///
+    /// # Parameters
+    /// - `transport`: The `HttpTransport` used for sending HTTP requests.
+    /// - `request`: A `SearchRequest` containing the search parameters.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Torrent>)`: The matching episodes, empty if the query has no IMDb id.
+    /// - `Err(ClientError)`: An error if a request or parsing fails.
+    async fn send_request(
+        &self,
+        transport: &dyn HttpTransport,
+        request: SearchRequest<'_>,
+    ) -> Result<Vec<Torrent>, ClientError> {
+        let Some(query) = EpisodeQuery::parse(request.query) else {
+            debug!(
+                "eztv only searches by imdb id, skipping '{}'",
+                request.query
+            );
+            return Ok(Vec::new());
+        };
+        if !request.categories.is_empty() && !request.categories.contains(&Category::TvShows) {
+            debug!(
+                "eztv only lists tv shows, skipping {:?}",
+                request.categories
+            );
+            return Ok(Vec::new());
+        }
+
+        // Episodes are filtered from every torrent of the show, so fetch full pages then
+        let limit = if query.season.is_some() {
+            MAX_PAGE_SIZE
+        } else {
+            request.number_of_results.clamp(1, MAX_PAGE_SIZE)
+        };
+        let mut torrents = Vec::new();
+        for page in 1..=MAX_PAGES {
+            let url = self.page_request(&query, limit, page)?.uri().to_string();
+            let response = parse_page(&html::fetch_page(transport, &url).await?)?;
+
+            let last_page = response.torrents.len() < limit
+                || response
+                    .torrents_count
+                    .is_some_and(|count| page * limit >= count);
+            torrents.extend(
+                response
+                    .torrents
+                    .into_iter()
+                    .map(EztvTorrent::into_torrent)
+                    .filter(|torrent| query.matches(torrent)),
+            );
+            if last_page || torrents.len() >= request.number_of_results {
+                break;
+            }
+        }
+
+        torrents.truncate(request.number_of_results);
+        Ok(torrents)
+    }
+
+    /// Builds the request for the first page of the torrents of the show.
+    ///
+    /// # Parameters
+    /// - `request`: The `SearchRequest` containing query parameters.
+    ///
+    /// # Returns
+    /// - `Ok(Request<Bytes>)`: The constructed HTTP request.
+    /// - `Err(ClientError)`: An error if the query does not start with an IMDb id.
+    fn build_request(&self, request: SearchRequest<'_>) -> Result<Request<Bytes>, ClientError> {
+        let query = EpisodeQuery::parse(request.query).ok_or_else(|| {
+            ClientError::ProviderError(anyhow!(
+                "eztv query '{}' does not start with an imdb id",
+                request.query
+            ))
+        })?;
+
+        self.page_request(&query, request.number_of_results.clamp(1, MAX_PAGE_SIZE), 1)
+    }
+
+    /// Parses a page of the API into a list of torrents.
+    ///
+    /// # Parameters
+    /// - `response`: The raw response body as a string.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Torrent>)`: A list of parsed torrents, empty if the show is unknown.
+    /// - `Err(ClientError)`: An error if parsing fails.
+    fn parse_response(&self, response: &str) -> Result<Vec<Torrent>, ClientError> {
+        Ok(parse_page(response)?
+            .torrents
+            .into_iter()
+            .map(EztvTorrent::into_torrent)
+            .collect())
+    }
+
+    /// Returns the unique identifier for this provider.
+    ///
+    /// # Returns
+    /// - `String`: The provider's API URL as its unique identifier.
+    fn id(&self) -> String {
+        self.api_url.clone()
+    }
+}
+
+/// An IMDb id, with the season and episode to return.
+#[derive(Debug, PartialEq, Eq)]
+struct EpisodeQuery {
+    /// The IMDb id without the "tt" prefix, as the API expects it.
+    imdb_id: String,
+
+    /// The season to return, every season if `None`.
+    season: Option<u32>,
+
+    /// The episode to return, every episode if `None`.
+    episode: Option<u32>,
+}
+
+impl EpisodeQuery {
+    /// Parses "tt0944947", "tt0944947 S08" or "tt0944947 S08E06".
+    ///
+    /// Any other text after the IMDb id is ignored.
+    fn parse(query: &str) -> Option<Self> {
+        let mut words = query.split_whitespace();
+        let imdb_id = words.next()?.to_lowercase();
+        let imdb_id = imdb_id.strip_prefix("tt")?;
+        if imdb_id.is_empty() || !imdb_id.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        let (season, episode) = words
+            .next()
+            .and_then(|word| {
+                let word = word.to_lowercase();
+                let rest = word.strip_prefix('s')?;
+                let (season, episode) = match rest.split_once('e') {
+                    Some((season, episode)) => (season, Some(episode.parse().ok()?)),
+                    None => (rest, None),
+                };
+                Some((Some(season.parse().ok()?), episode))
+            })
+            .unwrap_or_default();
+
+        Some(Self {
+            imdb_id: imdb_id.to_string(),
+            season,
+            episode,
+        })
+    }
+
+    /// Returns whether a torrent is of the requested season and episode.
+    fn matches(&self, torrent: &Torrent) -> bool {
+        let matches = |expected: Option<u32>, actual: Option<u32>| {
+            expected.is_none_or(|expected| actual == Some(expected))
+        };
+        matches(self.season, torrent.metadata.season)
+            && matches(self.episode, torrent.metadata.episode)
+    }
+}
+
+/// Parses a page of the API.
+fn parse_page(response: &str) -> Result<EztvResponse, ClientError> {
+    serde_json::from_str(response).map_err(|e| ClientError::DataParseError(e.into()))
+}
+
+/// Represents a page of the EZTV API response.
+#[derive(Debug, Deserialize)]
+struct EztvResponse {
+    /// The number of torrents of the show across all pages.
+    #[serde(default)]
+    torrents_count: Option<usize>,
+
+    /// The torrents of the page, missing if the show is unknown.
+    #[serde(default)]
+    torrents: Vec<EztvTorrent>,
+}
+
+/// Represents a single episode torrent in the EZTV API response.
+#[derive(Debug, Deserialize)]
+struct EztvTorrent {
+    /// The EZTV identifier of the torrent.
+    id: u64,
+
+    /// The release name, e.g. "Game of Thrones S08E06 1080p WEB H264-MEMENTO EZTV".
+    title: String,
+
+    /// The magnet link of the torrent.
+    magnet_url: String,
+
+    /// The URL of the episode's page on EZTV.
+    #[serde(default)]
+    episode_url: Option<String>,
+
+    /// The IMDb id of the show without the "tt" prefix.
+    #[serde(default)]
+    imdb_id: Option<String>,
+
+    /// The season number as a string, "0" if unknown.
+    #[serde(default, deserialize_with = "number_string")]
+    season: Option<u32>,
+
+    /// The episode number as a string, "0" if unknown.
+    #[serde(default, deserialize_with = "number_string")]
+    episode: Option<u32>,
+
+    /// The number of seeders.
+    seeds: u32,
+
+    /// The number of leechers.
+    peers: u32,
+
+    /// When the episode was released, as a Unix timestamp.
+    #[serde(default)]
+    date_released_unix: Option<u64>,
+
+    /// The size of the torrent in bytes, as a string.
+    #[serde(default, deserialize_with = "number_string")]
+    size_bytes: Option<u64>,
+}
+
+impl EztvTorrent {
+    /// Converts the API torrent into a `Torrent`.
+    fn into_torrent(self) -> Torrent {
+        Torrent {
+            name: self.title,
+            magnet_link: self.magnet_url,
+            seeders: self.seeds,
+            peers: self.peers,
+            size_bytes: self.size_bytes.unwrap_or(0),
+            provider: "eztv".to_string(),
+            metadata: TorrentMetadata {
+                imdb_id: self
+                    .imdb_id
+                    .filter(|id| !id.is_empty() && id != "0")
+                    .map(|id| format!("tt{}", id.trim_start_matches("tt"))),
+                provider_id: Some(self.id.to_string()),
+                details_url: self.episode_url,
+                uploaded_at: self
+                    .date_released_unix
+                    .and_then(|seconds| UNIX_EPOCH.checked_add(Duration::from_secs(seconds))),
+                season: self.season,
+                episode: self.episode,
+                ..Default::default()
+            },
+            stats_updated_at: None,
+        }
+    }
+}
+
+/// Deserializes a number sent as a string or a number; empty strings and 0 are `None`.
+fn number_string<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: std::str::FromStr + Default + PartialEq,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberString {
+        Number(u64),
+        String(String),
+    }
+
+    let value = match Option::<NumberString>::deserialize(deserializer)? {
+        Some(NumberString::Number(number)) => number.to_string(),
+        Some(NumberString::String(string)) => string,
+        None => return Ok(None),
+    };
+    Ok(value
+        .trim()
+        .parse()
+        .ok()
+        .filter(|number| *number != T::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::ReqwestTransport;
+    use mockito::{Matcher, Server};
+    use serde_json::json;
+
+    /// Returns an API torrent of an episode.
+    fn episode(id: u64, season: &str, episode: &str) -> serde_json::Value {
+        json!({
+            "id": id,
+            "hash": "0123456789abcdef0123456789abcdef01234567",
+            "filename": format!("Game.of.Thrones.S{:0>2}E{:0>2}.1080p.WEB.mkv", season, episode),
+            "episode_url": format!("https://eztvx.to/ep/{}/game-of-thrones/", id),
+            "torrent_url": format!("https://zoink.ch/torrent/{}.torrent", id),
+            "magnet_url": format!("magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567&dn={}", id),
+            "title": format!("Game of Thrones S{:0>2}E{:0>2} 1080p WEB H264-MEMENTO EZTV", season, episode),
+            "imdb_id": "0944947",
+            "season": season,
+            "episode": episode,
+            "small_screenshot": "",
+            "large_screenshot": "",
+            "seeds": 120,
+            "peers": 14,
+            "date_released_unix": 1558314420,
+            "size_bytes": "2147483648"
+        })
+    }
+
+    /// Tests parsing the episodes and the response for an unknown show.
+    #[test]
+    fn test_parse_response() {
+        let response = json!({
+            "imdb_id": "0944947",
+            "torrents_count": 1,
+            "limit": 30,
+            "page": 1,
+            "torrents": [episode(1234, "8", "6")]
+        });
+
+        let torrents = Eztv::new().parse_response(&response.to_string()).unwrap();
+        assert_eq!(torrents.len(), 1);
+        let torrent = &torrents[0];
+        assert_eq!(
+            torrent.name,
+            "Game of Thrones S08E06 1080p WEB H264-MEMENTO EZTV"
+        );
+        assert!(torrent.magnet_link.starts_with("magnet:?xt=urn:btih:"));
+        assert_eq!((torrent.seeders, torrent.peers), (120, 14));
+        assert_eq!(torrent.size_bytes, 2_147_483_648);
+        assert_eq!(torrent.provider, "eztv");
+        assert_eq!(torrent.metadata.imdb_id.as_deref(), Some("tt0944947"));
+        assert_eq!(torrent.metadata.provider_id.as_deref(), Some("1234"));
+        assert_eq!(
+            (torrent.metadata.season, torrent.metadata.episode),
+            (Some(8), Some(6))
+        );
+        assert_eq!(
+            torrent.metadata.uploaded_at,
+            Some(UNIX_EPOCH + Duration::from_secs(1558314420))
+        );
+
+        let mut far_future = episode(1235, "8", "6");
+        far_future["date_released_unix"] = json!(u64::MAX);
+        let response = json!({ "torrents": [far_future] }).to_string();
+        let torrents = Eztv::new().parse_response(&response).unwrap();
+        assert_eq!(torrents[0].metadata.uploaded_at, None);
+
+        let unknown = r#"{"imdb_id": "1", "torrents_count": 0, "limit": 30, "page": 1}"#;
+        assert!(Eztv::new().parse_response(unknown).unwrap().is_empty());
+        assert!(matches!(
+            Eztv::new().parse_response("<html>"),
+            Err(ClientError::DataParseError(_))
+        ));
+    }
+
+    /// Tests parsing IMDb ids with a season or episode.
+    #[test]
+    fn test_episode_query() {
+        let query = |imdb_id: &str, season, episode| {
+            Some(EpisodeQuery {
+                imdb_id: imdb_id.to_string(),
+                season,
+                episode,
+            })
+        };
+
+        assert_eq!(
+            EpisodeQuery::parse("tt0944947"),
+            query("0944947", None, None)
+        );
+        assert_eq!(
+            EpisodeQuery::parse("TT0944947 s08"),
+            query("0944947", Some(8), None)
+        );
+        assert_eq!(
+            EpisodeQuery::parse(" tt0944947 S08E06 1080p"),
+            query("0944947", Some(8), Some(6))
+        );
+        assert_eq!(
+            EpisodeQuery::parse("tt0944947 Winterfell"),
+            query("0944947", None, None)
+        );
+        assert_eq!(EpisodeQuery::parse("game of thrones"), None);
+        assert_eq!(EpisodeQuery::parse("tt"), None);
+
+        let request = Eztv::new()
+            .build_request(SearchRequest::new("tt0944947 S08E06"))
+            .unwrap();
+        assert_eq!(
+            request.uri().to_string(),
+            "https://eztvx.to/api/get-torrents?imdb_id=0944947&limit=50&page=1"
+        );
+        assert!(Eztv::new()
+            .build_request(SearchRequest::new("ubuntu"))
+            .is_err());
+    }
+
+    /// Tests fetching pages until the requested episode is found, and skipping other searches.
+    #[tokio::test]
+    async fn test_send_request() {
+        let mut server = Server::new_async().await;
+        let mut page = |page: &str, torrents: Vec<serde_json::Value>| {
+            // The count is sometimes missing, which must not end the search early
+            let mut body = json!({ "torrents": torrents });
+            if page != "1" {
+                body["torrents_count"] = json!(150);
+            }
+            server
+                .mock("GET", "/")
+                .match_query(Matcher::AllOf(vec![
+                    Matcher::UrlEncoded("imdb_id".into(), "0944947".into()),
+                    Matcher::UrlEncoded("limit".into(), "100".into()),
+                    Matcher::UrlEncoded("page".into(), page.into()),
+                ]))
+                .with_body(body.to_string())
+                .create()
+        };
+        let first = page("1", (0..100).map(|id| episode(id, "8", "5")).collect());
+        let second = page(
+            "2",
+            vec![
+                episode(100, "8", "6"),
+                episode(101, "8", "6"),
+                episode(102, "7", "6"),
+            ],
+        );
+
+        let provider = Eztv::with_url(server.url());
+        let transport = ReqwestTransport::new();
+        let torrents = provider
+            .send_request(&transport, SearchRequest::new("tt0944947 S08E06"))
+            .await
+            .unwrap();
+        first.assert_async().await;
+        second.assert_async().await;
+        let ids: Vec<Option<&str>> = torrents
+            .iter()
+            .map(|torrent| torrent.metadata.provider_id.as_deref())
+            .collect();
+        assert_eq!(ids, vec![Some("100"), Some("101")]);
+
+        for request in [
+            SearchRequest::new("game of thrones"),
+            SearchRequest::new("tt0944947").add_category(Category::Movies),
+        ] {
+            assert!(provider
+                .send_request(&transport, request)
+                .await
+                .unwrap()
+                .is_empty());
+        }
+    }
+}
@@ -0,0 +1,428 @@
+//! # HTML Scraping
+//!
+//! Helpers for `SearchProvider` implementations scraping sites without an API.
+//!
+//! `HtmlPage` parses a page and runs CSS selectors over its result rows, and
+//! `HtmlElement` extracts text, attributes and links, resolving relative links
+//! against the URL of the page. Sites only showing the magnet link on details
+//! pages are handled by `resolve_magnets`, which fetches those pages with
+//! bounded concurrency.
+//!
+//! Parsed pages cannot be held across `.await` points, since they are not
+//! `Send`; parse and extract the results in a synchronous block.
+//!
+//! # Example
+//! ```
+//! use magneto::search_providers::html::{selector, HtmlPage};
+//!
+//! let page = HtmlPage::parse(
+//!     r#"<table><tr><td><a href="/view/1">Ubuntu 24.04</a></td><td>12</td></tr></table>"#,
+//!     "https://site.example/search?q=ubuntu",
+//! )
+//! .unwrap();
+//!
+//! let row = page.rows(&selector("tr")).next().unwrap();
+//! assert_eq!(row.text_of(&selector("a")).as_deref(), Some("Ubuntu 24.04"));
+//! assert_eq!(row.link_of(&selector("a")).as_deref(), Some("https://site.example/view/1"));
+//! assert_eq!(row.text_of(&selector("td + td")).as_deref(), Some("12"));
+//! ```
+
+use std::sync::LazyLock;
+
+use bytes::Bytes;
+use futures_util::{stream, StreamExt};
+use http::Request;
+use log::debug;
+use scraper::{ElementRef, Html, Selector};
+use url::Url;
+
+use crate::{errors::ClientError, transport::HttpTransport, Torrent};
+
+/// The selector of magnet links.
+static MAGNET_LINK: LazyLock<Selector> = LazyLock::new(|| selector("a[href^='magnet:']"));
+
+/// Compiles a constant CSS selector.
+///
+/// # Parameters
+/// - `css`: The selector, e.g. "table.results tbody tr".
+///
+/// # Panics
+/// If the selector is invalid; use `Selector::parse` for selectors from user input.
+pub fn selector(css: &str) -> Selector {
+    Selector::parse(css).unwrap_or_else(|e| panic!("invalid selector '{}': {}", css, e))
+}
+
+/// Resolves a link against the URL of its page.
+///
+/// # Parameters
+/// - `base`: The URL of the page.
+/// - `href`: The link, absolute or relative; magnet links are returned as is.
+///
+/// # Returns
+/// - `Some(String)`: The absolute link.
+/// - `None`: If the link is empty or invalid.
+pub fn resolve_url(base: &Url, href: &str) -> Option<String> {
+    let href = href.trim();
+    if href.is_empty() {
+        return None;
+    }
+    if href.starts_with("magnet:") {
+        return Some(href.to_string());
+    }
+
+    base.join(href).ok().map(String::from)
+}
+
+/// A parsed HTML page and its URL.
+pub struct HtmlPage {
+    /// The parsed document.
+    document: Html,
+
+    /// The URL of the page, relative links are resolved against it.
+    url: Url,
+}
+
+impl HtmlPage {
+    /// Parses a page.
+    ///
+    /// # Parameters
+    /// - `body`: The HTML of the page; invalid markup is parsed as browsers do.
+    /// - `url`: The URL of the page.
+    ///
+    /// # Returns
+    /// - `Ok(HtmlPage)`: The parsed page.
+    /// - `Err(ClientError::DataParseError)`: An error if the URL is invalid.
+    pub fn parse(body: &str, url: &str) -> Result<Self, ClientError> {
+        Ok(Self {
+            document: Html::parse_document(body),
+            url: Url::parse(url).map_err(|e| ClientError::DataParseError(e.into()))?,
+        })
+    }
+
+    /// Returns the URL of the page.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Returns the elements matching a selector, e.g. the result rows.
+    pub fn rows<'a, 's>(
+        &'a self,
+        selector: &'s Selector,
+    ) -> impl Iterator<Item = HtmlElement<'a>> + 's
+    where
+        'a: 's,
+    {
+        let base_url = &self.url;
+        self.document
+            .select(selector)
+            .map(move |element| HtmlElement { element, base_url })
+    }
+
+    /// Returns the first element matching a selector.
+    pub fn find(&self, selector: &Selector) -> Option<HtmlElement<'_>> {
+        self.rows(selector).next()
+    }
+}
+
+/// An element of an `HtmlPage`.
+#[derive(Clone, Copy)]
+pub struct HtmlElement<'a> {
+    /// The element.
+    element: ElementRef<'a>,
+
+    /// The URL of the page.
+    base_url: &'a Url,
+}
+
+impl<'a> HtmlElement<'a> {
+    /// Returns the first descendant matching a selector.
+    pub fn find(&self, selector: &Selector) -> Option<HtmlElement<'a>> {
+        self.find_all(selector).next()
+    }
+
+    /// Returns the descendants matching a selector.
+    pub fn find_all<'s>(&self, selector: &'s Selector) -> impl Iterator<Item = HtmlElement<'a>> + 's
+    where
+        'a: 's,
+    {
+        let base_url = self.base_url;
+        self.element
+            .select(selector)
+            .map(move |element| HtmlElement { element, base_url })
+    }
+
+    /// Returns the text of the element, with whitespace collapsed to single spaces.
+    pub fn text(&self) -> String {
+        self.element
+            .text()
+            .flat_map(str::split_whitespace)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Returns the value of an attribute of the element.
+    pub fn attr(&self, name: &str) -> Option<&'a str> {
+        self.element.value().attr(name)
+    }
+
+    /// Returns the `href` of the element, resolved against the URL of the page.
+    pub fn link(&self) -> Option<String> {
+        resolve_url(self.base_url, self.attr("href")?)
+    }
+
+    /// Returns the text of the first descendant matching a selector, if not empty.
+    pub fn text_of(&self, selector: &Selector) -> Option<String> {
+        Some(self.find(selector)?.text()).filter(|text| !text.is_empty())
+    }
+
+    /// Returns an attribute of the first descendant matching a selector.
+    pub fn attr_of(&self, selector: &Selector, name: &str) -> Option<&'a str> {
+        self.find(selector)?.attr(name)
+    }
+
+    /// Returns the resolved `href` of the first descendant matching a selector.
+    pub fn link_of(&self, selector: &Selector) -> Option<String> {
+        self.find(selector)?.link()
+    }
+
+    /// Returns the first magnet link among the descendant links.
+    pub fn magnet_link(&self) -> Option<String> {
+        self.find_all(&MAGNET_LINK)
+            .find_map(|link| link.attr("href"))
+            .map(str::to_string)
+    }
+}
+
+/// Fetches a page and returns its body if the response is successful.
+///
+/// # Parameters
+/// - `transport`: The `HttpTransport` used for sending the request.
+/// - `url`: The URL of the page.
+///
+/// # Returns
+/// - `Ok(String)`: The body of the page.
+/// - `Err(ClientError)`: An error if the request fails or the response is unsuccessful.
+pub async fn fetch_page(transport: &dyn HttpTransport, url: &str) -> Result<String, ClientError> {
+    let request =
+        Request::get(url)
+            .body(Bytes::new())
+            .map_err(|e| ClientError::RequestBuildError {
+                source: e.into(),
+                url: url.to_string(),
+            })?;
+
+    let response = transport.send(request).await?;
+    let content = String::from_utf8_lossy(response.body()).into_owned();
+    if !response.status().is_success() {
+        return Err(ClientError::ServerResponseError {
+            code: response.status(),
+            content,
+        });
+    }
+
+    Ok(content)
+}
+
+/// Fetches pages, at most `concurrency` at a time, and extracts a value from each.
+///
+/// # Parameters
+/// - `transport`: The `HttpTransport` used for sending the requests.
+/// - `urls`: The URLs of the pages.
+/// - `concurrency`: The maximum number of requests in flight, at least 1.
+/// - `extract`: Extracts the value from a parsed page.
+///
+/// # Returns
+/// - `Vec<Result<T, ClientError>>`: The value or error of each page, in the order of `urls`.
+pub async fn fetch_pages<T, F>(
+    transport: &dyn HttpTransport,
+    urls: Vec<String>,
+    concurrency: usize,
+    extract: F,
+) -> Vec<Result<T, ClientError>>
+where
+    T: Send,
+    F: Fn(&HtmlPage) -> T + Sync,
+{
+    let extract = &extract;
+    stream::iter(urls)
+        .map(|url| async move {
+            let body = fetch_page(transport, &url).await?;
+            let page = HtmlPage::parse(&body, &url)?;
+            Ok(extract(&page))
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// Fills in the magnet links of torrents from their details pages.
+///
+/// Torrents without a magnet link are looked up on their `metadata.details_url`,
+/// at most `concurrency` pages at a time. Torrents whose magnet link cannot be
+/// found are dropped.
+///
+/// # Parameters
+/// - `transport`: The `HttpTransport` used for sending the requests.
+/// - `torrents`: The torrents, some without magnet link.
+/// - `concurrency`: The maximum number of requests in flight, at least 1.
+/// - `extract`: Extracts the magnet link from a details page, e.g. with `HtmlElement::magnet_link`.
+///
+/// # Returns
+/// - `Vec<Torrent>`: The torrents with a magnet link, in their original order.
+pub async fn resolve_magnets<F>(
+    transport: &dyn HttpTransport,
+    torrents: Vec<Torrent>,
+    concurrency: usize,
+    extract: F,
+) -> Vec<Torrent>
+where
+    F: Fn(&HtmlPage) -> Option<String> + Sync,
+{
+    let (resolved, unresolved): (Vec<_>, Vec<_>) = torrents
+        .into_iter()
+        .enumerate()
+        .partition(|(_, torrent)| !torrent.magnet_link.is_empty());
+    let (unresolved, urls): (Vec<_>, Vec<_>) = unresolved
+        .into_iter()
+        .filter_map(|(index, torrent)| {
+            let url = torrent.metadata.details_url.clone()?;
+            Some(((index, torrent), url))
+        })
+        .unzip();
+
+    let magnets = fetch_pages(transport, urls, concurrency, extract).await;
+    let mut torrents: Vec<(usize, Torrent)> =
+        resolved
+            .into_iter()
+            .chain(unresolved.into_iter().zip(magnets).filter_map(
+                |((index, mut torrent), magnet)| match magnet {
+                    Ok(Some(magnet)) => {
+                        torrent.magnet_link = magnet;
+                        Some((index, torrent))
+                    }
+                    Ok(None) => {
+                        debug!("no magnet link on details page of '{}'", torrent.name);
+                        None
+                    }
+                    Err(e) => {
+                        debug!("cannot fetch details page of '{}': {}", torrent.name, e);
+                        None
+                    }
+                },
+            ))
+            .collect();
+    torrents.sort_by_key(|(index, _)| *index);
+
+    torrents.into_iter().map(|(_, torrent)| torrent).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{transport::ReqwestTransport, TorrentMetadata};
+    use mockito::Server;
+
+    const PAGE: &str = r#"<html><body><table class="results">
+        <tr><th>Name</th></tr>
+        <tr class="result">
+          <td class="name"><a href="/view/1?x=1&amp;y=2">Ubuntu
+            24.04 <span>LTS</span></a></td>
+          <td class="seeders">12</td>
+          <td><a href="magnet:?xt=urn:btih:abc&amp;dn=ubuntu">Magnet</a></td>
+        </tr>
+        <tr class="result">
+          <td class="name"><a href="https://other.example/view/2">Debian</a></td>
+          <td class="seeders"></td>
+        </tr>
+    </table></body></html>"#;
+
+    /// Tests selecting rows and extracting text, attributes and links.
+    #[test]
+    fn test_html_page() {
+        let page = HtmlPage::parse(PAGE, "https://site.example/search/ubuntu/1/").unwrap();
+        let rows: Vec<HtmlElement> = page.rows(&selector("tr.result")).collect();
+        assert_eq!(rows.len(), 2);
+
+        let name = selector("td.name a");
+        let seeders = selector("td.seeders");
+        assert_eq!(rows[0].text_of(&name).as_deref(), Some("Ubuntu 24.04 LTS"));
+        assert_eq!(
+            rows[0].link_of(&name).as_deref(),
+            Some("https://site.example/view/1?x=1&y=2")
+        );
+        assert_eq!(rows[0].text_of(&seeders).as_deref(), Some("12"));
+        assert_eq!(
+            rows[0].magnet_link().as_deref(),
+            Some("magnet:?xt=urn:btih:abc&dn=ubuntu")
+        );
+
+        assert_eq!(
+            rows[1].link_of(&name).as_deref(),
+            Some("https://other.example/view/2")
+        );
+        assert_eq!(rows[1].text_of(&seeders), None);
+        assert_eq!(rows[1].magnet_link(), None);
+        assert!(page.find(&selector("div.missing")).is_none());
+
+        let base = page.url();
+        assert_eq!(resolve_url(base, " "), None);
+        assert_eq!(
+            resolve_url(base, "../2/").as_deref(),
+            Some("https://site.example/search/ubuntu/2/")
+        );
+        assert!(HtmlPage::parse(PAGE, "not a url").is_err());
+    }
+
+    /// Tests that magnet links are read from details pages, keeping the order of torrents.
+    #[tokio::test]
+    async fn test_resolve_magnets() {
+        let mut server = Server::new_async().await;
+        for (path, body) in [
+            ("/view/1", r#"<a href="magnet:?xt=urn:btih:one">Magnet</a>"#),
+            ("/view/2", "<p>Removed</p>"),
+        ] {
+            server
+                .mock("GET", path)
+                .with_body(body)
+                .expect(1)
+                .create_async()
+                .await;
+        }
+        server
+            .mock("GET", "/view/3")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let torrent = |name: &str, magnet_link: &str, details: Option<&str>| Torrent {
+            name: name.to_string(),
+            magnet_link: magnet_link.to_string(),
+            seeders: 0,
+            peers: 0,
+            size_bytes: 0,
+            provider: "site".to_string(),
+            metadata: TorrentMetadata {
+                details_url: details.map(|path| format!("{}{}", server.url(), path)),
+                ..Default::default()
+            },
+            stats_updated_at: None,
+        };
+        let torrents = vec![
+            torrent("one", "", Some("/view/1")),
+            torrent("known", "magnet:?xt=urn:btih:known", None),
+            torrent("removed", "", Some("/view/2")),
+            torrent("missing", "", Some("/view/3")),
+            torrent("no details", "", None),
+        ];
+
+        let transport = ReqwestTransport::new();
+        let torrents = resolve_magnets(&transport, torrents, 2, |page| {
+            page.find(&selector("body"))?.magnet_link()
+        })
+        .await;
+
+        let names: Vec<&str> = torrents.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["one", "known"]);
+        assert_eq!(torrents[0].magnet_link, "magnet:?xt=urn:btih:one");
+    }
+}
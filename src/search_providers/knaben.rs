@@ -5,10 +5,13 @@
 //! JSON bodies and parses the response into the `Torrent` structure.
 
 use async_trait::async_trait;
-use reqwest::{header::CONTENT_TYPE, Client, Request};
+use bytes::Bytes;
+use http::{header::CONTENT_TYPE, Request};
 use serde::{Deserialize, Serialize};
 
-use crate::{Category, ClientError, SearchProvider, SearchRequest, Torrent};
+use crate::{
+    Category, ClientError, OrderBy, SearchProvider, SearchRequest, Torrent, TorrentMetadata,
+};
 
 /// The `Knaben` provider handles querying and parsing data from the Knaben API.
 pub struct Knaben {
@@ -54,26 +57,19 @@ impl SearchProvider for Knaben {
     /// Builds the request to query the Knaben API.
     ///
     /// # Parameters
-    /// - `client`: The HTTP client used to build the request.
     /// - `request`: The `SearchRequest` containing query parameters.
     ///
     /// # Returns
-    /// - `Ok(Request)`: The constructed HTTP request.
+    /// - `Ok(Request<Bytes>)`: The constructed HTTP request.
     /// - `Err(ClientError)`: An error if request building fails.
-    fn build_request(
-        &self,
-        client: &Client,
-        request: SearchRequest<'_>,
-    ) -> Result<Request, ClientError> {
+    fn build_request(&self, request: SearchRequest<'_>) -> Result<Request<Bytes>, ClientError> {
         let knaben_request = KnabenRequest::from_search_request(request);
         let json = serde_json::to_value(&knaben_request)
             .map_err(|e| ClientError::DataParseError(e.into()))?;
 
-        client
-            .post(self.api_url.clone())
+        Request::post(self.api_url.as_str())
             .header(CONTENT_TYPE, "application/json")
-            .body(json.to_string())
-            .build()
+            .body(Bytes::from(json.to_string()))
             .map_err(|e| ClientError::RequestBuildError {
                 source: e.into(),
                 url: self.api_url.clone(),
@@ -103,6 +99,12 @@ impl SearchProvider for Knaben {
                     peers: entry.peers,
                     size_bytes: entry.bytes,
                     provider: format!("{} (via Knaben)", entry.tracker),
+                    metadata: TorrentMetadata {
+                        provider_id: Some(entry.id.to_owned()),
+                        details_url: entry.details.to_owned(),
+                        ..Default::default()
+                    },
+                    stats_updated_at: None,
                 })
             })
             .collect();
@@ -190,7 +192,10 @@ impl KnabenRequest {
             search_type: "100%".to_string(),
             search_field: "title".to_string(),
             query: request.query.to_string(),
-            order_by: request.order_by.to_string(),
+            order_by: match request.order_by {
+                OrderBy::ProfileScore => OrderBy::Seeders.to_string(),
+                order_by => order_by.to_string(),
+            },
             order_direction: "desc".to_string(),
             categories,
             size: 50,
@@ -240,12 +245,17 @@ pub struct ResponseEntry {
 
     /// Categories associated with the torrent.
     category_id: Vec<u32>,
+
+    /// Link to the torrent's details page on the originating tracker.
+    #[serde(default)]
+    details: Option<String>,
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::transport::ReplayTransport;
     use mockito::Server;
     use serde_json::{json, Value};
 
@@ -263,10 +273,9 @@ mod tests {
     #[tokio::test]
     async fn test_build_request_with_categories() {
         let provider = setup_mock_provider().await;
-        let client = Client::new();
 
         let search_request = SearchRequest::new("ubuntu").add_category(Category::Movies);
-        let request = provider.build_request(&client, search_request);
+        let request = provider.build_request(search_request);
 
         assert!(request.is_ok());
         let request = request.unwrap();
@@ -277,8 +286,7 @@ mod tests {
         );
 
         let body: serde_json::Value =
-            serde_json::from_slice(request.body().unwrap().as_bytes().unwrap())
-                .expect("Body should be valid JSON");
+            serde_json::from_slice(request.body()).expect("Body should be valid JSON");
         assert_eq!(body["query"], "ubuntu");
         assert_eq!(body["categories"], json![[3000000]]);
     }
@@ -289,18 +297,16 @@ mod tests {
     #[tokio::test]
     async fn test_build_request_without_categories() {
         let provider = setup_mock_provider().await;
-        let client = Client::new();
 
         let search_request = SearchRequest::new("ubuntu");
-        let request = provider.build_request(&client, search_request);
+        let request = provider.build_request(search_request);
 
         assert!(request.is_ok());
         let request = request.unwrap();
         assert_eq!(request.method(), "POST");
 
         let body: serde_json::Value =
-            serde_json::from_slice(request.body().unwrap().as_bytes().unwrap())
-                .expect("Body should be valid JSON");
+            serde_json::from_slice(request.body()).expect("Body should be valid JSON");
         assert_eq!(body["query"], "ubuntu");
         assert_eq!(body.get("categories").unwrap(), &Value::Null);
     }
@@ -346,6 +352,43 @@ mod tests {
         assert_eq!(torrent.provider, "knaben (via Knaben)");
     }
 
+    /// Tests parsing the extended metadata of an API response entry.
+    ///
+    /// Ensures that the Knaben id and the tracker details link are mapped
+    /// into `TorrentMetadata`.
+    #[tokio::test]
+    async fn test_parse_response_metadata() {
+        let provider = setup_mock_provider().await;
+
+        let response_body = r#"
+        {
+            "hits": [
+                {
+                    "id": "abc",
+                    "title": "Ubuntu ISO",
+                    "hash": "abc123",
+                    "peers": 10,
+                    "seeders": 20,
+                    "bytes": 2048,
+                    "date": "2024-01-01",
+                    "tracker": "knaben",
+                    "categoryId": [3000000],
+                    "details": "https://example.org/torrent/1"
+                }
+            ]
+        }
+        "#;
+
+        let torrents = provider.parse_response(response_body).unwrap();
+        let metadata = &torrents[0].metadata;
+        assert_eq!(metadata.provider_id.as_deref(), Some("abc"));
+        assert_eq!(
+            metadata.details_url.as_deref(),
+            Some("https://example.org/torrent/1")
+        );
+        assert_eq!(metadata.uploader, None);
+    }
+
     /// Tests parsing an API response with invalid JSON.
     ///
     /// Ensures that invalid JSON results in a `DataParseError`.
@@ -415,4 +458,26 @@ mod tests {
         let torrents = result.unwrap();
         assert!(torrents.is_empty(), "Expected empty results");
     }
+
+    /// Tests searching through the recorded Knaben fixture.
+    #[tokio::test]
+    async fn test_fixture_replay() {
+        let transport = ReplayTransport::from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/knaben.json"
+        ))
+        .unwrap();
+
+        let torrents = Knaben::new()
+            .send_request(&transport, SearchRequest::new("ubuntu"))
+            .await
+            .unwrap();
+
+        // The hit without a hash is skipped
+        assert_eq!(torrents.len(), 2);
+        assert_eq!(torrents[0].name, "ubuntu-24.04-desktop-amd64.iso");
+        assert_eq!(torrents[0].seeders, 912);
+        assert_eq!(torrents[0].provider, "1337x (via Knaben)");
+        assert_eq!(torrents[1].size_bytes, 2760966144);
+    }
 }
@@ -0,0 +1,533 @@
+//! # 1337x Search Provider
+//!
+//! The `Leetx` implementation of the `SearchProvider` trait scrapes the search
+//! pages of 1337x, or one of its mirrors, into a unified `Torrent` structure.
+//!
+//! 1337x has no API and search pages do not link magnets, so the details page
+//! of every result is fetched, a few at a time, to read its magnet link.
+//! Searches use the site's sorted search URLs, e.g.
+//! "/sort-category-search/ubuntu/Apps/seeders/desc/1/", with the first
+//! requested category.
+
+use std::{
+    sync::LazyLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::Request;
+use log::debug;
+use percent_encoding::utf8_percent_encode;
+use scraper::Selector;
+
+use crate::{
+    date::parse_date_relative_to,
+    errors::ClientError,
+    search_providers::{
+        html::{self, selector, HtmlElement, HtmlPage},
+        ENCODED,
+    },
+    size::parse_size,
+    transport::HttpTransport,
+    Category, Magnet, OrderBy, SearchProvider, SearchRequest, Torrent, TorrentMetadata,
+    UploaderStatus,
+};
+
+/// The number of details pages fetched at a time by default.
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// The result rows of a search page.
+static ROWS: LazyLock<Selector> = LazyLock::new(|| selector("table.table-list tbody tr"));
+
+/// The link to the details page in a result row, after the category icon link.
+static NAME: LazyLock<Selector> = LazyLock::new(|| selector("td.name a[href*='/torrent/']"));
+
+/// The seeders cell of a result row.
+static SEEDS: LazyLock<Selector> = LazyLock::new(|| selector("td.seeds"));
+
+/// The leechers cell of a result row.
+static LEECHES: LazyLock<Selector> = LazyLock::new(|| selector("td.leeches"));
+
+/// The size cell of a result row.
+static SIZE: LazyLock<Selector> = LazyLock::new(|| selector("td.size"));
+
+/// The upload date cell of a result row.
+static DATE: LazyLock<Selector> = LazyLock::new(|| selector("td.coll-date"));
+
+/// The uploader cell of a result row, whose class holds the uploader's status.
+static UPLOADER: LazyLock<Selector> = LazyLock::new(|| selector("td.coll-5"));
+
+/// The box shown instead of the results table when nothing matches.
+static NO_RESULTS: LazyLock<Selector> = LazyLock::new(|| selector("div.box-info-detail"));
+
+/// The body of a details page.
+static BODY: LazyLock<Selector> = LazyLock::new(|| selector("body"));
+
+/// The info hash on a details page.
+static INFO_HASH: LazyLock<Selector> = LazyLock::new(|| selector("div.infohash-box span"));
+
+/// The name of the torrent on a details page.
+static HEADING: LazyLock<Selector> = LazyLock::new(|| selector("div.box-info-heading h1"));
+
+/// The `Leetx` provider handles scraping search results from 1337x.
+pub struct Leetx {
+    /// The base URL of the site.
+    url: String,
+
+    /// The maximum number of details pages fetched at a time.
+    concurrency: usize,
+}
+
+impl Leetx {
+    /// Creates a new instance of the `Leetx` provider.
+    ///
+    /// # Returns
+    /// - `Leetx`: A new provider instance with the default URL.
+    pub fn new() -> Self {
+        Self::with_url("https://1337x.to/")
+    }
+
+    /// Creates a new instance of the `Leetx` provider with a custom URL, e.g. a mirror.
+    ///
+    /// # Parameters
+    /// - `url`: The base URL of the site, e.g. "https://1337x.st/".
+    ///
+    /// # Returns
+    /// - `Leetx`: A new provider instance with the specified URL.
+    pub fn with_url(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// Sets the maximum number of details pages fetched at a time, 4 by default.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Converts a result row into a `Torrent` without magnet link, or `None` without name.
+    fn torrent(row: &HtmlElement<'_>, now: SystemTime) -> Option<Torrent> {
+        let count = |selector: &Selector| {
+            row.text_of(selector)
+                .and_then(|count| count.replace(',', "").parse().ok())
+                .unwrap_or(0)
+        };
+
+        let link = row.find(&NAME)?;
+        let details_url = link.link();
+        let provider_id = link
+            .attr("href")
+            .and_then(|href| href.split('/').skip_while(|part| *part != "torrent").nth(1))
+            .map(str::to_string);
+
+        // The size cell also holds the seeders, shown in place of their column on mobile
+        let size_bytes = row
+            .text_of(&SIZE)
+            .and_then(|size| {
+                let size: Vec<&str> = size.split_whitespace().take(2).collect();
+                parse_size(&size.join(" ")).ok()
+            })
+            .unwrap_or(0);
+
+        let uploader_cell = row.find(&UPLOADER);
+        let uploader_status = uploader_cell
+            .and_then(|cell| cell.attr("class"))
+            .and_then(|class| {
+                class.split_whitespace().find_map(|class| match class {
+                    "vip" => Some(UploaderStatus::Vip),
+                    "uploader" => Some(UploaderStatus::Trusted),
+                    "trial-uploader" | "user" => Some(UploaderStatus::Member),
+                    _ => None,
+                })
+            });
+
+        Some(Torrent {
+            name: link.text(),
+            magnet_link: String::new(),
+            seeders: count(&SEEDS),
+            peers: count(&LEECHES),
+            size_bytes,
+            provider: "1337x".to_string(),
+            metadata: TorrentMetadata {
+                uploader: uploader_cell.map(|cell| cell.text()),
+                uploader_status,
+                provider_id,
+                details_url,
+                uploaded_at: row
+                    .text_of(&DATE)
+                    .and_then(|date| parse_upload_date(&date, now)),
+                ..Default::default()
+            },
+            stats_updated_at: None,
+        })
+        .filter(|torrent| !torrent.name.is_empty())
+    }
+}
+
+impl Default for Leetx {
+    /// Provides a default implementation for `Leetx`, returning an instance with the default URL.
+    fn default() -> Self {
+        Leetx::new()
+    }
+}
+
+#[async_trait]
+impl SearchProvider for Leetx {
+    /// Scrapes the search page, then reads the magnet links from the details pages.
+    ///
+    /// At most `number_of_results` details pages are fetched, `with_concurrency`
+    /// at a time. Results whose magnet link cannot be read are left out.
+    ///
+    /// # Parameters
+    /// - `transport`: The `HttpTransport` used for sending HTTP requests.
+    /// - `request`: A `SearchRequest` containing the search parameters.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Torrent>)`: A list of torrents with their magnet links.
+    /// - `Err(ClientError)`: An error if the search page cannot be fetched or parsed.
+    async fn send_request(
+        &self,
+        transport: &dyn HttpTransport,
+        request: SearchRequest<'_>,
+    ) -> Result<Vec<Torrent>, ClientError> {
+        let number_of_results = request.number_of_results;
+        let url = self.build_request(request)?.uri().to_string();
+        let mut torrents = self.parse_response(&html::fetch_page(transport, &url).await?)?;
+        torrents.truncate(number_of_results);
+
+        Ok(html::resolve_magnets(transport, torrents, self.concurrency, details_magnet).await)
+    }
+
+    /// Builds the request for the first sorted search page.
+    ///
+    /// # Parameters
+    /// - `request`: The `SearchRequest` containing query parameters.
+    ///
+    /// # Returns
+    /// - `Ok(Request<Bytes>)`: The constructed HTTP request.
+    /// - `Err(ClientError)`: An error if request building fails.
+    fn build_request(&self, request: SearchRequest<'_>) -> Result<Request<Bytes>, ClientError> {
+        let query = utf8_percent_encode(request.query, ENCODED).to_string();
+        let sort = match request.order_by {
+            OrderBy::Seeders | OrderBy::ProfileScore => "seeders",
+            OrderBy::Peers => "leechers",
+        };
+        // The site searches a single category, so the first requested one is used
+        let category = request.categories.first().map(|category| match category {
+            Category::Movies => "Movies",
+            Category::TvShows => "TV",
+            Category::Games => "Games",
+            Category::Software => "Apps",
+            Category::Audio => "Music",
+            Category::Anime => "Anime",
+            Category::Xxx => "XXX",
+        });
+
+        let path = match category {
+            Some(category) => format!(
+                "sort-category-search/{}/{}/{}/desc/1/",
+                query, category, sort
+            ),
+            None => format!("sort-search/{}/{}/desc/1/", query, sort),
+        };
+        let url = format!("{}/{}", self.url.trim_end_matches('/'), path);
+
+        Request::get(url.as_str())
+            .body(Bytes::new())
+            .map_err(|e| ClientError::RequestBuildError {
+                source: e.into(),
+                url,
+            })
+    }
+
+    /// Parses a search page into a list of torrents without magnet links.
+    ///
+    /// The magnet links are read from the details pages by `send_request`.
+    ///
+    /// # Parameters
+    /// - `response`: The raw response body as a string.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Torrent>)`: A list of parsed torrents.
+    /// - `Err(ClientError)`: An error if the page has neither results nor a no results notice.
+    fn parse_response(&self, response: &str) -> Result<Vec<Torrent>, ClientError> {
+        let page = HtmlPage::parse(response, &self.url)?;
+        let now = SystemTime::now();
+        let torrents: Vec<Torrent> = page
+            .rows(&ROWS)
+            .filter_map(|row| Leetx::torrent(&row, now))
+            .collect();
+
+        let no_results = page
+            .find(&NO_RESULTS)
+            .is_some_and(|notice| notice.text().contains("No results"));
+        if torrents.is_empty() && !no_results {
+            return Err(ClientError::DataParseError(anyhow!(
+                "response is not a 1337x search page"
+            )));
+        }
+
+        Ok(torrents)
+    }
+
+    /// Returns the unique identifier for this provider.
+    ///
+    /// # Returns
+    /// - `String`: The provider's base URL as its unique identifier.
+    fn id(&self) -> String {
+        self.url.clone()
+    }
+}
+
+/// Reads the magnet link from a details page, or builds it from the info hash.
+fn details_magnet(page: &HtmlPage) -> Option<String> {
+    page.find(&BODY)?.magnet_link().or_else(|| {
+        let info_hash: [u8; 20] = hex::decode(page.find(&INFO_HASH)?.text())
+            .ok()?
+            .try_into()
+            .ok()?;
+        debug!("no magnet link on {}, using its info hash", page.url());
+        let mut magnet = Magnet::from_info_hash(info_hash);
+        magnet.display_name = page.find(&HEADING).map(|heading| heading.text());
+        Some(magnet.to_string())
+    })
+}
+
+/// Parses the upload date of a result.
+///
+/// Older uploads show the date, "Apr. 25th '24", uploads of this year the
+/// hour and day, "8am Apr. 26th", and uploads of today the time, "10:35pm".
+fn parse_upload_date(text: &str, now: SystemTime) -> Option<SystemTime> {
+    let mut time = None;
+    let (mut month, mut day, mut year) = (None, None, None);
+    for word in text.split_whitespace() {
+        let word = word.trim_end_matches('.').to_lowercase();
+        if let Some(short_year) = word.strip_prefix('\'') {
+            year = Some(2000u32.checked_add(short_year.parse().ok()?)?);
+        } else if let Some(hour) = word.strip_suffix("am").or(word.strip_suffix("pm")) {
+            let (hour, minute) = hour.split_once(':').unwrap_or((hour, "0"));
+            let hour = hour.parse::<u32>().ok()? % 12 + if word.ends_with("pm") { 12 } else { 0 };
+            time = Some(format!("{:02}:{:02}", hour, minute.parse::<u32>().ok()?));
+        } else if word.starts_with(|c: char| c.is_ascii_digit()) {
+            day = Some(
+                word.trim_end_matches(char::is_alphabetic)
+                    .parse::<u32>()
+                    .ok()?,
+            );
+        } else {
+            month = Some(word);
+        }
+    }
+    let (Some(month), Some(day)) = (month.as_deref(), day) else {
+        // Only the time of an upload from today
+        let time = time.filter(|_| month.is_none() && day.is_none())?;
+        let seconds = now.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        let midnight = UNIX_EPOCH + Duration::from_secs(seconds - seconds % 86400);
+        let (hour, minute) = time.split_once(':')?;
+        let date = midnight
+            + Duration::from_secs(
+                hour.parse::<u64>().ok()? * 3600 + minute.parse::<u64>().ok()? * 60,
+            );
+        return Some(date).filter(|date| *date <= now);
+    };
+    let time = time.as_deref().unwrap_or("00:00");
+    let date = |year: u32| {
+        parse_date_relative_to(&format!("{} {} {} {}", day, month, year, time), now).ok()
+    };
+    if let Some(year) = year {
+        return date(year);
+    }
+
+    // Without a year the upload is from this year, the latest one not in the future
+    let this_year = 1970 + now.duration_since(UNIX_EPOCH).ok()?.as_secs() / 31_556_952;
+    let this_year = u32::try_from(this_year).ok()?;
+    [this_year + 1, this_year, this_year - 1]
+        .into_iter()
+        .filter_map(date)
+        .find(|date| *date <= now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testkit::ConformanceSuite, transport::ReqwestTransport};
+    use mockito::Server;
+
+    /// A search page with a VIP upload from 2024 and a recent upload of a member.
+    const SEARCH_PAGE: &str = r#"<html><body><main>
+        <table class="table-list table table-responsive table-striped">
+          <thead><tr><th class="coll-1 name">name</th><th class="coll-2">se</th></tr></thead>
+          <tbody>
+            <tr>
+              <td class="coll-1 name">
+                <a href="/sub/18/0/" class="icon"><i class="flaticon-apps"></i></a>
+                <a href="/torrent/6048596/Ubuntu-24-04-LTS-x64/">Ubuntu 24.04 LTS x64</a>
+              </td>
+              <td class="coll-2 seeds">1,204</td>
+              <td class="coll-3 leeches">35</td>
+              <td class="coll-date">Apr. 25th '24</td>
+              <td class="coll-4 size mob-vip">5.7 GB<span class="seeds">1,204</span></td>
+              <td class="coll-5 vip"><a href="/user/canonical/">canonical</a></td>
+            </tr>
+            <tr>
+              <td class="coll-1 name">
+                <a href="/sub/18/0/" class="icon"><i class="flaticon-apps"></i></a>
+                <a href="/torrent/6048600/Kubuntu-24-04/">Kubuntu 24.04</a>
+              </td>
+              <td class="coll-2 seeds">12</td>
+              <td class="coll-3 leeches">3</td>
+              <td class="coll-date">8am Feb. 3rd</td>
+              <td class="coll-4 size mob-user">3.1 GB<span class="seeds">12</span></td>
+              <td class="coll-5 user"><a href="/user/someone/">someone</a></td>
+            </tr>
+          </tbody>
+        </table>
+      </main></body></html>"#;
+
+    /// Returns a details page with a magnet link, or only the info hash.
+    fn details_page(name: &str, magnet: bool) -> String {
+        let magnet = if magnet {
+            format!(
+                r#"<ul><li><a class="btn" href="magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567&dn={}">Magnet Download</a></li></ul>"#,
+                name
+            )
+        } else {
+            String::new()
+        };
+        format!(
+            r#"<html><body><main>
+              <div class="box-info-heading"><h1>{}</h1></div>
+              {}
+              <div class="infohash-box"><p><strong>Infohash :</strong> <span>89ABCDEF0123456789ABCDEF0123456789ABCDEF</span></p></div>
+            </main></body></html>"#,
+            name, magnet
+        )
+    }
+
+    /// Tests the sorted search URLs with and without category.
+    #[test]
+    fn test_build_request() {
+        let provider = Leetx::with_url("https://1337x.st");
+
+        let request = provider
+            .build_request(SearchRequest::new("ubuntu 24.04").add_category(Category::Software))
+            .unwrap();
+        assert_eq!(
+            request.uri().to_string(),
+            "https://1337x.st/sort-category-search/ubuntu%2024.04/Apps/seeders/desc/1/"
+        );
+
+        let mut request = SearchRequest::new("C++ / C#");
+        request.order_by = OrderBy::Peers;
+        let request = provider.build_request(request).unwrap();
+        assert_eq!(
+            request.uri().to_string(),
+            "https://1337x.st/sort-search/C%2B%2B%20%2F%20C%23/leechers/desc/1/"
+        );
+    }
+
+    /// Tests parsing the result rows and the upload dates.
+    #[test]
+    fn test_parse_response() {
+        let torrents = Leetx::new().parse_response(SEARCH_PAGE).unwrap();
+        assert_eq!(torrents.len(), 2);
+
+        let torrent = &torrents[0];
+        assert_eq!(torrent.name, "Ubuntu 24.04 LTS x64");
+        assert!(torrent.magnet_link.is_empty());
+        assert_eq!((torrent.seeders, torrent.peers), (1204, 35));
+        assert_eq!(torrent.size_bytes, 5_700_000_000);
+        assert_eq!(torrent.provider, "1337x");
+        assert_eq!(torrent.metadata.uploader.as_deref(), Some("canonical"));
+        assert_eq!(torrent.metadata.uploader_status, Some(UploaderStatus::Vip));
+        assert_eq!(torrent.metadata.provider_id.as_deref(), Some("6048596"));
+        assert_eq!(
+            torrent.metadata.details_url.as_deref(),
+            Some("https://1337x.to/torrent/6048596/Ubuntu-24-04-LTS-x64/")
+        );
+        assert_eq!(
+            torrent.metadata.uploaded_at,
+            Some(UNIX_EPOCH + Duration::from_secs(1714003200))
+        );
+        assert_eq!(
+            torrents[1].metadata.uploader_status,
+            Some(UploaderStatus::Member)
+        );
+
+        // 2024-03-01T12:00:00Z
+        let now = UNIX_EPOCH + Duration::from_secs(1709294400);
+        let date = |seconds| Some(UNIX_EPOCH + Duration::from_secs(seconds));
+        assert_eq!(parse_upload_date("8am Feb. 3rd", now), date(1706947200));
+        assert_eq!(parse_upload_date("9pm Dec. 31st", now), date(1704056400));
+        assert_eq!(parse_upload_date("10:35am", now), date(1709289300));
+        assert_eq!(parse_upload_date("11pm", now), None);
+        assert_eq!(parse_upload_date("yesterday-ish", now), None);
+        assert_eq!(parse_upload_date("Apr. 25th '4294967295", now), None);
+
+        let no_results = r#"<div class="box-info-detail inner-table">
+            <p>No results were returned. Please refine your search.</p></div>"#;
+        assert!(Leetx::new().parse_response(no_results).unwrap().is_empty());
+        assert!(matches!(
+            Leetx::new().parse_response("<html>Just a moment...</html>"),
+            Err(ClientError::DataParseError(_))
+        ));
+    }
+
+    /// Tests reading the magnet links from the details pages.
+    #[tokio::test]
+    async fn test_send_request() {
+        let mut server = Server::new_async().await;
+        let search = server
+            .mock("GET", "/sort-search/ubuntu/seeders/desc/1/")
+            .with_body(SEARCH_PAGE)
+            .create_async()
+            .await;
+        let first = server
+            .mock("GET", "/torrent/6048596/Ubuntu-24-04-LTS-x64/")
+            .with_body(details_page("Ubuntu 24.04 LTS x64", true))
+            .create_async()
+            .await;
+        let second = server
+            .mock("GET", "/torrent/6048600/Kubuntu-24-04/")
+            .with_body(details_page("Kubuntu 24.04", false))
+            .create_async()
+            .await;
+
+        let torrents = Leetx::with_url(server.url())
+            .with_concurrency(1)
+            .send_request(&ReqwestTransport::new(), SearchRequest::new("ubuntu"))
+            .await
+            .unwrap();
+        search.assert_async().await;
+        first.assert_async().await;
+        second.assert_async().await;
+
+        assert_eq!(torrents.len(), 2);
+        assert_eq!(
+            torrents[0].magnet_link,
+            "magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567&dn=Ubuntu 24.04 LTS x64"
+        );
+        let magnet = torrents[1].magnet().unwrap();
+        assert_eq!(
+            magnet.info_hash.map(hex::encode).as_deref(),
+            Some("89abcdef0123456789abcdef0123456789abcdef")
+        );
+        assert_eq!(magnet.display_name.as_deref(), Some("Kubuntu 24.04"));
+    }
+
+    /// Tests that the provider passes the conformance suite.
+    #[tokio::test]
+    async fn test_conformance() {
+        // The mock server answers every page with the sample, so it doubles as details page
+        let sample = SEARCH_PAGE.replace("</main>", &details_page("Ubuntu", true));
+        ConformanceSuite::new(Leetx::with_url)
+            .with_sample_response(sample)
+            .run()
+            .await
+            .unwrap();
+    }
+}
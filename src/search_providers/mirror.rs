@@ -0,0 +1,145 @@
+//! # Mirrors
+//!
+//! The `Mirrored` provider queries a provider and falls back to its mirrors,
+//! in order, when it fails. Sites such as The Pirate Bay are often reachable
+//! under several addresses serving the same API.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::Request;
+use log::debug;
+
+use crate::{
+    errors::ClientError, search_providers::SearchProvider, transport::HttpTransport, SearchRequest,
+    Torrent,
+};
+
+/// A provider falling back to mirrors when the primary provider fails.
+///
+/// # Example
+/// ```
+/// use magneto::{search_providers::Mirrored, Magneto, PirateBay};
+///
+/// let piratebay = Mirrored::new(Box::new(PirateBay::new()))
+///     .with_mirror(Box::new(PirateBay::with_url("https://apibay.example/q.php")));
+/// let magneto = Magneto::default().add_provider(Box::new(piratebay));
+/// ```
+pub struct Mirrored {
+    /// The primary provider followed by its mirrors.
+    providers: Vec<Box<dyn SearchProvider>>,
+}
+
+impl Mirrored {
+    /// Creates a new `Mirrored` provider without mirrors.
+    ///
+    /// # Parameters
+    /// - `primary`: The provider queried first, which also gives the `id`.
+    pub fn new(primary: Box<dyn SearchProvider>) -> Self {
+        Self {
+            providers: vec![primary],
+        }
+    }
+
+    /// Adds a mirror, queried after the primary provider and the mirrors added before.
+    pub fn with_mirror(mut self, mirror: Box<dyn SearchProvider>) -> Self {
+        self.providers.push(mirror);
+        self
+    }
+
+    /// Returns the number of mirrors.
+    pub fn mirrors(&self) -> usize {
+        self.providers.len() - 1
+    }
+}
+
+#[async_trait]
+impl SearchProvider for Mirrored {
+    /// Sends the request to the primary provider, then to each mirror until one succeeds.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Torrent>)`: The torrents of the first provider that succeeded.
+    /// - `Err(ClientError)`: The error of the last mirror if every provider failed.
+    async fn send_request(
+        &self,
+        transport: &dyn HttpTransport,
+        request: SearchRequest<'_>,
+    ) -> Result<Vec<Torrent>, ClientError> {
+        let mut last_error = None;
+        for provider in &self.providers {
+            match provider.send_request(transport, request.clone()).await {
+                Ok(torrents) => return Ok(torrents),
+                Err(e) => {
+                    debug!(
+                        "provider {} failed, trying next mirror: {}",
+                        provider.id(),
+                        e
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.expect("a mirrored provider has a primary provider"))
+    }
+
+    /// Parses a response with the primary provider.
+    fn parse_response(&self, response: &str) -> Result<Vec<Torrent>, ClientError> {
+        self.providers[0].parse_response(response)
+    }
+
+    /// Builds a request with the primary provider.
+    fn build_request(&self, request: SearchRequest<'_>) -> Result<Request<Bytes>, ClientError> {
+        self.providers[0].build_request(request)
+    }
+
+    /// Returns the identifier of the primary provider.
+    fn id(&self) -> String {
+        self.providers[0].id()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testkit::MockProvider, transport::ReqwestTransport};
+    use mockito::Server;
+
+    /// Tests falling back to the next mirror when the primary provider fails.
+    #[tokio::test]
+    async fn test_mirror_fallback() {
+        let mut primary = Server::new_async().await;
+        let mut mirror = Server::new_async().await;
+        let _down = primary
+            .mock("GET", "/search?q=ubuntu")
+            .with_status(503)
+            .create_async()
+            .await;
+        let _up = mirror
+            .mock("GET", "/search?q=ubuntu")
+            .with_body(
+                r#"[{"name": "Ubuntu", "magnet_link": "magnet:?xt=urn:btih:abc",
+                    "seeders": 1, "peers": 2, "size_bytes": 3, "provider": "mirror"}]"#,
+            )
+            .create_async()
+            .await;
+
+        let provider = Mirrored::new(Box::new(MockProvider::new(primary.url())))
+            .with_mirror(Box::new(MockProvider::new(mirror.url())));
+        assert_eq!(provider.id(), primary.url());
+        assert_eq!(provider.mirrors(), 1);
+
+        let torrents = provider
+            .send_request(&ReqwestTransport::new(), SearchRequest::new("ubuntu"))
+            .await
+            .unwrap();
+        assert_eq!(torrents[0].provider, "mirror");
+
+        let result = Mirrored::new(Box::new(MockProvider::new(primary.url())))
+            .send_request(&ReqwestTransport::new(), SearchRequest::new("ubuntu"))
+            .await;
+        assert!(matches!(
+            result,
+            Err(ClientError::ServerResponseError { code, .. }) if code == 503
+        ));
+    }
+}
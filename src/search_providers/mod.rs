@@ -1,15 +1,33 @@
 use async_trait::async_trait;
+use bytes::Bytes;
+use http::Request;
 use log::debug;
-use reqwest::{Client, Request};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC};
+use url::Url;
 
-use crate::{errors::ClientError, SearchRequest, Torrent};
+use crate::{errors::ClientError, transport::HttpTransport, SearchRequest, Torrent};
 
+pub mod dht;
+pub mod eztv;
+pub mod html;
 pub mod knaben;
+pub mod leetx;
+pub mod mirror;
+pub mod nyaa;
 pub mod piratebay;
+pub mod rss;
+pub mod torznab;
 pub mod yts;
 
+pub use dht::Dht;
+pub use eztv::Eztv;
 pub use knaben::Knaben;
+pub use leetx::Leetx;
+pub use mirror::Mirrored;
+pub use nyaa::{Nyaa, NyaaFilter};
 pub use piratebay::PirateBay;
+pub use rss::{RssFields, RssProvider};
+pub use torznab::Torznab;
 pub use yts::Yts;
 
 /// The `SearchProvider` trait defines the interface for implementing
@@ -22,13 +40,20 @@ pub use yts::Yts;
 ///
 /// This trait includes a default implementation for sending requests (`send_request`),
 /// which handles the common request-sending logic.
+///
+/// Requests are sent through an `HttpTransport`, so providers are independent
+/// of the HTTP client in use.
+///
+/// Providers that do not query an HTTP API, such as `Dht`, override `send_request`
+/// instead and can ignore the transport, implementing `build_request` and
+/// `parse_response` to return `ClientError::ProviderError`.
 #[async_trait]
 pub trait SearchProvider: Send + Sync {
     /// Sends a search request to the provider's API, processes the response,
     /// and parses it into a list of torrents.
     ///
     /// # Parameters
-    /// - `client`: The `reqwest::Client` used for making HTTP requests.
+    /// - `transport`: The `HttpTransport` used for sending HTTP requests.
     /// - `request`: A `SearchRequest` containing the search parameters.
     ///
     /// # Returns
@@ -36,27 +61,21 @@ pub trait SearchProvider: Send + Sync {
     /// - `Err(ClientError)`: An error if the request or parsing fails.
     async fn send_request(
         &self,
-        client: &Client,
+        transport: &dyn HttpTransport,
         request: SearchRequest<'_>,
     ) -> Result<Vec<Torrent>, ClientError> {
-        let request = self.build_request(client, request)?;
+        let request = self.build_request(request)?;
         debug!(
             "client sending {} request to {} with {} bytes of data",
             request.method(),
-            request.url(),
-            request.body().as_slice().len()
+            request.uri(),
+            request.body().len()
         );
 
-        let response = client
-            .execute(request)
-            .await
-            .map_err(|e| ClientError::ResponseError(e.into()))?;
+        let response = transport.send(request).await?;
 
         let response_status = response.status();
-        let response_content = response
-            .text()
-            .await
-            .map_err(|e| ClientError::ResponseError(e.into()))?;
+        let response_content = String::from_utf8_lossy(response.body()).into_owned();
 
         debug!(
             "client received {} response with {} bytes of body data",
@@ -90,20 +109,15 @@ pub trait SearchProvider: Send + Sync {
     /// Builds an HTTP request for the provider's API.
     ///
     /// # Parameters
-    /// - `client`: The `reqwest::Client` used to build the request.
     /// - `request`: A `SearchRequest` containing the search parameters.
     ///
     /// # Returns
-    /// - `Ok(Request)`: The constructed HTTP request.
+    /// - `Ok(Request<Bytes>)`: The constructed HTTP request.
     /// - `Err(ClientError)`: An error if request building fails.
     ///
     /// Implementors should include provider-specific details, such as
     /// headers, query parameters, or request body content.
-    fn build_request(
-        &self,
-        client: &Client,
-        request: SearchRequest<'_>,
-    ) -> Result<Request, ClientError>;
+    fn build_request(&self, request: SearchRequest<'_>) -> Result<Request<Bytes>, ClientError>;
 
     /// Returns a unique identifier for the provider.
     ///
@@ -115,55 +129,39 @@ pub trait SearchProvider: Send + Sync {
     fn id(&self) -> String;
 }
 
+/// The characters percent-encoded in values substituted into URL templates, all but
+/// unreserved ones.
+pub(crate) const ENCODED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Builds a GET request to `api_url` with the given query parameters.
+pub(crate) fn build_get_request(
+    api_url: &str,
+    query: &[(&str, &str)],
+) -> Result<Request<Bytes>, ClientError> {
+    let build_error = |source: anyhow::Error| ClientError::RequestBuildError {
+        source,
+        url: api_url.to_string(),
+    };
+
+    let url = Url::parse_with_params(api_url, query).map_err(|e| build_error(e.into()))?;
+    Request::get(url.as_str())
+        .body(Bytes::new())
+        .map_err(|e| build_error(e.into()))
+}
+
 #[cfg(test)]
 mod tests {
     use core::panic;
 
     use super::*;
-    use async_trait::async_trait;
+    use crate::{testkit::MockProvider, transport::ReqwestTransport};
     use mockito::Server;
-    use reqwest::Client;
     use serde_json::json;
 
-    /// A mock implementation of the `SearchProvider` trait for testing purposes.
-    struct MockProvider {
-        url: String,
-    }
-
-    impl MockProvider {
-        fn new(url: &str) -> Self {
-            Self {
-                url: url.to_string(),
-            }
-        }
-    }
-
-    #[async_trait]
-    impl SearchProvider for MockProvider {
-        fn parse_response(&self, response: &str) -> Result<Vec<Torrent>, ClientError> {
-            let parsed: Vec<Torrent> = serde_json::from_str(response)
-                .map_err(|e| ClientError::DataParseError(e.into()))?;
-            Ok(parsed)
-        }
-
-        fn build_request(
-            &self,
-            client: &Client,
-            request: SearchRequest<'_>,
-        ) -> Result<Request, ClientError> {
-            let request = client
-                .get(format!("{}/search", self.url.clone()))
-                .query(&[("q", request.query)])
-                .build()
-                .unwrap();
-            Ok(request)
-        }
-
-        fn id(&self) -> String {
-            self.url.clone()
-        }
-    }
-
     /// Tests that the `send_request` method successfully parses a valid response.
     ///
     /// This test uses the `mockito` library to mock an HTTP response with valid torrent
@@ -171,8 +169,8 @@ mod tests {
     #[tokio::test]
     async fn test_send_request_success() {
         let mut server = Server::new_async().await;
-        let provider = MockProvider::new(&server.url());
-        let client = Client::new();
+        let provider = MockProvider::new(server.url());
+        let transport = ReqwestTransport::new();
 
         let _mock = server
             .mock("GET", "/search?q=ubuntu")
@@ -192,7 +190,7 @@ mod tests {
             .create();
 
         let search_request = SearchRequest::new("ubuntu");
-        let result = provider.send_request(&client, search_request).await;
+        let result = provider.send_request(&transport, search_request).await;
 
         assert!(result.is_ok());
         let torrents = result.unwrap();
@@ -214,8 +212,8 @@ mod tests {
     #[tokio::test]
     async fn test_send_request_error_response() {
         let mut server = Server::new_async().await;
-        let provider = MockProvider::new(&server.url());
-        let client = Client::new();
+        let provider = MockProvider::new(server.url());
+        let transport = ReqwestTransport::new();
 
         let _mock = server
             .mock("GET", "/search?q=ubuntu")
@@ -224,7 +222,7 @@ mod tests {
             .create();
 
         let search_request = SearchRequest::new("ubuntu");
-        let result = provider.send_request(&client, search_request).await;
+        let result = provider.send_request(&transport, search_request).await;
 
         assert!(result.is_err());
         if let ClientError::ServerResponseError { code, content } = result.unwrap_err() {
@@ -0,0 +1,474 @@
+//! # Nyaa (nyaa.si) Search Provider
+//!
+//! The `Nyaa` implementation of the `SearchProvider` trait queries the RSS
+//! search feed of Nyaa, the largest tracker for anime and Japanese media,
+//! and parses it into a unified `Torrent` structure.
+//!
+//! Results tell whether the submitter is trusted by the site, as
+//! `UploaderStatus::Trusted`, and whether the torrent is a remake, as
+//! `TorrentMetadata::remake`. The feed does not name the submitter, which is
+//! only read from the details pages when enabled with `with_submitters`.
+
+use std::sync::LazyLock;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::Request;
+use log::debug;
+use scraper::Selector;
+
+use crate::{
+    date::parse_date,
+    errors::ClientError,
+    search_providers::{
+        build_get_request,
+        html::{self, selector, HtmlPage},
+        rss::{parse_feed, RssItem},
+    },
+    size::parse_size,
+    transport::HttpTransport,
+    Category, Magnet, SearchProvider, SearchRequest, Torrent, TorrentMetadata, UploaderStatus,
+};
+
+/// The trackers listed in the magnet links of Nyaa.
+const TRACKERS: [&str; 3] = [
+    "http://nyaa.tracker.wf:7777/announce",
+    "udp://open.stealth.si:80/announce",
+    "udp://tracker.opentrackr.org:1337/announce",
+];
+
+/// The panels of a details page, the first one describing the torrent.
+static PANEL: LazyLock<Selector> = LazyLock::new(|| selector("div.panel-body"));
+
+/// The link to the submitter's profile in a panel.
+static SUBMITTER: LazyLock<Selector> = LazyLock::new(|| selector("a[href^='/user/']"));
+
+/// Which torrents Nyaa returns, as its "f" search parameter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NyaaFilter {
+    /// Every torrent.
+    #[default]
+    None,
+
+    /// Every torrent but remakes.
+    NoRemakes,
+
+    /// Only torrents of trusted submitters.
+    TrustedOnly,
+}
+
+impl NyaaFilter {
+    /// Returns the value of the "f" search parameter.
+    fn code(&self) -> &'static str {
+        match self {
+            NyaaFilter::None => "0",
+            NyaaFilter::NoRemakes => "1",
+            NyaaFilter::TrustedOnly => "2",
+        }
+    }
+}
+
+/// The `Nyaa` provider handles querying and parsing data from the Nyaa RSS feed.
+pub struct Nyaa {
+    /// The base URL of the site.
+    url: String,
+
+    /// Which torrents are returned.
+    filter: NyaaFilter,
+
+    /// How many details pages are fetched at a time to read submitters, if enabled.
+    submitter_concurrency: Option<usize>,
+}
+
+impl Nyaa {
+    /// Creates a new instance of the `Nyaa` provider.
+    ///
+    /// # Returns
+    /// - `Nyaa`: A new provider instance with the default URL, returning every torrent.
+    pub fn new() -> Self {
+        Self::with_url("https://nyaa.si/")
+    }
+
+    /// Creates a new instance of the `Nyaa` provider with a custom URL, e.g. a mirror.
+    ///
+    /// # Parameters
+    /// - `url`: The base URL of the site.
+    ///
+    /// # Returns
+    /// - `Nyaa`: A new provider instance with the specified URL.
+    pub fn with_url(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            filter: NyaaFilter::None,
+            submitter_concurrency: None,
+        }
+    }
+
+    /// Sets which torrents are returned, e.g. `NyaaFilter::TrustedOnly`.
+    pub fn with_filter(mut self, filter: NyaaFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Reads the submitter of every result from its details page.
+    ///
+    /// This sends one more request per result, so it is disabled by default.
+    /// Results whose page cannot be fetched are kept without submitter.
+    ///
+    /// # Parameters
+    /// - `concurrency`: The maximum number of details pages fetched at a time.
+    pub fn with_submitters(mut self, concurrency: usize) -> Self {
+        self.submitter_concurrency = Some(concurrency);
+        self
+    }
+
+    /// Converts a feed item into a `Torrent`, or `None` without info hash.
+    fn torrent(item: &RssItem) -> Option<Torrent> {
+        let count = |name: &str| {
+            item.get(name)
+                .and_then(|value| value.replace(',', "").parse().ok())
+                .unwrap_or(0)
+        };
+        let flag = |name: &str| item.get(name).map(|value| value == "Yes");
+        let name = item.get("title").unwrap_or_default().to_string();
+
+        let info_hash: [u8; 20] = hex::decode(item.get("nyaa:infoHash")?)
+            .ok()?
+            .try_into()
+            .ok()?;
+        let mut magnet = Magnet::from_info_hash(info_hash);
+        magnet.display_name = Some(name.clone());
+        magnet.trackers = TRACKERS.iter().map(|tracker| tracker.to_string()).collect();
+
+        let details_url = item.get("guid").map(str::to_string);
+        let provider_id = details_url
+            .as_deref()
+            .and_then(|url| url.rsplit('/').next())
+            .map(str::to_string);
+
+        Some(Torrent {
+            name,
+            magnet_link: magnet.to_string(),
+            seeders: count("nyaa:seeders"),
+            peers: count("nyaa:leechers"),
+            size_bytes: item
+                .get("nyaa:size")
+                .and_then(|size| parse_size(size).ok())
+                .unwrap_or(0),
+            provider: "nyaa".to_string(),
+            metadata: TorrentMetadata {
+                uploader_status: flag("nyaa:trusted").map(|trusted| {
+                    if trusted {
+                        UploaderStatus::Trusted
+                    } else {
+                        UploaderStatus::Member
+                    }
+                }),
+                provider_id,
+                details_url,
+                uploaded_at: item.get("pubDate").and_then(|date| parse_date(date).ok()),
+                remake: flag("nyaa:remake"),
+                ..Default::default()
+            },
+            stats_updated_at: None,
+        })
+    }
+}
+
+impl Default for Nyaa {
+    /// Provides a default implementation for `Nyaa`, returning an instance with the default URL.
+    fn default() -> Self {
+        Nyaa::new()
+    }
+}
+
+#[async_trait]
+impl SearchProvider for Nyaa {
+    /// Searches the feed, then reads the submitters from the details pages if enabled.
+    ///
+    /// # Parameters
+    /// - `transport`: The `HttpTransport` used for sending HTTP requests.
+    /// - `request`: A `SearchRequest` containing the search parameters.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Torrent>)`: A list of parsed torrents.
+    /// - `Err(ClientError)`: An error if the search request or parsing fails.
+    async fn send_request(
+        &self,
+        transport: &dyn HttpTransport,
+        request: SearchRequest<'_>,
+    ) -> Result<Vec<Torrent>, ClientError> {
+        let url = self.build_request(request)?.uri().to_string();
+        let mut torrents = self.parse_response(&html::fetch_page(transport, &url).await?)?;
+
+        let Some(concurrency) = self.submitter_concurrency else {
+            return Ok(torrents);
+        };
+        let (indices, urls): (Vec<usize>, Vec<String>) = torrents
+            .iter()
+            .enumerate()
+            .filter_map(|(index, torrent)| Some((index, torrent.metadata.details_url.clone()?)))
+            .unzip();
+        let submitters = html::fetch_pages(transport, urls, concurrency, submitter).await;
+
+        for (index, submitter) in indices.into_iter().zip(submitters) {
+            let torrent = &mut torrents[index];
+            match submitter {
+                Ok(submitter) => torrent.metadata.uploader = submitter,
+                Err(e) => debug!("cannot fetch nyaa page of '{}': {}", torrent.name, e),
+            }
+        }
+
+        Ok(torrents)
+    }
+
+    /// Builds the request to search the Nyaa RSS feed.
+    ///
+    /// Nyaa searches a single category, so the first requested category it
+    /// supports is used; other categories are ignored.
+    ///
+    /// # Parameters
+    /// - `request`: The `SearchRequest` containing query parameters.
+    ///
+    /// # Returns
+    /// - `Ok(Request<Bytes>)`: The constructed HTTP request.
+    /// - `Err(ClientError)`: An error if request building fails.
+    fn build_request(&self, request: SearchRequest<'_>) -> Result<Request<Bytes>, ClientError> {
+        let category = request
+            .categories
+            .iter()
+            .find_map(|category| match category {
+                Category::Anime => Some("1_0"),
+                Category::Audio => Some("2_0"),
+                Category::Software => Some("6_1"),
+                Category::Games => Some("6_2"),
+                _ => None, // Nyaa only has Japanese live action, not general movies and shows
+            })
+            .unwrap_or("0_0");
+
+        build_get_request(
+            &self.url,
+            &[
+                ("page", "rss"),
+                ("q", request.query),
+                ("c", category),
+                ("f", self.filter.code()),
+            ],
+        )
+    }
+
+    /// Parses the RSS feed of Nyaa into a list of torrents.
+    ///
+    /// # Parameters
+    /// - `response`: The raw response body as a string.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Torrent>)`: A list of parsed torrent metadata.
+    /// - `Err(ClientError)`: An error if the response is not a feed.
+    fn parse_response(&self, response: &str) -> Result<Vec<Torrent>, ClientError> {
+        Ok(parse_feed(response)?
+            .iter()
+            .filter_map(|item| {
+                let torrent = Nyaa::torrent(item);
+                if torrent.is_none() {
+                    debug!("skipping nyaa item without info hash");
+                }
+                torrent
+            })
+            .collect())
+    }
+
+    /// Returns the unique identifier for this provider.
+    ///
+    /// # Returns
+    /// - `String`: The provider's base URL as its unique identifier.
+    fn id(&self) -> String {
+        self.url.clone()
+    }
+}
+
+/// Reads the submitter from a details page, `None` for anonymous torrents.
+fn submitter(page: &HtmlPage) -> Option<String> {
+    // The first panel describes the torrent; later ones hold the description and comments
+    page.find(&PANEL)?
+        .find(&SUBMITTER)
+        .map(|link| link.text())
+        .filter(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testkit::ConformanceSuite, transport::ReqwestTransport};
+    use mockito::Server;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    /// Returns a search feed with a trusted release and an anonymous remake.
+    fn feed(url: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+            <rss xmlns:atom="http://www.w3.org/2005/Atom" xmlns:nyaa="https://nyaa.si/xmlns/nyaa" version="2.0">
+              <channel>
+                <title>Nyaa - "frieren" - Torrent File RSS</title>
+                <item>
+                  <title>[SubsPlease] Sousou no Frieren - 01 (1080p) [F02B9CEE].mkv</title>
+                  <link>{url}/download/1714235.torrent</link>
+                  <guid isPermaLink="true">{url}/view/1714235</guid>
+                  <pubDate>Fri, 29 Sep 2023 15:31:03 -0000</pubDate>
+                  <nyaa:seeders>1,204</nyaa:seeders>
+                  <nyaa:leechers>35</nyaa:leechers>
+                  <nyaa:infoHash>0123456789abcdef0123456789abcdef01234567</nyaa:infoHash>
+                  <nyaa:categoryId>1_2</nyaa:categoryId>
+                  <nyaa:size>1.4 GiB</nyaa:size>
+                  <nyaa:trusted>Yes</nyaa:trusted>
+                  <nyaa:remake>No</nyaa:remake>
+                </item>
+                <item>
+                  <title>Sousou no Frieren - 01 [re-encode]</title>
+                  <link>{url}/download/1714300.torrent</link>
+                  <guid isPermaLink="true">{url}/view/1714300</guid>
+                  <nyaa:seeders>3</nyaa:seeders>
+                  <nyaa:leechers>0</nyaa:leechers>
+                  <nyaa:infoHash>89abcdef0123456789abcdef0123456789abcdef</nyaa:infoHash>
+                  <nyaa:size>350.2 MiB</nyaa:size>
+                  <nyaa:trusted>No</nyaa:trusted>
+                  <nyaa:remake>Yes</nyaa:remake>
+                </item>
+                <item>
+                  <title>Missing info hash</title>
+                  <guid isPermaLink="true">{url}/view/1</guid>
+                </item>
+              </channel>
+            </rss>"#
+        )
+    }
+
+    /// Returns a details page naming the submitter, or an anonymous one.
+    fn details_page(submitter: Option<&str>) -> String {
+        let submitter = submitter.map_or("Anonymous".to_string(), |name| {
+            format!(r#"<a class="text-success" href="/user/{0}">{0}</a>"#, name)
+        });
+        format!(
+            r#"<div class="panel panel-success">
+              <div class="panel-body">
+                <div class="row"><div class="col-md-1">Category:</div><div class="col-md-5">Anime</div></div>
+                <div class="row"><div class="col-md-1">Submitter:</div><div class="col-md-5">{}</div></div>
+              </div>
+            </div>
+            <div class="panel panel-default"><div class="panel-body">
+              <a href="/user/commenter">commenter</a>
+            </div></div>"#,
+            submitter
+        )
+    }
+
+    /// Tests that categories and filters are mapped to the search parameters.
+    #[test]
+    fn test_build_request() {
+        let provider = Nyaa::new().with_filter(NyaaFilter::TrustedOnly);
+
+        let request = SearchRequest::new("frieren & co").add_categories(vec![
+            Category::Movies,
+            Category::Anime,
+            Category::Audio,
+        ]);
+        let request = provider.build_request(request).unwrap();
+        assert_eq!(
+            request.uri().to_string(),
+            "https://nyaa.si/?page=rss&q=frieren+%26+co&c=1_0&f=2"
+        );
+
+        let request = Nyaa::new()
+            .with_filter(NyaaFilter::NoRemakes)
+            .build_request(SearchRequest::new("frieren").add_category(Category::Audio))
+            .unwrap();
+        assert!(request.uri().to_string().ends_with("c=2_0&f=1"));
+
+        let request = Nyaa::new()
+            .build_request(SearchRequest::new("frieren").add_category(Category::TvShows))
+            .unwrap();
+        assert!(request.uri().to_string().ends_with("c=0_0&f=0"));
+    }
+
+    /// Tests parsing the flags, dates and statistics of the feed items.
+    #[test]
+    fn test_parse_response() {
+        let torrents = Nyaa::new()
+            .parse_response(&feed("https://nyaa.si"))
+            .unwrap();
+        assert_eq!(torrents.len(), 2);
+
+        let torrent = &torrents[0];
+        let magnet = torrent.magnet().unwrap();
+        assert_eq!(
+            magnet.display_name.as_deref(),
+            Some("[SubsPlease] Sousou no Frieren - 01 (1080p) [F02B9CEE].mkv")
+        );
+        assert_eq!(magnet.trackers.len(), TRACKERS.len());
+        assert_eq!((torrent.seeders, torrent.peers), (1204, 35));
+        assert_eq!(torrent.size_bytes, 1_503_238_554);
+        assert_eq!(torrent.provider, "nyaa");
+        assert_eq!(
+            torrent.metadata.uploader_status,
+            Some(UploaderStatus::Trusted)
+        );
+        assert_eq!(torrent.metadata.remake, Some(false));
+        assert_eq!(torrent.metadata.provider_id.as_deref(), Some("1714235"));
+        assert_eq!(
+            torrent.metadata.details_url.as_deref(),
+            Some("https://nyaa.si/view/1714235")
+        );
+        assert_eq!(
+            torrent.metadata.uploaded_at,
+            Some(UNIX_EPOCH + Duration::from_secs(1696001463))
+        );
+
+        let remake = &torrents[1].metadata;
+        assert_eq!(remake.uploader_status, Some(UploaderStatus::Member));
+        assert_eq!(remake.remake, Some(true));
+        assert_eq!(remake.uploaded_at, None);
+    }
+
+    /// Tests reading the submitters from the details pages.
+    #[tokio::test]
+    async fn test_submitters() {
+        let mut server = Server::new_async().await;
+        let _search = server
+            .mock("GET", "/")
+            .match_query(mockito::Matcher::Any)
+            .with_body(feed(&server.url()))
+            .create_async()
+            .await;
+        let _trusted = server
+            .mock("GET", "/view/1714235")
+            .with_body(details_page(Some("subsplease")))
+            .create_async()
+            .await;
+        let _anonymous = server
+            .mock("GET", "/view/1714300")
+            .with_body(details_page(None))
+            .create_async()
+            .await;
+
+        let torrents = Nyaa::with_url(server.url())
+            .with_submitters(4)
+            .send_request(&ReqwestTransport::new(), SearchRequest::new("frieren"))
+            .await
+            .unwrap();
+        let uploaders: Vec<Option<&str>> = torrents
+            .iter()
+            .map(|torrent| torrent.metadata.uploader.as_deref())
+            .collect();
+        assert_eq!(uploaders, vec![Some("subsplease"), None]);
+    }
+
+    /// Tests that the provider passes the conformance suite.
+    #[tokio::test]
+    async fn test_conformance() {
+        ConformanceSuite::new(Nyaa::with_url)
+            .with_sample_response(feed("https://nyaa.si"))
+            .run()
+            .await
+            .unwrap();
+    }
+}
@@ -6,10 +6,17 @@
 //! a unified `Torrent` structure.
 
 use async_trait::async_trait;
-use reqwest::{Client, Request};
+use bytes::Bytes;
+use http::Request;
 use serde::Deserialize;
 
-use crate::{errors::ClientError, Category, SearchProvider, SearchRequest, Torrent};
+use crate::{
+    errors::ClientError, search_providers::build_get_request, Category, SearchProvider,
+    SearchRequest, Torrent, TorrentMetadata, UploaderStatus,
+};
+
+/// The base URL of the PirateBay details pages, followed by the torrent id.
+const DETAILS_URL: &str = "https://thepiratebay.org/description.php?id=";
 
 /// The `PirateBay` provider handles querying and parsing data from the PirateBay API.
 pub struct PirateBay {
@@ -55,17 +62,12 @@ impl SearchProvider for PirateBay {
     /// Builds the request to query the PirateBay API.
     ///
     /// # Parameters
-    /// - `client`: The HTTP client used to build the request.
     /// - `request`: The `SearchRequest` containing query parameters.
     ///
     /// # Returns
-    /// - `Ok(Request)`: The constructed HTTP request.
+    /// - `Ok(Request<Bytes>)`: The constructed HTTP request.
     /// - `Err(ClientError)`: An error if request building fails.
-    fn build_request(
-        &self,
-        client: &Client,
-        request: SearchRequest<'_>,
-    ) -> Result<Request, ClientError> {
+    fn build_request(&self, request: SearchRequest<'_>) -> Result<Request<Bytes>, ClientError> {
         let categories: Vec<String> = request
             .categories
             .into_iter()
@@ -87,14 +89,7 @@ impl SearchProvider for PirateBay {
             query.push(("cat", &categories_string));
         };
 
-        client
-            .get(self.api_url.clone())
-            .query(&query)
-            .build()
-            .map_err(|e| ClientError::RequestBuildError {
-                source: e.into(),
-                url: self.api_url.clone(),
-            })
+        build_get_request(&self.api_url, &query)
     }
 
     /// Parses the response from the PirateBay API into a list of torrents.
@@ -128,6 +123,8 @@ impl SearchProvider for PirateBay {
                     peers,
                     size_bytes,
                     provider: "piratebay".to_string(),
+                    metadata: entry.metadata(),
+                    stats_updated_at: None,
                 })
             })
             .collect();
@@ -185,9 +182,35 @@ pub struct ResponseEntry {
     pub imdb: String,
 }
 
+impl ResponseEntry {
+    /// Extracts the provider-agnostic metadata from the entry.
+    ///
+    /// Empty strings are used by the API for missing values and are mapped to `None`.
+    fn metadata(&self) -> TorrentMetadata {
+        let non_empty = |value: &str| (!value.is_empty()).then(|| value.to_string());
+
+        TorrentMetadata {
+            uploader: non_empty(&self.username),
+            uploader_status: match self.status.as_str() {
+                "member" => Some(UploaderStatus::Member),
+                "trusted" => Some(UploaderStatus::Trusted),
+                "vip" => Some(UploaderStatus::Vip),
+                "helper" | "moderator" | "supermod" | "admin" => Some(UploaderStatus::Staff),
+                _ => None,
+            },
+            file_count: self.num_files.parse().ok().filter(|count| *count > 0),
+            imdb_id: non_empty(&self.imdb),
+            provider_id: non_empty(&self.id),
+            details_url: non_empty(&self.id).map(|id| format!("{}{}", DETAILS_URL, id)),
+            ..Default::default()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::transport::ReplayTransport;
     use mockito::Server;
 
     /// Sets up a mock PirateBay provider using a mock server.
@@ -204,16 +227,15 @@ mod tests {
     #[tokio::test]
     async fn test_build_request() {
         let provider = setup_mock_provider().await;
-        let client = Client::new();
 
         let search_request = SearchRequest::new("ubuntu").add_category(Category::Software);
-        let request = provider.build_request(&client, search_request);
+        let request = provider.build_request(search_request);
 
         assert!(request.is_ok());
         let request = request.unwrap();
         assert_eq!(request.method(), "GET");
-        assert!(request.url().as_str().contains("q=ubuntu"));
-        assert!(request.url().as_str().contains("cat=300"));
+        assert!(request.uri().to_string().contains("q=ubuntu"));
+        assert!(request.uri().to_string().contains("cat=300"));
     }
 
     /// Tests building a request with a valid query but no category.
@@ -223,15 +245,14 @@ mod tests {
     #[tokio::test]
     async fn test_build_request_no_category() {
         let provider = setup_mock_provider().await;
-        let client = Client::new();
 
         let search_request = SearchRequest::new("ubuntu");
-        let request = provider.build_request(&client, search_request);
+        let request = provider.build_request(search_request);
 
         assert!(request.is_ok());
         let request = request.unwrap();
-        assert!(request.url().as_str().contains("q=ubuntu"));
-        assert!(!request.url().as_str().contains("cat="));
+        assert!(request.uri().to_string().contains("q=ubuntu"));
+        assert!(!request.uri().to_string().contains("cat="));
     }
 
     /// Tests parsing a valid API response into a list of torrents.
@@ -275,6 +296,46 @@ mod tests {
         assert_eq!(torrent.provider, "piratebay");
     }
 
+    /// Tests parsing the extended metadata of an API response entry.
+    ///
+    /// Ensures that uploader, status, file count, IMDb id and details URL
+    /// are mapped into `TorrentMetadata`.
+    #[tokio::test]
+    async fn test_parse_response_metadata() {
+        let provider = setup_mock_provider().await;
+
+        let response_body = r#"
+        [
+            {
+                "id": "42",
+                "name": "Inception 2010 1080p",
+                "info_hash": "abc123",
+                "leechers": "10",
+                "seeders": "20",
+                "num_files": "3",
+                "size": "2048",
+                "username": "uploader",
+                "added": "today",
+                "status": "vip",
+                "category": "207",
+                "imdb": "tt1375666"
+            }
+        ]
+        "#;
+
+        let torrents = provider.parse_response(response_body).unwrap();
+        let metadata = &torrents[0].metadata;
+        assert_eq!(metadata.uploader.as_deref(), Some("uploader"));
+        assert_eq!(metadata.uploader_status, Some(UploaderStatus::Vip));
+        assert_eq!(metadata.file_count, Some(3));
+        assert_eq!(metadata.imdb_id.as_deref(), Some("tt1375666"));
+        assert_eq!(metadata.provider_id.as_deref(), Some("42"));
+        assert_eq!(
+            metadata.details_url.as_deref(),
+            Some("https://thepiratebay.org/description.php?id=42")
+        );
+    }
+
     /// Tests handling of invalid JSON responses from the API.
     ///
     /// Ensures that an invalid JSON string results in a `DataParseError`.
@@ -366,4 +427,30 @@ mod tests {
             "Expected empty results due to invalid entry"
         );
     }
+
+    /// Tests searching through the recorded PirateBay fixture.
+    #[tokio::test]
+    async fn test_fixture_replay() {
+        let transport = ReplayTransport::from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/piratebay.json"
+        ))
+        .unwrap();
+        let provider = PirateBay::new();
+
+        let torrents = provider
+            .send_request(&transport, SearchRequest::new("ubuntu"))
+            .await
+            .unwrap();
+        assert_eq!(torrents.len(), 2);
+        assert_eq!(torrents[0].name, "Ubuntu 24.04 LTS Server amd64");
+        assert_eq!(torrents[0].seeders, 254);
+        assert_eq!(torrents[0].peers, 11);
+
+        let torrents = provider
+            .send_request(&transport, SearchRequest::new("no such torrent"))
+            .await
+            .unwrap();
+        assert!(torrents.is_empty());
+    }
 }
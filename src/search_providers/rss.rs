@@ -0,0 +1,477 @@
+//! # RSS Search Provider
+//!
+//! The `RssProvider` implementation of the `SearchProvider` trait queries sites
+//! exposing their search as an RSS or Atom feed, such as Nyaa, many trackers
+//! and distribution feeds, and parses the feed into a unified `Torrent` structure.
+//!
+//! Searches are built from a URL template with `{query}` and `{category}`
+//! placeholders, e.g. "https://nyaa.si/?page=rss&q={query}&c={category}".
+//! Which elements hold the title, link, info hash, size, seeders and peers of
+//! an item is configured with `RssFields`.
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::Request;
+use log::debug;
+use percent_encoding::utf8_percent_encode;
+use quick_xml::{events::Event, Reader};
+use serde::Deserialize;
+
+use crate::{
+    search_providers::ENCODED, size::parse_size, Category, ClientError, Magnet, SearchProvider,
+    SearchRequest, Torrent, TorrentMetadata,
+};
+
+/// The `RssProvider` handles querying and parsing data from an RSS or Atom search feed.
+pub struct RssProvider {
+    /// The search URL with `{query}` and `{category}` placeholders.
+    url_template: String,
+
+    /// The name reported as the provider of the results.
+    name: String,
+
+    /// The code of the site for each supported category.
+    categories: Vec<(Category, String)>,
+
+    /// Where the fields of a torrent are found in an item.
+    fields: RssFields,
+}
+
+impl RssProvider {
+    /// Creates a new instance of the `RssProvider`.
+    ///
+    /// # Parameters
+    /// - `url_template`: The search URL, where `{query}` is replaced by the encoded
+    ///   query and `{category}` by the code of the requested category.
+    ///
+    /// # Returns
+    /// - `RssProvider`: A new provider instance reporting results as "rss", without categories.
+    pub fn new(url_template: impl Into<String>) -> Self {
+        Self {
+            url_template: url_template.into(),
+            name: "rss".to_string(),
+            categories: Vec::new(),
+            fields: RssFields::default(),
+        }
+    }
+
+    /// Sets the name reported as the provider of the results, e.g. "nyaa".
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets the code of the site substituted for `{category}` when the category is requested.
+    ///
+    /// # Parameters
+    /// - `category`: The category.
+    /// - `code`: The code of the site, e.g. "1_0" for anime on Nyaa.
+    pub fn with_category(mut self, category: Category, code: impl Into<String>) -> Self {
+        self.categories.retain(|(known, _)| *known != category);
+        self.categories.push((category, code.into()));
+        self
+    }
+
+    /// Sets where the fields of a torrent are found in an item.
+    pub fn with_fields(mut self, fields: RssFields) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    /// Returns the code substituted for `{category}`.
+    ///
+    /// Only one category fits in the URL, so the first requested category with
+    /// a code is used; without one, the placeholder is left empty.
+    fn category_code(&self, categories: &[Category]) -> &str {
+        categories
+            .iter()
+            .find_map(|category| {
+                self.categories
+                    .iter()
+                    .find(|(known, _)| known == category)
+                    .map(|(_, code)| code.as_str())
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl SearchProvider for RssProvider {
+    /// Builds the search request from the URL template.
+    ///
+    /// # Parameters
+    /// - `request`: The `SearchRequest` containing query parameters.
+    ///
+    /// # Returns
+    /// - `Ok(Request<Bytes>)`: The constructed HTTP request.
+    /// - `Err(ClientError)`: An error if request building fails.
+    fn build_request(&self, request: SearchRequest<'_>) -> Result<Request<Bytes>, ClientError> {
+        let url = self
+            .url_template
+            .replace(
+                "{query}",
+                &utf8_percent_encode(request.query, ENCODED).to_string(),
+            )
+            .replace(
+                "{category}",
+                &utf8_percent_encode(self.category_code(&request.categories), ENCODED).to_string(),
+            );
+
+        Request::get(url.as_str())
+            .body(Bytes::new())
+            .map_err(|e| ClientError::RequestBuildError {
+                source: e.into(),
+                url,
+            })
+    }
+
+    /// Parses the RSS or Atom feed into a list of torrents.
+    ///
+    /// Items without a magnet link or info hash, which can only be downloaded
+    /// as torrent files, are skipped.
+    ///
+    /// # Parameters
+    /// - `response`: The raw response body as a string.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Torrent>)`: A list of parsed torrent metadata.
+    /// - `Err(ClientError)`: An error if the response is not a feed.
+    fn parse_response(&self, response: &str) -> Result<Vec<Torrent>, ClientError> {
+        Ok(parse_feed(response)?
+            .iter()
+            .filter_map(|item| {
+                let torrent = self.fields.torrent(item, &self.name);
+                if torrent.is_none() {
+                    debug!("skipping rss item without magnet link");
+                }
+                torrent
+            })
+            .collect())
+    }
+
+    /// Returns the unique identifier for this provider.
+    ///
+    /// # Returns
+    /// - `String`: The URL template as its unique identifier.
+    fn id(&self) -> String {
+        self.url_template.clone()
+    }
+}
+
+/// Where the fields of a torrent are found in a feed item.
+///
+/// Each field names an element of the item as written in the feed, including
+/// its namespace prefix, e.g. "nyaa:seeders", or an attribute of an element
+/// as "element@attribute", e.g. "enclosure@url". The first matching element
+/// of an item is used.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RssFields {
+    /// The name of the torrent.
+    pub title: String,
+
+    /// The link of the item, used when it is a magnet link. An element without
+    /// text falls back to its `href` attribute, as Atom links are written.
+    pub link: String,
+
+    /// The enclosure of the item, used when it is a magnet link.
+    pub enclosure: String,
+
+    /// The hex info hash, used to build a magnet link when there is none.
+    pub info_hash: Option<String>,
+
+    /// The size, in bytes or human-readable such as "1.2 GiB".
+    pub size: Option<String>,
+
+    /// The number of seeders.
+    pub seeders: Option<String>,
+
+    /// The number of peers that are not seeders, e.g. "nyaa:leechers".
+    pub peers: Option<String>,
+
+    /// The details page of the torrent.
+    pub details_url: Option<String>,
+}
+
+impl Default for RssFields {
+    /// Returns the fields of a plain RSS or Atom feed, without seeders and peers.
+    fn default() -> Self {
+        Self {
+            title: "title".to_string(),
+            link: "link".to_string(),
+            enclosure: "enclosure@url".to_string(),
+            info_hash: None,
+            size: Some("enclosure@length".to_string()),
+            seeders: None,
+            peers: None,
+            details_url: None,
+        }
+    }
+}
+
+impl RssFields {
+    /// Converts an item into a `Torrent`, or `None` without magnet link or info hash.
+    fn torrent(&self, item: &RssItem, provider: &str) -> Option<Torrent> {
+        let field = |name: &Option<String>| name.as_deref().and_then(|name| item.get(name));
+        let count = |name: &Option<String>| {
+            field(name)
+                .and_then(|value| value.replace(',', "").parse::<u64>().ok())
+                .map(|value| u32::try_from(value).unwrap_or(u32::MAX))
+                .unwrap_or(0)
+        };
+        let title = item.get(&self.title).unwrap_or_default().to_string();
+
+        let href = item
+            .get(&self.link)
+            .is_none()
+            .then(|| item.get(&format!("{}@href", self.link)))
+            .flatten();
+        let magnet_link = [item.get(&self.link), href, item.get(&self.enclosure)]
+            .into_iter()
+            .flatten()
+            .find(|link| link.starts_with("magnet:"))
+            .map(str::to_string)
+            .or_else(|| {
+                let info_hash: [u8; 20] =
+                    hex::decode(field(&self.info_hash)?).ok()?.try_into().ok()?;
+                let mut magnet = Magnet::from_info_hash(info_hash);
+                magnet.display_name = Some(title.clone());
+                Some(magnet.to_string())
+            })?;
+
+        let size_bytes = field(&self.size)
+            .and_then(|size| size.parse().ok().or_else(|| parse_size(size).ok()))
+            .unwrap_or(0);
+        let provider_id = item.get("guid").or(item.get("id")).map(str::to_string);
+
+        Some(Torrent {
+            name: title,
+            magnet_link,
+            seeders: count(&self.seeders),
+            peers: count(&self.peers),
+            size_bytes,
+            provider: provider.to_string(),
+            metadata: TorrentMetadata {
+                provider_id,
+                details_url: field(&self.details_url).map(str::to_string),
+                ..Default::default()
+            },
+            stats_updated_at: None,
+        })
+    }
+}
+
+/// The text of the elements of a feed item and their attributes.
+#[derive(Debug, Default)]
+pub(super) struct RssItem {
+    /// The values by element name, or "element@attribute" for attributes.
+    values: HashMap<String, String>,
+}
+
+impl RssItem {
+    /// Returns the value of the first element or attribute with the name.
+    pub(super) fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .get(name)
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    }
+
+    /// Sets a value unless an earlier element already set it.
+    fn insert(&mut self, name: String, value: String) {
+        self.values.entry(name).or_insert(value);
+    }
+}
+
+/// Parses the items of an RSS feed, or the entries of an Atom feed.
+pub(super) fn parse_feed(xml: &str) -> Result<Vec<RssItem>, ClientError> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut items = Vec::new();
+    let mut item: Option<RssItem> = None;
+    let mut element: Option<String> = None;
+    let mut found_feed = false;
+
+    loop {
+        match reader.read_event().map_err(parse_error)? {
+            Event::Start(e) | Event::Empty(e) if item.is_none() => match e.local_name().as_ref() {
+                b"rss" | b"channel" | b"feed" => found_feed = true,
+                b"item" | b"entry" => item = Some(RssItem::default()),
+                _ => {}
+            },
+            Event::Start(e) | Event::Empty(e) => {
+                let current = item.as_mut().expect("inside item");
+                let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+                for attr in e.attributes().flatten() {
+                    let key = String::from_utf8_lossy(attr.key.as_ref()).into_owned();
+                    if let Ok(value) = attr.unescape_value() {
+                        current.insert(format!("{}@{}", name, key), value.trim().to_string());
+                    }
+                }
+                element = Some(name);
+            }
+            Event::Text(text) => {
+                let text = text.unescape().map_err(parse_error)?;
+                if let (Some(item), Some(element)) = (item.as_mut(), element.clone()) {
+                    item.insert(element, text.into_owned());
+                }
+            }
+            Event::CData(data) => {
+                let data = String::from_utf8_lossy(&data.into_inner())
+                    .trim()
+                    .to_string();
+                if let (Some(item), Some(element)) = (item.as_mut(), element.clone()) {
+                    item.insert(element, data);
+                }
+            }
+            Event::End(e) => {
+                if matches!(e.local_name().as_ref(), b"item" | b"entry") {
+                    items.extend(item.take());
+                }
+                element = None;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !found_feed {
+        return Err(ClientError::DataParseError(anyhow!(
+            "response is not an rss or atom feed"
+        )));
+    }
+
+    Ok(items)
+}
+
+/// Converts an XML error.
+fn parse_error(error: impl Into<anyhow::Error>) -> ClientError {
+    ClientError::DataParseError(error.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit::ConformanceSuite;
+    use assert_matches::assert_matches;
+
+    const NYAA_FEED: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+        <rss xmlns:atom="http://www.w3.org/2005/Atom" xmlns:nyaa="https://nyaa.si/xmlns/nyaa" version="2.0">
+          <channel>
+            <title>Nyaa - "frieren" - Torrent File RSS</title>
+            <item>
+              <title>[SubsPlease] Sousou no Frieren - 01 (1080p) [F02B9CEE].mkv</title>
+              <link>https://nyaa.si/download/1714235.torrent</link>
+              <guid isPermaLink="true">https://nyaa.si/view/1714235</guid>
+              <nyaa:seeders>1,204</nyaa:seeders>
+              <nyaa:leechers>35</nyaa:leechers>
+              <nyaa:infoHash>0123456789abcdef0123456789abcdef01234567</nyaa:infoHash>
+              <nyaa:size>1.4 GiB</nyaa:size>
+            </item>
+            <item>
+              <title>Torrent file only</title>
+              <link>https://nyaa.si/download/1.torrent</link>
+            </item>
+          </channel>
+        </rss>"#;
+
+    const ATOM_FEED: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+        <feed xmlns="http://www.w3.org/2005/Atom">
+          <title>Releases</title>
+          <entry>
+            <title><![CDATA[Ubuntu 24.04 & Friends]]></title>
+            <id>urn:release:1</id>
+            <link href="magnet:?xt=urn:btih:abc&amp;dn=ubuntu"/>
+            <link href="https://example.org/other"/>
+          </entry>
+        </feed>"#;
+
+    /// Returns fields for the Nyaa feed.
+    fn nyaa_fields() -> RssFields {
+        RssFields {
+            info_hash: Some("nyaa:infoHash".to_string()),
+            size: Some("nyaa:size".to_string()),
+            seeders: Some("nyaa:seeders".to_string()),
+            peers: Some("nyaa:leechers".to_string()),
+            details_url: Some("guid".to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Tests that the URL template is filled with the query and category code.
+    #[test]
+    fn test_build_request() {
+        let provider = RssProvider::new("https://nyaa.si/?page=rss&q={query}&c={category}")
+            .with_category(Category::Anime, "1_0")
+            .with_category(Category::Audio, "2_0");
+
+        let request = SearchRequest::new("frieren & co")
+            .add_categories(vec![Category::Movies, Category::Anime]);
+        let request = provider.build_request(request).unwrap();
+        assert_eq!(
+            request.uri().to_string(),
+            "https://nyaa.si/?page=rss&q=frieren%20%26%20co&c=1_0"
+        );
+
+        let request = provider
+            .build_request(SearchRequest::new("frieren"))
+            .unwrap();
+        assert_eq!(
+            request.uri().to_string(),
+            "https://nyaa.si/?page=rss&q=frieren&c="
+        );
+    }
+
+    /// Tests that RSS feeds with namespaced fields and Atom feeds are parsed.
+    #[test]
+    fn test_parse_response() {
+        let provider = RssProvider::new("https://nyaa.si/?page=rss&q={query}")
+            .with_name("nyaa")
+            .with_fields(nyaa_fields());
+        let torrents = provider.parse_response(NYAA_FEED).unwrap();
+
+        assert_eq!(torrents.len(), 1);
+        let torrent = &torrents[0];
+        assert!(torrent
+            .magnet_link
+            .starts_with("magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567"));
+        assert_eq!((torrent.seeders, torrent.peers), (1204, 35));
+        assert_eq!(torrent.size_bytes, 1503238554);
+        assert_eq!(torrent.provider, "nyaa");
+        assert_eq!(
+            torrent.metadata.details_url.as_deref(),
+            Some("https://nyaa.si/view/1714235")
+        );
+
+        let provider = RssProvider::new("https://example.org/feed?q={query}");
+        let torrents = provider.parse_response(ATOM_FEED).unwrap();
+        assert_eq!(torrents.len(), 1);
+        assert_eq!(torrents[0].name, "Ubuntu 24.04 & Friends");
+        assert_eq!(torrents[0].magnet_link, "magnet:?xt=urn:btih:abc&dn=ubuntu");
+        assert_eq!(
+            torrents[0].metadata.provider_id.as_deref(),
+            Some("urn:release:1")
+        );
+
+        assert_matches!(
+            provider.parse_response("{}"),
+            Err(ClientError::DataParseError(_))
+        );
+    }
+
+    /// Tests that the provider passes the conformance suite.
+    #[tokio::test]
+    async fn test_conformance() {
+        ConformanceSuite::new(|url| {
+            RssProvider::new(format!("{}?page=rss&q={{query}}", url)).with_fields(nyaa_fields())
+        })
+        .with_sample_response(NYAA_FEED)
+        .run()
+        .await
+        .unwrap();
+    }
+}
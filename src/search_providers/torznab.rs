@@ -0,0 +1,630 @@
+//! # Torznab Search Provider
+//!
+//! The `Torznab` implementation of the `SearchProvider` trait queries any
+//! Torznab endpoint, such as Jackett, Prowlarr or NZBHydra indexers, and
+//! parses the resulting RSS feed into a unified `Torrent` structure.
+//!
+//! Categories are sent as Newznab ids. The capabilities of the endpoint
+//! (`t=caps`) are read once, before the first search, and requested categories
+//! the endpoint does not support are left out.
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::Request;
+use log::{debug, warn};
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use tokio::sync::OnceCell;
+
+use crate::{
+    errors::ClientError, search_providers::build_get_request, transport::HttpTransport, Category,
+    Magnet, SearchProvider, SearchRequest, Torrent, TorrentMetadata,
+};
+
+/// The `Torznab` provider handles querying and parsing data from a Torznab endpoint.
+pub struct Torznab {
+    /// The API URL of the endpoint, e.g. "http://localhost:9696/1/api".
+    api_url: String,
+
+    /// The API key of the endpoint.
+    api_key: String,
+
+    /// The name reported as the provider of the results.
+    name: String,
+
+    /// The capabilities of the endpoint, read before the first search.
+    caps: OnceCell<TorznabCaps>,
+}
+
+impl Torznab {
+    /// Creates a new instance of the `Torznab` provider.
+    ///
+    /// # Parameters
+    /// - `url`: The API URL of the endpoint, e.g. "http://localhost:9696/1/api" for
+    ///   a Prowlarr indexer or ".../api/v2.0/indexers/all/results/torznab/api" for Jackett.
+    /// - `api_key`: The API key of the endpoint.
+    ///
+    /// # Returns
+    /// - `Torznab`: A new provider instance reporting results as "torznab".
+    pub fn new(url: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self {
+            api_url: url.into(),
+            api_key: api_key.into(),
+            name: "torznab".to_string(),
+            caps: OnceCell::new(),
+        }
+    }
+
+    /// Sets the name reported as the provider of the results, e.g. "prowlarr".
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets the capabilities of the endpoint instead of reading them before the first search.
+    pub fn with_caps(mut self, caps: TorznabCaps) -> Self {
+        self.caps = OnceCell::new_with(Some(caps));
+        self
+    }
+
+    /// Reads the capabilities of the endpoint with `t=caps`.
+    ///
+    /// # Parameters
+    /// - `transport`: The `HttpTransport` used for sending the request.
+    ///
+    /// # Returns
+    /// - `Ok(TorznabCaps)`: The capabilities of the endpoint.
+    /// - `Err(ClientError)`: An error if the request fails or the response is invalid.
+    pub async fn fetch_caps(
+        &self,
+        transport: &dyn HttpTransport,
+    ) -> Result<TorznabCaps, ClientError> {
+        let request = build_get_request(
+            &self.api_url,
+            &[("t", "caps"), ("apikey", self.api_key.as_str())],
+        )?;
+        let body = send(transport, request).await?;
+        TorznabCaps::parse(&body)
+    }
+
+    /// Returns the Newznab ids to search for the requested categories.
+    ///
+    /// Categories unsupported by the endpoint are replaced by their parent
+    /// category if supported, e.g. anime by TV, and left out otherwise.
+    ///
+    /// # Returns
+    /// - `Some(Vec<u32>)`: The ids, empty to search every category.
+    /// - `None`: If categories were requested but none is supported.
+    fn category_ids(&self, categories: &[Category]) -> Option<Vec<u32>> {
+        let caps = self.caps.get().filter(|caps| !caps.categories.is_empty());
+
+        let mut ids = Vec::new();
+        for category in categories {
+            let id = category.newznab_id();
+            let id = match caps {
+                None => Some(id),
+                Some(caps) if caps.supports(id) => Some(id),
+                Some(caps) => Some(id / 1000 * 1000).filter(|parent| caps.supports(*parent)),
+            };
+            if let Some(id) = id.filter(|id| !ids.contains(id)) {
+                ids.push(id);
+            }
+        }
+
+        if ids.is_empty() && !categories.is_empty() {
+            return None;
+        }
+        Some(ids)
+    }
+}
+
+#[async_trait]
+impl SearchProvider for Torznab {
+    /// Reads the capabilities of the endpoint if unknown, then searches it.
+    ///
+    /// Failing to read the capabilities is logged, and the search is sent
+    /// with every requested category.
+    ///
+    /// # Parameters
+    /// - `transport`: The `HttpTransport` used for sending HTTP requests.
+    /// - `request`: A `SearchRequest` containing the search parameters.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Torrent>)`: A list of parsed torrents, empty if no requested category is supported.
+    /// - `Err(ClientError)`: An error if the request or parsing fails.
+    async fn send_request(
+        &self,
+        transport: &dyn HttpTransport,
+        request: SearchRequest<'_>,
+    ) -> Result<Vec<Torrent>, ClientError> {
+        if let Err(e) = self
+            .caps
+            .get_or_try_init(|| self.fetch_caps(transport))
+            .await
+        {
+            warn!("cannot read torznab caps of {}: {}", self.api_url, e);
+        }
+
+        if self.category_ids(&request.categories).is_none() {
+            debug!(
+                "torznab endpoint {} supports none of the requested categories",
+                self.api_url
+            );
+            return Ok(Vec::new());
+        }
+
+        let body = send(transport, self.build_request(request)?).await?;
+        self.parse_response(&body)
+    }
+
+    /// Builds the `t=search` request to the Torznab endpoint.
+    ///
+    /// # Parameters
+    /// - `request`: The `SearchRequest` containing query parameters.
+    ///
+    /// # Returns
+    /// - `Ok(Request<Bytes>)`: The constructed HTTP request.
+    /// - `Err(ClientError)`: An error if request building fails.
+    fn build_request(&self, request: SearchRequest<'_>) -> Result<Request<Bytes>, ClientError> {
+        let limit = request.number_of_results.to_string();
+        let mut query = vec![
+            ("t", "search"),
+            ("apikey", self.api_key.as_str()),
+            ("q", request.query),
+            ("limit", limit.as_str()),
+            ("extended", "1"),
+        ];
+
+        let categories = self
+            .category_ids(&request.categories)
+            .unwrap_or_default()
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        if !categories.is_empty() {
+            query.push(("cat", categories.as_str()));
+        }
+
+        build_get_request(&self.api_url, &query)
+    }
+
+    /// Parses the RSS feed of the Torznab endpoint into a list of torrents.
+    ///
+    /// Items without a magnet link or info hash, which can only be downloaded
+    /// as torrent files, are skipped.
+    ///
+    /// # Parameters
+    /// - `response`: The raw response body as a string.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Torrent>)`: A list of parsed torrent metadata.
+    /// - `Err(ClientError)`: An error if the feed is invalid or the endpoint returned an error.
+    fn parse_response(&self, response: &str) -> Result<Vec<Torrent>, ClientError> {
+        let items = parse_feed(response)?;
+
+        Ok(items
+            .into_iter()
+            .filter_map(|item| {
+                let torrent = item.into_torrent(&self.name);
+                if torrent.is_none() {
+                    debug!("skipping torznab item without magnet link");
+                }
+                torrent
+            })
+            .collect())
+    }
+
+    /// Returns the unique identifier for this provider.
+    ///
+    /// # Returns
+    /// - `String`: The endpoint's API URL as its unique identifier.
+    fn id(&self) -> String {
+        self.api_url.clone()
+    }
+}
+
+/// The capabilities of a Torznab endpoint.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TorznabCaps {
+    /// The Newznab ids of the supported categories and subcategories.
+    pub categories: Vec<u32>,
+}
+
+impl TorznabCaps {
+    /// Parses the response of `t=caps`.
+    ///
+    /// # Parameters
+    /// - `xml`: The capabilities document.
+    ///
+    /// # Returns
+    /// - `Ok(TorznabCaps)`: The parsed capabilities.
+    /// - `Err(ClientError)`: An error if the document is invalid or an error document.
+    pub fn parse(xml: &str) -> Result<Self, ClientError> {
+        let mut caps = Self::default();
+        let mut reader = Reader::from_str(xml);
+
+        loop {
+            match reader.read_event().map_err(parse_error)? {
+                Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+                    b"error" => return Err(endpoint_error(&e)),
+                    b"category" | b"subcat" => {
+                        if let Some(id) = attribute(&e, b"id").and_then(|id| id.parse().ok()) {
+                            caps.categories.push(id);
+                        }
+                    }
+                    _ => {}
+                },
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        Ok(caps)
+    }
+
+    /// Returns whether the endpoint supports the Newznab category id.
+    pub fn supports(&self, id: u32) -> bool {
+        self.categories.contains(&id)
+    }
+}
+
+/// Represents a single item of a Torznab feed.
+#[derive(Debug, Default)]
+struct TorznabItem {
+    /// The title of the release.
+    title: String,
+
+    /// The unique id of the item, often its details URL.
+    guid: Option<String>,
+
+    /// The download link, either a magnet link or a torrent file URL.
+    link: Option<String>,
+
+    /// The details page of the release.
+    comments: Option<String>,
+
+    /// The size in bytes.
+    size: Option<u64>,
+
+    /// The URL of the enclosure, like `link`.
+    enclosure_url: Option<String>,
+
+    /// The `torznab:attr` attributes by name.
+    attrs: HashMap<String, String>,
+}
+
+impl TorznabItem {
+    /// Converts the item into a `Torrent`, or `None` without magnet link or info hash.
+    ///
+    /// Torznab counts seeders as peers, so `Torrent::peers` is `peers` minus `seeders`.
+    fn into_torrent(self, provider: &str) -> Option<Torrent> {
+        let attr = |name: &str| self.attrs.get(name).map(String::as_str);
+        let number = |name: &str| attr(name).and_then(|value| value.parse::<u64>().ok());
+        let count = |value: u64| u32::try_from(value).unwrap_or(u32::MAX);
+
+        let magnet_link = [attr("magneturl"), self.link.as_deref()]
+            .into_iter()
+            .chain([self.enclosure_url.as_deref()])
+            .flatten()
+            .find(|link| link.starts_with("magnet:"))
+            .map(str::to_string)
+            .or_else(|| {
+                let info_hash: [u8; 20] = hex::decode(attr("infohash")?).ok()?.try_into().ok()?;
+                let mut magnet = Magnet::from_info_hash(info_hash);
+                magnet.display_name = Some(self.title.clone());
+                Some(magnet.to_string())
+            })?;
+
+        let seeders = number("seeders").unwrap_or(0);
+        let peers = match number("leechers") {
+            Some(leechers) => leechers,
+            None => number("peers").unwrap_or(0).saturating_sub(seeders),
+        };
+
+        let imdb_id = attr("imdbid")
+            .or(attr("imdb"))
+            .filter(|id| !id.is_empty() && *id != "0")
+            .map(|id| match id.strip_prefix("tt") {
+                Some(_) => id.to_string(),
+                None => format!("tt{:0>7}", id),
+            });
+        let details_url = self.comments.clone().or_else(|| {
+            self.guid
+                .clone()
+                .filter(|guid| guid.starts_with("http://") || guid.starts_with("https://"))
+        });
+
+        Some(Torrent {
+            name: self.title.clone(),
+            magnet_link,
+            seeders: count(seeders),
+            peers: count(peers),
+            size_bytes: self.size.or(number("size")).unwrap_or(0),
+            provider: provider.to_string(),
+            metadata: TorrentMetadata {
+                imdb_id,
+                provider_id: self.guid.clone(),
+                details_url,
+                file_count: number("files").map(count),
+                year: number("year").map(count),
+                ..Default::default()
+            },
+            stats_updated_at: None,
+        })
+    }
+}
+
+/// Sends a request and returns the body of a successful response.
+async fn send(
+    transport: &dyn HttpTransport,
+    request: Request<Bytes>,
+) -> Result<String, ClientError> {
+    let response = transport.send(request).await?;
+    let content = String::from_utf8_lossy(response.body()).into_owned();
+
+    if !response.status().is_success() {
+        return Err(ClientError::ServerResponseError {
+            code: response.status(),
+            content,
+        });
+    }
+
+    Ok(content)
+}
+
+/// Parses the items of a Torznab feed.
+fn parse_feed(xml: &str) -> Result<Vec<TorznabItem>, ClientError> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut items = Vec::new();
+    let mut item: Option<TorznabItem> = None;
+    let mut element: Option<Vec<u8>> = None;
+    let mut found_feed = false;
+
+    loop {
+        match reader.read_event().map_err(parse_error)? {
+            Event::Start(e) | Event::Empty(e) if item.is_none() => match e.local_name().as_ref() {
+                b"error" => return Err(endpoint_error(&e)),
+                b"rss" | b"channel" => found_feed = true,
+                b"item" => item = Some(TorznabItem::default()),
+                _ => {}
+            },
+            Event::Start(e) | Event::Empty(e) => {
+                let current = item.as_mut().expect("inside item");
+                match e.local_name().as_ref() {
+                    b"attr" => {
+                        if let (Some(name), Some(value)) =
+                            (attribute(&e, b"name"), attribute(&e, b"value"))
+                        {
+                            current.attrs.insert(name.to_lowercase(), value);
+                        }
+                    }
+                    b"enclosure" => {
+                        current.enclosure_url = attribute(&e, b"url");
+                        if current.size.is_none() {
+                            current.size = attribute(&e, b"length").and_then(|l| l.parse().ok());
+                        }
+                    }
+                    name => element = Some(name.to_vec()),
+                }
+            }
+            Event::Text(text) => {
+                let text = text.unescape().map_err(parse_error)?;
+                set_field(item.as_mut(), element.as_deref(), &text);
+            }
+            Event::CData(data) => {
+                let data = String::from_utf8_lossy(&data.into_inner()).into_owned();
+                set_field(item.as_mut(), element.as_deref(), data.trim());
+            }
+            Event::End(e) => {
+                if e.local_name().as_ref() == b"item" {
+                    items.extend(item.take());
+                }
+                element = None;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !found_feed {
+        return Err(ClientError::DataParseError(anyhow!(
+            "response is not a torznab feed"
+        )));
+    }
+
+    Ok(items)
+}
+
+/// Sets the field of the item named by the current element.
+fn set_field(item: Option<&mut TorznabItem>, element: Option<&[u8]>, text: &str) {
+    let (Some(item), Some(element)) = (item, element) else {
+        return;
+    };
+
+    match element {
+        b"title" => item.title.push_str(text),
+        b"guid" => item.guid = Some(text.to_string()),
+        b"link" => item.link = Some(text.to_string()),
+        b"comments" => item.comments = Some(text.to_string()),
+        b"size" => item.size = text.parse().ok(),
+        _ => {}
+    }
+}
+
+/// Returns the unescaped value of an attribute of an element.
+fn attribute(element: &BytesStart<'_>, name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|attr| attr.key.local_name().as_ref() == name)
+        .and_then(|attr| attr.unescape_value().ok())
+        .map(|value| value.into_owned())
+}
+
+/// Converts the error document of an endpoint, e.g. for an invalid API key.
+fn endpoint_error(element: &BytesStart<'_>) -> ClientError {
+    ClientError::ProviderError(anyhow!(
+        "torznab error {}: {}",
+        attribute(element, b"code").unwrap_or_default(),
+        attribute(element, b"description").unwrap_or_default()
+    ))
+}
+
+/// Converts an XML error.
+fn parse_error(error: impl Into<anyhow::Error>) -> ClientError {
+    ClientError::DataParseError(error.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testkit::ConformanceSuite, transport::ReqwestTransport};
+    use assert_matches::assert_matches;
+    use mockito::{Matcher, Server};
+
+    const CAPS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <caps>
+          <server title="Prowlarr"/>
+          <categories>
+            <category id="2000" name="Movies"><subcat id="2040" name="Movies/HD"/></category>
+            <category id="5000" name="TV"/>
+          </categories>
+        </caps>"#;
+
+    const FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <rss version="2.0" xmlns:torznab="http://torznab.com/schemas/2015/feed">
+          <channel>
+            <title>Prowlarr</title>
+            <item>
+              <title>Inception.2010.1080p.BluRay.x264</title>
+              <guid>https://tracker.example/details/1</guid>
+              <link>https://prowlarr.example/download?id=1</link>
+              <size>2147483648</size>
+              <enclosure url="https://prowlarr.example/download?id=1" length="2147483648" type="application/x-bittorrent"/>
+              <torznab:attr name="seeders" value="120"/>
+              <torznab:attr name="peers" value="150"/>
+              <torznab:attr name="infohash" value="0123456789abcdef0123456789abcdef01234567"/>
+              <torznab:attr name="imdb" value="1375666"/>
+              <torznab:attr name="files" value="3"/>
+            </item>
+            <item>
+              <title><![CDATA[Show S01E02 & More]]></title>
+              <guid>abc</guid>
+              <comments>https://tracker.example/details/2</comments>
+              <torznab:attr name="magneturl" value="magnet:?xt=urn:btih:abc&amp;dn=show"/>
+              <torznab:attr name="size" value="1024"/>
+              <torznab:attr name="seeders" value="5"/>
+              <torznab:attr name="leechers" value="7"/>
+            </item>
+            <item>
+              <title>Torrent file only</title>
+              <link>https://prowlarr.example/download?id=3</link>
+            </item>
+          </channel>
+        </rss>"#;
+
+    /// Tests that the feed is parsed, including Torznab attributes.
+    #[test]
+    fn test_parse_response() {
+        let provider = Torznab::new("http://localhost/api", "key").with_name("prowlarr");
+        let torrents = provider.parse_response(FEED).unwrap();
+
+        assert_eq!(torrents.len(), 2);
+        let movie = &torrents[0];
+        assert_eq!(movie.name, "Inception.2010.1080p.BluRay.x264");
+        assert_eq!(
+            movie.magnet_link,
+            "magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567&dn=Inception.2010.1080p.BluRay.x264"
+        );
+        assert_eq!((movie.seeders, movie.peers), (120, 30));
+        assert_eq!(movie.size_bytes, 2147483648);
+        assert_eq!(movie.provider, "prowlarr");
+        assert_eq!(movie.metadata.imdb_id.as_deref(), Some("tt1375666"));
+        assert_eq!(movie.metadata.file_count, Some(3));
+        assert_eq!(
+            movie.metadata.details_url.as_deref(),
+            Some("https://tracker.example/details/1")
+        );
+
+        let show = &torrents[1];
+        assert_eq!(show.name, "Show S01E02 & More");
+        assert_eq!(show.magnet_link, "magnet:?xt=urn:btih:abc&dn=show");
+        assert_eq!((show.seeders, show.peers), (5, 7));
+        assert_eq!(show.size_bytes, 1024);
+        assert_eq!(show.metadata.provider_id.as_deref(), Some("abc"));
+
+        assert_matches!(
+            provider.parse_response(r#"<error code="100" description="Invalid API Key"/>"#),
+            Err(ClientError::ProviderError(e)) if e.to_string().contains("Invalid API Key")
+        );
+        assert_matches!(
+            provider.parse_response("{}"),
+            Err(ClientError::DataParseError(_))
+        );
+    }
+
+    /// Tests that the capabilities restrict the searched categories.
+    #[tokio::test]
+    async fn test_caps_categories() {
+        let mut server = Server::new_async().await;
+        let caps = server
+            .mock("GET", "/api")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("t".into(), "caps".into()),
+                Matcher::UrlEncoded("apikey".into(), "key".into()),
+            ]))
+            .with_body(CAPS)
+            .expect(1)
+            .create_async()
+            .await;
+        let search = server
+            .mock("GET", "/api")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("t".into(), "search".into()),
+                Matcher::UrlEncoded("q".into(), "inception".into()),
+                Matcher::UrlEncoded("cat".into(), "2000,5000".into()),
+            ]))
+            .with_body(FEED)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let provider = Torznab::new(format!("{}/api", server.url()), "key");
+        let transport = ReqwestTransport::new();
+
+        // Anime is searched as TV, software is unsupported
+        let request = SearchRequest::new("inception").add_categories(vec![
+            Category::Movies,
+            Category::Anime,
+            Category::Software,
+        ]);
+        let torrents = provider.send_request(&transport, request).await.unwrap();
+        assert_eq!(torrents.len(), 2);
+
+        let request = SearchRequest::new("inception").add_category(Category::Software);
+        let torrents = provider.send_request(&transport, request).await.unwrap();
+        assert!(torrents.is_empty());
+
+        caps.assert_async().await;
+        search.assert_async().await;
+    }
+
+    /// Tests that the provider passes the conformance suite.
+    #[tokio::test]
+    async fn test_conformance() {
+        // Known capabilities keep searches to a single request, as the suite expects
+        ConformanceSuite::new(|url| Torznab::new(url, "key").with_caps(TorznabCaps::default()))
+            .with_sample_response(FEED)
+            .run()
+            .await
+            .unwrap();
+    }
+}
@@ -6,10 +6,17 @@
 //! a unified `Torrent` structure.
 
 use async_trait::async_trait;
-use reqwest::{Client, Request};
+use bytes::Bytes;
+use http::Request;
+use log::warn;
 use serde::Deserialize;
 
-use crate::{errors::ClientError, Category, SearchProvider, SearchRequest, Torrent};
+use crate::{
+    errors::ClientError,
+    search_providers::build_get_request,
+    size::{parse_size, ParseSizeError},
+    Category, SearchProvider, SearchRequest, Torrent, TorrentMetadata, VideoDetails,
+};
 
 /// The `Yts` provider handles querying and parsing data from the YTS API.
 pub struct Yts {
@@ -54,17 +61,12 @@ impl SearchProvider for Yts {
     /// Builds the request to query the YTS API.
     ///
     /// # Parameters
-    /// - `client`: The HTTP client used to build the request.
     /// - `request`: The `SearchRequest` containing query parameters.
     ///
     /// # Returns
-    /// - `Ok(Request)`: The constructed HTTP request.
+    /// - `Ok(Request<Bytes>)`: The constructed HTTP request.
     /// - `Err(ClientError)`: An error if request building fails.
-    fn build_request(
-        &self,
-        client: &Client,
-        request: SearchRequest<'_>,
-    ) -> Result<Request, ClientError> {
+    fn build_request(&self, request: SearchRequest<'_>) -> Result<Request<Bytes>, ClientError> {
         let mut query = vec![("query_term", request.query)];
         if let Some(category) = request.categories.first() {
             let genre = match category {
@@ -77,14 +79,7 @@ impl SearchProvider for Yts {
             }
         }
 
-        client
-            .get(self.api_url.clone())
-            .query(&query)
-            .build()
-            .map_err(|e| ClientError::RequestBuildError {
-                source: e.into(),
-                url: self.api_url.clone(),
-            })
+        build_get_request(&self.api_url, &query)
     }
 
     /// Parses the response from the YTS API into a list of torrents.
@@ -104,14 +99,32 @@ impl SearchProvider for Yts {
 
         let torrents = movies
             .into_iter()
-            .flat_map(|movie| {
-                movie.torrents.into_iter().map(move |torrent| Torrent {
-                    name: movie.title.clone(),
-                    magnet_link: format!("magnet:?xt=urn:btih:{}", torrent.hash),
-                    seeders: torrent.seeds,
-                    peers: torrent.peers,
-                    size_bytes: torrent.size_bytes(),
-                    provider: "yts".to_string(),
+            .flat_map(|mut movie| {
+                let torrents = std::mem::take(&mut movie.torrents);
+                torrents.into_iter().filter_map(move |torrent| {
+                    // Skip torrents with an unknown size rather than reporting them as empty
+                    let size_bytes = torrent
+                        .size_bytes()
+                        .map_err(|e| warn!("skipping yts torrent {}: {}", torrent.hash, e))
+                        .ok()?;
+
+                    Some(Torrent {
+                        name: torrent.release_name(&movie),
+                        magnet_link: format!("magnet:?xt=urn:btih:{}", torrent.hash),
+                        seeders: torrent.seeds,
+                        peers: torrent.peers,
+                        size_bytes,
+                        provider: "yts".to_string(),
+                        metadata: TorrentMetadata {
+                            imdb_id: movie.imdb_code.clone(),
+                            provider_id: movie.id.map(|id| id.to_string()),
+                            details_url: movie.url.clone(),
+                            year: movie.year,
+                            video: Some(torrent.video_details()),
+                            ..Default::default()
+                        },
+                        stats_updated_at: None,
+                    })
                 })
             })
             .collect();
@@ -146,9 +159,25 @@ struct YtsData {
 /// Represents a single movie in the YTS API response.
 #[derive(Debug, Deserialize)]
 struct YtsMovie {
+    /// The YTS identifier of the movie.
+    #[serde(default)]
+    id: Option<u64>,
+
     /// The title of the movie.
     title: String,
 
+    /// The release year of the movie.
+    #[serde(default)]
+    year: Option<u32>,
+
+    /// The IMDb id of the movie, e.g. "tt1375666".
+    #[serde(default)]
+    imdb_code: Option<String>,
+
+    /// The URL of the movie's page on YTS.
+    #[serde(default)]
+    url: Option<String>,
+
     /// A list of available torrents for the movie.
     torrents: Vec<YtsTorrent>,
 }
@@ -167,25 +196,78 @@ struct YtsTorrent {
 
     /// The size of the torrent as a string, e.g., "700MB".
     size: String,
+
+    /// The video quality of the torrent, e.g. "1080p".
+    #[serde(default)]
+    quality: Option<String>,
+
+    /// The release type of the torrent, e.g. "bluray" or "web".
+    #[serde(default, rename = "type")]
+    release_type: Option<String>,
+
+    /// The video codec of the torrent, e.g. "x265".
+    #[serde(default)]
+    video_codec: Option<String>,
+
+    /// The video bit depth of the torrent as a string, e.g. "10".
+    #[serde(default)]
+    bit_depth: Option<String>,
+
+    /// The audio channel layout of the torrent, e.g. "5.1".
+    #[serde(default)]
+    audio_channels: Option<String>,
 }
 
 impl YtsTorrent {
-    /// Converts the size string into bytes.
-    fn size_bytes(&self) -> u64 {
-        let size = self.size.to_lowercase();
-        if size.ends_with("gb") {
-            (size.trim_end_matches("gb").parse::<f64>().unwrap_or(0.0) * 1_000_000_000.0) as u64
-        } else if size.ends_with("mb") {
-            (size.trim_end_matches("mb").parse::<f64>().unwrap_or(0.0) * 1_000_000.0) as u64
-        } else {
-            0
+    /// Builds a name distinguishing this torrent from the other releases of the movie,
+    /// e.g. "Inception (2010) [1080p BluRay x265]".
+    fn release_name(&self, movie: &YtsMovie) -> String {
+        let mut name = movie.title.clone();
+        if let Some(year) = movie.year {
+            name.push_str(&format!(" ({})", year));
+        }
+
+        let release_type = self.release_type.as_deref().map(|release_type| {
+            match release_type.to_lowercase().as_str() {
+                "bluray" => "BluRay".to_string(),
+                "web" => "WEB".to_string(),
+                _ => release_type.to_string(),
+            }
+        });
+
+        let tags: Vec<String> = [self.quality.clone(), release_type, self.video_codec.clone()]
+            .into_iter()
+            .flatten()
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        if !tags.is_empty() {
+            name.push_str(&format!(" [{}]", tags.join(" ")));
         }
+
+        name
+    }
+
+    /// Collects the video and audio details of the torrent.
+    fn video_details(&self) -> VideoDetails {
+        VideoDetails {
+            quality: self.quality.clone(),
+            release_type: self.release_type.clone(),
+            video_codec: self.video_codec.clone(),
+            bit_depth: self.bit_depth.as_ref().and_then(|depth| depth.parse().ok()),
+            audio_channels: self.audio_channels.clone(),
+        }
+    }
+
+    /// Converts the size string into bytes.
+    fn size_bytes(&self) -> Result<u64, ParseSizeError> {
+        parse_size(&self.size)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::transport::ReplayTransport;
     use mockito::Server;
 
     /// Sets up a mock YTS provider using a mock server.
@@ -202,16 +284,15 @@ mod tests {
     #[tokio::test]
     async fn test_build_request_with_category() {
         let provider = setup_mock_provider().await;
-        let client = Client::new();
 
         let search_request = SearchRequest::new("Inception").add_category(Category::Movies);
-        let request = provider.build_request(&client, search_request);
+        let request = provider.build_request(search_request);
 
         assert!(request.is_ok());
         let request = request.unwrap();
         assert_eq!(request.method(), "GET");
-        assert!(request.url().as_str().contains("query_term=Inception"));
-        assert!(request.url().as_str().contains("genre=movie"));
+        assert!(request.uri().to_string().contains("query_term=Inception"));
+        assert!(request.uri().to_string().contains("genre=movie"));
     }
 
     /// Tests building a request with a valid query but no category.
@@ -221,15 +302,14 @@ mod tests {
     #[tokio::test]
     async fn test_build_request_no_category() {
         let provider = setup_mock_provider().await;
-        let client = Client::new();
 
         let search_request = SearchRequest::new("Inception");
-        let request = provider.build_request(&client, search_request);
+        let request = provider.build_request(search_request);
 
         assert!(request.is_ok());
         let request = request.unwrap();
-        assert!(request.url().as_str().contains("query_term=Inception"));
-        assert!(!request.url().as_str().contains("genre="));
+        assert!(request.uri().to_string().contains("query_term=Inception"));
+        assert!(!request.uri().to_string().contains("genre="));
     }
 
     /// Tests parsing a valid API response into a list of torrents.
@@ -276,6 +356,147 @@ mod tests {
         assert_eq!(torrent.provider, "yts");
     }
 
+    /// Tests parsing the extended metadata of a movie in the API response.
+    ///
+    /// Ensures that the movie id, IMDb code and page URL are mapped into
+    /// `TorrentMetadata` for every torrent of the movie.
+    #[tokio::test]
+    async fn test_parse_response_metadata() {
+        let provider = setup_mock_provider().await;
+
+        let response_body = r#"
+        {
+            "status": "ok",
+            "status_message": "Query was successful",
+            "data": {
+                "movies": [
+                    {
+                        "id": 3175,
+                        "url": "https://yts.mx/movies/inception-2010",
+                        "imdb_code": "tt1375666",
+                        "title": "Inception",
+                        "torrents": [
+                            { "hash": "abc123", "seeds": 200, "peers": 50, "size": "1.5GB" },
+                            { "hash": "def456", "seeds": 100, "peers": 20, "size": "3GB" }
+                        ]
+                    }
+                ]
+            }
+        }
+        "#;
+
+        let torrents = provider.parse_response(response_body).unwrap();
+        assert_eq!(torrents.len(), 2);
+        for torrent in torrents {
+            assert_eq!(torrent.metadata.imdb_id.as_deref(), Some("tt1375666"));
+            assert_eq!(torrent.metadata.provider_id.as_deref(), Some("3175"));
+            assert_eq!(
+                torrent.metadata.details_url.as_deref(),
+                Some("https://yts.mx/movies/inception-2010")
+            );
+        }
+    }
+
+    /// Tests parsing the quality details of the torrents of a movie.
+    ///
+    /// Ensures that every release gets a distinguishing name and that the
+    /// quality, type, codec, bit depth and audio channels are exposed.
+    #[tokio::test]
+    async fn test_parse_response_quality() {
+        let provider = setup_mock_provider().await;
+
+        let response_body = r#"
+        {
+            "status": "ok",
+            "status_message": "Query was successful",
+            "data": {
+                "movies": [
+                    {
+                        "title": "Inception",
+                        "year": 2010,
+                        "torrents": [
+                            {
+                                "hash": "abc123",
+                                "seeds": 200,
+                                "peers": 50,
+                                "size": "1.5GB",
+                                "quality": "720p",
+                                "type": "web",
+                                "video_codec": "x264",
+                                "bit_depth": "8",
+                                "audio_channels": "2.0"
+                            },
+                            {
+                                "hash": "def456",
+                                "seeds": 100,
+                                "peers": 20,
+                                "size": "3GB",
+                                "quality": "1080p",
+                                "type": "bluray",
+                                "video_codec": "x265",
+                                "bit_depth": "10",
+                                "audio_channels": "5.1"
+                            }
+                        ]
+                    }
+                ]
+            }
+        }
+        "#;
+
+        let torrents = provider.parse_response(response_body).unwrap();
+        assert_eq!(torrents.len(), 2);
+        assert_eq!(torrents[0].name, "Inception (2010) [720p WEB x264]");
+        assert_eq!(torrents[1].name, "Inception (2010) [1080p BluRay x265]");
+
+        let metadata = &torrents[1].metadata;
+        assert_eq!(metadata.year, Some(2010));
+        assert_eq!(
+            metadata.video,
+            Some(VideoDetails {
+                quality: Some("1080p".to_string()),
+                release_type: Some("bluray".to_string()),
+                video_codec: Some("x265".to_string()),
+                bit_depth: Some(10),
+                audio_channels: Some("5.1".to_string()),
+            })
+        );
+    }
+
+    /// Tests parsing torrent sizes with different units.
+    ///
+    /// Ensures that sizes in any supported unit are converted and that
+    /// torrents with an unparsable size are skipped instead of reported as empty.
+    #[tokio::test]
+    async fn test_parse_response_sizes() {
+        let provider = setup_mock_provider().await;
+
+        let response_body = r#"
+        {
+            "status": "ok",
+            "status_message": "Query was successful",
+            "data": {
+                "movies": [
+                    {
+                        "title": "Inception",
+                        "torrents": [
+                            { "hash": "a", "seeds": 1, "peers": 1, "size": "1.2 TB" },
+                            { "hash": "b", "seeds": 1, "peers": 1, "size": "850 KB" },
+                            { "hash": "c", "seeds": 1, "peers": 1, "size": "1.4 GiB" },
+                            { "hash": "d", "seeds": 1, "peers": 1, "size": "unknown" }
+                        ]
+                    }
+                ]
+            }
+        }
+        "#;
+
+        let torrents = provider.parse_response(response_body).unwrap();
+        let sizes: Vec<u64> = torrents.iter().map(|t| t.size_bytes).collect();
+        assert_eq!(sizes, vec![1_200_000_000_000, 850_000, 1_503_238_554]);
+        assert_eq!(torrents[0].size_human(), "1.20 TB");
+    }
+
     /// Tests handling of invalid JSON responses from the API.
     ///
     /// Ensures that an invalid JSON string results in a `DataParseError`.
@@ -322,4 +543,22 @@ mod tests {
             "Expected empty results due to no movies"
         );
     }
+
+    /// Tests searching through the recorded YTS fixture.
+    #[tokio::test]
+    async fn test_fixture_replay() {
+        let transport =
+            ReplayTransport::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/yts.json"))
+                .unwrap();
+
+        let torrents = Yts::new()
+            .send_request(&transport, SearchRequest::new("inception"))
+            .await
+            .unwrap();
+
+        assert_eq!(torrents.len(), 2);
+        assert_eq!(torrents[1].seeders, 318);
+        assert_eq!(torrents[1].metadata.imdb_id.as_deref(), Some("tt1375666"));
+        assert_eq!(torrents[1].metadata.year, Some(2010));
+    }
 }
@@ -0,0 +1,255 @@
+//! # Auth
+//!
+//! Authenticates requests with the server's API keys and enforces their limits.
+//!
+//! Clients send their key as the `apikey` query parameter, as Torznab clients
+//! do, or in an `X-Api-Key` or `Authorization: Bearer` header. Requests per
+//! minute and per day are counted in fixed windows starting with the first
+//! request of each window.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use log::debug;
+use serde_json::json;
+use thiserror::Error;
+
+use super::{keys::ApiKeys, torznab, ApiKey, ServerState};
+
+/// The length of the per-minute window.
+const MINUTE: Duration = Duration::from_secs(60);
+
+/// The length of the per-day window.
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Represents errors rejecting a request.
+#[derive(Error, Debug, PartialEq)]
+pub enum AuthError {
+    /// The request has no API key.
+    #[error("missing api key")]
+    MissingKey,
+
+    /// The API key is unknown.
+    #[error("invalid api key")]
+    InvalidKey,
+
+    /// The key sent too many requests in the current minute or day.
+    #[error("request limit reached, retry in {} seconds", .retry_after.as_secs())]
+    LimitReached {
+        /// When the current window ends.
+        retry_after: Duration,
+    },
+}
+
+impl IntoResponse for AuthError {
+    /// Answers `401 Unauthorized`, or `429 Too Many Requests` with a `Retry-After` header.
+    fn into_response(self) -> Response {
+        let status = match self {
+            AuthError::MissingKey | AuthError::InvalidKey => StatusCode::UNAUTHORIZED,
+            AuthError::LimitReached { .. } => StatusCode::TOO_MANY_REQUESTS,
+        };
+        let mut response = (status, Json(json!({ "error": self.to_string() }))).into_response();
+        if let AuthError::LimitReached { retry_after } = self {
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(retry_after.as_secs().max(1)),
+            );
+        }
+        response
+    }
+}
+
+/// The requests counted for a key.
+#[derive(Debug, Clone, Copy)]
+struct Usage {
+    /// When the current minute window started.
+    minute_start: Instant,
+
+    /// The requests in the current minute window.
+    minute_count: u32,
+
+    /// When the current day window started.
+    day_start: Instant,
+
+    /// The requests in the current day window.
+    day_count: u64,
+}
+
+/// The keys of a server and their usage.
+pub(crate) struct KeyStore {
+    /// The keys by secret.
+    keys: HashMap<String, Arc<ApiKey>>,
+
+    /// The usage of each key by secret.
+    usage: Mutex<HashMap<String, Usage>>,
+}
+
+impl KeyStore {
+    /// Creates a new `KeyStore` without usage.
+    pub(crate) fn new(keys: ApiKeys) -> Self {
+        Self {
+            keys: keys
+                .keys
+                .into_iter()
+                .map(|key| (key.key.clone(), Arc::new(key)))
+                .collect(),
+            usage: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Checks a key and counts the request against its limits.
+    ///
+    /// Rejected requests are not counted.
+    pub(crate) fn authorize(
+        &self,
+        key: Option<&str>,
+        now: Instant,
+    ) -> Result<Arc<ApiKey>, AuthError> {
+        let key = key.ok_or(AuthError::MissingKey)?;
+        let api_key = self.keys.get(key).ok_or(AuthError::InvalidKey)?;
+
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        let usage = usage.entry(key.to_string()).or_insert(Usage {
+            minute_start: now,
+            minute_count: 0,
+            day_start: now,
+            day_count: 0,
+        });
+        if now.duration_since(usage.minute_start) >= MINUTE {
+            usage.minute_start = now;
+            usage.minute_count = 0;
+        }
+        if now.duration_since(usage.day_start) >= DAY {
+            usage.day_start = now;
+            usage.day_count = 0;
+        }
+
+        let retry_after = |start: Instant, window: Duration| AuthError::LimitReached {
+            retry_after: window.saturating_sub(now.duration_since(start)),
+        };
+        if api_key
+            .requests_per_minute
+            .is_some_and(|limit| usage.minute_count >= limit)
+        {
+            return Err(retry_after(usage.minute_start, MINUTE));
+        }
+        if api_key
+            .requests_per_day
+            .is_some_and(|limit| usage.day_count >= limit)
+        {
+            return Err(retry_after(usage.day_start, DAY));
+        }
+
+        usage.minute_count += 1;
+        usage.day_count += 1;
+        Ok(api_key.clone())
+    }
+}
+
+/// Rejects requests without a valid key within its limits, if the server has keys.
+///
+/// The key is added to the request extensions for the handlers. Torznab
+/// requests are rejected with Torznab error documents.
+pub(crate) async fn authenticate(
+    State(state): State<Arc<ServerState>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(keys) = &state.keys else {
+        return next.run(request).await;
+    };
+
+    match keys.authorize(request_key(&request).as_deref(), Instant::now()) {
+        Ok(key) => {
+            debug!(
+                "request to {} with key '{}'",
+                request.uri().path(),
+                key.name
+            );
+            request.extensions_mut().insert(key);
+            next.run(request).await
+        }
+        Err(e) if request.uri().path() == "/api" => torznab::auth_error(&e),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Returns the key of a request, from the query or headers.
+fn request_key(request: &Request) -> Option<String> {
+    let from_query = request.uri().query().and_then(|query| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(name, _)| name == "apikey")
+            .map(|(_, value)| value.into_owned())
+    });
+
+    let headers = request.headers();
+    let from_header = || {
+        let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+        header("x-api-key")
+            .or_else(|| header(header::AUTHORIZATION.as_str())?.strip_prefix("Bearer "))
+            .map(|key| key.trim().to_string())
+    };
+
+    from_query
+        .or_else(from_header)
+        .filter(|key| !key.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that limits are enforced per key and reset with their window.
+    #[test]
+    fn test_authorize() {
+        let keys = ApiKeys::new(vec![
+            ApiKey::new("limited", "limited").with_limits(Some(2), Some(3)),
+            ApiKey::new("free", "free"),
+        ])
+        .unwrap();
+        let store = KeyStore::new(keys);
+        let start = Instant::now();
+
+        assert_eq!(
+            store.authorize(None, start).unwrap_err(),
+            AuthError::MissingKey
+        );
+        assert_eq!(
+            store.authorize(Some("nope"), start).unwrap_err(),
+            AuthError::InvalidKey
+        );
+
+        assert!(store.authorize(Some("limited"), start).is_ok());
+        assert!(store.authorize(Some("limited"), start).is_ok());
+        let later = start + Duration::from_secs(20);
+        assert_eq!(
+            store.authorize(Some("limited"), later).unwrap_err(),
+            AuthError::LimitReached {
+                retry_after: Duration::from_secs(40)
+            }
+        );
+
+        // A new minute allows one more request before the daily limit
+        let next_minute = start + MINUTE;
+        assert!(store.authorize(Some("limited"), next_minute).is_ok());
+        assert!(matches!(
+            store.authorize(Some("limited"), next_minute),
+            Err(AuthError::LimitReached { retry_after }) if retry_after > MINUTE
+        ));
+        assert!(store.authorize(Some("limited"), start + DAY).is_ok());
+
+        for _ in 0..100 {
+            assert!(store.authorize(Some("free"), start).is_ok());
+        }
+    }
+}
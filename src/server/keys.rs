@@ -0,0 +1,297 @@
+//! # Keys
+//!
+//! The API keys of a server, loaded from a TOML or JSON file:
+//!
+//! ```toml
+//! [[keys]]
+//! key = "0123456789abcdef"
+//! name = "mobile"
+//! requests_per_minute = 30
+//! requests_per_day = 5000
+//! blocked_categories = ["xxx"]
+//!
+//! [[keys]]
+//! key = "fedcba9876543210"
+//! name = "sonarr"
+//! providers = ["https://api.knaben.org/v1"]
+//! ```
+//!
+//! Limits are optional, and an empty `providers` list allows every provider.
+
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::Category;
+
+/// Represents errors that can occur while loading API keys.
+#[derive(Error, Debug)]
+pub enum KeysError {
+    /// The keys file cannot be read.
+    #[error("cannot read keys file {path}: {source}")]
+    Io {
+        /// The path of the file.
+        path: PathBuf,
+        /// The underlying error.
+        #[source]
+        source: io::Error,
+    },
+
+    /// The keys file is not valid TOML or does not match the schema.
+    #[error("invalid toml keys: {0}")]
+    Toml(#[from] toml::de::Error),
+
+    /// The keys file is not valid JSON or does not match the schema.
+    #[error("invalid json keys: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// The keys file has an extension other than `.toml` or `.json`.
+    #[error("unsupported keys file format: {0}")]
+    UnsupportedFormat(PathBuf),
+
+    /// A key is empty or listed twice; the name of the entry is reported, not the key.
+    #[error("invalid key '{0}': keys must be unique and not empty")]
+    InvalidKey(String),
+}
+
+/// The API keys accepted by a server.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiKeys {
+    /// The accepted keys.
+    pub keys: Vec<ApiKey>,
+}
+
+/// An API key and what it is allowed to do.
+#[derive(Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKey {
+    /// The secret sent by clients.
+    pub key: String,
+
+    /// A name identifying the key in logs.
+    #[serde(default)]
+    pub name: String,
+
+    /// The maximum number of requests in a minute.
+    #[serde(default)]
+    pub requests_per_minute: Option<u32>,
+
+    /// The maximum number of requests in a day.
+    #[serde(default)]
+    pub requests_per_day: Option<u64>,
+
+    /// The ids of the providers the key may search; empty allows every provider.
+    #[serde(default)]
+    pub providers: Vec<String>,
+
+    /// The categories the key may not search.
+    #[serde(default)]
+    pub blocked_categories: Vec<Category>,
+}
+
+impl std::fmt::Debug for ApiKey {
+    /// Formats the key without its secret.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKey")
+            .field("name", &self.name)
+            .field("requests_per_minute", &self.requests_per_minute)
+            .field("requests_per_day", &self.requests_per_day)
+            .field("providers", &self.providers)
+            .field("blocked_categories", &self.blocked_categories)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ApiKey {
+    /// Creates a new `ApiKey` without limits or restrictions.
+    ///
+    /// # Parameters
+    /// - `key`: The secret sent by clients.
+    /// - `name`: A name identifying the key in logs.
+    pub fn new(key: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            name: name.into(),
+            requests_per_minute: None,
+            requests_per_day: None,
+            providers: Vec::new(),
+            blocked_categories: Vec::new(),
+        }
+    }
+
+    /// Limits the number of requests per minute and per day.
+    pub fn with_limits(mut self, per_minute: Option<u32>, per_day: Option<u64>) -> Self {
+        self.requests_per_minute = per_minute;
+        self.requests_per_day = per_day;
+        self
+    }
+
+    /// Restricts the key to the providers with the given ids.
+    pub fn with_providers(mut self, providers: Vec<String>) -> Self {
+        self.providers = providers;
+        self
+    }
+
+    /// Forbids the key to search the given categories.
+    pub fn with_blocked_categories(mut self, categories: Vec<Category>) -> Self {
+        self.blocked_categories = categories;
+        self
+    }
+
+    /// Returns whether the key may search the provider.
+    pub fn allows_provider(&self, id: &str) -> bool {
+        self.providers.is_empty() || self.providers.iter().any(|allowed| allowed == id)
+    }
+
+    /// Returns whether the key may search the category.
+    pub fn allows_category(&self, category: &Category) -> bool {
+        !self.blocked_categories.contains(category)
+    }
+
+    /// Returns the categories to search for the requested ones.
+    ///
+    /// Blocked categories are removed. Since no categories means every category,
+    /// a key with blocked categories searches every allowed category instead.
+    ///
+    /// # Parameters
+    /// - `requested`: The requested categories, empty for every category.
+    ///
+    /// # Returns
+    /// - `Vec<Category>`: The allowed categories, empty if none of the requested ones is
+    ///   allowed, or if every category is allowed and none was requested.
+    pub fn categories(&self, requested: &[Category]) -> Vec<Category> {
+        if self.blocked_categories.is_empty() {
+            return requested.to_vec();
+        }
+
+        let requested = if requested.is_empty() {
+            &Category::ALL[..]
+        } else {
+            requested
+        };
+        requested
+            .iter()
+            .filter(|category| self.allows_category(category))
+            .cloned()
+            .collect()
+    }
+}
+
+impl ApiKeys {
+    /// Creates a new `ApiKeys` accepting the given keys.
+    ///
+    /// # Returns
+    /// - `Err(KeysError::InvalidKey)`: An error if a key is empty or listed twice.
+    pub fn new(keys: Vec<ApiKey>) -> Result<Self, KeysError> {
+        Self { keys }.validated()
+    }
+
+    /// Loads keys from a `.toml` or `.json` file.
+    ///
+    /// # Parameters
+    /// - `path`: The path of the keys file.
+    ///
+    /// # Returns
+    /// - `Ok(ApiKeys)`: The loaded keys.
+    /// - `Err(KeysError)`: An error if the file cannot be read, parsed or has invalid keys.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, KeysError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|source| KeysError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml(&content),
+            Some("json") => Self::from_json(&content),
+            _ => Err(KeysError::UnsupportedFormat(path.to_path_buf())),
+        }
+    }
+
+    /// Parses keys from TOML.
+    pub fn from_toml(content: &str) -> Result<Self, KeysError> {
+        toml::from_str::<Self>(content)?.validated()
+    }
+
+    /// Parses keys from JSON.
+    pub fn from_json(content: &str) -> Result<Self, KeysError> {
+        serde_json::from_str::<Self>(content)?.validated()
+    }
+
+    /// Returns the key with the given secret.
+    pub fn get(&self, key: &str) -> Option<&ApiKey> {
+        self.keys.iter().find(|candidate| candidate.key == key)
+    }
+
+    /// Checks that keys are unique and not empty.
+    fn validated(self) -> Result<Self, KeysError> {
+        let mut seen = HashSet::new();
+        for key in &self.keys {
+            if key.key.is_empty() || !seen.insert(key.key.as_str()) {
+                return Err(KeysError::InvalidKey(key.name.clone()));
+            }
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests loading keys and their restrictions.
+    #[test]
+    fn test_api_keys() {
+        let keys = ApiKeys::from_toml(
+            r#"
+            [[keys]]
+            key = "secret"
+            name = "mobile"
+            requests_per_minute = 30
+            blocked_categories = ["xxx"]
+
+            [[keys]]
+            key = "other"
+            providers = ["https://api.knaben.org/v1"]
+            "#,
+        )
+        .unwrap();
+
+        let mobile = keys.get("secret").unwrap();
+        assert_eq!(mobile.requests_per_minute, Some(30));
+        assert!(!format!("{:?}", mobile).contains("secret"));
+        assert!(mobile.allows_provider("https://apibay.org/q.php"));
+        assert!(!mobile.allows_category(&Category::Xxx));
+        assert_eq!(mobile.categories(&[]).len(), Category::ALL.len() - 1);
+        assert_eq!(
+            mobile.categories(&[Category::Movies, Category::Xxx]),
+            vec![Category::Movies]
+        );
+        assert!(mobile.categories(&[Category::Xxx]).is_empty());
+
+        let other = keys.get("other").unwrap();
+        assert!(other.allows_provider("https://api.knaben.org/v1"));
+        assert!(!other.allows_provider("https://apibay.org/q.php"));
+        assert!(other.categories(&[]).is_empty());
+        assert!(keys.get("missing").is_none());
+
+        assert!(matches!(
+            ApiKeys::from_json(r#"{"keys": [{"key": "a"}, {"key": "a", "name": "copy"}]}"#),
+            Err(KeysError::InvalidKey(name)) if name == "copy"
+        ));
+        assert!(matches!(
+            ApiKeys::from_json(r#"{"keys": [{"key": "a", "quota": 1}]}"#),
+            Err(KeysError::Json(_))
+        ));
+        assert!(matches!(
+            ApiKeys::from_file("keys.yaml"),
+            Err(KeysError::Io { .. })
+        ));
+    }
+}
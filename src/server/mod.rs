@@ -0,0 +1,287 @@
+//! # Server
+//!
+//! Exposes a `Magneto` instance over HTTP, requires the `server` feature.
+//!
+//! The `/api` endpoint implements the Torznab protocol, so Sonarr, Radarr and
+//! other Torznab clients can use magneto as an indexer; see the `torznab`
+//! module for the supported functions. The other endpoints are a JSON API
+//! described in the `rest` module and by `GET /openapi.json`.
+//!
+//! Without API keys every request is accepted, which is only suitable on
+//! localhost. With `Server::with_api_keys`, every endpoint but `/health` and
+//! `/openapi.json` requires a key, and each key can have request limits, a
+//! provider allowlist and blocked categories; see the `keys` and `auth`
+//! modules. Browsers on other origins are allowed with `Server::with_cors`.
+//!
+//! # Example
+//! ```no_run
+//! use magneto::{server::Server, Magneto};
+//! use tokio::net::TcpListener;
+//!
+//! #[tokio::main]
+//! async fn main() -> std::io::Result<()> {
+//!     let listener = TcpListener::bind("127.0.0.1:9117").await?;
+//!     Server::new(Magneto::new()).serve(listener).await
+//! }
+//! ```
+
+pub mod auth;
+pub mod keys;
+pub mod rest;
+pub mod torznab;
+
+pub use keys::{ApiKey, ApiKeys, KeysError};
+
+use std::{
+    collections::HashMap,
+    io,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    http::{header, HeaderName, HeaderValue, Method},
+    middleware,
+    routing::get,
+    Router,
+};
+use log::warn;
+use tokio::net::TcpListener;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use auth::KeyStore;
+
+use crate::Magneto;
+
+/// The state shared by the request handlers.
+pub(crate) struct ServerState {
+    /// The instance answering searches.
+    pub(crate) magneto: Magneto,
+
+    /// The name of the server, reported to clients.
+    pub(crate) title: String,
+
+    /// The last search of each provider by id, for `GET /providers`.
+    pub(crate) last_searches: Mutex<HashMap<String, rest::LastSearch>>,
+
+    /// The accepted API keys, `None` to accept every request.
+    pub(crate) keys: Option<KeyStore>,
+}
+
+/// An HTTP server answering searches with a `Magneto` instance.
+pub struct Server {
+    /// The state shared by the request handlers.
+    state: ServerState,
+
+    /// The origins allowed to call the server from browsers.
+    cors_origins: Vec<String>,
+}
+
+impl Server {
+    /// Creates a new `Server` named "magneto".
+    ///
+    /// # Parameters
+    /// - `magneto`: The instance answering searches.
+    pub fn new(magneto: Magneto) -> Self {
+        Self {
+            state: ServerState {
+                magneto,
+                title: "magneto".to_string(),
+                last_searches: Mutex::new(HashMap::new()),
+                keys: None,
+            },
+            cors_origins: Vec::new(),
+        }
+    }
+
+    /// Sets the name of the server, reported to clients e.g. in Torznab capabilities.
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.state.title = title.into();
+        self
+    }
+
+    /// Requires one of the given API keys on every endpoint but `/health` and `/openapi.json`.
+    ///
+    /// # Parameters
+    /// - `keys`: The accepted keys, usually from `ApiKeys::from_file`.
+    pub fn with_api_keys(mut self, keys: ApiKeys) -> Self {
+        self.state.keys = Some(KeyStore::new(keys));
+        self
+    }
+
+    /// Allows browsers on the given origins to call the server.
+    ///
+    /// # Parameters
+    /// - `origins`: Origins such as "https://app.example", or "*" for any origin.
+    pub fn with_cors(mut self, origins: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.cors_origins = origins.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Builds the router of the server, e.g. to nest it in a larger application.
+    ///
+    /// # Returns
+    /// - `Router`: The router serving the Torznab and JSON APIs.
+    pub fn router(self) -> Router {
+        let cors = self.cors_layer();
+        let state = Arc::new(self.state);
+
+        let protected = Router::new()
+            .route("/api", get(torznab::handle))
+            .route("/search", get(rest::search_get).post(rest::search_post))
+            .route("/search/stream", get(rest::search_stream))
+            .route("/providers", get(rest::providers))
+            .route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::authenticate,
+            ));
+        let router = Router::new()
+            .route("/health", get(rest::health))
+            .route("/openapi.json", get(rest::openapi))
+            .merge(protected)
+            .with_state(state);
+
+        match cors {
+            Some(cors) => router.layer(cors),
+            None => router,
+        }
+    }
+
+    /// Builds the CORS layer of the allowed origins, if any; invalid origins are skipped.
+    fn cors_layer(&self) -> Option<CorsLayer> {
+        if self.cors_origins.is_empty() {
+            return None;
+        }
+
+        let allow_origin = if self.cors_origins.iter().any(|origin| origin == "*") {
+            AllowOrigin::any()
+        } else {
+            AllowOrigin::list(self.cors_origins.iter().filter_map(|origin| {
+                HeaderValue::from_str(origin)
+                    .map_err(|_| warn!("skipping invalid cors origin '{}'", origin))
+                    .ok()
+            }))
+        };
+
+        Some(
+            CorsLayer::new()
+                .allow_origin(allow_origin)
+                .allow_methods([Method::GET, Method::POST])
+                .allow_headers([
+                    header::CONTENT_TYPE,
+                    header::AUTHORIZATION,
+                    HeaderName::from_static("x-api-key"),
+                ]),
+        )
+    }
+
+    /// Serves requests on the listener until the task is cancelled.
+    ///
+    /// # Parameters
+    /// - `listener`: The bound listener to accept connections on.
+    ///
+    /// # Returns
+    /// - `Err(io::Error)`: An error if accepting connections fails.
+    pub async fn serve(self, listener: TcpListener) -> io::Result<()> {
+        axum::serve(listener, self.router()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testkit::MockProvider, Category};
+    use mockito::Server as MockServer;
+
+    /// Tests that keys, their restrictions and CORS are enforced on every endpoint.
+    #[tokio::test]
+    async fn test_server_keys() {
+        let mut provider = MockServer::new_async().await;
+        provider
+            .mock("GET", "/search?q=ubuntu")
+            .with_body("[]")
+            .expect_at_least(1)
+            .create_async()
+            .await;
+
+        let keys = ApiKeys::new(vec![
+            ApiKey::new("limited", "limited")
+                .with_limits(Some(2), None)
+                .with_blocked_categories(vec![Category::Xxx]),
+            ApiKey::new("restricted", "restricted")
+                .with_providers(vec!["https://apibay.org/q.php".to_string()]),
+        ])
+        .unwrap();
+        let magneto = Magneto::default().add_provider(Box::new(MockProvider::new(provider.url())));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = Server::new(magneto)
+            .with_api_keys(keys)
+            .with_cors(["https://app.example"]);
+        tokio::spawn(server.serve(listener));
+        let client = reqwest::Client::new();
+        let get = |path: &str| client.get(format!("{}{}", url, path));
+
+        let status = |response: reqwest::Response| response.status().as_u16();
+        assert_eq!(status(get("/health").send().await.unwrap()), 200);
+        assert_eq!(status(get("/openapi.json").send().await.unwrap()), 200);
+        assert_eq!(status(get("/providers").send().await.unwrap()), 401);
+        let response = get("/search?query=ubuntu")
+            .header("x-api-key", "wrong")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(status(response), 401);
+
+        let torznab = get("/api?t=caps&apikey=wrong")
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(torznab.contains(r#"<error code="100""#), "{}", torznab);
+
+        // The restricted key only sees its providers
+        let response = get(&format!(
+            "/search?query=ubuntu&providers={}",
+            provider.url()
+        ))
+        .bearer_auth("restricted")
+        .send()
+        .await
+        .unwrap();
+        assert_eq!(status(response), 403);
+        let providers = get("/providers")
+            .header("x-api-key", "restricted")
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(providers, "[]");
+
+        // The limited key cannot search blocked categories, nor exceed its limit
+        let response = get("/search?query=ubuntu&categories=xxx&apikey=limited")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(status(response), 403);
+        let response = get("/search?query=ubuntu&apikey=limited")
+            .header("origin", "https://app.example")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()["access-control-allow-origin"],
+            "https://app.example"
+        );
+        assert_eq!(status(response), 200);
+        let response = get("/search?query=ubuntu&apikey=limited")
+            .send()
+            .await
+            .unwrap();
+        assert!(response.headers().contains_key("retry-after"));
+        assert_eq!(status(response), 429);
+    }
+}
@@ -0,0 +1,902 @@
+//! # REST
+//!
+//! A JSON API for clients that do not embed the crate:
+//!
+//! | Endpoint | Description |
+//! |---|---|
+//! | `POST /search` | Searches with a JSON `SearchQuery` body |
+//! | `GET /search` | Searches with the `SearchQuery` fields as query parameters |
+//! | `GET /search/stream` | Like `GET /search`, streaming results as server-sent events |
+//! | `GET /providers` | The providers and the outcome of their last search |
+//! | `GET /health` | Reports that the server is up |
+//! | `GET /openapi.json` | The OpenAPI description of the API |
+//!
+//! Query parameters take lists comma-separated, e.g.
+//! `GET /search?query=ubuntu&categories=software&min_size=1GB`.
+//!
+//! Providers are searched concurrently, and a failing provider does not fail
+//! the others: every response reports the status of each provider. Searches
+//! where every provider failed are answered with `502 Bad Gateway`.
+//!
+//! The stream sends a `provider` event as each provider answers, with its
+//! status and filtered results, then a `done` event with the ranked results.
+//!
+//! Requests with an API key only search the providers and categories the key
+//! allows; explicitly requesting others is answered with `403 Forbidden`.
+
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    extract::{
+        rejection::{JsonRejection, QueryRejection},
+        Query, State,
+    },
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Extension, Json,
+};
+use futures_util::{
+    stream::{self, FuturesUnordered},
+    Stream, StreamExt,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+use super::{ApiKey, ServerState};
+use crate::{
+    config::{self, split_list},
+    filters::{parse_resolution, RESOLUTIONS},
+    size::parse_size,
+    Category, OrderBy, SearchFilters, Torrent,
+};
+
+/// The maximum number of results of a search, larger requests are clamped to it.
+pub(crate) const MAX_RESULTS: usize = 1000;
+
+/// Represents errors answering a request, sent as `{"error": "..."}`.
+#[derive(Error, Debug)]
+pub enum ApiError {
+    /// The request is malformed or has invalid values.
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+
+    /// The request names a provider that is not active.
+    #[error("unknown provider: {0}")]
+    UnknownProvider(String),
+
+    /// The API key may not search a requested provider or category.
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+}
+
+impl IntoResponse for ApiError {
+    /// Answers `403 Forbidden` for forbidden requests, and `400 Bad Request` otherwise.
+    fn into_response(self) -> Response {
+        let status = match self {
+            ApiError::InvalidRequest(_) | ApiError::UnknownProvider(_) => StatusCode::BAD_REQUEST,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+        };
+        (status, Json(json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::InvalidRequest(rejection.body_text())
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError::InvalidRequest(rejection.body_text())
+    }
+}
+
+/// A search, the body of `POST /search`.
+///
+/// Omitted fields take the defaults of the server's `Magneto` instance.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SearchQuery {
+    /// The query string to search for.
+    pub query: String,
+
+    /// Categories to filter results by.
+    #[serde(default)]
+    pub categories: Vec<Category>,
+
+    /// The order by which results are sorted.
+    #[serde(default)]
+    pub order_by: Option<OrderBy>,
+
+    /// The maximum number of results, clamped to 1000.
+    #[serde(default)]
+    pub number_of_results: Option<usize>,
+
+    /// The ids of the providers to search; empty searches every provider.
+    #[serde(default)]
+    pub providers: Vec<String>,
+
+    /// Filters applied to the results of every provider.
+    #[serde(default)]
+    pub filters: SearchFilters,
+}
+
+impl SearchQuery {
+    /// Returns the requested number of results, clamped to `MAX_RESULTS`.
+    fn number_of_results(&self) -> Option<usize> {
+        self.number_of_results.map(|count| count.min(MAX_RESULTS))
+    }
+}
+
+/// The query parameters of `GET /search`, with lists comma-separated and sizes like "700MB".
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct SearchParams {
+    query: String,
+    categories: Option<String>,
+    order_by: Option<String>,
+    number_of_results: Option<usize>,
+    providers: Option<String>,
+    min_seeders: Option<u32>,
+    min_size: Option<String>,
+    max_size: Option<String>,
+    resolutions: Option<String>,
+    excludes: Option<String>,
+    /// The API key, read by `auth::authenticate`.
+    #[allow(dead_code)]
+    apikey: Option<String>,
+}
+
+impl TryFrom<SearchParams> for SearchQuery {
+    type Error = ApiError;
+
+    fn try_from(params: SearchParams) -> Result<Self, Self::Error> {
+        let size = |name: &str, value: Option<String>| {
+            value
+                .map(|value| {
+                    parse_size(&value).map_err(|e| {
+                        ApiError::InvalidRequest(format!("{} '{}': {}", name, value, e))
+                    })
+                })
+                .transpose()
+        };
+
+        Ok(Self {
+            query: params.query,
+            categories: split(params.categories.as_deref())
+                .iter()
+                .map(|name| parse_enum("category", name))
+                .collect::<Result<_, _>>()?,
+            order_by: params
+                .order_by
+                .as_deref()
+                .map(|name| parse_enum("order_by", name))
+                .transpose()?,
+            number_of_results: params.number_of_results,
+            providers: split(params.providers.as_deref()),
+            filters: SearchFilters {
+                min_seeders: params.min_seeders,
+                min_size: size("min_size", params.min_size)?,
+                max_size: size("max_size", params.max_size)?,
+                resolutions: split(params.resolutions.as_deref())
+                    .iter()
+                    .map(|name| parse_resolution(name).map_err(ApiError::InvalidRequest))
+                    .collect::<Result<_, _>>()?,
+                excludes: split(params.excludes.as_deref()),
+            },
+        })
+    }
+}
+
+/// The response of a search.
+#[derive(Serialize, Debug, Clone)]
+pub struct SearchResponse {
+    /// The ranked results of every provider.
+    pub torrents: Vec<Torrent>,
+
+    /// The status of each searched provider, in the order they answered.
+    pub providers: Vec<ProviderStatus>,
+}
+
+/// The outcome of searching a provider.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ProviderStatus {
+    /// The id of the provider.
+    pub id: String,
+
+    /// Whether the search succeeded.
+    pub ok: bool,
+
+    /// The number of results passing the filters.
+    pub results: usize,
+
+    /// Why the search failed.
+    pub error: Option<String>,
+
+    /// How long the search took, in milliseconds.
+    pub elapsed_ms: u64,
+}
+
+/// The health of a provider, judged by its last search.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    /// The provider has not been searched yet.
+    Unknown,
+
+    /// The last search succeeded.
+    Up,
+
+    /// The last search failed.
+    Down,
+}
+
+/// An entry of `GET /providers`.
+#[derive(Serialize, Debug, Clone)]
+pub struct ProviderHealth {
+    /// The id of the provider.
+    pub id: String,
+
+    /// The health of the provider.
+    pub status: HealthStatus,
+
+    /// When the provider was last searched, in seconds since the Unix epoch.
+    pub last_searched_at: Option<u64>,
+
+    /// The outcome of the last search.
+    pub last_search: Option<ProviderStatus>,
+}
+
+/// The last search of a provider, kept for `GET /providers`.
+#[derive(Debug, Clone)]
+pub(crate) struct LastSearch {
+    /// When the search finished.
+    at: SystemTime,
+
+    /// The outcome of the search.
+    status: ProviderStatus,
+}
+
+/// The API key of a request, added by `auth::authenticate` if the server has keys.
+type RequestKey = Option<Extension<Arc<ApiKey>>>;
+
+/// Handles `GET /search`.
+pub(crate) async fn search_get(
+    State(state): State<Arc<ServerState>>,
+    key: RequestKey,
+    params: Result<Query<SearchParams>, QueryRejection>,
+) -> Result<Response, ApiError> {
+    let Query(params) = params?;
+    respond(&state, &params.try_into()?, key.as_deref().map(Arc::as_ref)).await
+}
+
+/// Handles `POST /search`.
+pub(crate) async fn search_post(
+    State(state): State<Arc<ServerState>>,
+    key: RequestKey,
+    query: Result<Json<SearchQuery>, JsonRejection>,
+) -> Result<Response, ApiError> {
+    let Json(query) = query?;
+    respond(&state, &query, key.as_deref().map(Arc::as_ref)).await
+}
+
+/// Handles `GET /search/stream`.
+pub(crate) async fn search_stream(
+    State(state): State<Arc<ServerState>>,
+    key: RequestKey,
+    params: Result<Query<SearchParams>, QueryRejection>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let Query(params) = params?;
+    let query: SearchQuery = params.try_into()?;
+    let key = key.map(|Extension(key)| key);
+    validate(&state, &query, key.as_deref())?;
+
+    let (events, receiver) = mpsc::channel(16);
+    tokio::spawn(async move {
+        let response = run_search(&state, &query, key.as_deref(), Some(&events)).await;
+        let _ = events.send(event("done", &response)).await;
+    });
+
+    let stream = stream::unfold(receiver, |mut receiver| async move {
+        let event = receiver.recv().await?;
+        Some((Ok(event), receiver))
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Handles `GET /providers`, listing the providers the API key allows.
+pub(crate) async fn providers(
+    State(state): State<Arc<ServerState>>,
+    key: RequestKey,
+) -> Json<Vec<ProviderHealth>> {
+    let last_searches = state
+        .last_searches
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+
+    let providers = state
+        .magneto
+        .active_providers
+        .iter()
+        .map(|provider| provider.id())
+        .filter(|id| key.as_ref().is_none_or(|key| key.allows_provider(id)))
+        .map(|id| {
+            let last = last_searches.get(&id);
+            ProviderHealth {
+                status: match last {
+                    None => HealthStatus::Unknown,
+                    Some(last) if last.status.ok => HealthStatus::Up,
+                    Some(_) => HealthStatus::Down,
+                },
+                last_searched_at: last.map(|last| {
+                    last.at
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs()
+                }),
+                last_search: last.map(|last| last.status.clone()),
+                id,
+            }
+        })
+        .collect();
+
+    Json(providers)
+}
+
+/// Handles `GET /health`.
+pub(crate) async fn health(State(state): State<Arc<ServerState>>) -> Json<Value> {
+    Json(json!({
+        "status": "ok",
+        "version": env!("CARGO_PKG_VERSION"),
+        "providers": state.magneto.active_providers.len(),
+    }))
+}
+
+/// Handles `GET /openapi.json`.
+pub(crate) async fn openapi(State(state): State<Arc<ServerState>>) -> Json<Value> {
+    Json(openapi_document(&state.title))
+}
+
+/// Searches and answers with the JSON response.
+async fn respond(
+    state: &ServerState,
+    query: &SearchQuery,
+    key: Option<&ApiKey>,
+) -> Result<Response, ApiError> {
+    validate(state, query, key)?;
+    let response = run_search(state, query, key, None).await;
+
+    let status = if response.providers.iter().all(|provider| !provider.ok)
+        && !response.providers.is_empty()
+    {
+        StatusCode::BAD_GATEWAY
+    } else {
+        StatusCode::OK
+    };
+    Ok((status, Json(response)).into_response())
+}
+
+/// Checks that the query is not empty, and names active providers and categories the key allows.
+fn validate(
+    state: &ServerState,
+    query: &SearchQuery,
+    key: Option<&ApiKey>,
+) -> Result<(), ApiError> {
+    if query.query.trim().is_empty() {
+        return Err(ApiError::InvalidRequest("query is empty".to_string()));
+    }
+
+    let ids: Vec<String> = state
+        .magneto
+        .active_providers
+        .iter()
+        .map(|provider| provider.id())
+        .collect();
+    if let Some(id) = query.providers.iter().find(|id| !ids.contains(id)) {
+        return Err(ApiError::UnknownProvider(id.clone()));
+    }
+
+    let Some(key) = key else {
+        return Ok(());
+    };
+    if let Some(id) = query.providers.iter().find(|id| !key.allows_provider(id)) {
+        return Err(ApiError::Forbidden(format!("provider {}", id)));
+    }
+    if let Some(category) = query
+        .categories
+        .iter()
+        .find(|category| !key.allows_category(category))
+    {
+        return Err(ApiError::Forbidden(format!("category {:?}", category)));
+    }
+    Ok(())
+}
+
+/// Searches the selected providers concurrently, sending a `provider` event as each answers.
+///
+/// Only the providers and categories the key allows are searched.
+pub(crate) async fn run_search(
+    state: &ServerState,
+    query: &SearchQuery,
+    key: Option<&ApiKey>,
+    events: Option<&mpsc::Sender<Event>>,
+) -> SearchResponse {
+    let magneto = &state.magneto;
+    let mut request = magneto.request(&query.query);
+    if !query.categories.is_empty() {
+        request.categories = query.categories.clone();
+    }
+    if let Some(key) = key {
+        request.categories = key.categories(&request.categories);
+        if request.categories.is_empty() && !key.blocked_categories.is_empty() {
+            // Every requested category is blocked, while no categories would search them all
+            return SearchResponse {
+                torrents: Vec::new(),
+                providers: Vec::new(),
+            };
+        }
+    }
+    if let Some(order_by) = &query.order_by {
+        request.order_by = order_by.clone();
+    }
+    if let Some(number_of_results) = query.number_of_results() {
+        request.number_of_results = number_of_results;
+    }
+
+    let mut searches: FuturesUnordered<_> = magneto
+        .active_providers
+        .iter()
+        .filter(|provider| {
+            let id = provider.id();
+            (query.providers.is_empty() || query.providers.contains(&id))
+                && key.is_none_or(|key| key.allows_provider(&id))
+        })
+        .map(|provider| {
+            let request = request.clone();
+            async move {
+                let started = Instant::now();
+                let result = provider
+                    .send_request(magneto.transport.as_ref(), request)
+                    .await;
+                (provider.id(), result, started.elapsed())
+            }
+        })
+        .collect();
+
+    let mut torrents = Vec::new();
+    let mut statuses = Vec::new();
+    while let Some((id, result, elapsed)) = searches.next().await {
+        let elapsed_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+        let (status, found) = match result {
+            Ok(found) => {
+                let found: Vec<Torrent> = found
+                    .into_iter()
+                    .filter(|torrent| query.filters.accepts(torrent))
+                    .collect();
+                let status = ProviderStatus {
+                    id,
+                    ok: true,
+                    results: found.len(),
+                    error: None,
+                    elapsed_ms,
+                };
+                (status, found)
+            }
+            Err(e) => {
+                let status = ProviderStatus {
+                    id,
+                    ok: false,
+                    results: 0,
+                    error: Some(e.to_string()),
+                    elapsed_ms,
+                };
+                (status, Vec::new())
+            }
+        };
+
+        state
+            .last_searches
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(
+                status.id.clone(),
+                LastSearch {
+                    at: SystemTime::now(),
+                    status: status.clone(),
+                },
+            );
+        if let Some(events) = events {
+            let data = json!({ "status": status, "torrents": found });
+            let _ = events.send(event("provider", &data)).await;
+        }
+
+        torrents.extend(found);
+        statuses.push(status);
+    }
+    drop(searches);
+
+    SearchResponse {
+        torrents: magneto.rank(torrents, &request).await,
+        providers: statuses,
+    }
+}
+
+/// Builds a server-sent event with a JSON payload.
+fn event(name: &str, data: &impl Serialize) -> Event {
+    Event::default()
+        .event(name)
+        .json_data(data)
+        .unwrap_or_else(|e| Event::default().event("error").data(e.to_string()))
+}
+
+/// Splits a comma-separated query parameter.
+fn split(list: Option<&str>) -> Vec<String> {
+    split_list(list.unwrap_or_default())
+}
+
+/// Parses an enum from its snake case name, e.g. "tv_shows".
+fn parse_enum<T: for<'de> Deserialize<'de>>(field: &str, name: &str) -> Result<T, ApiError> {
+    config::parse_enum(name)
+        .map_err(|_| ApiError::InvalidRequest(format!("invalid {} '{}'", field, name)))
+}
+
+/// Builds the OpenAPI description of the API.
+fn openapi_document(title: &str) -> Value {
+    let resolutions: Vec<String> = RESOLUTIONS.iter().map(|r| r.to_string()).collect();
+    let param = |name: &str, schema: Value, description: &str| json!({ "name": name, "in": "query", "schema": schema, "description": description });
+    let string = json!({ "type": "string" });
+    let count = json!({ "type": "integer", "minimum": 0 });
+    let search_params = json!([
+        param("query", string.clone(), "The query string to search for"),
+        param("categories", string.clone(), "Comma-separated categories"),
+        param(
+            "order_by",
+            json!({ "$ref": "#/components/schemas/OrderBy" }),
+            "The order of the results"
+        ),
+        param(
+            "number_of_results",
+            count.clone(),
+            "The maximum number of results, at most 1000"
+        ),
+        param("providers", string.clone(), "Comma-separated provider ids"),
+        param(
+            "min_seeders",
+            count.clone(),
+            "The minimum number of seeders"
+        ),
+        param("min_size", string.clone(), "The minimum size, e.g. 700MB"),
+        param("max_size", string.clone(), "The maximum size, e.g. 4.5GiB"),
+        param(
+            "resolutions",
+            string.clone(),
+            "Comma-separated resolutions, e.g. 1080p"
+        ),
+        param(
+            "excludes",
+            string,
+            "Comma-separated words rejected in names"
+        ),
+    ]);
+    let json_response = |description: &str, schema: &str| {
+        json!({
+            "description": description,
+            "content": { "application/json": { "schema": { "$ref": format!("#/components/schemas/{}", schema) } } }
+        })
+    };
+    let search_responses = json!({
+        "200": json_response("The ranked results and the status of each provider", "SearchResponse"),
+        "400": json_response("The request is invalid", "Error"),
+        "401": json_response("The API key is missing or invalid", "Error"),
+        "403": json_response("The API key may not search a provider or category", "Error"),
+        "429": json_response("The API key reached its request limit", "Error"),
+        "502": json_response("Every provider failed", "SearchResponse"),
+    });
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": title,
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Searches torrents across multiple providers.",
+        },
+        // Keys are only required when the server has some
+        "security": [{}, { "ApiKeyHeader": [] }, { "ApiKeyQuery": [] }, { "Bearer": [] }],
+        "paths": {
+            "/search": {
+                "get": {
+                    "summary": "Search torrents",
+                    "parameters": search_params,
+                    "responses": search_responses,
+                },
+                "post": {
+                    "summary": "Search torrents",
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": { "$ref": "#/components/schemas/SearchQuery" } } },
+                    },
+                    "responses": search_responses,
+                },
+            },
+            "/search/stream": {
+                "get": {
+                    "summary": "Search torrents, streaming results as server-sent events",
+                    "description": "Sends a `provider` event with a status and torrents as each provider answers, then a `done` event with a SearchResponse.",
+                    "parameters": search_params,
+                    "responses": {
+                        "200": { "description": "The event stream", "content": { "text/event-stream": { "schema": { "type": "string" } } } },
+                        "400": json_response("The request is invalid", "Error"),
+                    },
+                },
+            },
+            "/providers": {
+                "get": {
+                    "summary": "List the providers and the outcome of their last search",
+                    "responses": {
+                        "200": {
+                            "description": "The providers",
+                            "content": { "application/json": { "schema": { "type": "array", "items": { "$ref": "#/components/schemas/ProviderHealth" } } } },
+                        },
+                    },
+                },
+            },
+            "/health": {
+                "get": {
+                    "summary": "Report that the server is up",
+                    "security": [],
+                    "responses": { "200": { "description": "The server is up" } },
+                },
+            },
+        },
+        "components": {
+            "securitySchemes": {
+                "ApiKeyHeader": { "type": "apiKey", "in": "header", "name": "X-Api-Key" },
+                "ApiKeyQuery": { "type": "apiKey", "in": "query", "name": "apikey" },
+                "Bearer": { "type": "http", "scheme": "bearer" },
+            },
+            "schemas": {
+                "Category": {
+                    "type": "string",
+                    "enum": ["movies", "tv_shows", "games", "software", "audio", "anime", "xxx"],
+                },
+                "OrderBy": { "type": "string", "enum": ["seeders", "peers", "profile_score"] },
+                "SearchQuery": {
+                    "type": "object",
+                    "required": ["query"],
+                    "properties": {
+                        "query": { "type": "string" },
+                        "categories": { "type": "array", "items": { "$ref": "#/components/schemas/Category" } },
+                        "order_by": { "$ref": "#/components/schemas/OrderBy" },
+                        "number_of_results": { "type": "integer", "minimum": 0, "maximum": MAX_RESULTS },
+                        "providers": { "type": "array", "items": { "type": "string" } },
+                        "filters": {
+                            "type": "object",
+                            "properties": {
+                                "min_seeders": { "type": "integer", "minimum": 0 },
+                                "min_size": { "type": "integer", "minimum": 0 },
+                                "max_size": { "type": "integer", "minimum": 0 },
+                                "resolutions": { "type": "array", "items": { "type": "string", "enum": resolutions } },
+                                "excludes": { "type": "array", "items": { "type": "string" } },
+                            },
+                        },
+                    },
+                },
+                "Torrent": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "magnet_link": { "type": "string" },
+                        "seeders": { "type": "integer" },
+                        "peers": { "type": "integer" },
+                        "size_bytes": { "type": "integer" },
+                        "provider": { "type": "string" },
+                        "metadata": { "type": "object" },
+                        "stats_updated_at": { "type": "object", "nullable": true },
+                    },
+                },
+                "ProviderStatus": {
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "string" },
+                        "ok": { "type": "boolean" },
+                        "results": { "type": "integer" },
+                        "error": { "type": "string", "nullable": true },
+                        "elapsed_ms": { "type": "integer" },
+                    },
+                },
+                "ProviderHealth": {
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "string" },
+                        "status": { "type": "string", "enum": ["unknown", "up", "down"] },
+                        "last_searched_at": { "type": "integer", "nullable": true },
+                        "last_search": { "$ref": "#/components/schemas/ProviderStatus" },
+                    },
+                },
+                "SearchResponse": {
+                    "type": "object",
+                    "properties": {
+                        "torrents": { "type": "array", "items": { "$ref": "#/components/schemas/Torrent" } },
+                        "providers": { "type": "array", "items": { "$ref": "#/components/schemas/ProviderStatus" } },
+                    },
+                },
+                "Error": {
+                    "type": "object",
+                    "properties": { "error": { "type": "string" } },
+                },
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{server::Server, testkit::MockProvider, Magneto};
+    use mockito::Server as MockServer;
+    use tokio::net::TcpListener;
+
+    /// Tests that query parameters are parsed like the JSON body.
+    #[test]
+    fn test_search_params() {
+        let params: SearchParams = serde_urlencoded::from_str(
+            "query=ubuntu&categories=software,tv_shows&order_by=peers&number_of_results=5\
+             &providers=a,b&min_size=1KB&resolutions=1080p&excludes=cam",
+        )
+        .unwrap();
+        let query = SearchQuery::try_from(params).unwrap();
+
+        let json: SearchQuery = serde_json::from_str(
+            r#"{"query": "ubuntu", "categories": ["software", "tv_shows"], "order_by": "peers",
+                "number_of_results": 5, "providers": ["a", "b"],
+                "filters": {"min_size": 1000, "resolutions": ["1080p"], "excludes": ["cam"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(query, json);
+        assert_eq!(query.number_of_results(), Some(5));
+        let huge = SearchQuery {
+            number_of_results: Some(usize::MAX),
+            ..json
+        };
+        assert_eq!(huge.number_of_results(), Some(MAX_RESULTS));
+
+        let torrent = |name: &str, size_bytes| Torrent {
+            name: name.to_string(),
+            magnet_link: "magnet:?xt=urn:btih:abc".to_string(),
+            seeders: 1,
+            peers: 1,
+            size_bytes,
+            provider: "mock".to_string(),
+            metadata: Default::default(),
+            stats_updated_at: None,
+        };
+        assert!(query.filters.accepts(&torrent("Show.1080p.WEB", 2000)));
+        assert!(!query.filters.accepts(&torrent("Show.1080p.WEB", 10)));
+        assert!(!query.filters.accepts(&torrent("Show.720p.WEB", 2000)));
+        assert!(!query.filters.accepts(&torrent("Show.1080p.CAM", 2000)));
+
+        for invalid in [
+            "categories=books",
+            "order_by=size",
+            "min_size=huge",
+            "resolutions=8k",
+        ] {
+            let params: SearchParams =
+                serde_urlencoded::from_str(&format!("query=a&{}", invalid)).unwrap();
+            assert!(SearchQuery::try_from(params).is_err(), "{}", invalid);
+        }
+    }
+
+    /// Sends a request and returns the status and JSON body of the response.
+    async fn send(request: reqwest::RequestBuilder) -> (StatusCode, Value) {
+        let response = request.send().await.unwrap();
+        let status = StatusCode::from_u16(response.status().as_u16()).unwrap();
+        let body = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+        (status, body)
+    }
+
+    /// Tests the endpoints against a local listener, with a working and a failing provider.
+    #[tokio::test]
+    async fn test_rest_endpoints() {
+        let mut working = MockServer::new_async().await;
+        working
+            .mock("GET", "/search?q=ubuntu")
+            .with_body(
+                r#"[{"name": "Ubuntu 24.04", "magnet_link": "magnet:?xt=urn:btih:abc",
+                     "seeders": 10, "peers": 1, "size_bytes": 5000, "provider": "working"},
+                    {"name": "Ubuntu 22.04", "magnet_link": "magnet:?xt=urn:btih:def",
+                     "seeders": 20, "peers": 1, "size_bytes": 500, "provider": "working"}]"#,
+            )
+            .expect_at_least(1)
+            .create_async()
+            .await;
+        let mut failing = MockServer::new_async().await;
+        failing
+            .mock("GET", "/search?q=ubuntu")
+            .with_status(500)
+            .create_async()
+            .await;
+
+        let magneto = Magneto::default()
+            .add_provider(Box::new(MockProvider::new(working.url())))
+            .add_provider(Box::new(MockProvider::new(failing.url())));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(Server::new(magneto).serve(listener));
+        let client = reqwest::Client::new();
+        let get = |path: String| client.get(format!("{}{}", url, path));
+
+        let (_, providers) = send(get("/providers".into())).await;
+        assert_eq!(providers[0]["status"], "unknown");
+
+        let (status, body) = send(
+            client
+                .post(format!("{}/search", url))
+                .header("content-type", "application/json")
+                .body(r#"{"query": "ubuntu", "filters": {"min_size": 1000}}"#),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["torrents"].as_array().unwrap().len(), 1);
+        assert_eq!(body["torrents"][0]["name"], "Ubuntu 24.04");
+        let statuses = body["providers"].as_array().unwrap();
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(statuses.iter().filter(|s| s["ok"] == true).count(), 1);
+
+        // Sorted by seeders by default
+        let (_, body) = send(get("/search?query=ubuntu".into())).await;
+        assert_eq!(body["torrents"][0]["name"], "Ubuntu 22.04");
+
+        let (status, _) = send(get(format!(
+            "/search?query=ubuntu&providers={}",
+            failing.url()
+        )))
+        .await;
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+
+        for invalid in ["query=", "query=a&providers=nope", "query=a&limit=1"] {
+            let (status, body) = send(get(format!("/search?{}", invalid))).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", invalid);
+            assert!(body["error"].is_string());
+        }
+
+        let (_, providers) = send(get("/providers".into())).await;
+        let statuses: Vec<&Value> = providers
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|provider| &provider["status"])
+            .collect();
+        assert_eq!(statuses, vec!["up", "down"]);
+
+        let stream = get("/search/stream?query=ubuntu".into())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            stream.headers()["content-type"].to_str().unwrap(),
+            "text/event-stream"
+        );
+        let stream = stream.text().await.unwrap();
+        assert_eq!(stream.matches("event: provider").count(), 2);
+        assert!(stream.contains("event: done"));
+        assert!(stream.find("event: done") > stream.rfind("event: provider"));
+
+        let (_, health) = send(get("/health".into())).await;
+        assert_eq!(health["status"], "ok");
+
+        let (_, openapi) = send(get("/openapi.json".into())).await;
+        assert_eq!(openapi["openapi"], "3.0.3");
+        assert!(openapi["paths"]["/search/stream"]["get"].is_object());
+    }
+}
@@ -0,0 +1,656 @@
+//! # Torznab
+//!
+//! Implements the Torznab indexer API on `GET /api`:
+//!
+//! | Function | Parameters |
+//! |---|---|
+//! | `t=caps` | |
+//! | `t=search` | `q`, `cat`, `limit`, `offset` |
+//! | `t=tvsearch` | `q`, `season`, `ep`, `imdbid`, `cat`, `limit`, `offset` |
+//! | `t=movie` | `q`, `imdbid`, `cat`, `limit`, `offset` |
+//!
+//! `cat` takes comma-separated Newznab category ids, mapped with
+//! `Category::from_newznab_id`. Searches without a query, as sent by clients
+//! polling for recent releases, return an empty feed since providers cannot
+//! list recent torrents. Errors are reported as Torznab `<error>` documents.
+//!
+//! Providers are searched like `GET /search` of the `rest` module: failing
+//! providers are left out, and only a search where every provider failed is
+//! an error. Requests with an API key only search the providers the key
+//! allows, and blocked categories are left out.
+
+use std::{collections::HashMap, fmt::Write, sync::Arc, time::SystemTime};
+
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use thiserror::Error;
+
+use super::{
+    auth::AuthError,
+    rest::{self, SearchQuery},
+    ApiKey, ServerState,
+};
+use crate::{Category, Torrent};
+
+/// The maximum number of results of a search.
+const MAX_LIMIT: usize = 100;
+
+/// The maximum offset of a search, larger offsets are clamped to it.
+const MAX_OFFSET: usize = rest::MAX_RESULTS - MAX_LIMIT;
+
+/// A Newznab category id and name.
+type CapsCategory = (u32, &'static str);
+
+/// The categories advertised in the capabilities, with their subcategories.
+const CAPS_CATEGORIES: [(u32, &str, &[CapsCategory]); 6] = [
+    (1000, "Console", &[]),
+    (2000, "Movies", &[]),
+    (3000, "Audio", &[]),
+    (4000, "PC", &[(4050, "PC/Games")]),
+    (5000, "TV", &[(5070, "TV/Anime")]),
+    (6000, "XXX", &[]),
+];
+
+/// Represents errors reported to Torznab clients.
+#[derive(Error, Debug)]
+pub enum TorznabError {
+    /// A required parameter is missing.
+    #[error("missing parameter: {0}")]
+    MissingParameter(&'static str),
+
+    /// A parameter has an invalid value.
+    #[error("incorrect parameter {name}: {value}")]
+    IncorrectParameter {
+        /// The name of the parameter.
+        name: &'static str,
+        /// The value of the parameter.
+        value: String,
+    },
+
+    /// The requested function is not implemented.
+    #[error("no such function: {0}")]
+    NoSuchFunction(String),
+
+    /// The request has no valid API key.
+    #[error("incorrect user credentials")]
+    IncorrectCredentials,
+
+    /// The API key sent too many requests.
+    #[error("request limit reached")]
+    RequestLimitReached,
+
+    /// Every provider failed.
+    #[error("search failed: {0}")]
+    Search(String),
+}
+
+impl TorznabError {
+    /// Returns the Newznab error code of the error.
+    pub fn code(&self) -> u32 {
+        match self {
+            TorznabError::MissingParameter(_) => 200,
+            TorznabError::IncorrectParameter { .. } => 201,
+            TorznabError::NoSuchFunction(_) => 202,
+            TorznabError::IncorrectCredentials => 100,
+            TorznabError::RequestLimitReached => 500,
+            TorznabError::Search(_) => 900,
+        }
+    }
+}
+
+/// The search functions of the API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    /// A free text search, `t=search`.
+    Search,
+
+    /// A TV episode or season search, `t=tvsearch`.
+    TvSearch,
+
+    /// A movie search, `t=movie`.
+    Movie,
+}
+
+/// A search parsed from the query parameters.
+#[derive(Debug, Clone, PartialEq)]
+struct TorznabSearch {
+    /// The function searched with.
+    function: Function,
+
+    /// The query sent to providers, including season and episode.
+    query: String,
+
+    /// The IMDb id results must match, e.g. "tt1375666".
+    imdb_id: Option<String>,
+
+    /// The requested categories, `None` if unrestricted.
+    categories: Option<Vec<Category>>,
+
+    /// The maximum number of results.
+    limit: usize,
+
+    /// The number of results to skip.
+    offset: usize,
+}
+
+impl TorznabSearch {
+    /// Parses the parameters of a search function.
+    fn parse(
+        function: Function,
+        params: &HashMap<String, String>,
+        default_limit: usize,
+    ) -> Result<Self, TorznabError> {
+        let param = |name: &str| {
+            params
+                .get(name)
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+
+        let imdb_id = match function {
+            Function::Search => None,
+            Function::TvSearch | Function::Movie => param("imdbid").map(|id| {
+                let id = id.trim_start_matches("tt");
+                format!("tt{}", id)
+            }),
+        };
+
+        let mut query = param("q").unwrap_or_default().to_string();
+        if function == Function::TvSearch {
+            if let Some(episode) = episode_query(param("season"), param("ep"))? {
+                query = format!("{} {}", query, episode).trim().to_string();
+            }
+        }
+        if param("q").is_none() {
+            if let Some(imdb_id) = &imdb_id {
+                query = format!("{} {}", imdb_id, query).trim().to_string();
+            }
+        }
+
+        let categories = param("cat")
+            .map(|cat| {
+                cat.split(',')
+                    .map(|id| {
+                        id.trim()
+                            .parse::<u32>()
+                            .map_err(|_| TorznabError::IncorrectParameter {
+                                name: "cat",
+                                value: cat.to_string(),
+                            })
+                    })
+                    .try_fold(Vec::new(), |mut categories, id| {
+                        if let Some(category) = Category::from_newznab_id(id?) {
+                            if !categories.contains(&category) {
+                                categories.push(category);
+                            }
+                        }
+                        Ok::<_, TorznabError>(categories)
+                    })
+            })
+            .transpose()?;
+
+        let number = |name: &'static str| {
+            param(name)
+                .map(|value| {
+                    value
+                        .parse::<usize>()
+                        .map_err(|_| TorznabError::IncorrectParameter {
+                            name,
+                            value: value.to_string(),
+                        })
+                })
+                .transpose()
+        };
+
+        Ok(Self {
+            function,
+            query,
+            imdb_id,
+            categories,
+            limit: number("limit")?.unwrap_or(default_limit).min(MAX_LIMIT),
+            offset: number("offset")?.unwrap_or(0).min(MAX_OFFSET),
+        })
+    }
+
+    /// Returns the category reported for results: the only requested category,
+    /// or else the category of the function.
+    fn result_category(&self) -> Option<Category> {
+        match self.categories.as_deref() {
+            Some([category]) => Some(category.clone()),
+            _ => match self.function {
+                Function::Search => None,
+                Function::TvSearch => Some(Category::TvShows),
+                Function::Movie => Some(Category::Movies),
+            },
+        }
+    }
+}
+
+/// Formats the season and episode of a TV search, e.g. "S01E02".
+///
+/// Daily shows are searched by date, with the year as season and "month/day" as episode.
+fn episode_query(season: Option<&str>, ep: Option<&str>) -> Result<Option<String>, TorznabError> {
+    let Some(season) = season else {
+        return Ok(None);
+    };
+    let season_number = season
+        .parse::<u32>()
+        .map_err(|_| TorznabError::IncorrectParameter {
+            name: "season",
+            value: season.to_string(),
+        })?;
+
+    if season_number >= 1000 {
+        return Ok(Some(match ep {
+            Some(ep) => format!("{}.{}", season, ep.replace('/', ".")),
+            None => season.to_string(),
+        }));
+    }
+
+    match ep {
+        Some(ep) => {
+            let episode = ep
+                .parse::<u32>()
+                .map_err(|_| TorznabError::IncorrectParameter {
+                    name: "ep",
+                    value: ep.to_string(),
+                })?;
+            Ok(Some(format!("S{:02}E{:02}", season_number, episode)))
+        }
+        None => Ok(Some(format!("S{:02}", season_number))),
+    }
+}
+
+/// Handles `GET /api`.
+pub(crate) async fn handle(
+    State(state): State<Arc<ServerState>>,
+    key: Option<Extension<Arc<ApiKey>>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    match respond(&state, &params, key.as_deref().map(Arc::as_ref)).await {
+        Ok(response) => response,
+        Err(e) => xml_response("application/xml", error_xml(&e)),
+    }
+}
+
+/// Answers a request rejected by `auth::authenticate` with an error document.
+pub(crate) fn auth_error(error: &AuthError) -> Response {
+    let error = match error {
+        AuthError::MissingKey | AuthError::InvalidKey => TorznabError::IncorrectCredentials,
+        AuthError::LimitReached { .. } => TorznabError::RequestLimitReached,
+    };
+    xml_response("application/xml", error_xml(&error))
+}
+
+/// Answers a Torznab request.
+async fn respond(
+    state: &ServerState,
+    params: &HashMap<String, String>,
+    key: Option<&ApiKey>,
+) -> Result<Response, TorznabError> {
+    let function = match params.get("t").map(String::as_str) {
+        None | Some("") => return Err(TorznabError::MissingParameter("t")),
+        Some("caps") => {
+            let caps = caps_xml(&state.title, state.magneto.defaults.number_of_results);
+            return Ok(xml_response("application/xml", caps));
+        }
+        Some("search") => Function::Search,
+        Some("tvsearch") => Function::TvSearch,
+        Some("movie") => Function::Movie,
+        Some(other) => return Err(TorznabError::NoSuchFunction(other.to_string())),
+    };
+
+    let search = TorznabSearch::parse(function, params, state.magneto.defaults.number_of_results)?;
+    let torrents = run_search(state, &search, key).await?;
+    let feed = feed_xml(&state.title, &torrents, search.result_category().as_ref());
+    Ok(xml_response("application/rss+xml", feed))
+}
+
+/// Searches with the instance, returning the requested page of results.
+async fn run_search(
+    state: &ServerState,
+    search: &TorznabSearch,
+    key: Option<&ApiKey>,
+) -> Result<Vec<Torrent>, TorznabError> {
+    let categories = search.categories.as_ref().map(|categories| {
+        categories
+            .iter()
+            .filter(|category| key.is_none_or(|key| key.allows_category(category)))
+            .cloned()
+            .collect::<Vec<_>>()
+    });
+    if search.query.is_empty() || categories.as_ref().is_some_and(Vec::is_empty) {
+        return Ok(Vec::new());
+    }
+
+    let query = SearchQuery {
+        query: search.query.clone(),
+        categories: categories.unwrap_or_default(),
+        order_by: None,
+        number_of_results: Some(search.offset.saturating_add(search.limit)),
+        providers: Vec::new(),
+        filters: Default::default(),
+    };
+    let response = rest::run_search(state, &query, key, None).await;
+    if !response.providers.is_empty() && response.providers.iter().all(|status| !status.ok) {
+        let errors: Vec<String> = response
+            .providers
+            .into_iter()
+            .filter_map(|status| status.error)
+            .collect();
+        return Err(TorznabError::Search(errors.join("; ")));
+    }
+
+    Ok(response
+        .torrents
+        .into_iter()
+        .filter(
+            |torrent| match (&search.imdb_id, &torrent.metadata.imdb_id) {
+                (Some(expected), Some(actual)) => expected == actual,
+                _ => true,
+            },
+        )
+        .skip(search.offset)
+        .take(search.limit)
+        .collect())
+}
+
+/// Builds an XML response with the given content type.
+fn xml_response(content_type: &str, body: String) -> Response {
+    (
+        [(
+            header::CONTENT_TYPE,
+            format!("{}; charset=utf-8", content_type),
+        )],
+        body,
+    )
+        .into_response()
+}
+
+/// Escapes text for XML content and attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders the capabilities document of `t=caps`.
+fn caps_xml(title: &str, default_limit: usize) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<caps>\n");
+    let _ = writeln!(xml, "  <server title=\"{}\"/>", escape(title));
+    let _ = writeln!(
+        xml,
+        "  <limits default=\"{}\" max=\"{}\"/>",
+        default_limit.min(MAX_LIMIT),
+        MAX_LIMIT
+    );
+    xml.push_str(concat!(
+        "  <searching>\n",
+        "    <search available=\"yes\" supportedParams=\"q\"/>\n",
+        "    <tv-search available=\"yes\" supportedParams=\"q,season,ep,imdbid\"/>\n",
+        "    <movie-search available=\"yes\" supportedParams=\"q,imdbid\"/>\n",
+        "  </searching>\n",
+        "  <categories>\n",
+    ));
+    for (id, name, subcats) in CAPS_CATEGORIES {
+        if subcats.is_empty() {
+            let _ = writeln!(xml, "    <category id=\"{}\" name=\"{}\"/>", id, name);
+            continue;
+        }
+        let _ = writeln!(xml, "    <category id=\"{}\" name=\"{}\">", id, name);
+        for (id, name) in subcats {
+            let _ = writeln!(xml, "      <subcat id=\"{}\" name=\"{}\"/>", id, name);
+        }
+        xml.push_str("    </category>\n");
+    }
+    xml.push_str("  </categories>\n</caps>\n");
+    xml
+}
+
+/// Renders the RSS feed of a search.
+///
+/// Torznab counts seeders as peers, so the `peers` attribute is the sum of
+/// `Torrent::seeders` and `Torrent::peers`.
+fn feed_xml(title: &str, torrents: &[Torrent], category: Option<&Category>) -> String {
+    let pub_date = httpdate::fmt_http_date(SystemTime::now());
+    let mut xml = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\" ",
+        "xmlns:torznab=\"http://torznab.com/schemas/2015/feed\">\n",
+        "<channel>\n",
+    ));
+    let _ = writeln!(xml, "  <title>{}</title>", escape(title));
+
+    for torrent in torrents {
+        let magnet = escape(&torrent.magnet_link);
+        let info_hash = torrent.magnet().ok().and_then(|m| m.info_hash_hex());
+        let attr = |xml: &mut String, name: &str, value: &str| {
+            let _ = writeln!(
+                xml,
+                "    <torznab:attr name=\"{}\" value=\"{}\"/>",
+                name,
+                escape(value)
+            );
+        };
+
+        xml.push_str("  <item>\n");
+        let _ = writeln!(xml, "    <title>{}</title>", escape(&torrent.name));
+        let _ = writeln!(
+            xml,
+            "    <guid>{}</guid>",
+            info_hash.as_deref().map(escape).unwrap_or(magnet.clone())
+        );
+        let _ = writeln!(xml, "    <link>{}</link>", magnet);
+        if let Some(details_url) = &torrent.metadata.details_url {
+            let _ = writeln!(xml, "    <comments>{}</comments>", escape(details_url));
+        }
+        let _ = writeln!(xml, "    <pubDate>{}</pubDate>", pub_date);
+        let _ = writeln!(xml, "    <size>{}</size>", torrent.size_bytes);
+        if let Some(category) = category {
+            let _ = writeln!(xml, "    <category>{}</category>", category.newznab_id());
+        }
+        let _ = writeln!(
+            xml,
+            "    <enclosure url=\"{}\" length=\"{}\" type=\"application/x-bittorrent\"/>",
+            magnet, torrent.size_bytes
+        );
+
+        if let Some(category) = category {
+            attr(&mut xml, "category", &category.newznab_id().to_string());
+        }
+        attr(&mut xml, "size", &torrent.size_bytes.to_string());
+        attr(&mut xml, "seeders", &torrent.seeders.to_string());
+        attr(
+            &mut xml,
+            "peers",
+            &torrent.seeders.saturating_add(torrent.peers).to_string(),
+        );
+        attr(&mut xml, "magneturl", &torrent.magnet_link);
+        if let Some(info_hash) = &info_hash {
+            attr(&mut xml, "infohash", info_hash);
+        }
+        if let Some(imdb_id) = &torrent.metadata.imdb_id {
+            attr(&mut xml, "imdb", imdb_id.trim_start_matches("tt"));
+        }
+        if let Some(file_count) = torrent.metadata.file_count {
+            attr(&mut xml, "files", &file_count.to_string());
+        }
+        if let Some(season) = torrent.metadata.season {
+            attr(&mut xml, "season", &season.to_string());
+        }
+        if let Some(episode) = torrent.metadata.episode {
+            attr(&mut xml, "episode", &episode.to_string());
+        }
+        xml.push_str("  </item>\n");
+    }
+
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+/// Renders the error document of a failed request.
+fn error_xml(error: &TorznabError) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<error code=\"{}\" description=\"{}\"/>\n",
+        error.code(),
+        escape(&error.to_string())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{server::Server, testkit::MockProvider, Magneto};
+    use mockito::{Matcher, Server as MockServer};
+    use tokio::net::TcpListener;
+
+    /// Tests the parsing of search parameters.
+    #[test]
+    fn test_parse_search() {
+        let params = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+
+        let search = TorznabSearch::parse(
+            Function::TvSearch,
+            &params(&[
+                ("q", "Show"),
+                ("season", "1"),
+                ("ep", "2"),
+                ("cat", "5030,5070,7000"),
+            ]),
+            50,
+        )
+        .unwrap();
+        assert_eq!(search.query, "Show S01E02");
+        assert_eq!(
+            search.categories,
+            Some(vec![Category::TvShows, Category::Anime])
+        );
+        assert_eq!(search.result_category(), Some(Category::TvShows));
+        assert_eq!((search.limit, search.offset), (50, 0));
+
+        let search = TorznabSearch::parse(
+            Function::TvSearch,
+            &params(&[("q", "Daily"), ("season", "2024"), ("ep", "10/15")]),
+            50,
+        )
+        .unwrap();
+        assert_eq!(search.query, "Daily 2024.10.15");
+
+        let search = TorznabSearch::parse(
+            Function::Movie,
+            &params(&[("imdbid", "1375666"), ("limit", "500"), ("offset", "10")]),
+            50,
+        )
+        .unwrap();
+        assert_eq!(search.query, "tt1375666");
+        assert_eq!(search.imdb_id.as_deref(), Some("tt1375666"));
+        assert_eq!(search.result_category(), Some(Category::Movies));
+        assert_eq!((search.limit, search.offset), (MAX_LIMIT, 10));
+
+        let search = TorznabSearch::parse(
+            Function::Search,
+            &params(&[("q", "x"), ("offset", &usize::MAX.to_string())]),
+            50,
+        )
+        .unwrap();
+        assert_eq!(search.offset, MAX_OFFSET);
+
+        let error =
+            TorznabSearch::parse(Function::Search, &params(&[("limit", "many")]), 50).unwrap_err();
+        assert_eq!(error.code(), 201);
+        let error = TorznabSearch::parse(Function::TvSearch, &params(&[("season", "one")]), 50)
+            .unwrap_err();
+        assert_eq!(error.code(), 201);
+    }
+
+    /// Tests the endpoint against a local listener.
+    #[tokio::test]
+    async fn test_torznab_endpoint() {
+        let mut provider = MockServer::new_async().await;
+        let mock = provider
+            .mock("GET", "/search")
+            .match_query(Matcher::UrlEncoded("q".into(), "Show S01E02".into()))
+            .with_body(
+                r#"[
+                    {"name": "Show.S01E02.720p", "seeders": 10, "peers": 5, "size_bytes": 1024,
+                     "magnet_link": "magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567&dn=a&b",
+                     "provider": "mock",
+                     "metadata": {"imdb_id": "tt0000001", "season": 1, "episode": 2}},
+                    {"name": "Show.S01E02.1080p", "seeders": 20, "peers": 0, "size_bytes": 2048,
+                     "magnet_link": "magnet:?xt=urn:btih:abc", "provider": "mock"}
+                ]"#,
+            )
+            .create_async()
+            .await;
+        provider
+            .mock("GET", "/search")
+            .match_query(Matcher::UrlEncoded("q".into(), "broken".into()))
+            .with_status(500)
+            .create_async()
+            .await;
+
+        let magneto = Magneto::default().add_provider(Box::new(MockProvider::new(provider.url())));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/api", listener.local_addr().unwrap());
+        tokio::spawn(Server::new(magneto).with_title("test").serve(listener));
+
+        let get = |query: &'static str| {
+            let url = format!("{}?{}", url, query);
+            async move {
+                let response = reqwest::get(url).await.unwrap();
+                assert!(response.status().is_success());
+                response.text().await.unwrap()
+            }
+        };
+
+        let caps = get("t=caps").await;
+        assert!(caps.contains(r#"<server title="test"/>"#));
+        assert!(caps.contains(r#"<tv-search available="yes""#));
+        assert!(caps.contains(r#"<subcat id="5070" name="TV/Anime"/>"#));
+
+        let feed = get("t=tvsearch&q=Show&season=1&ep=2&cat=5000&apikey=key").await;
+        mock.assert_async().await;
+        assert_eq!(feed.matches("<item>").count(), 2);
+        // Sorted by seeders, peers count seeders too
+        assert!(feed.find("1080p").unwrap() < feed.find("720p").unwrap());
+        assert!(feed.contains(r#"<torznab:attr name="seeders" value="10"/>"#));
+        assert!(feed.contains(r#"<torznab:attr name="peers" value="15"/>"#));
+        assert!(feed.contains(r#"<torznab:attr name="category" value="5000"/>"#));
+        assert!(feed.contains(
+            r#"<torznab:attr name="infohash" value="0123456789abcdef0123456789abcdef01234567"/>"#
+        ));
+        assert!(feed.contains(r#"<torznab:attr name="imdb" value="0000001"/>"#));
+        assert!(feed.contains(r#"<torznab:attr name="season" value="1"/>"#));
+        assert!(feed.contains(r#"<torznab:attr name="episode" value="2"/>"#));
+        assert!(feed.contains("dn=a&amp;b"));
+
+        let feed = get("t=search").await;
+        assert!(feed.contains("<channel>"));
+        assert!(!feed.contains("<item>"));
+
+        let feed = get("t=search&q=Show&cat=7000").await;
+        assert!(!feed.contains("<item>"));
+
+        assert!(get("t=search&q=broken").await.contains(r#"code="900""#));
+        assert!(get("t=music").await.contains(r#"code="202""#));
+        assert!(get("q=Show").await.contains(r#"code="200""#));
+    }
+}
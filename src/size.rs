@@ -0,0 +1,201 @@
+//! # Sizes
+//!
+//! Parsing and formatting of human-readable sizes such as "1.2 TB", "850 KB"
+//! or "1.4 GiB". SI units (kB, MB, GB, ...) are powers of 1000 and IEC units
+//! (KiB, MiB, GiB, ...) are powers of 1024. Units are case-insensitive.
+
+use thiserror::Error;
+
+/// SI unit suffixes in ascending order, each 1000 times the previous.
+const SI_UNITS: [&str; 6] = ["B", "kB", "MB", "GB", "TB", "PB"];
+
+/// IEC unit suffixes in ascending order, each 1024 times the previous.
+const IEC_UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+
+/// Represents errors that can occur while parsing a size.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseSizeError {
+    /// The input is empty or only contains whitespace.
+    #[error("cannot parse size from empty string")]
+    Empty,
+
+    /// The numeric part of the size is missing or invalid.
+    #[error("invalid number in size '{0}'")]
+    InvalidNumber(String),
+
+    /// The unit of the size is not recognized.
+    #[error("unknown unit in size '{0}'")]
+    UnknownUnit(String),
+
+    /// The size does not fit in a `u64` number of bytes.
+    #[error("size '{0}' is out of range")]
+    OutOfRange(String),
+}
+
+/// Parses a human-readable size into a number of bytes.
+///
+/// Accepts an optional space between the number and the unit, decimal
+/// numbers, SI units (`k`, `kB`, `MB`, `GB`, `TB`, `PB`), IEC units (`KiB`,
+/// `MiB`, `GiB`, `TiB`, `PiB`) and bytes (`B`, `bytes` or no unit).
+///
+/// # Parameters
+/// - `input`: The size to parse, e.g. "1.5GB" or "850 KiB".
+///
+/// # Returns
+/// - `Ok(u64)`: The size in bytes, rounded to the nearest byte.
+/// - `Err(ParseSizeError)`: An error if the number or unit is invalid.
+///
+/// # Example
+/// ```rust
+/// use magneto::size::parse_size;
+///
+/// assert_eq!(parse_size("1.5GB"), Ok(1_500_000_000));
+/// assert_eq!(parse_size("2 kib"), Ok(2048));
+/// assert!(parse_size("12 parsecs").is_err());
+/// ```
+pub fn parse_size(input: &str) -> Result<u64, ParseSizeError> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(ParseSizeError::Empty);
+    }
+
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+    if number.is_empty() {
+        return Err(ParseSizeError::InvalidNumber(input.to_string()));
+    }
+
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" | "byte" | "bytes" => 1,
+        "k" | "kb" => 1_000,
+        "m" | "mb" => 1_000_000,
+        "g" | "gb" => 1_000_000_000,
+        "t" | "tb" => 1_000_000_000_000,
+        "p" | "pb" => 1_000_000_000_000_000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        "pib" => 1 << 50,
+        _ => return Err(ParseSizeError::UnknownUnit(input.to_string())),
+    };
+
+    // Whole numbers are computed exactly, decimals go through floating point
+    if let Ok(whole) = number.parse::<u64>() {
+        return whole
+            .checked_mul(multiplier)
+            .ok_or_else(|| ParseSizeError::OutOfRange(input.to_string()));
+    }
+
+    let value: f64 = number
+        .parse()
+        .map_err(|_| ParseSizeError::InvalidNumber(input.to_string()))?;
+    let bytes = (value * multiplier as f64).round();
+    if bytes >= u64::MAX as f64 {
+        return Err(ParseSizeError::OutOfRange(input.to_string()));
+    }
+
+    Ok(bytes as u64)
+}
+
+/// Formats a number of bytes using SI units, e.g. "1.50 GB".
+///
+/// # Example
+/// ```rust
+/// use magneto::size::format_size;
+///
+/// assert_eq!(format_size(512), "512 B");
+/// assert_eq!(format_size(1_500_000_000), "1.50 GB");
+/// ```
+pub fn format_size(bytes: u64) -> String {
+    format_with_units(bytes, 1000.0, &SI_UNITS)
+}
+
+/// Formats a number of bytes using IEC units, e.g. "1.40 GiB".
+///
+/// # Example
+/// ```rust
+/// use magneto::size::format_size_iec;
+///
+/// assert_eq!(format_size_iec(1023), "1023 B");
+/// assert_eq!(format_size_iec(1536), "1.50 KiB");
+/// ```
+pub fn format_size_iec(bytes: u64) -> String {
+    format_with_units(bytes, 1024.0, &IEC_UNITS)
+}
+
+/// Formats a number of bytes with the largest unit keeping the value at or above one.
+fn format_with_units(bytes: u64, base: f64, units: &[&str]) -> String {
+    if (bytes as f64) < base {
+        return format!("{} {}", bytes, units[0]);
+    }
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= base && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+
+    format!("{:.2} {}", value, units[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests parsing sizes with SI, IEC and byte units in different cases and spacing.
+    #[test]
+    fn test_parse_size_units() {
+        assert_eq!(parse_size("700MB"), Ok(700_000_000));
+        assert_eq!(parse_size("1.2 TB"), Ok(1_200_000_000_000));
+        assert_eq!(parse_size("850 KB"), Ok(850_000));
+        assert_eq!(parse_size("850 kb"), Ok(850_000));
+        assert_eq!(parse_size("1.4 GiB"), Ok(1_503_238_554));
+        assert_eq!(parse_size(" 3 mib "), Ok(3 * 1024 * 1024));
+        assert_eq!(parse_size("2048"), Ok(2048));
+        assert_eq!(parse_size("2048 bytes"), Ok(2048));
+        assert_eq!(parse_size("1.5g"), Ok(1_500_000_000));
+    }
+
+    /// Tests that invalid sizes produce errors instead of zero.
+    #[test]
+    fn test_parse_size_errors() {
+        assert_eq!(parse_size(""), Err(ParseSizeError::Empty));
+        assert_eq!(parse_size("   "), Err(ParseSizeError::Empty));
+        assert_eq!(
+            parse_size("GB"),
+            Err(ParseSizeError::InvalidNumber("GB".to_string()))
+        );
+        assert_eq!(
+            parse_size("1.2.3 GB"),
+            Err(ParseSizeError::InvalidNumber("1.2.3 GB".to_string()))
+        );
+        assert_eq!(
+            parse_size("5 XB"),
+            Err(ParseSizeError::UnknownUnit("5 XB".to_string()))
+        );
+        assert_eq!(
+            parse_size("-5 MB"),
+            Err(ParseSizeError::InvalidNumber("-5 MB".to_string()))
+        );
+        assert_eq!(
+            parse_size("99999999 PB"),
+            Err(ParseSizeError::OutOfRange("99999999 PB".to_string()))
+        );
+    }
+
+    /// Tests formatting sizes with SI and IEC units.
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(999), "999 B");
+        assert_eq!(format_size(1000), "1.00 kB");
+        assert_eq!(format_size(1_200_000_000_000), "1.20 TB");
+        assert_eq!(format_size(u64::MAX), "18446.74 PB");
+        assert_eq!(format_size_iec(1_503_238_554), "1.40 GiB");
+        assert_eq!(parse_size(&format_size(2_500_000)), Ok(2_500_000));
+    }
+}
@@ -0,0 +1,566 @@
+//! # Test kit
+//!
+//! A conformance suite for `SearchProvider` implementations, enabled with the
+//! `testkit` feature. It checks the behavior every HTTP provider should share:
+//!
+//! - `build_request` encodes queries with special characters and unicode.
+//! - `build_request` handles every `Category`.
+//! - `parse_response` tolerates empty, truncated and garbage bodies without panicking.
+//! - `id` is stable.
+//! - `send_request` parses a sample response and reports server errors when
+//!   talking to a local mock server.
+//!
+//! Providers that do not build HTTP requests, such as `Dht`, are out of scope.
+//!
+//! # Example
+//! ```
+//! use magneto::{testkit::ConformanceSuite, Knaben};
+//!
+//! # async fn example() {
+//! ConformanceSuite::new(Knaben::with_url)
+//!     .with_sample_response(
+//!         r#"{"hits": [{"id": "1", "title": "Ubuntu", "hash": "abc", "peers": 1,
+//!             "seeders": 2, "bytes": 3, "date": "2024-04-25T15:02:11+00:00",
+//!             "tracker": "1337x", "categoryId": [4002000]}]}"#,
+//!     )
+//!     .run()
+//!     .await
+//!     .unwrap();
+//! # }
+//! ```
+
+use core::fmt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::Request;
+use mockito::{Matcher, Server};
+use percent_encoding::percent_decode_str;
+use thiserror::Error;
+use url::{form_urlencoded, Url};
+
+use crate::{
+    search_providers::build_get_request, transport::ReqwestTransport, Category, ClientError,
+    SearchProvider, SearchRequest, Torrent,
+};
+
+/// Queries used to check request encoding.
+pub const SPECIAL_QUERIES: &[&str] = &[
+    "ubuntu 24.04",
+    "Tom & Jerry",
+    "100% pure=true?#1",
+    "C++ / C#",
+    "\"quoted\" 'single'",
+    "Amélie",
+    "千と千尋の神隠し",
+];
+
+/// Bodies every provider must parse without panicking.
+const GARBAGE_BODIES: &[&str] = &[
+    "",
+    " ",
+    "null",
+    "{}",
+    "[]",
+    "{",
+    "[{",
+    "[{\"unexpected\": true}]",
+    "{\"data\": null}",
+    "<html><body>502 Bad Gateway</body></html>",
+    "\u{fffd}\u{0}garbage",
+];
+
+/// A failed conformance check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConformanceFailure {
+    /// The name of the check, e.g. "query_encoding".
+    pub check: &'static str,
+
+    /// What went wrong.
+    pub message: String,
+}
+
+impl ConformanceFailure {
+    /// Creates a new failure of the given check.
+    fn new(check: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            check,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConformanceFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.check, self.message)
+    }
+}
+
+/// Represents a provider failing the conformance suite.
+#[derive(Error, Debug)]
+#[error("{} conformance check(s) failed:\n{}", .failures.len(), format_failures(.failures))]
+pub struct ConformanceError {
+    /// Every failed check.
+    pub failures: Vec<ConformanceFailure>,
+}
+
+/// Formats failures one per line.
+fn format_failures(failures: &[ConformanceFailure]) -> String {
+    failures
+        .iter()
+        .map(|failure| format!("- {}", failure))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The conformance suite for a `SearchProvider`.
+///
+/// The suite creates providers through a factory taking an API URL, so it can
+/// point them at a local mock server. Providers usually pass their `with_url`
+/// constructor.
+pub struct ConformanceSuite<F> {
+    /// Creates a provider sending requests to the given API URL.
+    factory: F,
+
+    /// A successful response body of the provider's API, containing results.
+    sample_response: Option<String>,
+}
+
+impl<P, F> ConformanceSuite<F>
+where
+    P: SearchProvider,
+    F: Fn(String) -> P,
+{
+    /// Creates a new `ConformanceSuite`.
+    ///
+    /// # Parameters
+    /// - `factory`: Creates a provider sending requests to the given API URL.
+    pub fn new(factory: F) -> Self {
+        Self {
+            factory,
+            sample_response: None,
+        }
+    }
+
+    /// Sets a successful response body of the provider's API.
+    ///
+    /// Without a sample, the checks parsing real responses are skipped. The
+    /// mock server answers every request with the sample, so providers fetching
+    /// details pages need a sample that also holds what they read from those.
+    ///
+    /// # Parameters
+    /// - `body`: A response body containing at least one result.
+    pub fn with_sample_response(mut self, body: impl Into<String>) -> Self {
+        self.sample_response = Some(body.into());
+        self
+    }
+
+    /// Runs every check.
+    ///
+    /// # Returns
+    /// - `Ok(())`: The provider passed every check.
+    /// - `Err(ConformanceError)`: The failed checks.
+    pub async fn run(&self) -> Result<(), ConformanceError> {
+        let mut server = Server::new_async().await;
+        let provider = (self.factory)(server.url());
+
+        let mut failures = check_query_encoding(&provider);
+        failures.extend(check_categories(&provider));
+        failures.extend(check_parse_robustness(
+            &provider,
+            self.sample_response.as_deref(),
+        ));
+        failures.extend(check_id_stable(&provider, &(self.factory)(server.url())));
+        failures.extend(self.check_mock_server(&mut server, &provider).await);
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(ConformanceError { failures })
+        }
+    }
+
+    /// Checks `send_request` against a mock server answering any path.
+    async fn check_mock_server(
+        &self,
+        server: &mut Server,
+        provider: &P,
+    ) -> Vec<ConformanceFailure> {
+        const CHECK: &str = "mock_server";
+        let transport = ReqwestTransport::new();
+        let mut failures = Vec::new();
+
+        let method = match provider.build_request(SearchRequest::new("ubuntu")) {
+            Ok(request) => request.method().to_string(),
+            Err(e) => return vec![ConformanceFailure::new(CHECK, e.to_string())],
+        };
+
+        if let Some(sample) = &self.sample_response {
+            let mock = server
+                .mock(&method, Matcher::Any)
+                .with_status(200)
+                .with_body(sample)
+                .expect_at_least(1)
+                .create_async()
+                .await;
+            match provider
+                .send_request(&transport, SearchRequest::new("ubuntu"))
+                .await
+            {
+                Ok(torrents) if torrents.is_empty() => failures.push(ConformanceFailure::new(
+                    CHECK,
+                    "the sample response returned no torrents",
+                )),
+                Ok(torrents) => failures.extend(torrents.iter().filter_map(check_torrent)),
+                Err(e) => failures.push(ConformanceFailure::new(
+                    CHECK,
+                    format!("the sample response failed: {}", e),
+                )),
+            }
+            if !mock.matched_async().await {
+                failures.push(ConformanceFailure::new(
+                    CHECK,
+                    "the request did not reach the api url",
+                ));
+            }
+            mock.remove_async().await;
+        }
+
+        let mock = server
+            .mock(&method, Matcher::Any)
+            .with_status(500)
+            .with_body("Internal Server Error")
+            .create_async()
+            .await;
+        match provider
+            .send_request(&transport, SearchRequest::new("ubuntu"))
+            .await
+        {
+            Err(ClientError::ServerResponseError { code, .. }) if code.as_u16() == 500 => {}
+            other => failures.push(ConformanceFailure::new(
+                CHECK,
+                format!("a 500 response returned {:?}", other),
+            )),
+        }
+        mock.remove_async().await;
+
+        failures
+    }
+}
+
+/// Checks that queries with special characters and unicode survive `build_request`.
+///
+/// The query must be found, decoded, in a query parameter, a path segment, a
+/// form-encoded body or a string of a JSON body.
+pub fn check_query_encoding(provider: &dyn SearchProvider) -> Vec<ConformanceFailure> {
+    const CHECK: &str = "query_encoding";
+
+    SPECIAL_QUERIES
+        .iter()
+        .filter_map(|query| {
+            let request = match catch_unwind(AssertUnwindSafe(|| {
+                provider.build_request(SearchRequest::new(query))
+            })) {
+                Ok(Ok(request)) => request,
+                Ok(Err(e)) => {
+                    return Some(ConformanceFailure::new(
+                        CHECK,
+                        format!("building a request for {:?} failed: {}", query, e),
+                    ))
+                }
+                Err(_) => {
+                    return Some(ConformanceFailure::new(
+                        CHECK,
+                        format!("building a request for {:?} panicked", query),
+                    ))
+                }
+            };
+
+            if request_contains_query(&request, query) {
+                None
+            } else {
+                Some(ConformanceFailure::new(
+                    CHECK,
+                    format!("query {:?} is not encoded in {}", query, request.uri()),
+                ))
+            }
+        })
+        .collect()
+}
+
+/// Checks that `build_request` handles every `Category`, alone and combined.
+pub fn check_categories(provider: &dyn SearchProvider) -> Vec<ConformanceFailure> {
+    const CHECK: &str = "categories";
+
+    let mut requests: Vec<(String, SearchRequest<'_>)> = Category::ALL
+        .iter()
+        .map(|category| {
+            (
+                format!("{:?}", category),
+                SearchRequest::new("ubuntu").add_category(category.clone()),
+            )
+        })
+        .collect();
+    requests.push((
+        "all categories".to_string(),
+        SearchRequest::new("ubuntu").add_categories(Category::ALL.to_vec()),
+    ));
+
+    requests
+        .into_iter()
+        .filter_map(|(name, request)| {
+            match catch_unwind(AssertUnwindSafe(|| provider.build_request(request))) {
+                Ok(Ok(_)) => None,
+                Ok(Err(e)) => Some(ConformanceFailure::new(
+                    CHECK,
+                    format!("building a request for {} failed: {}", name, e),
+                )),
+                Err(_) => Some(ConformanceFailure::new(
+                    CHECK,
+                    format!("building a request for {} panicked", name),
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Checks that `parse_response` does not panic on empty, garbage or truncated bodies.
+///
+/// Returning an error is fine; only panics fail the check.
+///
+/// # Parameters
+/// - `provider`: The provider to check.
+/// - `sample`: A successful response body, truncated at several points if given.
+pub fn check_parse_robustness(
+    provider: &dyn SearchProvider,
+    sample: Option<&str>,
+) -> Vec<ConformanceFailure> {
+    let mut bodies: Vec<String> = GARBAGE_BODIES.iter().map(|body| body.to_string()).collect();
+    if let Some(sample) = sample {
+        for fraction in [4, 2] {
+            let mut end = sample.len() / fraction;
+            while !sample.is_char_boundary(end) {
+                end -= 1;
+            }
+            bodies.push(sample[..end].to_string());
+        }
+        bodies.push(sample[..sample.len().saturating_sub(1)].to_string());
+    }
+
+    bodies
+        .into_iter()
+        .filter(|body| catch_unwind(AssertUnwindSafe(|| provider.parse_response(body))).is_err())
+        .map(|body| {
+            ConformanceFailure::new(
+                "parse_robustness",
+                format!("parsing {:?} panicked", truncate(&body, 40)),
+            )
+        })
+        .collect()
+}
+
+/// Checks that `id` is not empty and equal across calls and instances.
+///
+/// # Parameters
+/// - `provider`: The provider to check.
+/// - `other`: Another instance created with the same configuration.
+pub fn check_id_stable(
+    provider: &dyn SearchProvider,
+    other: &dyn SearchProvider,
+) -> Vec<ConformanceFailure> {
+    const CHECK: &str = "id_stable";
+
+    let id = provider.id();
+    if id.is_empty() {
+        vec![ConformanceFailure::new(CHECK, "the id is empty")]
+    } else if provider.id() != id || other.id() != id {
+        vec![ConformanceFailure::new(
+            CHECK,
+            format!("the id changed from {:?}", id),
+        )]
+    } else {
+        vec![]
+    }
+}
+
+/// Checks that a parsed torrent has the fields every provider must fill.
+fn check_torrent(torrent: &Torrent) -> Option<ConformanceFailure> {
+    let problem = if torrent.name.is_empty() {
+        "has no name"
+    } else if !torrent.magnet_link.starts_with("magnet:?") {
+        "has no magnet link"
+    } else if torrent.provider.is_empty() {
+        "has no provider"
+    } else {
+        return None;
+    };
+
+    Some(ConformanceFailure::new(
+        "mock_server",
+        format!("torrent {:?} {}", torrent.name, problem),
+    ))
+}
+
+/// Returns whether the decoded query appears anywhere in the request.
+fn request_contains_query(request: &Request<Bytes>, query: &str) -> bool {
+    let Ok(url) = Url::parse(&request.uri().to_string()) else {
+        return false;
+    };
+
+    let in_query = url.query_pairs().any(|(_, value)| value == query);
+    let in_path = url.path_segments().into_iter().flatten().any(|segment| {
+        percent_decode_str(segment)
+            .decode_utf8()
+            .is_ok_and(|segment| segment == query || segment.replace('+', " ") == query)
+    });
+    let in_form = form_urlencoded::parse(request.body()).any(|(_, value)| value == query);
+    let in_json = serde_json::from_slice(request.body())
+        .is_ok_and(|json: serde_json::Value| json_contains(&json, query));
+
+    in_query || in_path || in_form || in_json
+}
+
+/// Returns whether a JSON value contains the string.
+fn json_contains(json: &serde_json::Value, text: &str) -> bool {
+    match json {
+        serde_json::Value::String(value) => value == text,
+        serde_json::Value::Array(values) => values.iter().any(|value| json_contains(value, text)),
+        serde_json::Value::Object(map) => map.values().any(|value| json_contains(value, text)),
+        _ => false,
+    }
+}
+
+/// Truncates text to at most `max` characters.
+fn truncate(text: &str, max: usize) -> String {
+    text.chars().take(max).collect()
+}
+
+/// A minimal `SearchProvider` for tests.
+///
+/// It sends `GET {url}/search?q={query}` and parses a JSON list of `Torrent`s.
+#[derive(Debug, Clone)]
+pub struct MockProvider {
+    /// The base URL of the mock API.
+    url: String,
+}
+
+impl MockProvider {
+    /// Creates a new `MockProvider` querying the given base URL.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+}
+
+#[async_trait]
+impl SearchProvider for MockProvider {
+    /// Parses a JSON list of `Torrent`s.
+    fn parse_response(&self, response: &str) -> Result<Vec<Torrent>, ClientError> {
+        serde_json::from_str(response).map_err(|e| ClientError::DataParseError(e.into()))
+    }
+
+    /// Builds `GET {url}/search?q={query}`.
+    fn build_request(&self, request: SearchRequest<'_>) -> Result<Request<Bytes>, ClientError> {
+        build_get_request(&format!("{}/search", self.url), &[("q", request.query)])
+    }
+
+    /// Returns the base URL.
+    fn id(&self) -> String {
+        self.url.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Knaben, PirateBay, Yts};
+
+    /// Returns the response body of the first interaction of a provider fixture.
+    fn fixture_response(name: &str) -> String {
+        let path = format!("{}/fixtures/{}.json", env!("CARGO_MANIFEST_DIR"), name);
+        let fixtures = crate::transport::fixtures::load_fixtures(path).unwrap();
+        String::from_utf8(fixtures[0].response.body.to_bytes().to_vec()).unwrap()
+    }
+
+    /// Tests that the mock and in-house providers pass the suite.
+    #[tokio::test]
+    async fn test_providers_conform() {
+        ConformanceSuite::new(MockProvider::new)
+            .with_sample_response(
+                r#"[{"name": "Ubuntu", "magnet_link": "magnet:?xt=urn:btih:abc",
+                    "seeders": 1, "peers": 2, "size_bytes": 3, "provider": "mock"}]"#,
+            )
+            .run()
+            .await
+            .unwrap();
+        ConformanceSuite::new(Knaben::with_url)
+            .with_sample_response(fixture_response("knaben"))
+            .run()
+            .await
+            .unwrap();
+        ConformanceSuite::new(PirateBay::with_url)
+            .with_sample_response(fixture_response("piratebay"))
+            .run()
+            .await
+            .unwrap();
+        ConformanceSuite::new(Yts::with_url)
+            .with_sample_response(fixture_response("yts"))
+            .run()
+            .await
+            .unwrap();
+    }
+
+    /// A provider breaking most rules.
+    struct BrokenProvider {
+        url: String,
+    }
+
+    #[async_trait]
+    impl SearchProvider for BrokenProvider {
+        fn parse_response(&self, response: &str) -> Result<Vec<Torrent>, ClientError> {
+            assert!(!response.is_empty());
+            Ok(vec![])
+        }
+
+        fn build_request(&self, request: SearchRequest<'_>) -> Result<Request<Bytes>, ClientError> {
+            if request.categories.contains(&Category::Xxx) {
+                panic!("unsupported category");
+            }
+            Request::get(format!(
+                "{}/search?q={}",
+                self.url,
+                request.query.replace(' ', "+")
+            ))
+            .body(Bytes::new())
+            .map_err(|e| ClientError::RequestBuildError {
+                source: e.into(),
+                url: self.url.clone(),
+            })
+        }
+
+        fn id(&self) -> String {
+            format!("{:p}", self)
+        }
+    }
+
+    /// Tests that a broken provider fails every check.
+    #[tokio::test]
+    async fn test_broken_provider_fails() {
+        let error = ConformanceSuite::new(|url| BrokenProvider { url })
+            .with_sample_response("[]")
+            .run()
+            .await
+            .unwrap_err();
+
+        let checks: Vec<&str> = error.failures.iter().map(|failure| failure.check).collect();
+        for check in [
+            "query_encoding",
+            "categories",
+            "parse_robustness",
+            "id_stable",
+            "mock_server",
+        ] {
+            assert!(checks.contains(&check), "{} did not fail: {}", check, error);
+        }
+    }
+}
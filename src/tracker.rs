@@ -0,0 +1,863 @@
+//! # Trackers
+//!
+//! A client for BitTorrent trackers, speaking both the HTTP tracker protocol
+//! (BEP 3, with compact peer lists from BEP 23) and the UDP tracker protocol
+//! (BEP 15). Announces are used to discover peers for a torrent and scrapes
+//! to read live seeder and leecher counts for many torrents at once.
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use bytes::Bytes;
+use http::Request;
+use log::debug;
+use thiserror::Error;
+use tokio::{net::UdpSocket, time::timeout};
+use url::{form_urlencoded, Url};
+
+use crate::{
+    bencode::{self, BencodeError, Value},
+    transport::{HttpTransport, ReqwestTransport},
+    ClientError,
+};
+
+/// The magic constant identifying the UDP tracker protocol.
+const UDP_PROTOCOL_ID: u64 = 0x41727101980;
+
+/// UDP tracker action for obtaining a connection id.
+const UDP_ACTION_CONNECT: u32 = 0;
+
+/// UDP tracker action for announcing.
+const UDP_ACTION_ANNOUNCE: u32 = 1;
+
+/// UDP tracker action for scraping.
+const UDP_ACTION_SCRAPE: u32 = 2;
+
+/// UDP tracker action for error responses.
+const UDP_ACTION_ERROR: u32 = 3;
+
+/// The largest UDP datagram read from a tracker.
+const MAX_DATAGRAM_SIZE: usize = 65_536;
+
+/// The number of times a UDP request is sent again after a timeout by default.
+const DEFAULT_UDP_RETRIES: u32 = 2;
+
+/// The maximum number of UDP retransmissions, as in BEP 15.
+const MAX_UDP_RETRIES: u32 = 8;
+
+/// The maximum number of info hashes in a single UDP scrape request (BEP 15).
+pub const MAX_UDP_SCRAPE_HASHES: usize = 74;
+
+/// Represents errors that can occur while talking to a tracker.
+#[derive(Error, Debug)]
+pub enum TrackerError {
+    /// The tracker URL is invalid or uses an unsupported scheme.
+    #[error("invalid tracker url '{0}'")]
+    InvalidUrl(String),
+
+    /// The tracker did not respond in time.
+    #[error("tracker timed out")]
+    Timeout,
+
+    /// A network error occurred.
+    #[error("tracker i/o error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// An HTTP error occurred.
+    #[error("tracker http error: {0}")]
+    Http(#[from] ClientError),
+
+    /// The tracker response is not valid bencode.
+    #[error("invalid tracker response: {0}")]
+    Bencode(#[from] BencodeError),
+
+    /// The tracker response is malformed.
+    #[error("malformed tracker response: {0}")]
+    InvalidResponse(String),
+
+    /// The tracker reported a failure.
+    #[error("tracker failure: {0}")]
+    Failure(String),
+
+    /// The tracker does not support scraping.
+    #[error("tracker '{0}' does not support scraping")]
+    ScrapeUnsupported(String),
+}
+
+/// The result of an announce.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Announce {
+    /// The peers returned by the tracker.
+    pub peers: Vec<SocketAddr>,
+
+    /// The number of seeders reported by the tracker, if any.
+    pub seeders: Option<u32>,
+
+    /// The number of leechers reported by the tracker, if any.
+    pub leechers: Option<u32>,
+}
+
+/// Swarm statistics for a torrent, as reported by a tracker scrape.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScrapeStats {
+    /// The number of peers with the complete torrent.
+    pub seeders: u32,
+
+    /// The number of peers still downloading.
+    pub leechers: u32,
+
+    /// The number of completed downloads, if reported.
+    pub completed: Option<u32>,
+}
+
+/// A client for announcing to and scraping HTTP and UDP trackers.
+#[derive(Debug, Clone)]
+pub struct TrackerClient {
+    /// The transport used for HTTP trackers.
+    transport: Arc<dyn HttpTransport>,
+
+    /// The peer id sent to trackers.
+    peer_id: [u8; 20],
+
+    /// The port announced to trackers.
+    port: u16,
+
+    /// The timeout for a single tracker request.
+    timeout: Duration,
+
+    /// The number of times a UDP request is sent again after a timeout.
+    udp_retries: u32,
+}
+
+impl TrackerClient {
+    /// Creates a new `TrackerClient` with a random peer id and a 10 second timeout.
+    pub fn new() -> Self {
+        Self {
+            transport: Arc::new(ReqwestTransport::new()),
+            peer_id: generate_peer_id(),
+            port: 6881,
+            timeout: Duration::from_secs(10),
+            udp_retries: DEFAULT_UDP_RETRIES,
+        }
+    }
+
+    /// Sets the timeout for a single tracker request.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the number of times a UDP request is sent again after a timeout,
+    /// 2 by default and at most 8.
+    ///
+    /// As in BEP 15, each attempt waits twice as long as the previous one, with
+    /// the attempts together taking at most the timeout of the request.
+    pub fn with_udp_retries(mut self, retries: u32) -> Self {
+        self.udp_retries = retries.min(MAX_UDP_RETRIES);
+        self
+    }
+
+    /// Sets the transport used for HTTP trackers.
+    pub fn with_transport(mut self, transport: impl HttpTransport + 'static) -> Self {
+        self.transport = Arc::new(transport);
+        self
+    }
+
+    /// Returns the peer id sent to trackers.
+    pub fn peer_id(&self) -> [u8; 20] {
+        self.peer_id
+    }
+
+    /// Returns the timeout for a single tracker request.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Announces interest in a torrent to a tracker and returns its peers.
+    ///
+    /// # Parameters
+    /// - `tracker`: The tracker's announce URL, using the `http`, `https` or `udp` scheme.
+    /// - `info_hash`: The v1 info hash of the torrent.
+    ///
+    /// # Returns
+    /// - `Ok(Announce)`: The peers and swarm statistics reported by the tracker.
+    /// - `Err(TrackerError)`: An error if the tracker cannot be reached or fails.
+    pub async fn announce(
+        &self,
+        tracker: &str,
+        info_hash: &[u8; 20],
+    ) -> Result<Announce, TrackerError> {
+        let url = Url::parse(tracker).map_err(|_| TrackerError::InvalidUrl(tracker.to_string()))?;
+        debug!("announcing {} to {}", hex::encode(info_hash), tracker);
+
+        match url.scheme() {
+            "http" | "https" => self.announce_http(url, info_hash).await,
+            "udp" => self.announce_udp(&url, info_hash).await,
+            _ => Err(TrackerError::InvalidUrl(tracker.to_string())),
+        }
+    }
+
+    /// Announces to an HTTP tracker.
+    async fn announce_http(
+        &self,
+        mut url: Url,
+        info_hash: &[u8; 20],
+    ) -> Result<Announce, TrackerError> {
+        // The info hash and peer id are raw bytes and must be percent-encoded byte by byte
+        let query = format!(
+            "{}{}info_hash={}&peer_id={}&port={}&uploaded=0&downloaded=0&left=0&compact=1&numwant=50",
+            url.query().unwrap_or_default(),
+            if url.query().is_some() { "&" } else { "" },
+            form_urlencoded::byte_serialize(info_hash).collect::<String>(),
+            form_urlencoded::byte_serialize(&self.peer_id).collect::<String>(),
+            self.port,
+        );
+        url.set_query(Some(&query));
+
+        let body = self.get(url).await?;
+
+        let response = bencode::decode(&body)?;
+        if let Some(reason) = response.get("failure reason") {
+            return Err(TrackerError::Failure(
+                String::from_utf8_lossy(reason.as_bytes().unwrap_or_default()).into_owned(),
+            ));
+        }
+
+        let mut peers = match response.get("peers") {
+            Some(Value::Bytes(compact)) => parse_compact_peers(compact, 6),
+            Some(Value::List(list)) => list.iter().filter_map(parse_dict_peer).collect(),
+            _ => vec![],
+        };
+        if let Some(compact) = response.get("peers6").and_then(Value::as_bytes) {
+            peers.extend(parse_compact_peers(compact, 18));
+        }
+
+        let count = |key| {
+            response
+                .get(key)
+                .and_then(Value::as_integer)
+                .and_then(|count| u32::try_from(count).ok())
+        };
+
+        Ok(Announce {
+            peers,
+            seeders: count("complete"),
+            leechers: count("incomplete"),
+        })
+    }
+
+    /// Announces to a UDP tracker.
+    async fn announce_udp(
+        &self,
+        url: &Url,
+        info_hash: &[u8; 20],
+    ) -> Result<Announce, TrackerError> {
+        let (socket, connection_id) = self.connect_udp(url).await?;
+
+        let transaction_id: u32 = rand::random();
+        let mut request = Vec::with_capacity(98);
+        request.extend_from_slice(&connection_id.to_be_bytes());
+        request.extend_from_slice(&UDP_ACTION_ANNOUNCE.to_be_bytes());
+        request.extend_from_slice(&transaction_id.to_be_bytes());
+        request.extend_from_slice(info_hash);
+        request.extend_from_slice(&self.peer_id);
+        request.extend_from_slice(&0u64.to_be_bytes()); // downloaded
+        request.extend_from_slice(&0u64.to_be_bytes()); // left
+        request.extend_from_slice(&0u64.to_be_bytes()); // uploaded
+        request.extend_from_slice(&0u32.to_be_bytes()); // event: none
+        request.extend_from_slice(&0u32.to_be_bytes()); // ip: sender address
+        request.extend_from_slice(&rand::random::<u32>().to_be_bytes()); // key
+        request.extend_from_slice(&(-1i32).to_be_bytes()); // num_want: default
+        request.extend_from_slice(&self.port.to_be_bytes());
+
+        let response = self
+            .udp_request(&socket, &request, UDP_ACTION_ANNOUNCE, transaction_id)
+            .await?;
+        if response.len() < 20 {
+            return Err(TrackerError::InvalidResponse(
+                "announce response too short".to_string(),
+            ));
+        }
+
+        let leechers = u32::from_be_bytes(response[12..16].try_into().unwrap_or_default());
+        let seeders = u32::from_be_bytes(response[16..20].try_into().unwrap_or_default());
+        let peer_size = if socket.peer_addr()?.is_ipv6() { 18 } else { 6 };
+
+        Ok(Announce {
+            peers: parse_compact_peers(&response[20..], peer_size),
+            seeders: Some(seeders),
+            leechers: Some(leechers),
+        })
+    }
+
+    /// Requests swarm statistics for several torrents from a tracker.
+    ///
+    /// HTTP scrape URLs are derived from announce URLs as described in BEP 48;
+    /// trackers whose announce path does not end in "announce" cannot be scraped.
+    /// UDP trackers accept at most `MAX_UDP_SCRAPE_HASHES` info hashes per request.
+    ///
+    /// # Parameters
+    /// - `tracker`: The tracker's announce URL, using the `http`, `https` or `udp` scheme.
+    /// - `info_hashes`: The v1 info hashes of the torrents.
+    ///
+    /// # Returns
+    /// - `Ok(HashMap<[u8; 20], ScrapeStats>)`: The statistics of the torrents known to the tracker.
+    /// - `Err(TrackerError)`: An error if the tracker cannot be reached or fails.
+    pub async fn scrape(
+        &self,
+        tracker: &str,
+        info_hashes: &[[u8; 20]],
+    ) -> Result<HashMap<[u8; 20], ScrapeStats>, TrackerError> {
+        let url = Url::parse(tracker).map_err(|_| TrackerError::InvalidUrl(tracker.to_string()))?;
+        debug!("scraping {} torrents from {}", info_hashes.len(), tracker);
+
+        match url.scheme() {
+            "http" | "https" => self.scrape_http(url, info_hashes).await,
+            "udp" => self.scrape_udp(&url, info_hashes).await,
+            _ => Err(TrackerError::InvalidUrl(tracker.to_string())),
+        }
+    }
+
+    /// Scrapes an HTTP tracker.
+    async fn scrape_http(
+        &self,
+        mut url: Url,
+        info_hashes: &[[u8; 20]],
+    ) -> Result<HashMap<[u8; 20], ScrapeStats>, TrackerError> {
+        let scrape_path = match url.path().rsplit_once('/') {
+            Some((base, name)) if name.starts_with("announce") => {
+                format!("{}/{}", base, name.replacen("announce", "scrape", 1))
+            }
+            _ => return Err(TrackerError::ScrapeUnsupported(url.to_string())),
+        };
+        url.set_path(&scrape_path);
+
+        let mut query = url.query().unwrap_or_default().to_string();
+        for info_hash in info_hashes {
+            if !query.is_empty() {
+                query.push('&');
+            }
+            query.push_str("info_hash=");
+            query.extend(form_urlencoded::byte_serialize(info_hash));
+        }
+        url.set_query(Some(&query));
+
+        let body = self.get(url).await?;
+
+        let response = bencode::decode(&body)?;
+        if let Some(reason) = response.get("failure reason") {
+            return Err(TrackerError::Failure(
+                String::from_utf8_lossy(reason.as_bytes().unwrap_or_default()).into_owned(),
+            ));
+        }
+
+        let files = response
+            .get("files")
+            .and_then(Value::as_dict)
+            .ok_or_else(|| TrackerError::InvalidResponse("missing 'files'".to_string()))?;
+
+        let mut stats = HashMap::new();
+        for (info_hash, file) in files {
+            let Ok(info_hash) = <[u8; 20]>::try_from(info_hash.as_ref()) else {
+                continue;
+            };
+            let count = |key| {
+                file.get(key)
+                    .and_then(Value::as_integer)
+                    .and_then(|count| u32::try_from(count).ok())
+            };
+            stats.insert(
+                info_hash,
+                ScrapeStats {
+                    seeders: count("complete").unwrap_or_default(),
+                    leechers: count("incomplete").unwrap_or_default(),
+                    completed: count("downloaded"),
+                },
+            );
+        }
+
+        Ok(stats)
+    }
+
+    /// Scrapes a UDP tracker, splitting the info hashes into requests of
+    /// `MAX_UDP_SCRAPE_HASHES`.
+    async fn scrape_udp(
+        &self,
+        url: &Url,
+        info_hashes: &[[u8; 20]],
+    ) -> Result<HashMap<[u8; 20], ScrapeStats>, TrackerError> {
+        let (socket, connection_id) = self.connect_udp(url).await?;
+
+        let mut stats = HashMap::new();
+        for batch in info_hashes.chunks(MAX_UDP_SCRAPE_HASHES) {
+            let transaction_id: u32 = rand::random();
+            let mut request = Vec::with_capacity(16 + 20 * batch.len());
+            request.extend_from_slice(&connection_id.to_be_bytes());
+            request.extend_from_slice(&UDP_ACTION_SCRAPE.to_be_bytes());
+            request.extend_from_slice(&transaction_id.to_be_bytes());
+            for info_hash in batch {
+                request.extend_from_slice(info_hash);
+            }
+
+            let response = self
+                .udp_request(&socket, &request, UDP_ACTION_SCRAPE, transaction_id)
+                .await?;
+            if response.len() < 8 + 12 * batch.len() {
+                return Err(TrackerError::InvalidResponse(
+                    "scrape response too short".to_string(),
+                ));
+            }
+
+            for (info_hash, entry) in batch.iter().zip(response[8..].chunks_exact(12)) {
+                let field = |index: usize| {
+                    u32::from_be_bytes(entry[index..index + 4].try_into().unwrap_or_default())
+                };
+                stats.insert(
+                    *info_hash,
+                    ScrapeStats {
+                        seeders: field(0),
+                        completed: Some(field(4)),
+                        leechers: field(8),
+                    },
+                );
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Sends a GET request to an HTTP tracker and returns the response body.
+    async fn get(&self, url: Url) -> Result<Bytes, TrackerError> {
+        let request = Request::get(url.as_str())
+            .body(Bytes::new())
+            .map_err(|_| TrackerError::InvalidUrl(url.to_string()))?;
+
+        let response = timeout(self.timeout, self.transport.send(request))
+            .await
+            .map_err(|_| TrackerError::Timeout)??;
+        if !response.status().is_success() {
+            return Err(TrackerError::Http(ClientError::ServerResponseError {
+                code: response.status(),
+                content: String::from_utf8_lossy(response.body()).into_owned(),
+            }));
+        }
+        Ok(response.into_body())
+    }
+
+    /// Connects to a UDP tracker and obtains a connection id.
+    pub(crate) async fn connect_udp(&self, url: &Url) -> Result<(UdpSocket, u64), TrackerError> {
+        let host = url
+            .host_str()
+            .ok_or_else(|| TrackerError::InvalidUrl(url.to_string()))?;
+        let port = url
+            .port()
+            .ok_or_else(|| TrackerError::InvalidUrl(url.to_string()))?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+
+        let address = timeout(self.timeout, tokio::net::lookup_host((host, port)))
+            .await
+            .map_err(|_| TrackerError::Timeout)??
+            .next()
+            .ok_or_else(|| TrackerError::InvalidUrl(url.to_string()))?;
+
+        let bind: SocketAddr = if address.is_ipv6() {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(bind).await?;
+        socket.connect(address).await?;
+
+        let transaction_id: u32 = rand::random();
+        let mut request = Vec::with_capacity(16);
+        request.extend_from_slice(&UDP_PROTOCOL_ID.to_be_bytes());
+        request.extend_from_slice(&UDP_ACTION_CONNECT.to_be_bytes());
+        request.extend_from_slice(&transaction_id.to_be_bytes());
+
+        let response = self
+            .udp_request(&socket, &request, UDP_ACTION_CONNECT, transaction_id)
+            .await?;
+        if response.len() < 16 {
+            return Err(TrackerError::InvalidResponse(
+                "connect response too short".to_string(),
+            ));
+        }
+
+        let connection_id = u64::from_be_bytes(response[8..16].try_into().unwrap_or_default());
+        Ok((socket, connection_id))
+    }
+
+    /// Sends a UDP tracker request and waits for the matching response,
+    /// sending it again with doubling timeouts when no response arrives.
+    ///
+    /// Responses with other transaction ids are ignored. Error responses are
+    /// returned as `TrackerError::Failure`.
+    pub(crate) async fn udp_request(
+        &self,
+        socket: &UdpSocket,
+        request: &[u8],
+        action: u32,
+        transaction_id: u32,
+    ) -> Result<Vec<u8>, TrackerError> {
+        // The attempts wait t, 2t, 4t, ..., adding up to the timeout
+        let mut attempt_timeout = self.timeout / (2u32.pow(self.udp_retries + 1) - 1);
+        for attempt in 0..=self.udp_retries {
+            socket.send(request).await?;
+            match timeout(attempt_timeout, receive_udp(socket, action, transaction_id)).await {
+                Ok(result) => return result,
+                Err(_) => debug!("udp tracker request timed out, attempt {}", attempt + 1),
+            }
+            attempt_timeout *= 2;
+        }
+        Err(TrackerError::Timeout)
+    }
+}
+
+impl Default for TrackerClient {
+    /// Provides a default implementation for `TrackerClient`, equal to `TrackerClient::new()`.
+    fn default() -> Self {
+        TrackerClient::new()
+    }
+}
+
+/// Generates a random Azureus-style peer id, e.g. "-MG0024-xxxxxxxxxxxx".
+pub fn generate_peer_id() -> [u8; 20] {
+    let mut peer_id = [0u8; 20];
+    peer_id[..8].copy_from_slice(b"-MG0024-");
+    for byte in &mut peer_id[8..] {
+        *byte = b'0' + rand::random_range(0..10u8);
+    }
+    peer_id
+}
+
+/// Parses a compact peer list of 6 byte (IPv4) or 18 byte (IPv6) entries.
+pub(crate) fn parse_compact_peers(compact: &[u8], entry_size: usize) -> Vec<SocketAddr> {
+    compact
+        .chunks_exact(entry_size)
+        .map(|entry| {
+            let (ip, port) = entry.split_at(entry_size - 2);
+            let ip = match <[u8; 4]>::try_from(ip) {
+                Ok(ip) => IpAddr::from(ip),
+                Err(_) => IpAddr::from(<[u8; 16]>::try_from(ip).unwrap_or_default()),
+            };
+            SocketAddr::new(ip, u16::from_be_bytes([port[0], port[1]]))
+        })
+        .collect()
+}
+
+/// Parses a peer from a non-compact peer list entry.
+fn parse_dict_peer(peer: &Value<'_>) -> Option<SocketAddr> {
+    let ip = peer.get("ip")?.as_str()?.parse().ok()?;
+    let port = u16::try_from(peer.get("port")?.as_integer()?).ok()?;
+    Some(SocketAddr::new(ip, port))
+}
+
+/// Receives UDP tracker responses until one matches the transaction id.
+async fn receive_udp(
+    socket: &UdpSocket,
+    action: u32,
+    transaction_id: u32,
+) -> Result<Vec<u8>, TrackerError> {
+    let mut buffer = vec![0u8; MAX_DATAGRAM_SIZE];
+    loop {
+        let length = socket.recv(&mut buffer).await?;
+        let response = &buffer[..length];
+        if length < 8 || response[4..8] != transaction_id.to_be_bytes() {
+            continue;
+        }
+
+        let response_action = u32::from_be_bytes(response[0..4].try_into().unwrap_or_default());
+        if response_action == UDP_ACTION_ERROR {
+            return Err(TrackerError::Failure(
+                String::from_utf8_lossy(&response[8..]).into_owned(),
+            ));
+        }
+        if response_action != action {
+            return Err(TrackerError::InvalidResponse(format!(
+                "unexpected action {}",
+                response_action
+            )));
+        }
+        return Ok(response.to_vec());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+
+    /// Tests announcing to an HTTP tracker returning compact and non-compact peers.
+    #[tokio::test]
+    async fn test_announce_http() {
+        let mut server = Server::new_async().await;
+        let client = TrackerClient::new();
+        let info_hash = [0xab; 20];
+
+        let mut body = b"d8:completei5e10:incompletei3e5:peers12:".to_vec();
+        body.extend_from_slice(&[127, 0, 0, 1, 0x1a, 0xe1, 10, 0, 0, 2, 0, 80]);
+        body.extend_from_slice(b"e");
+
+        let _mock = server
+            .mock("GET", "/announce")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::Regex("info_hash=(%AB){20}&".into()),
+                Matcher::UrlEncoded("compact".into(), "1".into()),
+            ]))
+            .with_body(body)
+            .create_async()
+            .await;
+
+        let announce = client
+            .announce(&format!("{}/announce", server.url()), &info_hash)
+            .await
+            .unwrap();
+        assert_eq!(
+            announce.peers,
+            vec![
+                "127.0.0.1:6881".parse().unwrap(),
+                "10.0.0.2:80".parse().unwrap()
+            ]
+        );
+        assert_eq!(announce.seeders, Some(5));
+        assert_eq!(announce.leechers, Some(3));
+
+        let _mock = server
+            .mock("GET", "/list")
+            .match_query(Matcher::Any)
+            .with_body("d5:peersld2:ip9:127.0.0.14:porti80eeee")
+            .create_async()
+            .await;
+        let announce = client
+            .announce(&format!("{}/list", server.url()), &info_hash)
+            .await
+            .unwrap();
+        assert_eq!(announce.peers, vec!["127.0.0.1:80".parse().unwrap()]);
+    }
+
+    /// Tests that tracker failures are reported as errors.
+    #[tokio::test]
+    async fn test_announce_http_failure() {
+        let mut server = Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/announce")
+            .match_query(Matcher::Any)
+            .with_body("d14:failure reason12:unregisterede")
+            .create_async()
+            .await;
+
+        let result = TrackerClient::new()
+            .announce(&format!("{}/announce", server.url()), &[0; 20])
+            .await;
+        assert!(matches!(result, Err(TrackerError::Failure(reason)) if reason == "unregistered"));
+
+        let result = TrackerClient::new()
+            .announce("wss://tracker", &[0; 20])
+            .await;
+        assert!(matches!(result, Err(TrackerError::InvalidUrl(_))));
+
+        let _mock = server
+            .mock("GET", "/down")
+            .match_query(Matcher::Any)
+            .with_status(503)
+            .with_body("d5:peers0:e")
+            .create_async()
+            .await;
+        let result = TrackerClient::new()
+            .announce(&format!("{}/down", server.url()), &[0; 20])
+            .await;
+        assert!(matches!(
+            result,
+            Err(TrackerError::Http(ClientError::ServerResponseError { code, .. })) if code == 503
+        ));
+    }
+
+    /// Tests announcing to a local UDP tracker stand-in.
+    #[tokio::test]
+    async fn test_announce_udp() {
+        let tracker = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("udp://{}", tracker.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 1024];
+
+            let (length, from) = tracker.recv_from(&mut buffer).await.unwrap();
+            assert_eq!(length, 16);
+            assert_eq!(buffer[..8], UDP_PROTOCOL_ID.to_be_bytes());
+            let mut response = vec![0, 0, 0, 0];
+            response.extend_from_slice(&buffer[12..16]);
+            response.extend_from_slice(&42u64.to_be_bytes());
+            tracker.send_to(&response, from).await.unwrap();
+
+            let (length, from) = tracker.recv_from(&mut buffer).await.unwrap();
+            assert_eq!(length, 98);
+            assert_eq!(buffer[..8], 42u64.to_be_bytes());
+            assert_eq!(buffer[16..36], [0xcd; 20]);
+            let mut response = vec![0, 0, 0, 1];
+            response.extend_from_slice(&buffer[12..16]);
+            response.extend_from_slice(&1800u32.to_be_bytes());
+            response.extend_from_slice(&7u32.to_be_bytes());
+            response.extend_from_slice(&9u32.to_be_bytes());
+            response.extend_from_slice(&[192, 168, 1, 1, 0x1a, 0xe1]);
+            tracker.send_to(&response, from).await.unwrap();
+        });
+
+        let announce = TrackerClient::new()
+            .announce(&url, &[0xcd; 20])
+            .await
+            .unwrap();
+        assert_eq!(announce.peers, vec!["192.168.1.1:6881".parse().unwrap()]);
+        assert_eq!(announce.seeders, Some(9));
+        assert_eq!(announce.leechers, Some(7));
+    }
+
+    /// Tests that an unresponsive UDP tracker times out.
+    #[tokio::test]
+    async fn test_announce_udp_timeout() {
+        let tracker = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("udp://{}", tracker.local_addr().unwrap());
+
+        let result = TrackerClient::new()
+            .with_timeout(Duration::from_millis(100))
+            .announce(&url, &[0; 20])
+            .await;
+        assert!(matches!(result, Err(TrackerError::Timeout)));
+    }
+
+    /// Tests that UDP requests are sent again when the tracker does not respond.
+    #[tokio::test]
+    async fn test_udp_retransmission() {
+        let tracker = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("udp://{}", tracker.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 1024];
+
+            // The first connect request is lost
+            tracker.recv_from(&mut buffer).await.unwrap();
+            let (_, from) = tracker.recv_from(&mut buffer).await.unwrap();
+            let mut response = vec![0, 0, 0, 0];
+            response.extend_from_slice(&buffer[12..16]);
+            response.extend_from_slice(&42u64.to_be_bytes());
+            tracker.send_to(&response, from).await.unwrap();
+
+            let (_, from) = tracker.recv_from(&mut buffer).await.unwrap();
+            let mut response = vec![0, 0, 0, 2];
+            response.extend_from_slice(&buffer[12..16]);
+            response.extend_from_slice(&[0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 1]);
+            tracker.send_to(&response, from).await.unwrap();
+        });
+
+        let stats = TrackerClient::new()
+            .with_timeout(Duration::from_millis(700))
+            .scrape(&url, &[[0x11; 20]])
+            .await
+            .unwrap();
+        assert_eq!(stats[&[0x11; 20]].seeders, 3);
+
+        let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let result = TrackerClient::new()
+            .with_timeout(Duration::from_millis(100))
+            .with_udp_retries(0)
+            .scrape(
+                &format!("udp://{}", silent.local_addr().unwrap()),
+                &[[0x11; 20]],
+            )
+            .await;
+        assert!(matches!(result, Err(TrackerError::Timeout)));
+    }
+
+    /// Tests scraping an HTTP tracker, deriving the scrape URL from the announce URL.
+    #[tokio::test]
+    async fn test_scrape_http() {
+        let mut server = Server::new_async().await;
+
+        let mut body = b"d5:filesd20:".to_vec();
+        body.extend_from_slice(&[0x11; 20]);
+        body.extend_from_slice(b"d8:completei12e10:downloadedi40e10:incompletei4eeee");
+        let _mock = server
+            .mock("GET", "/x/scrape")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::Regex("passkey=abc&info_hash=(%11){20}&".into()),
+                Matcher::Regex("info_hash=(%22){20}$".into()),
+            ]))
+            .with_body(body)
+            .create_async()
+            .await;
+
+        let stats = TrackerClient::new()
+            .scrape(
+                &format!("{}/x/announce?passkey=abc", server.url()),
+                &[[0x11; 20], [0x22; 20]],
+            )
+            .await
+            .unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(
+            stats[&[0x11; 20]],
+            ScrapeStats {
+                seeders: 12,
+                leechers: 4,
+                completed: Some(40)
+            }
+        );
+
+        let result = TrackerClient::new()
+            .scrape(&format!("{}/tracker", server.url()), &[[0x11; 20]])
+            .await;
+        assert!(matches!(result, Err(TrackerError::ScrapeUnsupported(_))));
+    }
+
+    /// Tests scraping a local UDP tracker stand-in, batching the info hashes.
+    #[tokio::test]
+    async fn test_scrape_udp() {
+        let tracker = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("udp://{}", tracker.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 2048];
+
+            let (_, from) = tracker.recv_from(&mut buffer).await.unwrap();
+            let mut response = vec![0, 0, 0, 0];
+            response.extend_from_slice(&buffer[12..16]);
+            response.extend_from_slice(&42u64.to_be_bytes());
+            tracker.send_to(&response, from).await.unwrap();
+
+            for expected in [MAX_UDP_SCRAPE_HASHES, 6] {
+                let (length, from) = tracker.recv_from(&mut buffer).await.unwrap();
+                assert_eq!(length, 16 + 20 * expected);
+                assert_eq!(buffer[8..12], UDP_ACTION_SCRAPE.to_be_bytes());
+
+                let mut response = vec![0, 0, 0, 2];
+                response.extend_from_slice(&buffer[12..16]);
+                for hash in buffer[16..length].chunks_exact(20) {
+                    let seeders = u32::from(hash[0]);
+                    response.extend_from_slice(&seeders.to_be_bytes());
+                    response.extend_from_slice(&100u32.to_be_bytes());
+                    response.extend_from_slice(&(seeders * 2).to_be_bytes());
+                }
+                tracker.send_to(&response, from).await.unwrap();
+            }
+        });
+
+        let info_hashes: Vec<[u8; 20]> = (0..80).map(|i| [i as u8; 20]).collect();
+        let stats = TrackerClient::new()
+            .scrape(&url, &info_hashes)
+            .await
+            .unwrap();
+
+        assert_eq!(stats.len(), 80);
+        assert_eq!(
+            stats[&[79; 20]],
+            ScrapeStats {
+                seeders: 79,
+                leechers: 158,
+                completed: Some(100)
+            }
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! # Cache
+//!
+//! An in-memory cache of successful responses. Search requests are
+//! idempotent, so repeated searches within the time to live are answered
+//! without contacting the provider. Requests are told apart by method, URI,
+//! headers and body, so responses are never shared between requests with
+//! different credentials.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::{HeaderMap, Request, Response, StatusCode};
+
+use super::HttpTransport;
+use crate::ClientError;
+
+/// Identifies a request by method, URI, headers sorted by name, and body.
+type CacheKey = (String, String, Vec<(String, Bytes)>, Bytes);
+
+/// A cached response.
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    /// When the response was received.
+    stored_at: Instant,
+
+    /// The response status.
+    status: StatusCode,
+
+    /// The response headers.
+    headers: HeaderMap,
+
+    /// The response body.
+    body: Bytes,
+}
+
+/// A transport caching the successful responses of an inner transport.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+///
+/// use magneto::{transport::CachingTransport, Magneto, ReqwestTransport};
+///
+/// let transport = CachingTransport::new(ReqwestTransport::new(), Duration::from_secs(300));
+/// let magneto = Magneto::new().with_transport(transport);
+/// ```
+#[derive(Debug)]
+pub struct CachingTransport<T> {
+    /// The transport sending requests on a cache miss.
+    inner: T,
+
+    /// How long responses are served from the cache.
+    ttl: Duration,
+
+    /// The maximum number of cached responses.
+    max_entries: usize,
+
+    /// The cached responses.
+    entries: Mutex<HashMap<CacheKey, CacheEntry>>,
+}
+
+impl<T: HttpTransport> CachingTransport<T> {
+    /// Creates a new `CachingTransport` holding up to 256 responses.
+    ///
+    /// # Parameters
+    /// - `inner`: The transport sending requests on a cache miss.
+    /// - `ttl`: How long responses are served from the cache.
+    pub fn new(inner: T, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            max_entries: 256,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets the maximum number of cached responses; the oldest are evicted first.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Removes every cached response.
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Stores a response, evicting expired and then the oldest entries if full.
+    fn store(&self, key: CacheKey, entry: CacheEntry) {
+        if self.max_entries == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            entries.retain(|_, cached| cached.stored_at.elapsed() < self.ttl);
+        }
+        while entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, cached)| cached.stored_at)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(oldest) => entries.remove(&oldest),
+                None => break,
+            };
+        }
+        entries.insert(key, entry);
+    }
+}
+
+#[async_trait]
+impl<T: HttpTransport> HttpTransport for CachingTransport<T> {
+    /// Answers from the cache if a fresh response is stored, otherwise sends the request.
+    async fn send(&self, request: Request<Bytes>) -> Result<Response<Bytes>, ClientError> {
+        let mut headers: Vec<(String, Bytes)> = request
+            .headers()
+            .iter()
+            .map(|(name, value)| (name.to_string(), Bytes::copy_from_slice(value.as_bytes())))
+            .collect();
+        headers.sort();
+        let key = (
+            request.method().to_string(),
+            request.uri().to_string(),
+            headers,
+            request.body().clone(),
+        );
+
+        let cached = self
+            .entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&key)
+            .filter(|cached| cached.stored_at.elapsed() < self.ttl)
+            .cloned();
+        if let Some(cached) = cached {
+            let mut response = Response::new(cached.body);
+            *response.status_mut() = cached.status;
+            *response.headers_mut() = cached.headers;
+            return Ok(response);
+        }
+
+        let response = self.inner.send(request).await?;
+        if response.status().is_success() {
+            self.store(
+                key,
+                CacheEntry {
+                    stored_at: Instant::now(),
+                    status: response.status(),
+                    headers: response.headers().clone(),
+                    body: response.body().clone(),
+                },
+            );
+        }
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::ReqwestTransport;
+    use mockito::Server;
+
+    /// Tests that fresh successful responses are cached and errors, expired and evicted ones are not.
+    #[tokio::test]
+    async fn test_caching_transport() {
+        let mut server = Server::new_async().await;
+        let ok = server
+            .mock("GET", "/ok")
+            .with_body("cached")
+            .expect(2)
+            .create_async()
+            .await;
+        let other = server
+            .mock("GET", "/other")
+            .with_body("other")
+            .expect(1)
+            .create_async()
+            .await;
+        let error = server
+            .mock("GET", "/error")
+            .with_status(500)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let transport = CachingTransport::new(ReqwestTransport::new(), Duration::from_secs(60))
+            .with_max_entries(1);
+        let url = server.url();
+        let get = |path: &str| {
+            Request::get(format!("{}{}", url, path))
+                .body(Bytes::new())
+                .unwrap()
+        };
+
+        for _ in 0..2 {
+            let response = transport.send(get("/ok")).await.unwrap();
+            assert_eq!(response.body().as_ref(), b"cached");
+            transport.send(get("/error")).await.unwrap();
+        }
+
+        // Caching another response evicts the only entry
+        transport.send(get("/other")).await.unwrap();
+        transport.send(get("/ok")).await.unwrap();
+
+        ok.assert_async().await;
+        other.assert_async().await;
+        error.assert_async().await;
+
+        let expiring = CachingTransport::new(ReqwestTransport::new(), Duration::ZERO);
+        let expired = server
+            .mock("GET", "/expired")
+            .expect(2)
+            .create_async()
+            .await;
+        expiring.send(get("/expired")).await.unwrap();
+        expiring.send(get("/expired")).await.unwrap();
+        expired.assert_async().await;
+
+        // Requests differing only in headers do not share responses
+        let private = server
+            .mock("GET", "/private")
+            .expect(2)
+            .create_async()
+            .await;
+        let transport = CachingTransport::new(ReqwestTransport::new(), Duration::from_secs(60));
+        for key in ["a", "b", "a"] {
+            let request = Request::get(format!("{}/private", url))
+                .header("authorization", key)
+                .body(Bytes::new())
+                .unwrap();
+            transport.send(request).await.unwrap();
+        }
+        private.assert_async().await;
+    }
+}
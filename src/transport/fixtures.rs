@@ -0,0 +1,421 @@
+//! # Fixtures
+//!
+//! Recording and replaying of HTTP interactions. A `RecordingTransport` wraps
+//! another transport and saves every request/response pair it sends to a
+//! fixture file. A `ReplayTransport` serves those pairs offline, matching
+//! incoming requests by method, URL, query parameters and body.
+//!
+//! Fixture files are JSON arrays of `Fixture`s. JSON bodies are stored as JSON
+//! so fixtures stay readable and diffable when upstream schemas change.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::{header::CONTENT_TYPE, Request, Response};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use url::Url;
+
+use super::HttpTransport;
+use crate::ClientError;
+
+/// Represents errors that can occur while reading or writing fixture files.
+#[derive(Error, Debug)]
+pub enum FixtureError {
+    /// The fixture file cannot be read or written.
+    #[error("fixture i/o error: {0}")]
+    Io(#[from] io::Error),
+
+    /// The fixture file is not valid.
+    #[error("invalid fixture file: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// A recorded HTTP body, stored as JSON when it parses as JSON and as text otherwise.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct RecordedBody {
+    /// The body as JSON.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json: Option<serde_json::Value>,
+
+    /// The body as text, if it is not JSON.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+impl RecordedBody {
+    /// Records a body, which must be UTF-8.
+    pub fn from_bytes(body: &[u8]) -> Self {
+        if body.is_empty() {
+            return Self::default();
+        }
+        match serde_json::from_slice(body) {
+            Ok(json) => Self {
+                json: Some(json),
+                text: None,
+            },
+            Err(_) => Self {
+                json: None,
+                text: Some(String::from_utf8_lossy(body).into_owned()),
+            },
+        }
+    }
+
+    /// Returns the body as bytes.
+    pub fn to_bytes(&self) -> Bytes {
+        match (&self.json, &self.text) {
+            (Some(json), _) => Bytes::from(json.to_string()),
+            (None, Some(text)) => Bytes::from(text.clone()),
+            (None, None) => Bytes::new(),
+        }
+    }
+
+    /// Returns whether the body is empty.
+    fn is_empty(&self) -> bool {
+        self.json.is_none() && self.text.is_none()
+    }
+}
+
+/// A recorded request.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordedRequest {
+    /// The request method, e.g. "GET".
+    pub method: String,
+
+    /// The full request URL, including the query.
+    pub url: String,
+
+    /// The request body.
+    #[serde(default, flatten)]
+    pub body: RecordedBody,
+}
+
+/// A recorded response.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordedResponse {
+    /// The response status code.
+    pub status: u16,
+
+    /// The recorded response headers. Only `content-type` is recorded.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+
+    /// The response body.
+    #[serde(default, flatten)]
+    pub body: RecordedBody,
+}
+
+/// A recorded request/response pair.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Fixture {
+    /// The request sent.
+    pub request: RecordedRequest,
+
+    /// The response received.
+    pub response: RecordedResponse,
+}
+
+impl Fixture {
+    /// Returns whether the fixture was recorded for a request equivalent to the given one.
+    ///
+    /// Requests match when their methods, URLs without query, query parameters in
+    /// any order and bodies are equal. JSON bodies are compared as JSON values.
+    fn matches(&self, method: &str, url: &Url, body: &RecordedBody) -> bool {
+        let Ok(recorded_url) = Url::parse(&self.request.url) else {
+            return false;
+        };
+
+        self.request.method.eq_ignore_ascii_case(method)
+            && without_query(&recorded_url) == without_query(url)
+            && sorted_query(&recorded_url) == sorted_query(url)
+            && (self.request.body == *body || self.request.body.is_empty() && body.is_empty())
+    }
+}
+
+/// Reads the fixtures of a fixture file.
+pub fn load_fixtures(path: impl AsRef<Path>) -> Result<Vec<Fixture>, FixtureError> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// Writes fixtures to a fixture file.
+pub fn save_fixtures(path: impl AsRef<Path>, fixtures: &[Fixture]) -> Result<(), FixtureError> {
+    let mut json = serde_json::to_string_pretty(fixtures)?;
+    json.push('\n');
+    fs::write(path, json)?;
+    Ok(())
+}
+
+/// A transport recording every interaction of an inner transport to a fixture file.
+///
+/// The file is rewritten after each request, so it is complete even if the
+/// program stops early.
+///
+/// # Example
+/// ```no_run
+/// use magneto::{
+///     transport::fixtures::RecordingTransport, Knaben, ReqwestTransport, SearchProvider,
+///     SearchRequest,
+/// };
+///
+/// # async fn example() {
+/// let transport = RecordingTransport::new(ReqwestTransport::new(), "fixtures/knaben.json");
+/// Knaben::new()
+///     .send_request(&transport, SearchRequest::new("ubuntu"))
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RecordingTransport<T> {
+    /// The transport sending the requests.
+    inner: T,
+
+    /// The fixture file written to.
+    path: PathBuf,
+
+    /// The interactions recorded so far.
+    fixtures: Mutex<Vec<Fixture>>,
+}
+
+impl<T: HttpTransport> RecordingTransport<T> {
+    /// Creates a new `RecordingTransport`, replacing the fixture file on the first request.
+    ///
+    /// # Parameters
+    /// - `inner`: The transport sending the requests, usually `ReqwestTransport`.
+    /// - `path`: The fixture file to write.
+    pub fn new(inner: T, path: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            path: path.into(),
+            fixtures: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the interactions recorded so far.
+    pub fn fixtures(&self) -> Vec<Fixture> {
+        self.fixtures
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+#[async_trait]
+impl<T: HttpTransport> HttpTransport for RecordingTransport<T> {
+    /// Sends the request with the inner transport and records the interaction.
+    async fn send(&self, request: Request<Bytes>) -> Result<Response<Bytes>, ClientError> {
+        let recorded_request = RecordedRequest {
+            method: request.method().to_string(),
+            url: request.uri().to_string(),
+            body: RecordedBody::from_bytes(request.body()),
+        };
+
+        let response = self.inner.send(request).await?;
+
+        let headers = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| (CONTENT_TYPE.to_string(), value.to_string()))
+            .into_iter()
+            .collect();
+        let fixture = Fixture {
+            request: recorded_request,
+            response: RecordedResponse {
+                status: response.status().as_u16(),
+                headers,
+                body: RecordedBody::from_bytes(response.body()),
+            },
+        };
+
+        let fixtures = {
+            let mut fixtures = self.fixtures.lock().unwrap_or_else(|e| e.into_inner());
+            fixtures.push(fixture);
+            fixtures.clone()
+        };
+        save_fixtures(&self.path, &fixtures).map_err(|e| ClientError::ResponseError(e.into()))?;
+
+        Ok(response)
+    }
+}
+
+/// A transport answering requests from recorded fixtures, without network access.
+///
+/// Requests without a matching fixture fail with `ClientError::ResponseError`.
+///
+/// # Example
+/// ```no_run
+/// use magneto::{transport::fixtures::ReplayTransport, Magneto, SearchRequest};
+///
+/// # async fn example() {
+/// let transport = ReplayTransport::from_file("fixtures/knaben.json").unwrap();
+/// let magneto = Magneto::new().with_transport(transport);
+/// let torrents = magneto.search(SearchRequest::new("ubuntu")).await;
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ReplayTransport {
+    /// The fixtures served.
+    fixtures: Vec<Fixture>,
+}
+
+impl ReplayTransport {
+    /// Creates a new `ReplayTransport` serving the given fixtures.
+    pub fn new(fixtures: Vec<Fixture>) -> Self {
+        Self { fixtures }
+    }
+
+    /// Creates a new `ReplayTransport` serving the fixtures of a fixture file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, FixtureError> {
+        Ok(Self::new(load_fixtures(path)?))
+    }
+
+    /// Adds the fixtures of another fixture file.
+    pub fn add_file(mut self, path: impl AsRef<Path>) -> Result<Self, FixtureError> {
+        self.fixtures.extend(load_fixtures(path)?);
+        Ok(self)
+    }
+}
+
+#[async_trait]
+impl HttpTransport for ReplayTransport {
+    /// Answers the request with the response of the first matching fixture.
+    async fn send(&self, request: Request<Bytes>) -> Result<Response<Bytes>, ClientError> {
+        let url = Url::parse(&request.uri().to_string())
+            .map_err(|e| ClientError::ResponseError(e.into()))?;
+        let body = RecordedBody::from_bytes(request.body());
+
+        let fixture = self
+            .fixtures
+            .iter()
+            .find(|fixture| fixture.matches(request.method().as_str(), &url, &body))
+            .ok_or_else(|| {
+                ClientError::ResponseError(anyhow!(
+                    "no fixture matches {} {}",
+                    request.method(),
+                    url
+                ))
+            })?;
+
+        let mut response = Response::builder().status(fixture.response.status);
+        for (name, value) in &fixture.response.headers {
+            response = response.header(name, value);
+        }
+        response
+            .body(fixture.response.body.to_bytes())
+            .map_err(|e| ClientError::ResponseError(e.into()))
+    }
+}
+
+/// Returns the URL without query and fragment.
+fn without_query(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_query(None);
+    url.set_fragment(None);
+    url.to_string()
+}
+
+/// Returns the query parameters of the URL, sorted.
+fn sorted_query(url: &Url) -> Vec<(String, String)> {
+    let mut query: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    query.sort();
+    query
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Server;
+
+    use crate::transport::ReqwestTransport;
+
+    /// Tests recording interactions against a live server and replaying them offline.
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let mut server = Server::new_async().await;
+        let _json = server
+            .mock("POST", "/search?a=1&b=2")
+            .with_header("content-type", "application/json")
+            .with_header("set-cookie", "secret")
+            .with_body(r#"{"hits": [1, 2]}"#)
+            .create_async()
+            .await;
+        let _text = server
+            .mock("GET", "/text")
+            .with_status(404)
+            .with_body("not found")
+            .create_async()
+            .await;
+
+        let path =
+            std::env::temp_dir().join(format!("magneto-fixture-{}.json", rand::random::<u64>()));
+        let recorder = RecordingTransport::new(ReqwestTransport::new(), &path);
+        recorder
+            .send(
+                Request::post(format!("{}/search?a=1&b=2", server.url()))
+                    .body(Bytes::from_static(br#"{"query": "x", "size": 5}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        recorder
+            .send(
+                Request::get(format!("{}/text", server.url()))
+                    .body(Bytes::new())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let fixtures = load_fixtures(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(fixtures, recorder.fixtures());
+        assert_eq!(
+            fixtures[0].response.headers,
+            BTreeMap::from([("content-type".to_string(), "application/json".to_string())])
+        );
+
+        let replay = ReplayTransport::new(fixtures);
+        // Query order and JSON formatting do not matter
+        let response = replay
+            .send(
+                Request::post(format!("{}/search?b=2&a=1", server.url()))
+                    .body(Bytes::from_static(br#"{"size":5,"query":"x"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(response.body()).unwrap(),
+            serde_json::json!({"hits": [1, 2]})
+        );
+
+        let response = replay
+            .send(
+                Request::get(format!("{}/text", server.url()))
+                    .body(Bytes::new())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+        assert_eq!(response.body().as_ref(), b"not found");
+
+        let result = replay
+            .send(
+                Request::post(format!("{}/search?a=1&b=2", server.url()))
+                    .body(Bytes::from_static(br#"{"query": "y"}"#))
+                    .unwrap(),
+            )
+            .await;
+        assert!(matches!(result, Err(ClientError::ResponseError(_))));
+    }
+}
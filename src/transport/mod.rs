@@ -0,0 +1,161 @@
+//! # Transport
+//!
+//! The HTTP layer used by search providers and trackers. Requests and
+//! responses are expressed with the types of the `http` crate, so any HTTP
+//! client can be plugged in by implementing `HttpTransport`. The default
+//! transport, `ReqwestTransport`, is backed by `reqwest`. The `cache`
+//! module caches responses, and the `fixtures` module records and replays
+//! interactions for offline testing.
+
+use core::fmt;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::{Request, Response};
+
+use crate::ClientError;
+
+pub mod cache;
+pub mod fixtures;
+
+pub use cache::CachingTransport;
+pub use fixtures::{RecordingTransport, ReplayTransport};
+
+/// The `HttpTransport` trait sends HTTP requests on behalf of providers.
+///
+/// Implementors convert the request into their client's request type, send it
+/// and convert the response back. Errors should be reported as
+/// `ClientError::ResponseError`; non-success status codes are not errors at
+/// this level and are handled by the caller.
+///
+/// # Example
+/// ```
+/// use magneto::{async_trait, Bytes, ClientError, HttpTransport, Request, Response};
+///
+/// /// A transport answering every request with an empty JSON list.
+/// struct StaticTransport;
+///
+/// #[async_trait]
+/// impl HttpTransport for StaticTransport {
+///     async fn send(&self, _request: Request<Bytes>) -> Result<Response<Bytes>, ClientError> {
+///         Ok(Response::new(Bytes::from_static(b"[]")))
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait HttpTransport: Send + Sync {
+    /// Sends a request and returns the response with its full body.
+    ///
+    /// # Parameters
+    /// - `request`: The HTTP request to send.
+    ///
+    /// # Returns
+    /// - `Ok(Response<Bytes>)`: The response, whatever its status code.
+    /// - `Err(ClientError)`: An error if no response was received.
+    async fn send(&self, request: Request<Bytes>) -> Result<Response<Bytes>, ClientError>;
+}
+
+impl fmt::Debug for dyn HttpTransport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HttpTransport")
+    }
+}
+
+#[async_trait]
+impl<T: HttpTransport + ?Sized> HttpTransport for Arc<T> {
+    async fn send(&self, request: Request<Bytes>) -> Result<Response<Bytes>, ClientError> {
+        (**self).send(request).await
+    }
+}
+
+/// The default `HttpTransport`, sending requests with a `reqwest::Client`.
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    /// The client used to send requests.
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    /// Creates a new `ReqwestTransport` with a default client.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new `ReqwestTransport` with a custom client, e.g. one with a proxy or timeouts.
+    ///
+    /// # Parameters
+    /// - `client`: The `reqwest::Client` used to send requests.
+    pub fn with_client(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl HttpTransport for ReqwestTransport {
+    /// Sends the request with the `reqwest::Client`.
+    async fn send(&self, request: Request<Bytes>) -> Result<Response<Bytes>, ClientError> {
+        let url = request.uri().to_string();
+        let request =
+            reqwest::Request::try_from(request.map(reqwest::Body::from)).map_err(|e| {
+                ClientError::RequestBuildError {
+                    source: e.into(),
+                    url,
+                }
+            })?;
+
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(|e| ClientError::ResponseError(e.into()))?;
+
+        let mut builder = Response::builder()
+            .status(response.status())
+            .version(response.version());
+        if let Some(headers) = builder.headers_mut() {
+            *headers = response.headers().clone();
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| ClientError::ResponseError(e.into()))?;
+
+        builder
+            .body(body)
+            .map_err(|e| ClientError::ResponseError(e.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Server;
+
+    /// Tests that the reqwest transport sends method, headers and body and returns the response.
+    #[tokio::test]
+    async fn test_reqwest_transport() {
+        let mut server = Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/echo?q=1")
+            .match_header("x-test", "yes")
+            .match_body("ping")
+            .with_status(201)
+            .with_header("x-reply", "ok")
+            .with_body("pong")
+            .create_async()
+            .await;
+
+        let request = Request::post(format!("{}/echo?q=1", server.url()))
+            .header("x-test", "yes")
+            .body(Bytes::from_static(b"ping"))
+            .unwrap();
+        let transport: Arc<dyn HttpTransport> = Arc::new(ReqwestTransport::new());
+        let response = transport.send(request).await.unwrap();
+
+        assert_eq!(response.status(), 201);
+        assert_eq!(response.headers()["x-reply"], "ok");
+        assert_eq!(response.body().as_ref(), b"pong");
+    }
+}
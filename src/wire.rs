@@ -0,0 +1,691 @@
+//! # Peer wire protocol
+//!
+//! A minimal client for the BitTorrent peer wire protocol (BEP 3) that
+//! downloads the info dictionary of a torrent from peers using the extension
+//! protocol (BEP 10) and the metadata extension (BEP 9). This turns a magnet
+//! link into the full file list and a .torrent file without downloading any
+//! content.
+
+use std::{net::SocketAddr, time::Duration};
+
+use log::debug;
+use sha1::{Digest, Sha1};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    task::JoinSet,
+    time::timeout,
+};
+
+use crate::{
+    bencode::{self, BencodeError, Value},
+    magnet::{Magnet, MagnetError},
+    metainfo::{self, Metainfo, MetainfoError},
+    tracker::TrackerClient,
+    Torrent,
+};
+
+/// The protocol string sent in the handshake.
+const PROTOCOL: &[u8; 19] = b"BitTorrent protocol";
+
+/// The message id of extension protocol messages (BEP 10).
+const EXTENDED_MESSAGE_ID: u8 = 20;
+
+/// The extended message id of the extension handshake.
+const EXTENDED_HANDSHAKE_ID: u8 = 0;
+
+/// The extended message id we assign to `ut_metadata` in our handshake.
+const UT_METADATA_ID: u8 = 1;
+
+/// The size of a metadata piece (BEP 9).
+const METADATA_PIECE_SIZE: usize = 16 * 1024;
+
+/// The largest accepted info dictionary.
+const MAX_METADATA_SIZE: usize = 16 * 1024 * 1024;
+
+/// The largest accepted peer message.
+const MAX_MESSAGE_SIZE: usize = 2 * 1024 * 1024;
+
+/// `ut_metadata` message types.
+const METADATA_REQUEST: i64 = 0;
+const METADATA_DATA: i64 = 1;
+const METADATA_REJECT: i64 = 2;
+
+/// Represents errors that can occur while fetching metadata from peers.
+#[derive(Error, Debug)]
+pub enum WireError {
+    /// A network error occurred.
+    #[error("peer i/o error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The peer did not respond in time.
+    #[error("peer timed out")]
+    Timeout,
+
+    /// The peer sent an invalid handshake or a different info hash.
+    #[error("invalid handshake: {0}")]
+    Handshake(String),
+
+    /// The peer does not support the extension protocol or metadata exchange.
+    #[error("peer does not support metadata exchange")]
+    Unsupported,
+
+    /// The peer rejected a metadata request.
+    #[error("peer rejected metadata request for piece {0}")]
+    Rejected(usize),
+
+    /// The peer sent a malformed message.
+    #[error("invalid peer message: {0}")]
+    InvalidMessage(String),
+
+    /// The downloaded metadata does not match the info hash.
+    #[error("metadata does not match info hash")]
+    HashMismatch,
+
+    /// An extension message is not valid bencode.
+    #[error("invalid bencode in peer message: {0}")]
+    Bencode(#[from] BencodeError),
+
+    /// The downloaded info dictionary cannot be parsed.
+    #[error("invalid metadata: {0}")]
+    Metainfo(#[from] MetainfoError),
+
+    /// The torrent's magnet link is invalid.
+    #[error("invalid magnet link: {0}")]
+    Magnet(#[from] MagnetError),
+
+    /// The magnet link has no v1 info hash, which the wire protocol requires.
+    #[error("magnet link has no v1 info hash")]
+    MissingInfoHash,
+
+    /// No peers were found or all peers failed.
+    #[error("no peer provided the metadata")]
+    NoPeers,
+}
+
+/// Metadata downloaded from peers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchedMetadata {
+    /// The parsed metadata, including the trackers used to find peers.
+    pub metainfo: Metainfo,
+
+    /// The raw bencoded info dictionary, matching the info hash.
+    pub info_bytes: Vec<u8>,
+}
+
+impl FetchedMetadata {
+    /// Builds the contents of a .torrent file from the downloaded metadata.
+    pub fn to_torrent_file(&self) -> Vec<u8> {
+        metainfo::build_torrent_file(
+            &self.info_bytes,
+            &self.metainfo.announce_list,
+            &self.metainfo.web_seeds,
+        )
+    }
+}
+
+/// An open connection to a peer that supports metadata exchange.
+pub struct PeerConnection {
+    /// The TCP stream to the peer.
+    stream: TcpStream,
+
+    /// The extended message id the peer assigned to `ut_metadata`.
+    peer_metadata_id: u8,
+
+    /// The size of the info dictionary as announced by the peer.
+    metadata_size: usize,
+}
+
+impl PeerConnection {
+    /// Connects to a peer and performs the BitTorrent and extension handshakes.
+    ///
+    /// # Parameters
+    /// - `address`: The address of the peer.
+    /// - `info_hash`: The v1 info hash of the torrent.
+    /// - `peer_id`: Our peer id.
+    ///
+    /// # Returns
+    /// - `Ok(PeerConnection)`: A connection to a peer able to send the metadata.
+    /// - `Err(WireError)`: An error if the peer is unreachable or lacks support.
+    pub async fn connect(
+        address: SocketAddr,
+        info_hash: &[u8; 20],
+        peer_id: &[u8; 20],
+    ) -> Result<Self, WireError> {
+        let mut stream = TcpStream::connect(address).await?;
+
+        let mut handshake = Vec::with_capacity(68);
+        handshake.push(PROTOCOL.len() as u8);
+        handshake.extend_from_slice(PROTOCOL);
+        // Reserved bytes, with the bit announcing extension protocol support
+        handshake.extend_from_slice(&[0, 0, 0, 0, 0, 0x10, 0, 0]);
+        handshake.extend_from_slice(info_hash);
+        handshake.extend_from_slice(peer_id);
+        stream.write_all(&handshake).await?;
+
+        let mut response = [0u8; 68];
+        stream.read_exact(&mut response).await?;
+        if response[0] as usize != PROTOCOL.len() || &response[1..20] != PROTOCOL {
+            return Err(WireError::Handshake("unknown protocol".to_string()));
+        }
+        if &response[28..48] != info_hash {
+            return Err(WireError::Handshake("info hash mismatch".to_string()));
+        }
+        if response[25] & 0x10 == 0 {
+            return Err(WireError::Unsupported);
+        }
+
+        let extension_handshake = Value::dict().with(
+            "m",
+            Value::dict().with("ut_metadata", Value::Integer(UT_METADATA_ID.into())),
+        );
+        let mut connection = Self {
+            stream,
+            peer_metadata_id: 0,
+            metadata_size: 0,
+        };
+        connection
+            .send_extended(
+                EXTENDED_HANDSHAKE_ID,
+                &bencode::encode(&extension_handshake),
+            )
+            .await?;
+
+        // Other messages, such as bitfields, may arrive before the extension handshake
+        loop {
+            let (extended_id, payload) = connection.read_extended().await?;
+            if extended_id != EXTENDED_HANDSHAKE_ID {
+                continue;
+            }
+
+            let handshake = bencode::decode(&payload)?;
+            let peer_metadata_id = handshake
+                .get("m")
+                .and_then(|m| m.get("ut_metadata"))
+                .and_then(Value::as_integer)
+                .and_then(|id| u8::try_from(id).ok())
+                .filter(|id| *id != 0)
+                .ok_or(WireError::Unsupported)?;
+            let metadata_size = handshake
+                .get("metadata_size")
+                .and_then(Value::as_integer)
+                .and_then(|size| usize::try_from(size).ok())
+                .filter(|size| (1..=MAX_METADATA_SIZE).contains(size))
+                .ok_or_else(|| WireError::InvalidMessage("invalid metadata_size".to_string()))?;
+
+            connection.peer_metadata_id = peer_metadata_id;
+            connection.metadata_size = metadata_size;
+            return Ok(connection);
+        }
+    }
+
+    /// Downloads the info dictionary and verifies it against the info hash.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<u8>)`: The raw bencoded info dictionary.
+    /// - `Err(WireError)`: An error if the peer rejects a request or sends invalid data.
+    pub async fn fetch_metadata(&mut self, info_hash: &[u8; 20]) -> Result<Vec<u8>, WireError> {
+        let pieces = self.metadata_size.div_ceil(METADATA_PIECE_SIZE);
+        for piece in 0..pieces {
+            let request = Value::dict()
+                .with("msg_type", Value::Integer(METADATA_REQUEST))
+                .with("piece", Value::Integer(piece as i64));
+            self.send_extended(self.peer_metadata_id, &bencode::encode(&request))
+                .await?;
+        }
+
+        let mut metadata = vec![0u8; self.metadata_size];
+        let mut received = vec![false; pieces];
+        while received.iter().any(|received| !received) {
+            let (extended_id, payload) = self.read_extended().await?;
+            if extended_id != UT_METADATA_ID {
+                continue;
+            }
+
+            let (header, header_length) = bencode::decode_prefix(&payload)?;
+            let piece = header
+                .get("piece")
+                .and_then(Value::as_integer)
+                .and_then(|piece| usize::try_from(piece).ok())
+                .filter(|piece| *piece < pieces)
+                .ok_or_else(|| WireError::InvalidMessage("invalid metadata piece".to_string()))?;
+
+            match header.get("msg_type").and_then(Value::as_integer) {
+                Some(METADATA_DATA) => {
+                    let data = &payload[header_length..];
+                    let start = piece * METADATA_PIECE_SIZE;
+                    let end = (start + METADATA_PIECE_SIZE).min(self.metadata_size);
+                    if data.len() != end - start {
+                        return Err(WireError::InvalidMessage(format!(
+                            "metadata piece {} has {} bytes, expected {}",
+                            piece,
+                            data.len(),
+                            end - start
+                        )));
+                    }
+                    metadata[start..end].copy_from_slice(data);
+                    received[piece] = true;
+                }
+                Some(METADATA_REJECT) => return Err(WireError::Rejected(piece)),
+                _ => continue,
+            }
+        }
+
+        if Sha1::digest(&metadata).as_slice() != info_hash {
+            return Err(WireError::HashMismatch);
+        }
+
+        Ok(metadata)
+    }
+
+    /// Sends an extension protocol message.
+    async fn send_extended(&mut self, extended_id: u8, payload: &[u8]) -> Result<(), WireError> {
+        let length = u32::try_from(payload.len() + 2)
+            .map_err(|_| WireError::InvalidMessage("message too large".to_string()))?;
+
+        let mut message = Vec::with_capacity(payload.len() + 6);
+        message.extend_from_slice(&length.to_be_bytes());
+        message.push(EXTENDED_MESSAGE_ID);
+        message.push(extended_id);
+        message.extend_from_slice(payload);
+        self.stream.write_all(&message).await?;
+        Ok(())
+    }
+
+    /// Reads messages until an extension protocol message arrives.
+    ///
+    /// # Returns
+    /// - `Ok((u8, Vec<u8>))`: The extended message id and the message payload.
+    async fn read_extended(&mut self) -> Result<(u8, Vec<u8>), WireError> {
+        loop {
+            let length = self.stream.read_u32().await? as usize;
+            if length == 0 {
+                continue; // keep-alive
+            }
+            if length > MAX_MESSAGE_SIZE {
+                return Err(WireError::InvalidMessage(format!(
+                    "message of {} bytes",
+                    length
+                )));
+            }
+
+            let mut message = vec![0u8; length];
+            self.stream.read_exact(&mut message).await?;
+            if message[0] == EXTENDED_MESSAGE_ID && message.len() >= 2 {
+                return Ok((message[1], message.split_off(2)));
+            }
+        }
+    }
+}
+
+/// Fetches torrent metadata by finding peers through trackers and asking them
+/// for the info dictionary.
+///
+/// # Example
+/// ```no_run
+/// use magneto::{wire::MetadataFetcher, Magneto, SearchRequest};
+///
+/// # async fn example() {
+/// let magneto = Magneto::new();
+/// let torrents = magneto.search(SearchRequest::new("Ubuntu")).await.unwrap();
+///
+/// let fetcher = MetadataFetcher::new();
+/// let metadata = fetcher.fetch(&torrents[0]).await.unwrap();
+/// for file in metadata.metainfo.files {
+///     println!("{} ({} bytes)", file.path.join("/"), file.length);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MetadataFetcher {
+    /// The client used to find peers through trackers.
+    tracker_client: TrackerClient,
+
+    /// Trackers used in addition to the ones in the magnet link.
+    trackers: Vec<String>,
+
+    /// The timeout for fetching the metadata from a single peer.
+    peer_timeout: Duration,
+
+    /// The maximum number of peers contacted at the same time.
+    max_connections: usize,
+}
+
+impl MetadataFetcher {
+    /// Creates a new `MetadataFetcher` with a 10 second peer timeout and up to 8 connections.
+    pub fn new() -> Self {
+        Self {
+            tracker_client: TrackerClient::new(),
+            trackers: vec![],
+            peer_timeout: Duration::from_secs(10),
+            max_connections: 8,
+        }
+    }
+
+    /// Sets the timeout for fetching the metadata from a single peer.
+    pub fn with_peer_timeout(mut self, timeout: Duration) -> Self {
+        self.peer_timeout = timeout;
+        self
+    }
+
+    /// Sets the maximum number of peers contacted at the same time.
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections.max(1);
+        self
+    }
+
+    /// Adds trackers used in addition to the ones in the magnet link.
+    pub fn with_trackers(mut self, trackers: Vec<String>) -> Self {
+        self.trackers.extend(trackers);
+        self
+    }
+
+    /// Sets the client used to find peers through trackers.
+    pub fn with_tracker_client(mut self, tracker_client: TrackerClient) -> Self {
+        self.tracker_client = tracker_client;
+        self
+    }
+
+    /// Fetches the metadata of a torrent returned by a search provider.
+    ///
+    /// # Parameters
+    /// - `torrent`: The torrent, whose magnet link provides the info hash and trackers.
+    ///
+    /// # Returns
+    /// - `Ok(FetchedMetadata)`: The metadata, with the file list and a .torrent file.
+    /// - `Err(WireError)`: An error if no peer provided valid metadata.
+    pub async fn fetch(&self, torrent: &Torrent) -> Result<FetchedMetadata, WireError> {
+        self.fetch_magnet(&torrent.magnet()?).await
+    }
+
+    /// Fetches the metadata of a magnet link.
+    ///
+    /// Peers are requested from the magnet link's trackers and the fetcher's
+    /// additional trackers. Tracker errors are logged and otherwise ignored.
+    pub async fn fetch_magnet(&self, magnet: &Magnet) -> Result<FetchedMetadata, WireError> {
+        let info_hash = magnet.info_hash.ok_or(WireError::MissingInfoHash)?;
+
+        let mut trackers = magnet.trackers.clone();
+        for tracker in &self.trackers {
+            if !trackers.contains(tracker) {
+                trackers.push(tracker.clone());
+            }
+        }
+
+        let mut announces = JoinSet::new();
+        for tracker in &trackers {
+            let client = self.tracker_client.clone();
+            let tracker = tracker.clone();
+            announces.spawn(async move {
+                let result = client.announce(&tracker, &info_hash).await;
+                (tracker, result)
+            });
+        }
+
+        let mut peers = Vec::new();
+        while let Some(Ok((tracker, result))) = announces.join_next().await {
+            match result {
+                Ok(announce) => {
+                    for peer in announce.peers {
+                        if !peers.contains(&peer) {
+                            peers.push(peer);
+                        }
+                    }
+                }
+                Err(e) => debug!("announce to {} failed: {}", tracker, e),
+            }
+        }
+
+        let mut metadata = self.fetch_from_peers(&info_hash, &peers).await?;
+        metadata.metainfo.announce_list =
+            trackers.into_iter().map(|tracker| vec![tracker]).collect();
+        metadata.metainfo.web_seeds = magnet.web_seeds.clone();
+        Ok(metadata)
+    }
+
+    /// Fetches metadata directly from the given peers.
+    ///
+    /// Up to `max_connections` peers are contacted at once and the first valid
+    /// metadata is returned.
+    pub async fn fetch_from_peers(
+        &self,
+        info_hash: &[u8; 20],
+        peers: &[SocketAddr],
+    ) -> Result<FetchedMetadata, WireError> {
+        let mut pending = peers.iter().copied();
+        let mut tasks = JoinSet::new();
+        let mut last_error = None;
+
+        loop {
+            while tasks.len() < self.max_connections {
+                let Some(peer) = pending.next() else { break };
+                let info_hash = *info_hash;
+                let peer_id = self.tracker_client.peer_id();
+                let peer_timeout = self.peer_timeout;
+
+                tasks.spawn(async move {
+                    let fetch = async {
+                        let mut connection =
+                            PeerConnection::connect(peer, &info_hash, &peer_id).await?;
+                        connection.fetch_metadata(&info_hash).await
+                    };
+                    let result = timeout(peer_timeout, fetch)
+                        .await
+                        .unwrap_or(Err(WireError::Timeout));
+                    (peer, result)
+                });
+            }
+
+            let Some(joined) = tasks.join_next().await else {
+                break;
+            };
+            let Ok((peer, result)) = joined else { continue };
+
+            match result {
+                Ok(info_bytes) => {
+                    let metainfo = Metainfo::from_info_bytes(&info_bytes)?;
+                    return Ok(FetchedMetadata {
+                        metainfo,
+                        info_bytes,
+                    });
+                }
+                Err(e) => {
+                    debug!("fetching metadata from {} failed: {}", peer, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or(WireError::NoPeers))
+    }
+}
+
+impl Default for MetadataFetcher {
+    /// Provides a default implementation for `MetadataFetcher`, equal to `MetadataFetcher::new()`.
+    fn default() -> Self {
+        MetadataFetcher::new()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+    use tokio::net::TcpListener;
+
+    /// Builds an info dictionary larger than one metadata piece.
+    fn info_bytes() -> Vec<u8> {
+        bencode::encode(
+            &Value::dict()
+                .with("name", Value::bytes("large.iso"))
+                .with("length", Value::Integer(1 << 30))
+                .with("piece length", Value::Integer(1 << 20))
+                .with("pieces", Value::bytes(vec![7u8; 20 * 1024])),
+        )
+    }
+
+    /// How the local test peer answers metadata requests.
+    #[derive(Clone, Copy, PartialEq)]
+    pub(crate) enum PeerBehavior {
+        Serve,
+        Reject,
+        Corrupt,
+    }
+
+    /// Starts a local peer serving the given info dictionary and returns its address.
+    pub(crate) async fn spawn_peer(info: Vec<u8>, behavior: PeerBehavior) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let info_hash: [u8; 20] = Sha1::digest(&info).into();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+
+            let mut handshake = [0u8; 68];
+            stream.read_exact(&mut handshake).await.unwrap();
+            assert_eq!(handshake[25] & 0x10, 0x10);
+            let mut response = handshake;
+            response[48..].copy_from_slice(&[b'p'; 20]);
+            response[28..48].copy_from_slice(&info_hash);
+            stream.write_all(&response).await.unwrap();
+
+            // A bitfield before the extension handshake must be skipped by the client
+            stream.write_all(&[0, 0, 0, 2, 5, 0xff]).await.unwrap();
+
+            let handshake = bencode::encode(
+                &Value::dict()
+                    .with("m", Value::dict().with("ut_metadata", Value::Integer(3)))
+                    .with("metadata_size", Value::Integer(info.len() as i64)),
+            );
+            write_extended(&mut stream, 0, &handshake).await;
+
+            loop {
+                let Ok(length) = stream.read_u32().await else {
+                    return;
+                };
+                let mut message = vec![0u8; length as usize];
+                stream.read_exact(&mut message).await.unwrap();
+                if message[1] != 3 {
+                    continue;
+                }
+
+                let request = bencode::decode(&message[2..]).unwrap();
+                let piece = request.get("piece").and_then(Value::as_integer).unwrap();
+                let msg_type = if behavior == PeerBehavior::Reject {
+                    2
+                } else {
+                    1
+                };
+                let mut payload = bencode::encode(
+                    &Value::dict()
+                        .with("msg_type", Value::Integer(msg_type))
+                        .with("piece", Value::Integer(piece))
+                        .with("total_size", Value::Integer(info.len() as i64)),
+                );
+                if behavior != PeerBehavior::Reject {
+                    let start = piece as usize * METADATA_PIECE_SIZE;
+                    let end = (start + METADATA_PIECE_SIZE).min(info.len());
+                    let mut data = info[start..end].to_vec();
+                    if behavior == PeerBehavior::Corrupt {
+                        data[0] ^= 0xff;
+                    }
+                    payload.extend_from_slice(&data);
+                }
+                write_extended(&mut stream, UT_METADATA_ID, &payload).await;
+            }
+        });
+
+        address
+    }
+
+    /// Writes an extension protocol message from the test peer.
+    async fn write_extended(stream: &mut TcpStream, extended_id: u8, payload: &[u8]) {
+        stream
+            .write_all(&(payload.len() as u32 + 2).to_be_bytes())
+            .await
+            .unwrap();
+        stream
+            .write_all(&[EXTENDED_MESSAGE_ID, extended_id])
+            .await
+            .unwrap();
+        stream.write_all(payload).await.unwrap();
+    }
+
+    /// Tests fetching multi-piece metadata from a local peer.
+    #[tokio::test]
+    async fn test_fetch_from_peer() {
+        let info = info_bytes();
+        let info_hash: [u8; 20] = Sha1::digest(&info).into();
+        let peer = spawn_peer(info.clone(), PeerBehavior::Serve).await;
+
+        let metadata = MetadataFetcher::new()
+            .fetch_from_peers(&info_hash, &[peer])
+            .await
+            .unwrap();
+
+        assert_eq!(metadata.info_bytes, info);
+        assert_eq!(metadata.metainfo.name, "large.iso");
+        assert_eq!(metadata.metainfo.info_hash, Some(info_hash));
+        assert_eq!(metadata.metainfo.total_size(), 1 << 30);
+
+        let torrent_file = Metainfo::from_bytes(&metadata.to_torrent_file()).unwrap();
+        assert_eq!(torrent_file, metadata.metainfo);
+    }
+
+    /// Tests that rejected requests and corrupt metadata are reported as errors.
+    #[tokio::test]
+    async fn test_fetch_errors() {
+        let info = info_bytes();
+        let info_hash: [u8; 20] = Sha1::digest(&info).into();
+        let fetcher = MetadataFetcher::new();
+
+        let peer = spawn_peer(info.clone(), PeerBehavior::Reject).await;
+        let result = fetcher.fetch_from_peers(&info_hash, &[peer]).await;
+        assert!(matches!(result, Err(WireError::Rejected(_))));
+
+        let peer = spawn_peer(info.clone(), PeerBehavior::Corrupt).await;
+        let result = fetcher.fetch_from_peers(&info_hash, &[peer]).await;
+        assert!(matches!(result, Err(WireError::HashMismatch)));
+
+        let result = fetcher.fetch_from_peers(&info_hash, &[]).await;
+        assert!(matches!(result, Err(WireError::NoPeers)));
+    }
+
+    /// Tests fetching the metadata of a search result, finding the peer through its tracker.
+    #[tokio::test]
+    async fn test_fetch_torrent_through_tracker() {
+        let info = info_bytes();
+        let info_hash: [u8; 20] = Sha1::digest(&info).into();
+        let peer = spawn_peer(info.clone(), PeerBehavior::Serve).await;
+
+        let mut tracker = Server::new_async().await;
+        let mut body = b"d5:peers6:".to_vec();
+        body.extend_from_slice(&[127, 0, 0, 1]);
+        body.extend_from_slice(&peer.port().to_be_bytes());
+        body.push(b'e');
+        let _mock = tracker
+            .mock("GET", "/announce")
+            .match_query(Matcher::Any)
+            .with_body(body)
+            .create_async()
+            .await;
+
+        let mut magnet = Magnet::from_info_hash(info_hash);
+        magnet.trackers = vec![format!("{}/announce", tracker.url())];
+        let torrent = Torrent {
+            name: "large.iso".to_string(),
+            magnet_link: magnet.to_string(),
+            seeders: 1,
+            peers: 0,
+            size_bytes: 1 << 30,
+            provider: "test".to_string(),
+            metadata: Default::default(),
+            stats_updated_at: None,
+        };
+
+        let metadata = MetadataFetcher::new().fetch(&torrent).await.unwrap();
+        assert_eq!(metadata.info_bytes, info);
+        assert_eq!(metadata.metainfo.trackers(), magnet.trackers);
+    }
+}